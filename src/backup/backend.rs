@@ -0,0 +1,474 @@
+//! Способы резервного копирования перед очисткой.
+//!
+//! Копирование файлов работает везде, но на Btrfs, ZFS и системах со snapper
+//! или timeshift мгновенный снимок файловой системы почти ничего не стоит.
+//! Подходящий способ выбирает [`detect_backup_backend`].
+
+use crate::backup::journal::{self, JournalRecord};
+use crate::backup::manager::{
+    Backup, BackupFormat, BackupItem, BackupManager, backup_roots, generate_backup_id,
+};
+use crate::error::{RcleanerError, Result};
+use crate::models::CleanupItem;
+use crate::system::detection::{
+    BackupBackendType, btrfs_subvolume_root, detect_backup_backend, snapper_config_for,
+};
+use crate::system::mounts::mount_for_path;
+use crate::system::package_manager::{command_failed, run_command};
+use chrono::Utc;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Каталог для снимков Btrfs внутри подтома.
+///
+/// Снимок держит блоки удалённых файлов, где бы он ни лежал, поэтому место
+/// освобождается только вместе с бэкапом; очистка сообщает эти байты
+/// отдельно (`CleanupResult::held_by_snapshots`).
+const BTRFS_SNAPSHOT_DIR: &str = ".rcleaner-snapshots";
+const SNAPSHOT_DESCRIPTION: &str = "rCleaner pre-cleanup";
+
+/// Способ создания резервной копии.
+pub trait BackupBackend {
+    /// Возвращает имя способа.
+    fn name(&self) -> &str;
+
+    /// Создаёт резервную копию путей и записывает её метаданные.
+    fn create_backup(&self, manager: &BackupManager, paths: &[PathBuf]) -> Result<Option<Backup>>;
+}
+
+/// Создаёт резервную копию элементов подходящим способом.
+///
+/// Если снимок создать не удалось, откатывается на копирование файлов.
+pub fn create_backup_for_items(items: &[CleanupItem]) -> Result<Option<Backup>> {
+    let manager = BackupManager::from_config()?;
+    let roots = backup_roots(items);
+    let backend = backend_for(detect_backup_backend(&roots));
+
+    let backup = match backend.create_backup(&manager, &roots) {
+        Ok(backup) => backup,
+        Err(err) if backend.name() != FileCopyBackend.name() => {
+            log::warn!("{} backup failed, copying files: {}", backend.name(), err);
+            FileCopyBackend.create_backup(&manager, &roots)?
+        }
+        Err(err) => return Err(err),
+    };
+
+    if let Some(backup) = &backup {
+        journal::record(JournalRecord::Backup {
+            backup_id: backup.id.clone(),
+        });
+    }
+    Ok(backup)
+}
+
+/// Возвращает реализацию для типа резервного копирования.
+pub fn backend_for(backend_type: BackupBackendType) -> Box<dyn BackupBackend> {
+    match backend_type {
+        BackupBackendType::FileCopy => Box::new(FileCopyBackend),
+        BackupBackendType::BtrfsSnapshot => Box::new(BtrfsSnapshotBackend),
+        BackupBackendType::ZfsSnapshot => Box::new(ZfsSnapshotBackend),
+        BackupBackendType::Snapper => Box::new(SnapperBackend),
+        BackupBackendType::Timeshift => Box::new(TimeshiftBackend),
+    }
+}
+
+/// Удаляет снимки файловой системы, на которые ссылается бэкап.
+pub fn release_snapshots(backup: &Backup) -> Result<()> {
+    for snapshot in &backup.snapshots {
+        let output = match backup.format {
+            BackupFormat::BtrfsSnapshot => {
+                run_command("btrfs", &["subvolume", "delete", snapshot])?
+            }
+            BackupFormat::ZfsSnapshot => run_command("zfs", &["destroy", snapshot])?,
+            BackupFormat::Snapper => {
+                let Some((config, number)) = snapshot.split_once(':') else {
+                    continue;
+                };
+                run_command("snapper", &["-c", config, "delete", number])?
+            }
+            BackupFormat::Timeshift => run_command(
+                "timeshift",
+                &["--delete", "--snapshot", snapshot, "--scripted"],
+            )?,
+            BackupFormat::Copy | BackupFormat::Chunked => continue,
+        };
+        if !output.status.success() {
+            return Err(command_failed(snapshot_program(backup.format), &output));
+        }
+    }
+    Ok(())
+}
+
+/// Копирование файлов в сжатое хранилище блоков.
+pub struct FileCopyBackend;
+
+impl BackupBackend for FileCopyBackend {
+    fn name(&self) -> &str {
+        "file copy"
+    }
+
+    fn create_backup(&self, manager: &BackupManager, paths: &[PathBuf]) -> Result<Option<Backup>> {
+        manager.create_backup_from_paths(paths)
+    }
+}
+
+/// Read-only снимки подтомов Btrfs.
+pub struct BtrfsSnapshotBackend;
+
+impl BackupBackend for BtrfsSnapshotBackend {
+    fn name(&self) -> &str {
+        "btrfs snapshot"
+    }
+
+    fn create_backup(&self, manager: &BackupManager, paths: &[PathBuf]) -> Result<Option<Backup>> {
+        let groups = group_paths(paths, btrfs_subvolume_root)?;
+        if groups.is_empty() {
+            return Ok(None);
+        }
+
+        let id = generate_backup_id();
+        let mut snapshots = Vec::new();
+        let mut items = Vec::new();
+        for (subvolume, paths) in groups {
+            let snapshot_dir = subvolume.join(BTRFS_SNAPSHOT_DIR);
+            if let Err(err) = fs::create_dir_all(&snapshot_dir) {
+                release_partial(BackupFormat::BtrfsSnapshot, &snapshots);
+                return Err(err.into());
+            }
+            let snapshot = snapshot_dir.join(&id);
+            let snapshot_str = snapshot.to_string_lossy().to_string();
+            let subvolume_str = subvolume.to_string_lossy().to_string();
+
+            let output = run_command(
+                "btrfs",
+                &["subvolume", "snapshot", "-r", &subvolume_str, &snapshot_str],
+            );
+            let output = match output {
+                Ok(output) if output.status.success() => output,
+                Ok(output) => {
+                    release_partial(BackupFormat::BtrfsSnapshot, &snapshots);
+                    return Err(command_failed("btrfs", &output));
+                }
+                Err(err) => {
+                    release_partial(BackupFormat::BtrfsSnapshot, &snapshots);
+                    return Err(err);
+                }
+            };
+            log::debug!("{}", output.stdout.trim());
+
+            items.extend(snapshot_items(&paths, &subvolume, &snapshot));
+            snapshots.push(snapshot_str);
+        }
+
+        record_snapshot_backup(manager, id, BackupFormat::BtrfsSnapshot, items, snapshots)
+    }
+}
+
+/// Снимки ZFS dataset.
+pub struct ZfsSnapshotBackend;
+
+impl BackupBackend for ZfsSnapshotBackend {
+    fn name(&self) -> &str {
+        "zfs snapshot"
+    }
+
+    fn create_backup(&self, manager: &BackupManager, paths: &[PathBuf]) -> Result<Option<Backup>> {
+        let mut datasets: BTreeMap<String, (PathBuf, Vec<PathBuf>)> = BTreeMap::new();
+        for path in paths {
+            let mount = mount_for_path(path)
+                .filter(|mount| mount.fs_type == "zfs")
+                .ok_or_else(|| {
+                    RcleanerError::Backup(format!("{} is not on ZFS", path.display()))
+                })?;
+            datasets
+                .entry(mount.source)
+                .or_insert_with(|| (mount.mount_point, Vec::new()))
+                .1
+                .push(path.clone());
+        }
+        if datasets.is_empty() {
+            return Ok(None);
+        }
+
+        let id = generate_backup_id();
+        let mut snapshots = Vec::new();
+        let mut items = Vec::new();
+        for (dataset, (mount_point, paths)) in datasets {
+            let name = format!("{dataset}@{id}");
+            match run_command("zfs", &["snapshot", &name]) {
+                Ok(output) if output.status.success() => {}
+                Ok(output) => {
+                    release_partial(BackupFormat::ZfsSnapshot, &snapshots);
+                    return Err(command_failed("zfs", &output));
+                }
+                Err(err) => {
+                    release_partial(BackupFormat::ZfsSnapshot, &snapshots);
+                    return Err(err);
+                }
+            }
+
+            let snapshot_root = mount_point.join(".zfs").join("snapshot").join(&id);
+            items.extend(snapshot_items(&paths, &mount_point, &snapshot_root));
+            snapshots.push(name);
+        }
+
+        record_snapshot_backup(manager, id, BackupFormat::ZfsSnapshot, items, snapshots)
+    }
+}
+
+/// Снимки через snapper.
+pub struct SnapperBackend;
+
+impl BackupBackend for SnapperBackend {
+    fn name(&self) -> &str {
+        "snapper"
+    }
+
+    fn create_backup(&self, manager: &BackupManager, paths: &[PathBuf]) -> Result<Option<Backup>> {
+        let mut configs: BTreeMap<String, (PathBuf, Vec<PathBuf>)> = BTreeMap::new();
+        for path in paths {
+            let mount = mount_for_path(path).ok_or_else(|| {
+                RcleanerError::Backup(format!("No mount found for {}", path.display()))
+            })?;
+            let config = snapper_config_for(&mount.mount_point).ok_or_else(|| {
+                RcleanerError::Backup(format!(
+                    "No snapper config for {}",
+                    mount.mount_point.display()
+                ))
+            })?;
+            configs
+                .entry(config)
+                .or_insert_with(|| (mount.mount_point, Vec::new()))
+                .1
+                .push(path.clone());
+        }
+        if configs.is_empty() {
+            return Ok(None);
+        }
+
+        let id = generate_backup_id();
+        let mut snapshots = Vec::new();
+        let mut items = Vec::new();
+        for (config, (subvolume, paths)) in configs {
+            let output = run_command(
+                "snapper",
+                &[
+                    "-c",
+                    &config,
+                    "create",
+                    "--type",
+                    "single",
+                    "--cleanup-algorithm",
+                    "number",
+                    "--description",
+                    SNAPSHOT_DESCRIPTION,
+                    "--print-number",
+                ],
+            );
+            let output = match output {
+                Ok(output) if output.status.success() => output,
+                Ok(output) => {
+                    release_partial(BackupFormat::Snapper, &snapshots);
+                    return Err(command_failed("snapper", &output));
+                }
+                Err(err) => {
+                    release_partial(BackupFormat::Snapper, &snapshots);
+                    return Err(err);
+                }
+            };
+            let number = output.stdout.trim().to_string();
+            if number.parse::<u64>().is_err() {
+                release_partial(BackupFormat::Snapper, &snapshots);
+                return Err(RcleanerError::Backup(format!(
+                    "Unexpected snapper output: {number}"
+                )));
+            }
+
+            let snapshot_root = subvolume.join(".snapshots").join(&number).join("snapshot");
+            items.extend(snapshot_items(&paths, &subvolume, &snapshot_root));
+            snapshots.push(format!("{config}:{number}"));
+        }
+
+        record_snapshot_backup(manager, id, BackupFormat::Snapper, items, snapshots)
+    }
+}
+
+/// Снимок через timeshift.
+///
+/// Timeshift хранит снимки на отдельном устройстве, поэтому восстановление
+/// выполняется средствами самого timeshift.
+pub struct TimeshiftBackend;
+
+impl BackupBackend for TimeshiftBackend {
+    fn name(&self) -> &str {
+        "timeshift"
+    }
+
+    fn create_backup(&self, manager: &BackupManager, paths: &[PathBuf]) -> Result<Option<Backup>> {
+        if paths.is_empty() {
+            return Ok(None);
+        }
+
+        let output = run_command(
+            "timeshift",
+            &["--create", "--comments", SNAPSHOT_DESCRIPTION, "--scripted"],
+        )?;
+        if !output.status.success() {
+            return Err(command_failed("timeshift", &output));
+        }
+        let name = parse_timeshift_snapshot_name(&output.stdout).ok_or_else(|| {
+            RcleanerError::Backup("Could not determine timeshift snapshot name".to_string())
+        })?;
+
+        let items = paths
+            .iter()
+            .map(|path| BackupItem {
+                original_path: path.to_string_lossy().to_string(),
+                backup_path: String::new(),
+                size: 0,
+                checksum: String::new(),
+                entries: Vec::new(),
+            })
+            .collect();
+
+        record_snapshot_backup(
+            manager,
+            generate_backup_id(),
+            BackupFormat::Timeshift,
+            items,
+            vec![name],
+        )
+    }
+}
+
+fn group_paths<F>(paths: &[PathBuf], root_of: F) -> Result<BTreeMap<PathBuf, Vec<PathBuf>>>
+where
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    let mut groups: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    for path in paths {
+        let root = root_of(path).ok_or_else(|| {
+            RcleanerError::Backup(format!("Cannot resolve subvolume for {}", path.display()))
+        })?;
+        groups.entry(root).or_default().push(path.clone());
+    }
+    Ok(groups)
+}
+
+fn snapshot_items(paths: &[PathBuf], source_root: &Path, snapshot_root: &Path) -> Vec<BackupItem> {
+    paths
+        .iter()
+        .map(|path| {
+            let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            let relative = resolved.strip_prefix(source_root).unwrap_or(&resolved);
+            BackupItem {
+                original_path: path.to_string_lossy().to_string(),
+                backup_path: snapshot_root.join(relative).to_string_lossy().to_string(),
+                size: 0,
+                checksum: String::new(),
+                entries: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+fn record_snapshot_backup(
+    manager: &BackupManager,
+    id: String,
+    format: BackupFormat,
+    items: Vec<BackupItem>,
+    snapshots: Vec<String>,
+) -> Result<Option<Backup>> {
+    let backup = Backup {
+        id,
+        timestamp: Utc::now(),
+        items,
+        size: 0,
+        stored_size: 0,
+        format,
+        snapshots,
+        pinned: false,
+    };
+    if let Err(err) = manager.record_backup(&backup) {
+        let _ = release_snapshots(&backup);
+        return Err(err);
+    }
+    if let Err(err) = manager.apply_retention(Some(&backup.id)) {
+        log::warn!("Failed to apply backup retention: {}", err);
+    }
+    Ok(Some(backup))
+}
+
+fn release_partial(format: BackupFormat, snapshots: &[String]) {
+    if snapshots.is_empty() {
+        return;
+    }
+    let partial = Backup {
+        id: String::new(),
+        timestamp: Utc::now(),
+        items: Vec::new(),
+        size: 0,
+        stored_size: 0,
+        format,
+        snapshots: snapshots.to_vec(),
+        pinned: false,
+    };
+    if let Err(err) = release_snapshots(&partial) {
+        log::warn!("Failed to release partial snapshots: {}", err);
+    }
+}
+
+fn snapshot_program(format: BackupFormat) -> &'static str {
+    match format {
+        BackupFormat::BtrfsSnapshot => "btrfs",
+        BackupFormat::ZfsSnapshot => "zfs",
+        BackupFormat::Snapper => "snapper",
+        BackupFormat::Timeshift => "timeshift",
+        BackupFormat::Copy | BackupFormat::Chunked => "backup",
+    }
+}
+
+fn parse_timeshift_snapshot_name(output: &str) -> Option<String> {
+    let re = Regex::new(r"\d{4}-\d{2}-\d{2}_\d{2}-\d{2}-\d{2}").ok()?;
+    output
+        .lines()
+        .filter(|line| line.contains("snapshot"))
+        .find_map(|line| re.find(line))
+        .map(|found| found.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeshift_snapshot_name() {
+        let output = "\
+Creating new snapshot...(RSYNC)
+Saving to device: /dev/sda2, mounted at path: /run/timeshift/backup
+Synching files with rsync...
+Created control file: /run/timeshift/backup/timeshift/snapshots/2024-05-01_10-15-30/info.json
+RSYNC Snapshot saved successfully (12s)
+Tagged snapshot '2024-05-01_10-15-30': ondemand";
+        assert_eq!(
+            parse_timeshift_snapshot_name(output).as_deref(),
+            Some("2024-05-01_10-15-30")
+        );
+        assert!(parse_timeshift_snapshot_name("nothing here").is_none());
+    }
+
+    #[test]
+    fn test_snapshot_items_map_into_snapshot() {
+        let items = snapshot_items(
+            &[PathBuf::from("/rcleaner-missing/home/user/.cache")],
+            Path::new("/rcleaner-missing/home"),
+            Path::new("/rcleaner-missing/home/.rcleaner-snapshots/backup-1"),
+        );
+        assert_eq!(
+            items[0].backup_path,
+            "/rcleaner-missing/home/.rcleaner-snapshots/backup-1/user/.cache"
+        );
+    }
+}
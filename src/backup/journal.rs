@@ -0,0 +1,410 @@
+//! Журнал очистки с упреждающей записью.
+//!
+//! Перед удалением в журнал записывается план очистки, затем каждый
+//! созданный бэкап, каждый удалённый путь и каждый завершённый элемент.
+//! Каждая запись сбрасывается на диск до продолжения работы, поэтому после
+//! аварийного завершения по журналу видно, что уже было сделано, и сессию
+//! можно довести до конца или откатить.
+
+use crate::backup::manager::default_backup_dir;
+use crate::backup::rollback::restore_backup;
+use crate::cleaner::deletion::restore_moved;
+use crate::error::{RcleanerError, Result};
+use crate::models::CleanupItem;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Имя файла журнала в каталоге бэкапов.
+pub const JOURNAL_FILE: &str = "cleanup-journal.jsonl";
+
+/// Журнал текущей очистки, в который пишут бэкапы и удаление файлов.
+static ACTIVE: Mutex<Option<CleanupJournal>> = Mutex::new(None);
+
+/// Запись журнала.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalRecord {
+    /// Начало сессии с планом очистки.
+    Begin {
+        session_id: String,
+        started_at: DateTime<Utc>,
+        items: Vec<CleanupItem>,
+    },
+    /// Создан бэкап.
+    Backup { backup_id: String },
+    /// Удалён путь; `moved_to` заполнен для корзины и карантина.
+    Removed {
+        path: String,
+        #[serde(default)]
+        moved_to: Option<String>,
+    },
+    /// Элемент плана обработан.
+    ItemDone { item_id: String },
+    /// Сессия завершена.
+    Commit,
+}
+
+/// Открытый журнал очистки.
+#[derive(Debug)]
+pub struct CleanupJournal {
+    path: PathBuf,
+    file: File,
+}
+
+impl CleanupJournal {
+    /// Начинает новую сессию и записывает план очистки.
+    ///
+    /// Пока по тому же пути лежит журнал прерванной сессии, новая не
+    /// начинается: иначе пропали бы данные для её завершения и отката, а её
+    /// бэкапы потеряли бы защиту от политики хранения.
+    pub fn begin(path: &Path, items: &[CleanupItem]) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if let Some(session) = read_journal(path)?.filter(|session| !session.committed) {
+            return Err(RcleanerError::Backup(format!(
+                "Interrupted cleanup from {} is pending; resume or roll it back first",
+                session.started_at.format("%Y-%m-%d %H:%M")
+            )));
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(path)?;
+        let mut journal = Self {
+            path: path.to_path_buf(),
+            file,
+        };
+        journal.append(&JournalRecord::Begin {
+            session_id: format!(
+                "{}-{}",
+                Utc::now().format("%Y%m%d%H%M%S"),
+                std::process::id()
+            ),
+            started_at: Utc::now(),
+            items: items.to_vec(),
+        })?;
+        Ok(journal)
+    }
+
+    /// Продолжает журнал прерванной сессии.
+    ///
+    /// Новые записи дописываются после старых; оборванная последняя строка
+    /// закрывается, чтобы не склеиться со следующей записью.
+    pub fn reopen(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new().append(true).open(path)?;
+        if fs::read(path)?.last().is_some_and(|byte| *byte != b'\n') {
+            file.write_all(b"\n")?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    /// Добавляет запись и дожидается её сброса на диск.
+    pub fn append(&mut self, record: &JournalRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Завершает сессию и удаляет журнал.
+    pub fn commit(mut self) -> Result<()> {
+        self.append(&JournalRecord::Commit)?;
+        fs::remove_file(&self.path)?;
+        Ok(())
+    }
+}
+
+/// Состояние сессии, восстановленное из журнала.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalSession {
+    pub session_id: String,
+    pub started_at: DateTime<Utc>,
+    /// Запланированные элементы.
+    pub items: Vec<CleanupItem>,
+    /// Идентификаторы обработанных элементов.
+    pub completed_items: HashSet<String>,
+    /// Созданные бэкапы в порядке создания.
+    pub backup_ids: Vec<String>,
+    /// Удалённые пути и их новое расположение.
+    pub removed: Vec<(String, Option<String>)>,
+    /// Была ли сессия завершена.
+    pub committed: bool,
+}
+
+impl JournalSession {
+    /// Возвращает элементы, которые ещё не были обработаны.
+    pub fn remaining_items(&self) -> Vec<CleanupItem> {
+        self.items
+            .iter()
+            .filter(|item| !self.completed_items.contains(&item.id))
+            .cloned()
+            .map(|mut item| {
+                item.selected = true;
+                item
+            })
+            .collect()
+    }
+}
+
+/// Итог отката прерванной сессии.
+#[derive(Debug, Clone, Default)]
+pub struct RollbackReport {
+    /// Восстановленные бэкапы.
+    pub restored_backups: usize,
+    /// Пути, возвращённые из корзины или карантина.
+    pub restored_paths: usize,
+    /// Ошибки восстановления.
+    pub errors: Vec<String>,
+}
+
+/// Читает журнал.
+///
+/// Недописанная последняя строка (обрыв при записи) пропускается.
+pub fn read_journal(path: &Path) -> Result<Option<JournalSession>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path)?;
+    let mut session: Option<JournalSession> = None;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        let Ok(record) = serde_json::from_str::<JournalRecord>(line) else {
+            log::warn!("Skipping damaged cleanup journal record");
+            continue;
+        };
+
+        match record {
+            JournalRecord::Begin {
+                session_id,
+                started_at,
+                items,
+            } => {
+                session = Some(JournalSession {
+                    session_id,
+                    started_at,
+                    items,
+                    completed_items: HashSet::new(),
+                    backup_ids: Vec::new(),
+                    removed: Vec::new(),
+                    committed: false,
+                });
+            }
+            other => {
+                let Some(session) = session.as_mut() else {
+                    return Err(RcleanerError::Backup(
+                        "Cleanup journal does not start with a plan".to_string(),
+                    ));
+                };
+                match other {
+                    JournalRecord::Backup { backup_id } => session.backup_ids.push(backup_id),
+                    JournalRecord::Removed { path, moved_to } => {
+                        session.removed.push((path, moved_to))
+                    }
+                    JournalRecord::ItemDone { item_id } => {
+                        session.completed_items.insert(item_id);
+                    }
+                    JournalRecord::Commit => session.committed = true,
+                    JournalRecord::Begin { .. } => {}
+                }
+            }
+        }
+    }
+
+    Ok(session)
+}
+
+/// Путь к журналу по умолчанию.
+pub fn default_journal_path() -> PathBuf {
+    default_backup_dir().join(JOURNAL_FILE)
+}
+
+/// Возвращает прерванную сессию, если она есть.
+pub fn find_interrupted() -> Result<Option<JournalSession>> {
+    Ok(read_journal(&default_journal_path())?.filter(|session| !session.committed))
+}
+
+/// Удаляет журнал прерванной сессии без отката.
+pub fn discard_interrupted() -> Result<()> {
+    let path = default_journal_path();
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Откатывает прерванную сессию и удаляет журнал.
+///
+/// Пути из корзины и карантина возвращаются на место, затем бэкапы
+/// восстанавливаются от новых к старым. Безвозвратно удалённые файлы без
+/// бэкапа восстановить нельзя.
+pub fn rollback_session(session: &JournalSession) -> Result<RollbackReport> {
+    let mut report = RollbackReport::default();
+
+    for (path, moved_to) in session.removed.iter().rev() {
+        let Some(moved_to) = moved_to else {
+            continue;
+        };
+        match restore_moved(Path::new(moved_to), Path::new(path)) {
+            Ok(()) => report.restored_paths += 1,
+            Err(err) => report.errors.push(format!("{path}: {err}")),
+        }
+    }
+
+    for backup_id in session.backup_ids.iter().rev() {
+        match restore_backup(backup_id) {
+            Ok(()) => report.restored_backups += 1,
+            Err(err) => report.errors.push(format!("{backup_id}: {err}")),
+        }
+    }
+
+    discard_interrupted()?;
+    Ok(report)
+}
+
+/// Делает журнал активным для текущей очистки.
+pub(crate) fn activate(journal: CleanupJournal) {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = Some(journal);
+    }
+}
+
+/// Снимает активный журнал и возвращает его.
+pub(crate) fn deactivate() -> Option<CleanupJournal> {
+    ACTIVE.lock().ok().and_then(|mut active| active.take())
+}
+
+/// Добавляет запись в активный журнал, если очистка идёт.
+pub(crate) fn record(record: JournalRecord) {
+    let Ok(mut active) = ACTIVE.lock() else {
+        return;
+    };
+    if let Some(journal) = active.as_mut()
+        && let Err(err) = journal.append(&record)
+    {
+        log::warn!("Failed to write cleanup journal: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CleanupCategory, CleanupSource};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_journal_path() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut path = std::env::temp_dir();
+        path.push(format!("rcleaner-journal-{nanos}-{}", std::process::id()));
+        path.push(JOURNAL_FILE);
+        path
+    }
+
+    fn item(id: &str) -> CleanupItem {
+        CleanupItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: Some(format!("/tmp/{id}")),
+            size: 1,
+            description: String::new(),
+            category: CleanupCategory::Cache,
+            source: CleanupSource::FileSystem,
+            selected: true,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_interrupted_journal_reports_progress() {
+        let path = temp_journal_path();
+        let mut journal = CleanupJournal::begin(&path, &[item("a"), item("b")]).unwrap();
+        journal
+            .append(&JournalRecord::Backup {
+                backup_id: "backup-1".to_string(),
+            })
+            .unwrap();
+        journal
+            .append(&JournalRecord::Removed {
+                path: "/tmp/a".to_string(),
+                moved_to: None,
+            })
+            .unwrap();
+        journal
+            .append(&JournalRecord::ItemDone {
+                item_id: "a".to_string(),
+            })
+            .unwrap();
+        drop(journal);
+
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("{\"event\":\"item_do");
+        fs::write(&path, content).unwrap();
+
+        let session = read_journal(&path).unwrap().unwrap();
+        assert!(!session.committed);
+        assert_eq!(session.backup_ids, ["backup-1"]);
+        assert_eq!(session.removed.len(), 1);
+        let remaining = session.remaining_items();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "b");
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_interrupted_journal_blocks_new_session_until_resumed() {
+        let path = temp_journal_path();
+        let mut journal = CleanupJournal::begin(&path, &[item("a"), item("b")]).unwrap();
+        journal
+            .append(&JournalRecord::Backup {
+                backup_id: "backup-1".to_string(),
+            })
+            .unwrap();
+        drop(journal);
+        let mut content = fs::read_to_string(&path).unwrap();
+        content.push_str("{\"event\":\"item_do");
+        fs::write(&path, content).unwrap();
+
+        assert!(CleanupJournal::begin(&path, &[item("c")]).is_err());
+
+        let mut journal = CleanupJournal::reopen(&path).unwrap();
+        journal
+            .append(&JournalRecord::ItemDone {
+                item_id: "a".to_string(),
+            })
+            .unwrap();
+        drop(journal);
+
+        let session = read_journal(&path).unwrap().unwrap();
+        assert_eq!(session.backup_ids, ["backup-1"]);
+        assert_eq!(session.remaining_items().len(), 1);
+
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_commit_removes_journal() {
+        let path = temp_journal_path();
+        let journal = CleanupJournal::begin(&path, &[item("a")]).unwrap();
+        journal.commit().unwrap();
+        assert!(read_journal(&path).unwrap().is_none());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
@@ -1,15 +1,23 @@
+use crate::backup::backend;
+use crate::backup::journal::{JOURNAL_FILE, read_journal};
+use crate::backup::store::{CHUNK_SIZE, ChunkStore, to_hex};
 use crate::config::Config;
 use crate::error::{RcleanerError, Result};
 use crate::models::CleanupItem;
+use crate::utils::safe_fs;
 use chrono::{DateTime, Utc};
-use fs_extra::dir::CopyOptions;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs::{self, File};
-use std::io::Read;
-use std::path::{Component, Path, PathBuf};
+use std::io::{Read, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 use walkdir::WalkDir;
 
+const CHUNKS_DIR: &str = "chunks";
+
 pub async fn create_backup_manager() -> Result<BackupManager> {
     BackupManager::from_config()
 }
@@ -20,30 +28,153 @@ pub struct Backup {
     pub timestamp: DateTime<Utc>,
     pub items: Vec<BackupItem>,
     pub size: u64,
+    /// Сжатые байты, впервые записанные в хранилище блоков этим бэкапом.
+    #[serde(default)]
+    pub stored_size: u64,
+    #[serde(default)]
+    pub format: BackupFormat,
+    /// Идентификаторы снимков файловой системы (для снимковых бэкапов).
+    #[serde(default)]
+    pub snapshots: Vec<String>,
+    /// Закреплённые бэкапы не удаляются политиками хранения.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl Backup {
+    /// Возвращает краткое описание бэкапа для списков.
+    pub fn summary(&self) -> BackupSummary {
+        BackupSummary {
+            id: self.id.clone(),
+            timestamp: self.timestamp,
+            item_count: self.items.len(),
+            size: self.size,
+            stored_size: self.stored_size,
+            format: self.format,
+            pinned: self.pinned,
+        }
+    }
+}
+
+/// Краткое описание бэкапа без списка файлов.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub id: String,
+    pub timestamp: DateTime<Utc>,
+    pub item_count: usize,
+    pub size: u64,
+    pub stored_size: u64,
+    pub format: BackupFormat,
+    pub pinned: bool,
+}
+
+/// Политика хранения бэкапов.
+///
+/// Нулевое значение отключает соответствующее ограничение.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Максимальный возраст бэкапа в днях.
+    pub max_age_days: u64,
+    /// Максимальное число незакреплённых бэкапов.
+    pub max_count: usize,
+    /// Максимальный занимаемый размер в байтах.
+    pub max_size: u64,
+}
+
+/// Формат хранения данных бэкапа.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupFormat {
+    /// Полная копия дерева в каталоге бэкапа (старый формат).
+    #[default]
+    Copy,
+    /// Сжатые блоки в общем хранилище `chunks/`.
+    Chunked,
+    /// Read-only снимки подтомов Btrfs.
+    BtrfsSnapshot,
+    /// Снимки ZFS dataset.
+    ZfsSnapshot,
+    /// Снимки snapper.
+    Snapper,
+    /// Снимок timeshift.
+    Timeshift,
+}
+
+impl BackupFormat {
+    /// Хранятся ли данные бэкапа в снимке файловой системы.
+    pub fn is_snapshot(self) -> bool {
+        !matches!(self, Self::Copy | Self::Chunked)
+    }
+
+    /// Возвращает название формата.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Copy => "copy",
+            Self::Chunked => "chunked",
+            Self::BtrfsSnapshot => "btrfs",
+            Self::ZfsSnapshot => "zfs",
+            Self::Snapper => "snapper",
+            Self::Timeshift => "timeshift",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupItem {
     pub original_path: String,
+    #[serde(default)]
     pub backup_path: String,
     pub size: u64,
     pub checksum: String,
+    #[serde(default)]
+    pub entries: Vec<BackupEntry>,
+}
+
+/// Запись о файле, каталоге или ссылке внутри сохранённого пути.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    /// Путь относительно `original_path` (пустой для самого корня).
+    pub relative_path: String,
+    pub kind: BackupEntryKind,
+    pub mode: u32,
+    pub size: u64,
+    /// Хеши блоков с содержимым файла по порядку.
+    #[serde(default)]
+    pub chunks: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupEntryKind {
+    File,
+    Directory,
+    Symlink(String),
 }
 
 pub struct BackupManager {
     pub backup_dir: PathBuf,
-    pub max_size: u64,
+    pub retention: RetentionPolicy,
+    store: ChunkStore,
 }
 
 impl BackupManager {
     pub fn new(backup_dir: PathBuf, max_size: u64) -> Result<Self> {
         fs::create_dir_all(&backup_dir)?;
+        let store = ChunkStore::open(backup_dir.join(CHUNKS_DIR))?;
         Ok(Self {
             backup_dir,
-            max_size,
+            retention: RetentionPolicy {
+                max_size,
+                ..RetentionPolicy::default()
+            },
+            store,
         })
     }
 
+    /// Задаёт политику хранения.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
     pub fn from_config() -> Result<Self> {
         let config = Config::load(&Config::default_path()).unwrap_or_default();
 
@@ -56,48 +187,58 @@ impl BackupManager {
         let max_size = profile.max_backup_size_gb as u64 * 1024 * 1024 * 1024;
         let backup_dir = default_backup_dir();
 
-        Self::new(backup_dir, max_size)
+        Ok(
+            Self::new(backup_dir, max_size)?.with_retention(RetentionPolicy {
+                max_age_days: profile.backup_max_age_days,
+                max_count: profile.backup_max_count,
+                max_size,
+            }),
+        )
     }
 
     pub fn create_backup(&self, items: &[CleanupItem]) -> Result<Option<Backup>> {
-        let mut candidates = collect_paths(items);
-        if candidates.is_empty() {
-            return Ok(None);
-        }
+        self.create_backup_from_paths(&backup_roots(items))
+    }
 
-        candidates.sort_by_key(|path| path.as_os_str().len());
-        let mut selected = Vec::new();
-        for path in candidates {
-            if selected.iter().any(|root: &PathBuf| path.starts_with(root)) {
-                continue;
-            }
-            selected.push(path);
+    /// Сохраняет указанные пути в хранилище блоков.
+    pub fn create_backup_from_paths(&self, paths: &[PathBuf]) -> Result<Option<Backup>> {
+        if paths.is_empty() {
+            return Ok(None);
         }
 
-        let estimated_size = estimate_total_size(&selected)?;
-        self.ensure_capacity(estimated_size)?;
-
         let id = generate_backup_id();
         let backup_root = self.backup_dir.join(&id);
         fs::create_dir_all(&backup_root)?;
+        // До записи метаданных на новые блоки ничто не ссылается, и сборка
+        // мусора в другом процессе удалила бы их.
+        let lock = self.store.lock(false)?;
 
         let mut backup_items = Vec::new();
         let mut total_size = 0u64;
+        let mut stored_size = 0u64;
 
-        for path in selected {
-            if !path.exists() {
+        for path in paths {
+            if fs::symlink_metadata(path).is_err() {
                 continue;
             }
 
-            let backup_path = backup_root.join(sanitize_path(&path));
-            let (size, checksum) = backup_path_data(&path, &backup_path)?;
-            total_size += size;
+            let stored = match self.store_path(path) {
+                Ok(stored) => stored,
+                Err(err) => {
+                    drop(lock);
+                    self.discard_backup(&id)?;
+                    return Err(err);
+                }
+            };
+            total_size += stored.size;
+            stored_size += stored.written;
 
             backup_items.push(BackupItem {
                 original_path: path.to_string_lossy().to_string(),
-                backup_path: backup_path.to_string_lossy().to_string(),
-                size,
-                checksum,
+                backup_path: String::new(),
+                size: stored.size,
+                checksum: stored.checksum,
+                entries: stored.entries,
             });
         }
 
@@ -106,14 +247,60 @@ impl BackupManager {
             timestamp: Utc::now(),
             items: backup_items,
             size: total_size,
+            stored_size,
+            format: BackupFormat::Chunked,
+            snapshots: Vec::new(),
+            pinned: false,
         };
 
-        write_metadata(&backup_root, &backup)?;
-        self.enforce_max_size()?;
+        if let Err(err) = write_metadata(&backup_root, &backup) {
+            drop(lock);
+            self.discard_backup(&id)?;
+            return Err(err);
+        }
+        drop(lock);
+        self.apply_retention(Some(&id))?;
 
         Ok(Some(backup))
     }
 
+    /// Восстанавливает сохранённый путь из хранилища блоков.
+    pub fn restore_item(&self, item: &BackupItem) -> Result<()> {
+        let original = Path::new(&item.original_path);
+        for entry in &item.entries {
+            let target = if entry.relative_path.is_empty() {
+                original.to_path_buf()
+            } else {
+                original.join(&entry.relative_path)
+            };
+
+            match &entry.kind {
+                BackupEntryKind::Directory => {
+                    File::from(safe_fs::create_dir_all_nofollow(&target)?)
+                        .set_permissions(fs::Permissions::from_mode(entry.mode))?;
+                }
+                BackupEntryKind::File => {
+                    // Недочитанный блок не оставляет на месте файла обрывок.
+                    safe_fs::replace_file(&target, entry.mode, |file| {
+                        for hash in &entry.chunks {
+                            file.write_all(&self.store.get(hash)?)?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                BackupEntryKind::Symlink(link) => {
+                    if fs::symlink_metadata(&target).is_err() {
+                        if let Some(parent) = target.parent() {
+                            safe_fs::create_dir_all_nofollow(parent)?;
+                        }
+                        std::os::unix::fs::symlink(link, &target)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn list_backups(&self) -> Result<Vec<Backup>> {
         let mut backups = Vec::new();
         if !self.backup_dir.exists() {
@@ -136,6 +323,14 @@ impl BackupManager {
         Ok(backups)
     }
 
+    /// Возвращает краткие описания бэкапов, новые первыми.
+    pub fn list_summaries(&self) -> Result<Vec<BackupSummary>> {
+        let mut summaries: Vec<BackupSummary> =
+            self.list_backups()?.iter().map(Backup::summary).collect();
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.timestamp));
+        Ok(summaries)
+    }
+
     pub fn load_backup(&self, backup_id: &str) -> Result<Backup> {
         let metadata_path = self.backup_dir.join(backup_id).join("metadata.json");
         let content = fs::read_to_string(metadata_path)?;
@@ -143,70 +338,281 @@ impl BackupManager {
         Ok(backup)
     }
 
+    /// Сохраняет метаданные бэкапа, созданного внешним способом.
+    pub fn record_backup(&self, backup: &Backup) -> Result<()> {
+        let backup_root = self.backup_dir.join(&backup.id);
+        fs::create_dir_all(&backup_root)?;
+        write_metadata(&backup_root, backup)
+    }
+
+    /// Закрепляет или открепляет бэкап.
+    pub fn set_pinned(&self, backup_id: &str, pinned: bool) -> Result<()> {
+        let mut backup = self.load_backup(backup_id)?;
+        backup.pinned = pinned;
+        write_metadata(&self.backup_dir.join(backup_id), &backup)
+    }
+
     pub fn delete_backup(&self, backup_id: &str) -> Result<()> {
+        self.remove_backup(backup_id).map(|_| ())
+    }
+
+    /// Удаляет бэкап и возвращает, на сколько уменьшился [`Self::used_space`].
+    fn remove_backup(&self, backup_id: &str) -> Result<u64> {
+        let backup = self.load_backup(backup_id).ok();
+        if let Some(backup) = &backup
+            && backup.format.is_snapshot()
+        {
+            backend::release_snapshots(backup)?;
+        }
+        let legacy = backup
+            .filter(|backup| backup.format == BackupFormat::Copy)
+            .map_or(0, |backup| backup.size);
+
         let backup_path = self.backup_dir.join(backup_id);
         if backup_path.exists() {
             fs::remove_dir_all(backup_path)?;
         }
-        Ok(())
+        Ok(self.collect_garbage()?.saturating_add(legacy))
+    }
+
+    /// Возвращает занятое бэкапами место: сжатые блоки плюс старые полные копии.
+    pub fn used_space(&self) -> Result<u64> {
+        let legacy: u64 = self
+            .list_backups()?
+            .iter()
+            .filter(|backup| backup.format == BackupFormat::Copy)
+            .map(|backup| backup.size)
+            .sum();
+        Ok(self.store.total_size()?.saturating_add(legacy))
     }
 
-    fn enforce_max_size(&self) -> Result<()> {
-        if self.max_size == 0 {
-            return Ok(());
+    /// Удаляет блоки, на которые не ссылается ни один бэкап.
+    pub fn collect_garbage(&self) -> Result<u64> {
+        let _lock = self.store.lock(true)?;
+        let mut live = HashSet::new();
+        for backup in self.list_backups()? {
+            for item in backup.items {
+                for entry in item.entries {
+                    live.extend(entry.chunks);
+                }
+            }
         }
+        self.store.retain(&live)
+    }
 
-        let mut backups = self.list_backups()?;
-        backups.sort_by_key(|backup| backup.timestamp);
+    fn store_path(&self, source: &Path) -> Result<StoredPath> {
+        let mut stored = StoredPath::default();
+        let mut hasher = Sha256::new();
 
-        let mut total: u64 = backups.iter().map(|backup| backup.size).sum();
-        for backup in backups {
-            if total <= self.max_size {
-                break;
+        let root_metadata = fs::symlink_metadata(source)?;
+        if !root_metadata.is_dir() {
+            let entry = self.store_entry(source, "", &root_metadata, &mut stored, &mut hasher)?;
+            stored.entries.push(entry);
+            stored.checksum = to_hex(&hasher.finalize());
+            return Ok(stored);
+        }
+
+        // Непрочитанная запись проваливает бэкап: иначе он сообщил бы об
+        // успехе без части файлов.
+        let mut paths = Vec::new();
+        for entry in WalkDir::new(source).same_file_system(true) {
+            let entry = entry.map_err(|err| {
+                RcleanerError::Backup(format!("Cannot read {}: {}", source.display(), err))
+            })?;
+            paths.push(entry.into_path());
+        }
+        paths.sort();
+
+        for path in paths {
+            let relative = path
+                .strip_prefix(source)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            let metadata = fs::symlink_metadata(&path)?;
+            if metadata.is_file() {
+                hasher.update(relative.as_bytes());
             }
-            self.delete_backup(&backup.id)?;
-            total = total.saturating_sub(backup.size);
+            let entry = self.store_entry(&path, &relative, &metadata, &mut stored, &mut hasher)?;
+            stored.entries.push(entry);
         }
 
-        Ok(())
+        stored.checksum = to_hex(&hasher.finalize());
+        Ok(stored)
     }
 
-    fn ensure_capacity(&self, incoming_size: u64) -> Result<()> {
-        if self.max_size == 0 {
-            return Ok(());
-        }
-
-        if incoming_size > self.max_size {
+    fn store_entry(
+        &self,
+        path: &Path,
+        relative: &str,
+        metadata: &fs::Metadata,
+        stored: &mut StoredPath,
+        hasher: &mut Sha256,
+    ) -> Result<BackupEntry> {
+        let mode = metadata.mode() & 0o7777;
+        let file_type = metadata.file_type();
+
+        let kind = if file_type.is_symlink() {
+            BackupEntryKind::Symlink(fs::read_link(path)?.to_string_lossy().to_string())
+        } else if file_type.is_dir() {
+            BackupEntryKind::Directory
+        } else if file_type.is_file() {
+            BackupEntryKind::File
+        } else {
             return Err(RcleanerError::Backup(format!(
-                "Backup size {} exceeds limit {}",
-                incoming_size, self.max_size
+                "Unsupported file type: {}",
+                path.display()
             )));
+        };
+
+        let mut chunks = Vec::new();
+        let mut size = 0u64;
+        if kind == BackupEntryKind::File {
+            let mut file = File::open(path)?;
+            let mut buffer = vec![0u8; CHUNK_SIZE];
+            loop {
+                let read = read_full(&mut file, &mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                let data = &buffer[..read];
+                hasher.update(data);
+                let (hash, written) = self.store.put(data)?;
+                stored.written += written;
+                size += read as u64;
+                chunks.push(hash);
+            }
+            stored.size += size;
+        }
+
+        Ok(BackupEntry {
+            relative_path: relative.to_string(),
+            kind,
+            mode,
+            size,
+            chunks,
+        })
+    }
+
+    fn discard_backup(&self, backup_id: &str) -> Result<()> {
+        let backup_path = self.backup_dir.join(backup_id);
+        if backup_path.exists() {
+            fs::remove_dir_all(backup_path)?;
         }
+        self.collect_garbage()?;
+        Ok(())
+    }
 
+    /// Применяет политику хранения и возвращает идентификаторы удалённых бэкапов.
+    ///
+    /// Сначала удаляются бэкапы старше `max_age_days`, затем лишние сверх
+    /// `max_count` и, наконец, самые старые, пока размер превышает
+    /// `max_size`. Закреплённые бэкапы и `current_id` не удаляются; если
+    /// только что созданный бэкап сам не помещается в лимит, он отбрасывается.
+    pub fn apply_retention(&self, current_id: Option<&str>) -> Result<Vec<String>> {
+        // Бэкапы незавершённой очистки нужны для отката и не удаляются.
+        // Нечитаемый журнал не снимает защиту: хранение прерывается.
+        let journaled = read_journal(&self.backup_dir.join(JOURNAL_FILE))?
+            .map(|session| session.backup_ids)
+            .unwrap_or_default();
         let mut backups = self.list_backups()?;
+        backups.retain(|backup| !journaled.contains(&backup.id));
         backups.sort_by_key(|backup| backup.timestamp);
-        let mut total: u64 = backups.iter().map(|backup| backup.size).sum();
 
-        for backup in backups {
-            if total + incoming_size <= self.max_size {
-                break;
+        let mut removed = Vec::new();
+        for id in select_expired(&backups, &self.retention, Utc::now(), current_id) {
+            self.delete_backup(&id)?;
+            removed.push(id);
+        }
+
+        let max_size = self.retention.max_size;
+        if max_size == 0 {
+            return Ok(removed);
+        }
+
+        // Хранилище обходится один раз; дальше вычитаются освобождённые байты.
+        let mut used = self.used_space()?;
+        for backup in &backups {
+            if used <= max_size {
+                return Ok(removed);
             }
-            self.delete_backup(&backup.id)?;
-            total = total.saturating_sub(backup.size);
+            if backup.pinned
+                || Some(backup.id.as_str()) == current_id
+                || removed.contains(&backup.id)
+            {
+                continue;
+            }
+            used = used.saturating_sub(self.remove_backup(&backup.id)?);
+            removed.push(backup.id.clone());
         }
 
-        if total + incoming_size > self.max_size {
+        if used > max_size
+            && let Some(current_id) = current_id
+        {
+            self.discard_backup(current_id)?;
             return Err(RcleanerError::Backup(format!(
-                "Insufficient backup capacity for size {}",
-                incoming_size
+                "Backup size {} exceeds limit {}",
+                used, max_size
             )));
         }
 
-        Ok(())
+        Ok(removed)
     }
 }
 
-fn default_backup_dir() -> PathBuf {
+/// Выбирает бэкапы, нарушающие ограничения по возрасту и количеству.
+///
+/// Закреплённые бэкапы не учитываются в `max_count`.
+/// `backups` должны быть отсортированы от старых к новым.
+pub fn select_expired(
+    backups: &[Backup],
+    policy: &RetentionPolicy,
+    now: DateTime<Utc>,
+    current_id: Option<&str>,
+) -> Vec<String> {
+    let removable = |backup: &&Backup| !backup.pinned && Some(backup.id.as_str()) != current_id;
+    let mut expired: Vec<String> = Vec::new();
+
+    if policy.max_age_days > 0 {
+        let max_age = chrono::Duration::days(policy.max_age_days as i64);
+        expired.extend(
+            backups
+                .iter()
+                .filter(removable)
+                .filter(|backup| now - backup.timestamp > max_age)
+                .map(|backup| backup.id.clone()),
+        );
+    }
+
+    if policy.max_count > 0 {
+        let mut remaining = backups
+            .iter()
+            .filter(|backup| !backup.pinned && !expired.contains(&backup.id))
+            .count();
+        for backup in backups.iter().filter(removable) {
+            if remaining <= policy.max_count {
+                break;
+            }
+            if expired.contains(&backup.id) {
+                continue;
+            }
+            expired.push(backup.id.clone());
+            remaining -= 1;
+        }
+    }
+
+    expired
+}
+
+#[derive(Default)]
+struct StoredPath {
+    entries: Vec<BackupEntry>,
+    size: u64,
+    written: u64,
+    checksum: String,
+}
+
+pub(crate) fn default_backup_dir() -> PathBuf {
     if let Ok(home) = std::env::var("HOME") {
         PathBuf::from(home)
             .join(".local")
@@ -218,32 +624,33 @@ fn default_backup_dir() -> PathBuf {
     }
 }
 
-fn collect_paths(items: &[CleanupItem]) -> Vec<PathBuf> {
-    items
+/// Возвращает минимальный набор путей, покрывающий все элементы.
+///
+/// Вложенные пути отбрасываются, если уже сохраняется их предок.
+pub fn backup_roots(items: &[CleanupItem]) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = items
         .iter()
         .filter(|item| item.can_clean)
         .filter_map(|item| item.path.as_ref())
         .map(PathBuf::from)
-        .collect()
-}
+        .collect();
+    candidates.sort_by_key(|path| path.as_os_str().len());
 
-fn generate_backup_id() -> String {
-    let timestamp = Utc::now().format("%Y%m%d%H%M%S");
-    format!("backup-{timestamp}-{}", std::process::id())
-}
-
-fn sanitize_path(path: &Path) -> PathBuf {
-    let mut sanitized = PathBuf::new();
-    for component in path.components() {
-        match component {
-            Component::RootDir => {}
-            Component::CurDir => {}
-            Component::ParentDir => {}
-            Component::Normal(part) => sanitized.push(part),
-            _ => {}
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for path in candidates {
+        if roots.iter().any(|root| path.starts_with(root)) {
+            continue;
         }
+        roots.push(path);
     }
-    sanitized
+    roots
+}
+
+pub(crate) fn generate_backup_id() -> String {
+    static SEQUENCE: AtomicU32 = AtomicU32::new(0);
+    let timestamp = Utc::now().format("%Y%m%d%H%M%S");
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    format!("backup-{timestamp}-{}-{sequence}", std::process::id())
 }
 
 fn write_metadata(backup_root: &Path, backup: &Backup) -> Result<()> {
@@ -253,100 +660,251 @@ fn write_metadata(backup_root: &Path, backup: &Backup) -> Result<()> {
     Ok(())
 }
 
-fn backup_path_data(source: &Path, dest: &Path) -> Result<(u64, String)> {
-    if source.is_dir() {
-        fs::create_dir_all(dest)?;
-        let mut options = CopyOptions::new();
-        options.copy_inside = true;
-        options.overwrite = true;
-        fs_extra::dir::copy(source, dest, &options)?;
-    } else if source.is_file() {
-        if let Some(parent) = dest.parent() {
-            fs::create_dir_all(parent)?;
+fn read_full(file: &mut File, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let read = file.read(&mut buffer[filled..])?;
+        if read == 0 {
+            break;
         }
-        fs::copy(source, dest)?;
-    } else {
-        return Err(RcleanerError::NotFound(format!(
-            "Backup source not found: {}",
-            source.display()
-        )));
+        filled += read;
     }
-
-    let size = calculate_path_size(source)?;
-    let checksum = hash_path(source)?;
-    Ok((size, checksum))
+    Ok(filled)
 }
 
-fn estimate_total_size(paths: &[PathBuf]) -> Result<u64> {
-    let mut total = 0u64;
-    for path in paths {
-        total = total.saturating_add(calculate_path_size(path)?);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CleanupCategory, CleanupSource};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut path = std::env::temp_dir();
+        path.push(format!("rcleaner-{label}-{nanos}-{}", std::process::id()));
+        path
     }
-    Ok(total)
-}
 
-fn calculate_path_size(path: &Path) -> Result<u64> {
-    if path.is_file() {
-        let metadata = fs::metadata(path)?;
-        return Ok(metadata.len());
+    fn item_for(path: &Path) -> CleanupItem {
+        CleanupItem {
+            id: path.to_string_lossy().to_string(),
+            name: "test".to_string(),
+            path: Some(path.to_string_lossy().to_string()),
+            size: 0,
+            description: "test".to_string(),
+            category: CleanupCategory::Cache,
+            source: CleanupSource::FileSystem,
+            selected: true,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        }
     }
 
-    if !path.exists() {
-        return Ok(0);
+    #[test]
+    fn test_chunked_backup_deduplicates_and_restores() {
+        let source = temp_dir("backup-src");
+        fs::create_dir_all(source.join("nested")).unwrap();
+        let payload = vec![42u8; 300 * 1024];
+        fs::write(source.join("a.bin"), &payload).unwrap();
+        fs::write(source.join("nested").join("b.bin"), &payload).unwrap();
+
+        let manager = BackupManager::new(temp_dir("backup-store"), 0).unwrap();
+        let backup = manager
+            .create_backup(&[item_for(&source)])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(backup.format, BackupFormat::Chunked);
+        assert_eq!(backup.size, 2 * payload.len() as u64);
+        assert!(backup.stored_size < payload.len() as u64);
+        assert_eq!(manager.used_space().unwrap(), backup.stored_size);
+
+        let second = manager
+            .create_backup(&[item_for(&source)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.stored_size, 0);
+
+        fs::remove_dir_all(&source).unwrap();
+        manager.restore_item(&backup.items[0]).unwrap();
+        assert_eq!(
+            fs::read(source.join("nested").join("b.bin")).unwrap(),
+            payload
+        );
+
+        manager.delete_backup(&backup.id).unwrap();
+        manager.delete_backup(&second.id).unwrap();
+        assert_eq!(manager.used_space().unwrap(), 0);
+
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&manager.backup_dir);
     }
 
-    let mut total = 0u64;
-    for entry in WalkDir::new(path).into_iter().flatten() {
-        if let Ok(metadata) = entry.metadata()
-            && metadata.is_file()
-        {
-            total += metadata.len();
-        }
+    #[test]
+    fn test_restore_does_not_write_through_planted_symlink() {
+        let source = temp_dir("backup-link-src");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.txt"), b"backup").unwrap();
+        let victim = temp_dir("backup-link-victim");
+        fs::write(&victim, b"victim").unwrap();
+
+        let manager = BackupManager::new(temp_dir("backup-link-store"), 0).unwrap();
+        let backup = manager
+            .create_backup(&[item_for(&source)])
+            .unwrap()
+            .unwrap();
+
+        fs::remove_file(source.join("a.txt")).unwrap();
+        std::os::unix::fs::symlink(&victim, source.join("a.txt")).unwrap();
+        manager.restore_item(&backup.items[0]).unwrap();
+
+        assert_eq!(fs::read(&victim).unwrap(), b"victim");
+        assert!(
+            fs::symlink_metadata(source.join("a.txt"))
+                .unwrap()
+                .is_file()
+        );
+        assert_eq!(fs::read(source.join("a.txt")).unwrap(), b"backup");
+
+        let _ = fs::remove_file(&victim);
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&manager.backup_dir);
     }
-    Ok(total)
-}
 
-fn hash_path(path: &Path) -> Result<String> {
-    let mut hasher = Sha256::new();
+    #[test]
+    fn test_failed_restore_keeps_existing_file() {
+        let source = temp_dir("backup-partial-src");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("a.txt"), b"backup").unwrap();
 
-    if path.is_file() {
-        hash_file(path, &mut hasher)?;
-    } else if path.is_dir() {
-        let mut entries: Vec<PathBuf> = WalkDir::new(path)
-            .into_iter()
-            .flatten()
-            .filter(|entry| entry.path().is_file())
-            .map(|entry| entry.path().to_path_buf())
-            .collect();
-        entries.sort();
+        let manager = BackupManager::new(temp_dir("backup-partial-store"), 0).unwrap();
+        let backup = manager
+            .create_backup(&[item_for(&source)])
+            .unwrap()
+            .unwrap();
+
+        fs::write(source.join("a.txt"), b"current").unwrap();
+        fs::remove_dir_all(manager.store.root()).unwrap();
+        assert!(manager.restore_item(&backup.items[0]).is_err());
 
-        for entry in entries {
-            let relative = entry.strip_prefix(path).unwrap_or(&entry);
-            hasher.update(relative.to_string_lossy().as_bytes());
-            hash_file(&entry, &mut hasher)?;
+        assert_eq!(fs::read(source.join("a.txt")).unwrap(), b"current");
+        assert_eq!(fs::read_dir(&source).unwrap().count(), 1);
+
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&manager.backup_dir);
+    }
+
+    fn backup_at(id: &str, days_ago: i64, pinned: bool) -> Backup {
+        Backup {
+            id: id.to_string(),
+            timestamp: Utc::now() - chrono::Duration::days(days_ago),
+            items: Vec::new(),
+            size: 0,
+            stored_size: 0,
+            format: BackupFormat::Chunked,
+            snapshots: Vec::new(),
+            pinned,
         }
     }
 
-    Ok(to_hex(&hasher.finalize()))
-}
+    #[test]
+    fn test_select_expired_respects_age_count_and_pins() {
+        let backups = vec![
+            backup_at("old-pinned", 90, true),
+            backup_at("old", 60, false),
+            backup_at("mid", 10, false),
+            backup_at("recent", 5, false),
+            backup_at("new", 1, false),
+        ];
+
+        let by_age = RetentionPolicy {
+            max_age_days: 30,
+            ..RetentionPolicy::default()
+        };
+        assert_eq!(select_expired(&backups, &by_age, Utc::now(), None), ["old"]);
 
-fn hash_file(path: &Path, hasher: &mut Sha256) -> Result<()> {
-    let mut file = File::open(path)?;
-    let mut buffer = [0u8; 8192];
-    loop {
-        let read = file.read(&mut buffer)?;
-        if read == 0 {
-            break;
+        let by_count = RetentionPolicy {
+            max_count: 2,
+            ..RetentionPolicy::default()
+        };
+        assert_eq!(
+            select_expired(&backups, &by_count, Utc::now(), Some("mid")),
+            ["old", "recent"]
+        );
+    }
+
+    #[test]
+    fn test_apply_retention_trims_oldest_until_under_size_limit() {
+        let source = temp_dir("backup-size-src");
+        fs::create_dir_all(&source).unwrap();
+        let mut state = 1u32;
+        let mut noise = || {
+            (0..200 * 1024)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (state >> 16) as u8
+                })
+                .collect::<Vec<u8>>()
+        };
+
+        let manager = BackupManager::new(temp_dir("backup-size-store"), 0).unwrap();
+        let mut backups = Vec::new();
+        for name in ["a.bin", "b.bin", "c.bin"] {
+            let path = source.join(name);
+            fs::write(&path, noise()).unwrap();
+            let backup = manager.create_backup_from_paths(&[path]).unwrap().unwrap();
+            backups.push(backup);
+            std::thread::sleep(std::time::Duration::from_millis(5));
         }
-        hasher.update(&buffer[..read]);
+
+        let limit = backups[2].stored_size + backups[1].stored_size;
+        let manager = manager.with_retention(RetentionPolicy {
+            max_size: limit,
+            ..RetentionPolicy::default()
+        });
+        let removed = manager.apply_retention(Some(&backups[2].id)).unwrap();
+
+        assert_eq!(removed, [backups[0].id.clone()]);
+        assert!(manager.used_space().unwrap() <= limit);
+
+        let _ = fs::remove_dir_all(&source);
+        let _ = fs::remove_dir_all(&manager.backup_dir);
     }
-    Ok(())
-}
 
-fn to_hex(bytes: &[u8]) -> String {
-    let mut output = String::with_capacity(bytes.len() * 2);
-    for byte in bytes {
-        output.push_str(&format!("{:02x}", byte));
+    #[test]
+    fn test_apply_retention_keeps_pinned_backups() {
+        let manager = BackupManager::new(temp_dir("backup-retention"), 0)
+            .unwrap()
+            .with_retention(RetentionPolicy {
+                max_count: 1,
+                ..RetentionPolicy::default()
+            });
+        for backup in [
+            backup_at("first", 3, true),
+            backup_at("second", 2, false),
+            backup_at("third", 1, false),
+        ] {
+            manager.record_backup(&backup).unwrap();
+        }
+
+        let removed = manager.apply_retention(None).unwrap();
+        assert_eq!(removed, ["second"]);
+
+        let ids: Vec<String> = manager
+            .list_summaries()
+            .unwrap()
+            .into_iter()
+            .map(|summary| summary.id)
+            .collect();
+        assert_eq!(ids, ["third", "first"]);
+
+        manager.set_pinned("first", false).unwrap();
+        assert_eq!(manager.apply_retention(None).unwrap(), ["first"]);
+        let _ = fs::remove_dir_all(&manager.backup_dir);
     }
-    output
 }
@@ -1,4 +1,11 @@
+pub mod backend;
+pub mod journal;
 pub mod manager;
 pub mod rollback;
+pub mod store;
 
-pub use manager::{Backup, BackupItem, BackupManager};
+pub use backend::{BackupBackend, create_backup_for_items};
+pub use manager::{
+    Backup, BackupEntry, BackupEntryKind, BackupFormat, BackupItem, BackupManager, BackupSummary,
+    RetentionPolicy,
+};
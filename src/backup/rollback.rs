@@ -1,13 +1,30 @@
-use crate::backup::manager::BackupManager;
-use crate::error::Result;
-use std::fs;
+use crate::backup::manager::{BackupFormat, BackupManager};
+use crate::error::{RcleanerError, Result};
+use crate::utils::safe_fs::copy_tree;
 use std::path::Path;
 
 pub async fn perform_rollback(backup_id: &str) -> Result<()> {
+    restore_backup(backup_id)
+}
+
+/// Восстанавливает все пути из бэкапа.
+pub fn restore_backup(backup_id: &str) -> Result<()> {
     let manager = BackupManager::from_config()?;
     let backup = manager.load_backup(backup_id)?;
 
+    if backup.format == BackupFormat::Timeshift {
+        let snapshot = backup.snapshots.first().cloned().unwrap_or_default();
+        return Err(RcleanerError::Backup(format!(
+            "Restore timeshift snapshot {snapshot} with `timeshift --restore`"
+        )));
+    }
+
     for item in backup.items {
+        if backup.format == BackupFormat::Chunked {
+            manager.restore_item(&item)?;
+            continue;
+        }
+
         let original = Path::new(&item.original_path);
         let backup_path = Path::new(&item.backup_path);
 
@@ -22,18 +39,10 @@ pub async fn perform_rollback(backup_id: &str) -> Result<()> {
 }
 
 fn restore_file(backup_path: &Path, original: &Path) -> Result<()> {
-    if let Some(parent) = original.parent() {
-        fs::create_dir_all(parent)?;
-    }
-    fs::copy(backup_path, original)?;
-    Ok(())
+    // Откат идёт от root: копирование не проходит по подложенным ссылкам.
+    copy_tree(backup_path, original)
 }
 
 fn restore_directory(backup_path: &Path, original: &Path) -> Result<()> {
-    fs::create_dir_all(original)?;
-    let mut options = fs_extra::dir::CopyOptions::new();
-    options.copy_inside = true;
-    options.overwrite = true;
-    fs_extra::dir::copy(backup_path, original, &options)?;
-    Ok(())
+    copy_tree(backup_path, original)
 }
@@ -0,0 +1,247 @@
+//! Контентно-адресуемое хранилище блоков для бэкапов.
+//!
+//! Каждый блок хранится один раз под своим SHA-256 хешем и сжимается zstd,
+//! поэтому одинаковые данные в разных бэкапах занимают место только однажды.
+
+use crate::error::{RcleanerError, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Размер блока, на который режутся файлы.
+pub const CHUNK_SIZE: usize = 1024 * 1024;
+
+const COMPRESSION_LEVEL: i32 = 3;
+const CHUNK_EXTENSION: &str = "zst";
+const LOCK_FILE: &str = ".lock";
+const HASH_LEN: usize = 64;
+
+pub struct ChunkStore {
+    root: PathBuf,
+}
+
+/// Блокировка хранилища, снимается при освобождении.
+///
+/// Запись бэкапа держит разделяемую блокировку, пока не сохранены его
+/// метаданные, а сборка мусора берёт исключительную: иначе она удалит блоки,
+/// на которые ещё не ссылается ни один `metadata.json`.
+pub struct StoreLock {
+    _file: File,
+}
+
+impl ChunkStore {
+    pub fn open(root: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Сохраняет блок и возвращает его хеш и число новых байт на диске.
+    ///
+    /// Если блок уже есть в хранилище, второй раз он не записывается.
+    pub fn put(&self, data: &[u8]) -> Result<(String, u64)> {
+        let hash = to_hex(&Sha256::digest(data));
+        let path = self.chunk_path(&hash)?;
+        if path.exists() {
+            return Ok((hash, 0));
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let compressed = zstd::bulk::compress(data, COMPRESSION_LEVEL)?;
+        let tmp_path = path.with_extension(format!("tmp-{}", std::process::id()));
+        fs::write(&tmp_path, &compressed)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok((hash, compressed.len() as u64))
+    }
+
+    /// Читает и распаковывает блок, проверяя его хеш.
+    pub fn get(&self, hash: &str) -> Result<Vec<u8>> {
+        let path = self.chunk_path(hash)?;
+        let compressed = fs::read(&path)
+            .map_err(|err| RcleanerError::Backup(format!("Missing chunk {hash}: {err}")))?;
+        let data = zstd::stream::decode_all(compressed.as_slice())?;
+        if to_hex(&Sha256::digest(&data)) != hash {
+            return Err(RcleanerError::Backup(format!("Chunk {hash} is corrupted")));
+        }
+        Ok(data)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.chunk_path(hash).is_ok_and(|path| path.exists())
+    }
+
+    /// Возвращает суммарный сжатый размер всех блоков.
+    pub fn total_size(&self) -> Result<u64> {
+        let mut total = 0u64;
+        for hash in self.list_chunks()? {
+            if let Ok(metadata) = fs::metadata(self.chunk_path(&hash)?) {
+                total = total.saturating_add(metadata.len());
+            }
+        }
+        Ok(total)
+    }
+
+    /// Удаляет блоки, которых нет в `live`, и возвращает освобождённые байты.
+    pub fn retain(&self, live: &HashSet<String>) -> Result<u64> {
+        let mut freed = 0u64;
+        for hash in self.list_chunks()? {
+            if live.contains(&hash) {
+                continue;
+            }
+            let path = self.chunk_path(&hash)?;
+            if let Ok(metadata) = fs::metadata(&path) {
+                freed = freed.saturating_add(metadata.len());
+            }
+            fs::remove_file(&path)?;
+        }
+        Ok(freed)
+    }
+
+    fn list_chunks(&self) -> Result<Vec<String>> {
+        let mut hashes = Vec::new();
+        for entry in WalkDir::new(&self.root).min_depth(2).max_depth(2) {
+            let entry = entry.map_err(|err| RcleanerError::Backup(err.to_string()))?;
+            let path = entry.path();
+            if !entry.file_type().is_file()
+                || path.extension().and_then(|ext| ext.to_str()) != Some(CHUNK_EXTENSION)
+            {
+                continue;
+            }
+            if let Some(stem) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|stem| is_valid_hash(stem))
+            {
+                hashes.push(stem.to_string());
+            }
+        }
+        Ok(hashes)
+    }
+
+    /// Берёт разделяемую или исключительную блокировку хранилища.
+    pub fn lock(&self, exclusive: bool) -> Result<StoreLock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.root.join(LOCK_FILE))?;
+        let operation = if exclusive {
+            libc::LOCK_EX
+        } else {
+            libc::LOCK_SH
+        };
+        // SAFETY: дескриптор принадлежит `file` и открыт.
+        if unsafe { libc::flock(file.as_raw_fd(), operation) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(StoreLock { _file: file })
+    }
+
+    /// Хеш приходит из `metadata.json`, поэтому проверяется перед тем, как
+    /// стать частью пути.
+    fn chunk_path(&self, hash: &str) -> Result<PathBuf> {
+        if !is_valid_hash(hash) {
+            return Err(RcleanerError::Backup(format!(
+                "Invalid chunk hash {hash:?}"
+            )));
+        }
+        Ok(self
+            .root
+            .join(&hash[..2])
+            .join(format!("{hash}.{CHUNK_EXTENSION}")))
+    }
+}
+
+fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == HASH_LEN
+        && hash
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        output.push_str(&format!("{:02x}", byte));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_store_dir() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut path = std::env::temp_dir();
+        path.push(format!("rcleaner-chunks-{nanos}-{}", std::process::id()));
+        path
+    }
+
+    #[test]
+    fn test_put_deduplicates_and_roundtrips() {
+        let dir = temp_store_dir();
+        let store = ChunkStore::open(dir.clone()).unwrap();
+        let data = vec![7u8; 64 * 1024];
+
+        let (hash, written) = store.put(&data).unwrap();
+        assert!(written > 0);
+        assert!(written < data.len() as u64);
+
+        let (same_hash, written_again) = store.put(&data).unwrap();
+        assert_eq!(hash, same_hash);
+        assert_eq!(written_again, 0);
+        assert_eq!(store.total_size().unwrap(), written);
+
+        assert_eq!(store.get(&hash).unwrap(), data);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_retain_removes_unreferenced_chunks() {
+        let dir = temp_store_dir();
+        let store = ChunkStore::open(dir.clone()).unwrap();
+        let (keep, _) = store.put(b"keep me").unwrap();
+        let (drop, _) = store.put(b"drop me").unwrap();
+
+        let live = HashSet::from([keep.clone()]);
+        let freed = store.retain(&live).unwrap();
+
+        assert!(freed > 0);
+        assert!(store.contains(&keep));
+        assert!(!store.contains(&drop));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rejects_malformed_hashes() {
+        let dir = temp_store_dir();
+        let store = ChunkStore::open(dir.clone()).unwrap();
+        let (hash, _) = store.put(b"data").unwrap();
+
+        for bad in [
+            "../../../etc/passwd",
+            "ab",
+            &hash.to_uppercase(),
+            &hash[1..],
+        ] {
+            assert!(matches!(store.get(bad), Err(RcleanerError::Backup(_))));
+            assert!(!store.contains(bad));
+        }
+        assert!(store.contains(&hash));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,11 +1,16 @@
-use crate::backup::BackupManager;
+use crate::backup::create_backup_for_items;
 use crate::cleaner::base::Cleaner;
+use crate::cleaner::deletion::Remover;
 use crate::error::{RcleanerError, Result};
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
+use crate::privilege;
+use crate::system::usage::{UsageAnalyzer, describe_last_used};
+use crate::system::user_packages::{self, UserPackage, UserPackageKind};
 use crate::system::{flatpak, snap};
 use crate::utils::size_format::parse_size_string;
 use std::env;
 use std::path::Path;
+use std::time::SystemTime;
 
 pub struct ApplicationsCleaner;
 
@@ -31,7 +36,8 @@ impl Cleaner for ApplicationsCleaner {
     }
 
     fn scan(&self) -> Result<Vec<CleanupItem>> {
-        let mut items = Vec::new();
+        let usage = UsageAnalyzer::load();
+        let mut applications: Vec<(CleanupItem, Option<SystemTime>)> = Vec::new();
 
         if flatpak::is_flatpak_available()
             && let Ok(apps) = flatpak::list_installed_with_sizes() {
@@ -39,19 +45,21 @@ impl Cleaner for ApplicationsCleaner {
                     if app.trim().is_empty() {
                         continue;
                     }
-                    items.push(CleanupItem {
+                    let last_used = usage.flatpak_app(&app);
+                    applications.push((CleanupItem {
                         id: format!("flatpak:{}", app),
                         name: app.clone(),
                         path: None,
                         size,
-                        description: "Flatpak application".to_string(),
+                        description: format!("Flatpak application, {}", describe_last_used(last_used)),
                         category: self.category(),
                         source: CleanupSource::PackageManager("flatpak".to_string()),
                         selected: false,
                         can_clean: true,
                         blocked_reason: None,
                         dependencies: Vec::new(),
-                    });
+                        verdicts: Vec::new(),
+                    }, last_used));
                 }
             }
 
@@ -61,22 +69,33 @@ impl Cleaner for ApplicationsCleaner {
                     if app.trim().is_empty() || app == "Name" {
                         continue;
                     }
-                    items.push(CleanupItem {
+                    let last_used = usage.snap_app(&app);
+                    applications.push((CleanupItem {
                         id: format!("snap:{}", app),
                         name: app.clone(),
                         path: None,
                         size,
-                        description: "Snap application".to_string(),
+                        description: format!("Snap application, {}", describe_last_used(last_used)),
                         category: self.category(),
                         source: CleanupSource::PackageManager("snap".to_string()),
                         selected: false,
                         can_clean: true,
                         blocked_reason: None,
                         dependencies: Vec::new(),
-                    });
+                        verdicts: Vec::new(),
+                    }, last_used));
                 }
             }
 
+        applications.extend(user_packages::inventory().into_iter().map(|package| {
+            let last_used = package.last_used;
+            (user_package_item(package), last_used)
+        }));
+
+        // Дольше всего не использованные — первыми; без записей об использовании — в самом начале.
+        applications.sort_by_key(|(_, last_used)| *last_used);
+        let mut items: Vec<CleanupItem> = applications.into_iter().map(|(item, _)| item).collect();
+
         items.extend(list_container_images("docker")?);
         items.extend(list_container_images("podman")?);
 
@@ -89,11 +108,15 @@ impl Cleaner for ApplicationsCleaner {
         let mut snap_apps = Vec::new();
         let mut docker_images = Vec::new();
         let mut podman_images = Vec::new();
+        let mut user_apps: Vec<(UserPackageKind, &CleanupItem)> = Vec::new();
+        let remover = Remover::from_config();
 
-        if !dry_run {
-            let manager = BackupManager::from_config()?;
-            let _backup = manager.create_backup(items)?;
-        }
+        let backup = if dry_run {
+            None
+        } else {
+            create_backup_for_items(items)?
+        };
+        let in_snapshot = backup.is_some_and(|backup| backup.format.is_snapshot());
 
         for item in items {
             if !self.can_clean(item) {
@@ -103,45 +126,78 @@ impl Cleaner for ApplicationsCleaner {
 
             match &item.source {
                 CleanupSource::PackageManager(manager) => match manager.as_str() {
-                    "flatpak" => flatpak_apps.push(item.name.clone()),
-                    "snap" => snap_apps.push(item.name.clone()),
+                    "flatpak" => flatpak_apps.push(item),
+                    "snap" => snap_apps.push(item),
+                    "brew" if item.id.starts_with("brew:old-kegs:") => {
+                        user_apps.push((UserPackageKind::BrewOldKegs, item))
+                    }
+                    "brew" => user_apps.push((UserPackageKind::Brew, item)),
+                    "pipx" => user_apps.push((UserPackageKind::Pipx, item)),
+                    "cargo" => user_apps.push((UserPackageKind::Cargo, item)),
+                    "npm" => user_apps.push((UserPackageKind::Npm, item)),
                     _ => result.skipped_items += 1,
                 },
                 CleanupSource::Container(runtime) => match runtime.as_str() {
-                    "docker" => docker_images.push(item.name.clone()),
-                    "podman" => podman_images.push(item.name.clone()),
+                    "docker" => docker_images.push(item),
+                    "podman" => podman_images.push(item),
                     _ => result.skipped_items += 1,
                 },
-                CleanupSource::FileSystem => result.skipped_items += 1,
+                // AppImage — обычный файл.
+                CleanupSource::FileSystem => match &item.path {
+                    Some(path) if dry_run => {
+                        log::info!("[DRY RUN] Would remove: {}", path);
+                        result.item_done(item);
+                        result.freed_bytes += item.size;
+                    }
+                    Some(path) => match remover.remove(Path::new(path)) {
+                        Ok(moved_to) => {
+                            result.item_done(item);
+                            result.add_removed(item.size, moved_to.is_some(), in_snapshot);
+                        }
+                        Err(err) => result.errors.push(format!("{}: {}", path, err)),
+                    },
+                    None => result.skipped_items += 1,
+                },
             }
         }
 
         if !flatpak_apps.is_empty() {
-            flatpak::remove_packages(&flatpak_apps, dry_run)?;
-            result.cleaned_items += flatpak_apps.len();
+            flatpak::remove_packages(&names(&flatpak_apps), dry_run)?;
+            flatpak_apps.iter().for_each(|item| result.item_done(item));
         }
 
         if !snap_apps.is_empty() {
-            snap::remove_packages(&snap_apps, dry_run)?;
-            result.cleaned_items += snap_apps.len();
+            privilege::remove_packages("snap", &names(&snap_apps), dry_run)?;
+            snap_apps.iter().for_each(|item| result.item_done(item));
+        }
+
+        for (kind, item) in user_apps {
+            match user_packages::remove(kind, std::slice::from_ref(&item.name), dry_run) {
+                Ok(()) => result.item_done(item),
+                Err(err) => result.errors.push(format!("{}: {err}", item.name)),
+            }
         }
 
         if !docker_images.is_empty() {
-            remove_container_images("docker", &docker_images, dry_run)
+            remove_container_images("docker", &names(&docker_images), dry_run)
                 .map_err(RcleanerError::Command)?;
-            result.cleaned_items += docker_images.len();
+            docker_images.iter().for_each(|item| result.item_done(item));
         }
 
         if !podman_images.is_empty() {
-            remove_container_images("podman", &podman_images, dry_run)
+            remove_container_images("podman", &names(&podman_images), dry_run)
                 .map_err(RcleanerError::Command)?;
-            result.cleaned_items += podman_images.len();
+            podman_images.iter().for_each(|item| result.item_done(item));
         }
 
         Ok(result)
     }
 }
 
+fn names(items: &[&CleanupItem]) -> Vec<String> {
+    items.iter().map(|item| item.name.clone()).collect()
+}
+
 fn list_container_images(runtime: &str) -> Result<Vec<CleanupItem>> {
     if !command_exists(runtime) {
         return Ok(Vec::new());
@@ -184,12 +240,52 @@ fn list_container_images(runtime: &str) -> Result<Vec<CleanupItem>> {
             can_clean: true,
             blocked_reason: None,
             dependencies: Vec::new(),
+            verdicts: Vec::new(),
         });
     }
 
     Ok(items)
 }
 
+fn user_package_item(package: UserPackage) -> CleanupItem {
+    let source = package.kind.source();
+    let (id, path, source) = match package.kind {
+        UserPackageKind::BrewOldKegs => (
+            format!("brew:old-kegs:{}", package.name),
+            None,
+            CleanupSource::PackageManager(source.to_string()),
+        ),
+        UserPackageKind::AppImage => (
+            format!("appimage:{}", package.path.display()),
+            Some(package.path.to_string_lossy().to_string()),
+            CleanupSource::FileSystem,
+        ),
+        _ => (
+            format!("{source}:{}", package.name),
+            None,
+            CleanupSource::PackageManager(source.to_string()),
+        ),
+    };
+    let description = match package.kind {
+        UserPackageKind::BrewOldKegs => format!("{} (brew cleanup)", package.kind.label()),
+        kind => format!("{}, {}", kind.label(), describe_last_used(package.last_used)),
+    };
+    CleanupItem {
+        id,
+        name: package.name,
+        path,
+        size: package.size,
+        description,
+        category: CleanupCategory::Applications,
+        source,
+        selected: false,
+        can_clean: true,
+        blocked_reason: None,
+        dependencies: Vec::new(),
+        verdicts: Vec::new(),
+    }
+}
+
 fn remove_container_images(
     runtime: &str,
     images: &[String],
@@ -1,9 +1,13 @@
-use crate::backup::BackupManager;
+use crate::backup::create_backup_for_items;
 use crate::cleaner::base::Cleaner;
+use crate::cleaner::deletion::Remover;
 use crate::error::Result;
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
+use crate::privilege;
+use crate::system::detection::command_exists;
+use crate::system::{apk, xbps, zypper};
+use crate::utils::disk_size::SizeCounter;
 use std::path::Path;
-use walkdir::WalkDir;
 
 pub struct CacheCleaner;
 
@@ -17,27 +21,6 @@ impl CacheCleaner {
     pub fn new() -> Self {
         Self {}
     }
-
-    fn calculate_directory_size(&self, path: &str) -> Result<u64> {
-        self.calculate_directory_size_path(Path::new(path))
-    }
-
-    fn calculate_directory_size_path(&self, path: &Path) -> Result<u64> {
-        if !path.exists() {
-            return Ok(0);
-        }
-
-        let mut total_size = 0u64;
-
-        for entry in WalkDir::new(path).into_iter().flatten() {
-            if let Ok(metadata) = entry.metadata()
-                && metadata.is_file() {
-                    total_size += metadata.len();
-                }
-        }
-
-        Ok(total_size)
-    }
 }
 
 impl Cleaner for CacheCleaner {
@@ -51,10 +34,23 @@ impl Cleaner for CacheCleaner {
 
     fn scan(&self) -> Result<Vec<CleanupItem>> {
         let mut items = Vec::new();
+        // Один счётчик на весь скан: общие жёсткие ссылки учитываются один раз.
+        let mut counter = SizeCounter::new();
 
         let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        // Общий кэш удаляется целиком вместе с вложенными каталогами, поэтому
+        // считается отдельным счётчиком и показывает полный размер.
+        let user_cache = format!("{}/.cache", home);
+        if let Some(item) = self.directory_item(
+            &mut SizeCounter::new(),
+            "User cache".to_string(),
+            Path::new(&user_cache),
+            format!("Cache directory: {}", user_cache),
+        ) {
+            items.push(item);
+        }
+
         let cache_dirs = [
-            ("User cache", format!("{}/.cache", home)),
             ("Thumbnails", format!("{}/.cache/thumbnails", home)),
             ("Firefox cache", format!("{}/.cache/mozilla/firefox", home)),
             ("Chrome cache", format!("{}/.cache/google-chrome", home)),
@@ -64,22 +60,15 @@ impl Cleaner for CacheCleaner {
         ];
 
         for (label, cache_dir) in &cache_dirs {
-            if let Ok(size) = self.calculate_directory_size(cache_dir)
-                && size > 0 {
-                    items.push(CleanupItem {
-                        id: cache_dir.clone(),
-                        name: label.to_string(),
-                        path: Some(cache_dir.clone()),
-                        size,
-                        description: format!("Cache directory: {}", cache_dir),
-                        category: self.category(),
-                        source: CleanupSource::FileSystem,
-                        selected: false,
-                        can_clean: true,
-                        blocked_reason: None,
-                        dependencies: Vec::new(),
-                    });
-                }
+            let description = format!("Cache directory: {}", cache_dir);
+            if let Some(item) = self.directory_item(
+                &mut counter,
+                label.to_string(),
+                Path::new(cache_dir),
+                description,
+            ) {
+                items.push(item);
+            }
         }
 
         let flatpak_root = format!("{}/.var/app", home);
@@ -88,25 +77,52 @@ impl Cleaner for CacheCleaner {
                 let app_path = entry.path();
                 let app_name = entry.file_name().to_string_lossy().trim().to_string();
                 let cache_path = app_path.join("cache");
-                if let Ok(size) = self.calculate_directory_size_path(&cache_path)
-                    && size > 0 {
-                        items.push(CleanupItem {
-                            id: cache_path.to_string_lossy().to_string(),
-                            name: format!("Flatpak cache: {}", app_name),
-                            path: Some(cache_path.to_string_lossy().to_string()),
-                            size,
-                            description: format!(
-                                "Flatpak cache directory: {}",
-                                cache_path.to_string_lossy()
-                            ),
-                            category: self.category(),
-                            source: CleanupSource::FileSystem,
-                            selected: false,
-                            can_clean: true,
-                            blocked_reason: None,
-                            dependencies: Vec::new(),
-                        });
-                    }
+                let name = format!("Flatpak cache: {}", app_name);
+                let description =
+                    format!("Flatpak cache directory: {}", cache_path.to_string_lossy());
+                if let Some(item) =
+                    self.directory_item(&mut counter, name, &cache_path, description)
+                {
+                    items.push(item);
+                }
+            }
+        }
+
+        let zypper_cache = counter
+            .measure(Path::new(zypper::PACKAGE_CACHE_DIR))
+            .allocated;
+        if zypper_cache > 0 && command_exists("zypper") {
+            items.push(package_cache_item(
+                "zypper",
+                "Zypper package cache",
+                format!("Downloaded packages: {}", zypper::PACKAGE_CACHE_DIR),
+                zypper_cache,
+            ));
+        }
+
+        if command_exists("apk")
+            && let Some(dir) = apk::cache_dir()
+        {
+            let apk_cache = apk::obsolete_cache_size();
+            if apk_cache > 0 {
+                items.push(package_cache_item(
+                    "apk",
+                    "APK package cache",
+                    format!("Outdated cached packages: {}", dir.display()),
+                    apk_cache,
+                ));
+            }
+        }
+
+        if command_exists("xbps-remove") {
+            let xbps_cache = xbps::obsolete_cache_size();
+            if xbps_cache > 0 {
+                items.push(package_cache_item(
+                    "xbps",
+                    "XBPS package cache",
+                    format!("Obsolete cached packages: {}", xbps::PACKAGE_CACHE_DIR),
+                    xbps_cache,
+                ));
             }
         }
 
@@ -115,11 +131,14 @@ impl Cleaner for CacheCleaner {
 
     fn clean(&self, items: &[CleanupItem], dry_run: bool) -> Result<CleanupResult> {
         let mut result = CleanupResult::default();
+        let remover = Remover::from_config();
 
-        if !dry_run {
-            let manager = BackupManager::from_config()?;
-            let _backup = manager.create_backup(items)?;
-        }
+        let backup = if dry_run {
+            None
+        } else {
+            create_backup_for_items(items)?
+        };
+        let in_snapshot = backup.is_some_and(|backup| backup.format.is_snapshot());
 
         for item in items {
             if !self.can_clean(item) {
@@ -129,19 +148,28 @@ impl Cleaner for CacheCleaner {
             if let Some(ref path) = item.path {
                 if dry_run {
                     log::info!("[DRY RUN] Would clean: {}", path);
-                    result.cleaned_items += 1;
+                    result.item_done(item);
                     result.freed_bytes += item.size;
                 } else {
-                    match std::fs::remove_dir_all(path) {
-                        Ok(()) => {
-                            result.cleaned_items += 1;
-                            result.freed_bytes += item.size;
+                    match remover.remove(Path::new(path)) {
+                        Ok(moved_to) => {
+                            result.item_done(item);
+                            result.add_removed(item.size, moved_to.is_some(), in_snapshot);
                         }
                         Err(err) => {
                             result.errors.push(format!("{}: {}", path, err));
                         }
                     }
                 }
+            } else if let CleanupSource::PackageManager(manager) = &item.source {
+                // Кэш пакетов очищает сам менеджер.
+                match privilege::clean_package_cache(manager, dry_run) {
+                    Ok(()) => {
+                        result.item_done(item);
+                        result.freed_bytes += item.size;
+                    }
+                    Err(err) => result.errors.push(format!("{}: {}", item.name, err)),
+                }
             } else {
                 result.skipped_items += 1;
             }
@@ -151,6 +179,56 @@ impl Cleaner for CacheCleaner {
     }
 }
 
+impl CacheCleaner {
+    /// Элемент для каталога кэша; `None`, если счётчик не нашёл в нём
+    /// неучтённых байтов.
+    fn directory_item(
+        &self,
+        counter: &mut SizeCounter,
+        name: String,
+        path: &Path,
+        description: String,
+    ) -> Option<CleanupItem> {
+        let size = counter.measure(path).allocated;
+        if size == 0 {
+            return None;
+        }
+        let path = path.to_string_lossy().to_string();
+        Some(CleanupItem {
+            id: path.clone(),
+            name,
+            path: Some(path),
+            size,
+            description,
+            category: self.category(),
+            source: CleanupSource::FileSystem,
+            selected: false,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        })
+    }
+}
+
+/// Кэш пакетов, который очищает сам пакетный менеджер.
+fn package_cache_item(manager: &str, name: &str, description: String, size: u64) -> CleanupItem {
+    CleanupItem {
+        id: format!("{manager}-cache"),
+        name: name.to_string(),
+        path: None,
+        size,
+        description,
+        category: CleanupCategory::Cache,
+        source: CleanupSource::PackageManager(manager.to_string()),
+        selected: false,
+        can_clean: true,
+        blocked_reason: None,
+        dependencies: Vec::new(),
+        verdicts: Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,6 +266,7 @@ mod tests {
             can_clean: true,
             blocked_reason: None,
             dependencies: Vec::new(),
+            verdicts: Vec::new(),
         };
 
         let cleaner = CacheCleaner::new();
@@ -196,6 +275,40 @@ mod tests {
         assert!(file_path.exists());
         assert_eq!(result.cleaned_items, 1);
         assert_eq!(result.freed_bytes, size);
+        assert_eq!(result.done_items, vec!["test".to_string()]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_scan_counts_shared_hardlinks_once() {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut dir = std::env::temp_dir();
+        dir.push(format!(
+            "rcleaner-cache-links-{nanos}-{}",
+            std::process::id()
+        ));
+        let first = dir.join("first");
+        let second = dir.join("second");
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("blob"), vec![1u8; 64 * 1024]).unwrap();
+        std::fs::hard_link(first.join("blob"), second.join("blob")).unwrap();
+
+        let cleaner = CacheCleaner::new();
+        let mut counter = SizeCounter::new();
+        let first_item = cleaner
+            .directory_item(&mut counter, "first".to_string(), &first, String::new())
+            .unwrap();
+        let second_item =
+            cleaner.directory_item(&mut counter, "second".to_string(), &second, String::new());
+
+        assert!(first_item.size >= 64 * 1024);
+        // Во втором каталоге остался только сам каталог, без общего файла.
+        assert!(second_item.is_none_or(|item| item.size < 64 * 1024));
 
         let _ = std::fs::remove_dir_all(&dir);
     }
@@ -0,0 +1,425 @@
+//! Стратегии удаления файлов: безвозвратно, в корзину или в карантин.
+//!
+//! Корзина реализована по спецификации freedesktop.org Trash: файл
+//! перемещается в `files/`, а рядом в `info/` пишется `.trashinfo` с
+//! исходным путём и временем удаления. Карантин — собственный каталог
+//! rCleaner, записи из которого удаляются через `quarantine_days` дней.
+
+use crate::backup::journal::{self, JournalRecord};
+use crate::config::{Config, DeletionMode};
+use crate::error::{RcleanerError, Result};
+use crate::system::mounts::mount_for_path;
+use crate::utils::safe_fs::{
+    check_mount_boundaries, copy_tree, create_dir_all_nofollow, remove_tree,
+};
+use chrono::{Local, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const QUARANTINE_ORIGIN_FILE: &str = "origin";
+const QUARANTINE_ITEM: &str = "item";
+
+/// Удаляет файлы выбранным способом.
+pub struct Remover {
+    mode: DeletionMode,
+    quarantine_root: PathBuf,
+}
+
+/// Запись в карантине.
+#[derive(Debug, Clone)]
+pub struct QuarantineEntry {
+    /// Идентификатор записи (имя каталога).
+    pub id: String,
+    /// Исходный путь.
+    pub original_path: PathBuf,
+    /// Когда запись была помещена в карантин.
+    pub quarantined_at: SystemTime,
+}
+
+impl Remover {
+    pub fn new(mode: DeletionMode) -> Self {
+        Self {
+            mode,
+            quarantine_root: default_quarantine_dir(),
+        }
+    }
+
+    /// Создаёт удаление по настройкам активного профиля.
+    pub fn from_config() -> Self {
+        let config = Config::load(&Config::default_path()).unwrap_or_default();
+        Self::new(config.current_profile().deletion_mode)
+    }
+
+    /// Использует указанный каталог карантина.
+    pub fn with_quarantine_root(mut self, root: PathBuf) -> Self {
+        self.quarantine_root = root;
+        self
+    }
+
+    pub fn mode(&self) -> DeletionMode {
+        self.mode
+    }
+
+    /// Удаляет путь выбранным способом.
+    ///
+    /// Возвращает новое расположение для корзины и карантина.
+    ///
+    /// Удаление записывается в журнал текущей очистки.
+    pub fn remove(&self, path: &Path) -> Result<Option<PathBuf>> {
+        check_mount_boundaries(path)?;
+        let moved_to = match self.mode {
+            DeletionMode::Permanent => {
+                remove_permanently(path)?;
+                None
+            }
+            DeletionMode::Trash => Some(move_to_trash(path)?),
+            DeletionMode::Quarantine => Some(quarantine_path(path, &self.quarantine_root)?),
+        };
+
+        journal::record(JournalRecord::Removed {
+            path: path.to_string_lossy().to_string(),
+            moved_to: moved_to
+                .as_ref()
+                .map(|moved| moved.to_string_lossy().to_string()),
+        });
+        Ok(moved_to)
+    }
+}
+
+/// Безвозвратно удаляет файл, ссылку или каталог.
+///
+/// Ссылки не разыменовываются, точки монтирования не пересекаются
+/// (см. [`remove_tree`]).
+pub fn remove_permanently(path: &Path) -> Result<()> {
+    remove_tree(path)
+}
+
+/// Перемещает путь в корзину freedesktop.
+pub fn move_to_trash(path: &Path) -> Result<PathBuf> {
+    let path = absolute(path)?;
+    let (trash_dir, info_path_base) = trash_dir_for(&path)?;
+    let files_dir = trash_dir.join("files");
+    let info_dir = trash_dir.join("info");
+    fs::create_dir_all(&files_dir)?;
+    fs::create_dir_all(&info_dir)?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| RcleanerError::InvalidInput(format!("Cannot trash {}", path.display())))?
+        .to_string_lossy()
+        .to_string();
+
+    let recorded_path = match &info_path_base {
+        Some(topdir) => path.strip_prefix(topdir).unwrap_or(&path).to_path_buf(),
+        None => path.clone(),
+    };
+    let info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        encode_trash_path(&recorded_path.to_string_lossy()),
+        Local::now().format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    for attempt in 1..10_000u32 {
+        let name = if attempt == 1 {
+            file_name.clone()
+        } else {
+            format!("{file_name}.{attempt}")
+        };
+        let info_path = info_dir.join(format!("{name}.trashinfo"));
+        let target = files_dir.join(&name);
+        if fs::symlink_metadata(&target).is_ok() {
+            continue;
+        }
+
+        let mut info_file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        };
+        info_file.write_all(info.as_bytes())?;
+
+        if let Err(err) = fs::rename(&path, &target) {
+            let _ = fs::remove_file(&info_path);
+            return Err(err.into());
+        }
+        return Ok(target);
+    }
+
+    Err(RcleanerError::Cleaner(format!(
+        "No free trash name for {}",
+        path.display()
+    )))
+}
+
+/// Перемещает путь в карантин rCleaner.
+pub fn quarantine_path(path: &Path, quarantine_root: &Path) -> Result<PathBuf> {
+    let path = absolute(path)?;
+    let stamp = Utc::now().format("%Y%m%d%H%M%S");
+    let mut entry_dir = quarantine_root.join(format!("{stamp}-{}", std::process::id()));
+    let mut counter = 1;
+    while entry_dir.exists() {
+        counter += 1;
+        entry_dir = quarantine_root.join(format!("{stamp}-{}-{counter}", std::process::id()));
+    }
+    fs::create_dir_all(&entry_dir)?;
+    fs::write(
+        entry_dir.join(QUARANTINE_ORIGIN_FILE),
+        path.to_string_lossy().as_bytes(),
+    )?;
+
+    let target = entry_dir.join(QUARANTINE_ITEM);
+    match fs::rename(&path, &target) {
+        Ok(()) => {}
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            copy_tree(&path, &target)?;
+            remove_permanently(&path)?;
+        }
+        Err(err) => {
+            let _ = fs::remove_dir_all(&entry_dir);
+            return Err(err.into());
+        }
+    }
+    Ok(target)
+}
+
+/// Возвращает записи карантина.
+pub fn list_quarantine(quarantine_root: &Path) -> Result<Vec<QuarantineEntry>> {
+    let mut entries = Vec::new();
+    if !quarantine_root.exists() {
+        return Ok(entries);
+    }
+
+    for entry in fs::read_dir(quarantine_root)? {
+        let entry = entry?;
+        let dir = entry.path();
+        let Ok(origin) = fs::read_to_string(dir.join(QUARANTINE_ORIGIN_FILE)) else {
+            continue;
+        };
+        let quarantined_at = fs::metadata(&dir)
+            .and_then(|metadata| metadata.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push(QuarantineEntry {
+            id: entry.file_name().to_string_lossy().to_string(),
+            original_path: PathBuf::from(origin.trim_end_matches('\n')),
+            quarantined_at,
+        });
+    }
+    entries.sort_by_key(|entry| entry.quarantined_at);
+    Ok(entries)
+}
+
+/// Возвращает запись из карантина на исходное место.
+pub fn restore_quarantined(quarantine_root: &Path, id: &str) -> Result<PathBuf> {
+    if id.is_empty() || id.contains('/') || id == "." || id == ".." {
+        return Err(RcleanerError::InvalidInput(format!(
+            "invalid quarantine entry {id:?}"
+        )));
+    }
+    let entry_dir = quarantine_root.join(id);
+    let origin = fs::read_to_string(entry_dir.join(QUARANTINE_ORIGIN_FILE))?;
+    let original = PathBuf::from(origin.trim_end_matches('\n'));
+    restore_moved(&entry_dir.join(QUARANTINE_ITEM), &original)?;
+    Ok(original)
+}
+
+/// Возвращает путь из корзины или карантина на исходное место.
+///
+/// Убирает `.trashinfo` для корзины и каталог записи для карантина.
+pub fn restore_moved(moved_to: &Path, original: &Path) -> Result<()> {
+    if fs::symlink_metadata(original).is_ok() {
+        return Err(RcleanerError::Cleaner(format!(
+            "Restore target already exists: {}",
+            original.display()
+        )));
+    }
+    if let Some(parent) = original.parent() {
+        create_dir_all_nofollow(parent)?;
+    }
+
+    match fs::rename(moved_to, original) {
+        Ok(()) => {}
+        Err(err) if err.raw_os_error() == Some(libc::EXDEV) => {
+            copy_tree(moved_to, original)?;
+            remove_permanently(moved_to)?;
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    let Some(parent) = moved_to.parent() else {
+        return Ok(());
+    };
+    if moved_to
+        .file_name()
+        .is_some_and(|name| name == QUARANTINE_ITEM)
+        && parent.join(QUARANTINE_ORIGIN_FILE).exists()
+    {
+        remove_tree(parent)?;
+    } else if parent.file_name().is_some_and(|name| name == "files")
+        && let (Some(trash_dir), Some(name)) = (parent.parent(), moved_to.file_name())
+    {
+        let info_path = trash_dir
+            .join("info")
+            .join(format!("{}.trashinfo", name.to_string_lossy()));
+        let _ = fs::remove_file(info_path);
+    }
+    Ok(())
+}
+
+/// Окончательно удаляет записи карантина старше `max_age_days` дней.
+///
+/// Возвращает количество удалённых записей.
+pub fn purge_quarantine(quarantine_root: &Path, max_age_days: u64) -> Result<usize> {
+    let max_age = Duration::from_secs(max_age_days.saturating_mul(24 * 60 * 60));
+    let cutoff = SystemTime::now()
+        .checked_sub(max_age)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut purged = 0;
+    for entry in list_quarantine(quarantine_root)? {
+        if entry.quarantined_at > cutoff {
+            continue;
+        }
+        remove_tree(&quarantine_root.join(&entry.id))?;
+        purged += 1;
+    }
+    Ok(purged)
+}
+
+/// Каталог карантина по умолчанию.
+pub fn default_quarantine_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+        return Path::new(&dir).join("rcleaner").join("quarantine");
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home)
+        .join(".local")
+        .join("share")
+        .join("rcleaner")
+        .join("quarantine")
+}
+
+/// Возвращает домашнюю корзину пользователя.
+pub fn home_trash_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("XDG_DATA_HOME") {
+        return Path::new(&dir).join("Trash");
+    }
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home)
+        .join(".local")
+        .join("share")
+        .join("Trash")
+}
+
+/// Выбирает корзину для пути.
+///
+/// Возвращает каталог корзины и `topdir`, если пути в `.trashinfo` должны
+/// записываться относительно корня раздела.
+fn trash_dir_for(path: &Path) -> Result<(PathBuf, Option<PathBuf>)> {
+    let home_trash = home_trash_dir();
+    let path_device = fs::symlink_metadata(path)?.dev();
+    let home_device = home_trash
+        .ancestors()
+        .find_map(|ancestor| fs::metadata(ancestor).ok())
+        .map(|metadata| metadata.dev());
+    if home_device == Some(path_device) {
+        return Ok((home_trash, None));
+    }
+
+    let topdir = mount_for_path(path)
+        .map(|mount| mount.mount_point)
+        .ok_or_else(|| RcleanerError::Cleaner(format!("No mount found for {}", path.display())))?;
+    let uid = unsafe { libc::geteuid() };
+
+    let shared = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared)
+        && metadata.is_dir()
+        && metadata.permissions().mode() & 0o1000 != 0
+    {
+        return Ok((shared.join(uid.to_string()), Some(topdir)));
+    }
+
+    Ok((topdir.join(format!(".Trash-{uid}")), Some(topdir)))
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(path))
+    }
+}
+
+/// Кодирует путь для поля `Path=` (RFC 2396, `/` не кодируется).
+fn encode_trash_path(path: &str) -> String {
+    let mut output = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.!~*'()".contains(&byte) {
+            output.push(byte as char);
+        } else {
+            output.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let mut path = std::env::temp_dir();
+        path.push(format!("rcleaner-{label}-{nanos}-{}", std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_encode_trash_path() {
+        assert_eq!(
+            encode_trash_path("/home/user/My Files/ä.txt"),
+            "/home/user/My%20Files/%C3%A4.txt"
+        );
+    }
+
+    #[test]
+    fn test_quarantine_restore_and_purge() {
+        let root = temp_dir("quarantine");
+        let work = temp_dir("quarantine-src");
+        let file = work.join("cache.bin");
+        fs::write(&file, b"data").unwrap();
+
+        let remover = Remover::new(DeletionMode::Quarantine).with_quarantine_root(root.clone());
+        let moved = remover.remove(&file).unwrap().unwrap();
+        assert!(!file.exists());
+        assert!(moved.exists());
+
+        let entries = list_quarantine(&root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original_path, file);
+
+        assert!(restore_quarantined(&root, "..").is_err());
+        assert!(restore_quarantined(&root, &format!("../{}", entries[0].id)).is_err());
+        restore_quarantined(&root, &entries[0].id).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), b"data");
+
+        remover.remove(&file).unwrap();
+        assert_eq!(purge_quarantine(&root, 7).unwrap(), 0);
+        assert_eq!(purge_quarantine(&root, 0).unwrap(), 1);
+        assert!(list_quarantine(&root).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(&work);
+    }
+}
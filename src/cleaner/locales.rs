@@ -0,0 +1,105 @@
+use crate::cleaner::base::Cleaner;
+use crate::config::Config;
+use crate::error::Result;
+use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
+use crate::privilege;
+use crate::system::locales::{self, LocaleDir, LocaleDirKind};
+use crate::system::mounts::{find_mount, read_mounts};
+use std::path::Path;
+
+pub struct LocalesCleaner;
+
+impl Default for LocalesCleaner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LocalesCleaner {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Cleaner for LocalesCleaner {
+    fn name(&self) -> &str {
+        "Locales Cleaner"
+    }
+
+    fn category(&self) -> CleanupCategory {
+        CleanupCategory::Locales
+    }
+
+    fn scan(&self) -> Result<Vec<CleanupItem>> {
+        let config = Config::load(&Config::default_path()).unwrap_or_default();
+        if !config.locales.enabled {
+            return Ok(Vec::new());
+        }
+        // На Atomic и transactional-update системах `/usr` только для чтения.
+        if find_mount(&read_mounts(), Path::new(locales::TRANSLATION_DIRS[0]))
+            .is_some_and(|mount| mount.options.iter().any(|option| option == "ro"))
+        {
+            log::info!("/usr is read-only, skipping locales cleanup");
+            return Ok(Vec::new());
+        }
+
+        Ok(locales::prunable(&config.locales)
+            .iter()
+            .map(|dir| self.item(dir))
+            .filter(|item| item.size > 0)
+            .collect())
+    }
+
+    fn clean(&self, items: &[CleanupItem], dry_run: bool) -> Result<CleanupResult> {
+        let mut result = CleanupResult::default();
+        let mut paths = Vec::new();
+        let mut pruned = Vec::new();
+
+        for item in items {
+            match item.path.as_ref().filter(|_| self.can_clean(item)) {
+                Some(path) => {
+                    paths.push(path.clone());
+                    pruned.push(item);
+                }
+                None => result.skipped_items += 1,
+            }
+        }
+
+        if !paths.is_empty() {
+            privilege::prune_locales(&paths, dry_run)?;
+            for item in pruned {
+                result.item_done(item);
+                result.freed_bytes += item.size;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl LocalesCleaner {
+    fn item(&self, dir: &LocaleDir) -> CleanupItem {
+        let path = dir.path.to_string_lossy().to_string();
+        let name = match &dir.language {
+            Some(language) => format!("{language} {}", dir.kind.label()),
+            None => format!("All {}", dir.kind.label()),
+        };
+        CleanupItem {
+            id: format!("locale:{path}"),
+            name,
+            path: Some(path.clone()),
+            size: locales::size(dir),
+            description: match dir.kind {
+                LocaleDirKind::Docs => format!("{path} (copyright files are kept)"),
+                _ => path,
+            },
+            category: self.category(),
+            source: CleanupSource::FileSystem,
+            selected: false,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        }
+    }
+}
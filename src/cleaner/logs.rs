@@ -1,9 +1,16 @@
 use crate::cleaner::base::Cleaner;
-use crate::error::Result;
+use crate::cleaner::deletion::Remover;
+use crate::error::{RcleanerError, Result};
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
+use crate::privilege;
+use crate::utils::disk_size::{DiskSize, allocated_size};
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+/// Записи журнала systemd старше этого срока удаляются.
+const JOURNAL_MAX_AGE_DAYS: u32 = 7;
+
 pub struct LogsCleaner;
 
 impl Default for LogsCleaner {
@@ -31,9 +38,8 @@ impl Cleaner for LogsCleaner {
         let mut items = Vec::new();
 
         let log_path = PathBuf::from("/var/log");
-        if let Ok(size) = calculate_directory_size(&log_path)
-            && size > 0
-        {
+        let size = allocated_size(&log_path);
+        if size > 0 {
             items.push(CleanupItem {
                 id: log_path.to_string_lossy().to_string(),
                 name: "System logs".to_string(),
@@ -46,6 +52,7 @@ impl Cleaner for LogsCleaner {
                 can_clean: true,
                 blocked_reason: None,
                 dependencies: Vec::new(),
+                verdicts: Vec::new(),
             });
         }
 
@@ -62,6 +69,7 @@ impl Cleaner for LogsCleaner {
                 can_clean: true,
                 blocked_reason: None,
                 dependencies: Vec::new(),
+                verdicts: Vec::new(),
             });
         }
 
@@ -70,6 +78,7 @@ impl Cleaner for LogsCleaner {
 
     fn clean(&self, items: &[CleanupItem], dry_run: bool) -> Result<CleanupResult> {
         let mut result = CleanupResult::default();
+        let remover = Remover::from_config();
 
         for item in items {
             if !self.can_clean(item) {
@@ -80,21 +89,14 @@ impl Cleaner for LogsCleaner {
             if item.id == "systemd-journal" {
                 if dry_run {
                     log::info!("[DRY RUN] Would vacuum systemd journal");
-                    result.cleaned_items += 1;
+                    result.item_done(item);
                     result.freed_bytes += item.size;
                 } else {
-                    let output = std::process::Command::new("journalctl")
-                        .args(["--vacuum-time=7d"])
-                        .output();
-                    match output {
-                        Ok(output) if output.status.success() => {
-                            result.cleaned_items += 1;
+                    match privilege::vacuum_journal(JOURNAL_MAX_AGE_DAYS) {
+                        Ok(()) => {
+                            result.item_done(item);
                             result.freed_bytes += item.size;
                         }
-                        Ok(output) => {
-                            let stderr = String::from_utf8_lossy(&output.stderr);
-                            result.errors.push(stderr.trim().to_string());
-                        }
                         Err(err) => {
                             result.errors.push(err.to_string());
                         }
@@ -110,14 +112,14 @@ impl Cleaner for LogsCleaner {
             let path = Path::new(path);
             if dry_run {
                 log::info!("[DRY RUN] Would clean: {}", path.display());
-                result.cleaned_items += 1;
+                result.item_done(item);
                 result.freed_bytes += item.size;
                 continue;
             }
 
-            match remove_rotated_logs(path) {
+            match remove_rotated_logs(path, &remover) {
                 Ok(removed_bytes) => {
-                    result.cleaned_items += 1;
+                    result.item_done(item);
                     result.freed_bytes += removed_bytes;
                 }
                 Err(err) => {
@@ -130,22 +132,6 @@ impl Cleaner for LogsCleaner {
     }
 }
 
-fn calculate_directory_size(path: &Path) -> Result<u64> {
-    if !path.exists() {
-        return Ok(0);
-    }
-
-    let mut total_size = 0u64;
-    for entry in WalkDir::new(path).into_iter().flatten() {
-        if let Ok(metadata) = entry.metadata()
-            && metadata.is_file()
-        {
-            total_size += metadata.len();
-        }
-    }
-    Ok(total_size)
-}
-
 fn journal_usage() -> Option<(u64, String)> {
     let output = std::process::Command::new("journalctl")
         .args(["--disk-usage"])
@@ -193,7 +179,7 @@ fn parse_size_to_bytes(value: &str) -> Option<u64> {
     Some((number * multiplier) as u64)
 }
 
-fn remove_rotated_logs(path: &Path) -> Result<u64> {
+fn remove_rotated_logs(path: &Path, remover: &Remover) -> Result<u64> {
     if !path.exists() {
         return Ok(0);
     }
@@ -201,7 +187,11 @@ fn remove_rotated_logs(path: &Path) -> Result<u64> {
     let mut removed_bytes = 0u64;
     let extensions = ["gz", "xz", "bz2", "zip", "old"];
 
-    for entry in WalkDir::new(path).into_iter().flatten() {
+    for entry in WalkDir::new(path)
+        .same_file_system(true)
+        .into_iter()
+        .flatten()
+    {
         let entry_path = entry.path();
         if !entry_path.is_file() {
             continue;
@@ -224,10 +214,21 @@ fn remove_rotated_logs(path: &Path) -> Result<u64> {
             || file_name.ends_with(".5");
 
         if is_rotated {
-            if let Ok(metadata) = entry.metadata() {
-                removed_bytes += metadata.len();
+            let size = entry
+                .metadata()
+                .map(|metadata| DiskSize::of(&metadata).allocated)
+                .unwrap_or(0);
+            let removed = match remover.remove(entry_path) {
+                Ok(_) => true,
+                // Системные логи принадлежат root: удаляем через помощника.
+                Err(RcleanerError::Io(err)) if err.kind() == ErrorKind::PermissionDenied => {
+                    privilege::remove_path(entry_path).is_ok()
+                }
+                Err(_) => false,
+            };
+            if removed {
+                removed_bytes += size;
             }
-            let _ = std::fs::remove_file(entry_path);
         }
     }
 
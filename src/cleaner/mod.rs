@@ -7,20 +7,29 @@
 //! - [`logs`] - журналы и логи
 //! - [`old_packages`] - старые пакеты
 //! - [`old_kernels`] - старые ядра
+//! - [`locales`] - переводы и документация (включается в конфигурации)
+//!
+//! Способ удаления (безвозвратно, корзина, карантин) задаётся в [`deletion`].
 
 pub mod applications;
 pub mod base;
 pub mod cache;
+pub mod deletion;
+pub mod locales;
 pub mod logs;
 pub mod old_kernels;
 pub mod old_packages;
 pub mod temp_files;
 
+use crate::backup::journal::{self, CleanupJournal, JournalRecord, JournalSession};
 use crate::cleaner::base::Cleaner;
 use crate::config::Config;
 use crate::error::Result;
-use crate::models::{CleanupCategory, CleanupItem, CleanupResult};
+use crate::models::{
+    CleanupCategory, CleanupItem, CleanupResult, Verdict, VerdictCheck, VerdictOutcome,
+};
 use crate::safety::SafetyChecker;
+use crate::system::disk_usage::{self, FreeSpaceSnapshot};
 
 /// Сканирует все категории и возвращает список элементов для очистки.
 ///
@@ -33,6 +42,7 @@ pub fn scan_all() -> Result<Vec<CleanupItem>> {
         Box::new(logs::LogsCleaner::new()),
         Box::new(old_packages::OldPackagesCleaner::new()),
         Box::new(old_kernels::OldKernelsCleaner::new()),
+        Box::new(locales::LocalesCleaner::new()),
     ];
 
     let mut items = Vec::new();
@@ -45,6 +55,12 @@ pub fn scan_all() -> Result<Vec<CleanupItem>> {
         }
     }
 
+    apply_safety(&mut items);
+    Ok(items)
+}
+
+/// Применяет правила безопасности; ошибка проверки блокирует элемент.
+fn apply_safety(items: &mut [CleanupItem]) {
     let config = match Config::load(&Config::default_path()) {
         Ok(config) => config,
         Err(err) => {
@@ -53,17 +69,21 @@ pub fn scan_all() -> Result<Vec<CleanupItem>> {
         }
     };
     let checker = SafetyChecker::new(config);
+    let removing = checker.removal_sets(items);
     for item in items.iter_mut() {
-        if let Err(err) = checker.apply_to_item(item) {
+        if let Err(err) = checker.apply_with_removal(item, &removing) {
             log::warn!("Safety check failed for {}: {}", item.name, err);
             item.can_clean = false;
+            item.verdicts.push(Verdict::new(
+                VerdictCheck::Error,
+                VerdictOutcome::Block,
+                format!("Safety check failed: {}", err),
+            ));
             if item.blocked_reason.is_none() {
                 item.blocked_reason = Some(format!("Safety check failed: {}", err));
             }
         }
     }
-
-    Ok(items)
 }
 
 /// Очищает выбранные элементы.
@@ -78,6 +98,9 @@ pub fn clean_selected(items: &[CleanupItem], dry_run: bool) -> Result<CleanupRes
 
 /// Очищает выбранные элементы с отслеживанием прогресса.
 ///
+/// Настоящая очистка ведёт журнал (см. [`crate::backup::journal`]), по
+/// которому прерванную сессию можно завершить или откатить.
+///
 /// # Arguments
 ///
 /// * `items` - элементы для очистки
@@ -86,11 +109,93 @@ pub fn clean_selected(items: &[CleanupItem], dry_run: bool) -> Result<CleanupRes
 pub fn clean_selected_with_progress<F>(
     items: &[CleanupItem],
     dry_run: bool,
+    on_progress: F,
+) -> Result<CleanupResult>
+where
+    F: FnMut(f64, &str),
+{
+    run_cleanup(items, dry_run, false, on_progress)
+}
+
+/// Завершает прерванную сессию очистки.
+///
+/// Обрабатывает только оставшиеся элементы, заново проверив их правилами
+/// безопасности: с момента прерывания система могла измениться. Записи
+/// продолжают журнал прерванной сессии, поэтому её бэкапы и перемещённые
+/// пути по-прежнему можно откатить.
+pub fn resume_session<F>(session: &JournalSession, on_progress: F) -> Result<CleanupResult>
+where
+    F: FnMut(f64, &str),
+{
+    let mut items = session.remaining_items();
+    apply_safety(&mut items);
+    run_cleanup(&items, false, true, on_progress)
+}
+
+fn run_cleanup<F>(
+    items: &[CleanupItem],
+    dry_run: bool,
+    resume: bool,
     mut on_progress: F,
 ) -> Result<CleanupResult>
 where
     F: FnMut(f64, &str),
 {
+    let planned: Vec<CleanupItem> = items.iter().filter(|item| item.selected).cloned().collect();
+    let free_space = (!dry_run).then(|| FreeSpaceSnapshot::capture(&planned));
+    if !dry_run {
+        let path = journal::default_journal_path();
+        let cleanup_journal = if resume {
+            CleanupJournal::reopen(&path)?
+        } else {
+            CleanupJournal::begin(&path, &planned)?
+        };
+        journal::activate(cleanup_journal);
+    }
+
+    let outcome = clean_categories(items, dry_run, &mut on_progress);
+    let category_failed = matches!(outcome, Ok((_, true)));
+    let mut result = outcome.map(|(result, _)| result);
+    if let Ok(result) = &mut result {
+        result.freed_by_mount = match free_space {
+            Some(snapshot) => snapshot.freed_since(),
+            None => disk_usage::estimate_by_mount(&planned, &disk_usage::list_filesystems())
+                .into_iter()
+                .map(|estimate| {
+                    (
+                        estimate
+                            .filesystem
+                            .mount_point
+                            .to_string_lossy()
+                            .to_string(),
+                        estimate.bytes,
+                    )
+                })
+                .collect(),
+        };
+    }
+
+    // Незавершённый журнал позволяет дочистить или откатить сессию при
+    // следующем запуске.
+    if let Some(cleanup_journal) = journal::deactivate() {
+        if category_failed {
+            log::warn!("A cleanup category failed, leaving the journal open for recovery");
+        } else if let Err(err) = cleanup_journal.commit() {
+            log::warn!("Failed to commit cleanup journal: {}", err);
+        }
+    }
+    result
+}
+
+/// Очищает категории по очереди.
+///
+/// Возвращает общий результат и признак того, что очистка какой-то
+/// категории завершилась ошибкой.
+fn clean_categories(
+    items: &[CleanupItem],
+    dry_run: bool,
+    on_progress: &mut dyn FnMut(f64, &str),
+) -> Result<(CleanupResult, bool)> {
     let mut cleaners: Vec<(CleanupCategory, Box<dyn Cleaner>)> = vec![
         (CleanupCategory::Cache, Box::new(cache::CacheCleaner::new())),
         (
@@ -110,9 +215,18 @@ where
             CleanupCategory::OldKernels,
             Box::new(old_kernels::OldKernelsCleaner::new()),
         ),
+        (
+            CleanupCategory::Locales,
+            Box::new(locales::LocalesCleaner::new()),
+        ),
     ];
 
     let mut total = CleanupResult::default();
+    let mut category_failed = false;
+
+    if !dry_run {
+        purge_expired_quarantine();
+    }
 
     let mut steps = 0usize;
     for (category, _) in cleaners.iter() {
@@ -141,12 +255,23 @@ where
 
         match cleaner.clean(&selected, dry_run) {
             Ok(result) => {
+                if !dry_run {
+                    for item_id in &result.done_items {
+                        journal::record(JournalRecord::ItemDone {
+                            item_id: item_id.clone(),
+                        });
+                    }
+                }
                 total.cleaned_items += result.cleaned_items;
                 total.freed_bytes += result.freed_bytes;
+                total.held_by_snapshots += result.held_by_snapshots;
+                total.moved_bytes += result.moved_bytes;
                 total.skipped_items += result.skipped_items;
                 total.errors.extend(result.errors);
+                total.done_items.extend(result.done_items);
             }
             Err(err) => {
+                category_failed = true;
                 total.errors.push(format!("{}: {}", cleaner.name(), err));
             }
         }
@@ -155,5 +280,16 @@ where
     }
 
     on_progress(1.0, "Done");
-    Ok(total)
+    Ok((total, category_failed))
+}
+
+/// Удаляет записи карантина, срок хранения которых истёк.
+fn purge_expired_quarantine() {
+    let config = Config::load(&Config::default_path()).unwrap_or_default();
+    let days = config.current_profile().quarantine_days;
+    match deletion::purge_quarantine(&deletion::default_quarantine_dir(), days) {
+        Ok(0) => {}
+        Ok(purged) => log::info!("Purged {} expired quarantine entries", purged),
+        Err(err) => log::warn!("Failed to purge quarantine: {}", err),
+    }
 }
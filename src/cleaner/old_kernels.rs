@@ -2,8 +2,13 @@ use crate::cleaner::base::Cleaner;
 use crate::config::Config;
 use crate::error::Result;
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
-use crate::system::{apt, rpm};
-use std::collections::{HashMap, HashSet};
+use crate::privilege;
+use crate::system::bootloader;
+use crate::system::detection::command_exists;
+use crate::system::kernels::{self, KernelLeftover, LeftoverKind};
+use crate::system::{pacman, xbps, zypper};
+use crate::utils::disk_size::allocated_size;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::Path;
 use std::time::UNIX_EPOCH;
 
@@ -34,18 +39,23 @@ impl Cleaner for OldKernelsCleaner {
         let mut items = Vec::new();
 
         let keep_recent = keep_recent_kernels();
-        let current_kernel = current_kernel_version();
+        let current_kernel = kernels::running_version();
+        let pinned = pinned_kernels(&current_kernel);
 
-        items.extend(scan_rpm_kernels(&current_kernel, keep_recent)?);
-        items.extend(scan_apt_kernels(&current_kernel, keep_recent)?);
+        items.extend(scan_rpm_kernels(&current_kernel, keep_recent, &pinned)?);
+        items.extend(scan_apt_kernels(&current_kernel, keep_recent, &pinned)?);
+        items.extend(scan_pacman_kernels()?);
+        items.extend(scan_zypper_kernels(&current_kernel)?);
+        items.extend(scan_void_kernels(&current_kernel, keep_recent, &pinned)?);
+        items.extend(scan_kernel_leftovers(&current_kernel, keep_recent, &pinned));
+        items.extend(scan_dkms_leftovers(&current_kernel));
 
         Ok(items)
     }
 
     fn clean(&self, items: &[CleanupItem], dry_run: bool) -> Result<CleanupResult> {
         let mut result = CleanupResult::default();
-        let mut rpm_packages = Vec::new();
-        let mut apt_packages = Vec::new();
+        let mut packages: BTreeMap<&str, Vec<&CleanupItem>> = BTreeMap::new();
 
         for item in items {
             if !self.can_clean(item) {
@@ -55,36 +65,33 @@ impl Cleaner for OldKernelsCleaner {
 
             match &item.source {
                 CleanupSource::PackageManager(manager) => match manager.as_str() {
-                    "rpm" => rpm_packages.push(item.name.clone()),
-                    "apt" => apt_packages.push(item.name.clone()),
+                    manager @ ("rpm" | "dnf" | "apt" | "pacman" | "zypper" | "vkpurge"
+                    | "kernel-files" | "dkms") => packages.entry(manager).or_default().push(item),
                     _ => result.skipped_items += 1,
                 },
                 _ => result.skipped_items += 1,
             }
         }
 
-        if !rpm_packages.is_empty() {
-            rpm::remove_packages(&rpm_packages, dry_run)?;
-            result.cleaned_items += rpm_packages.len();
-        }
-
-        if !apt_packages.is_empty() {
-            apt::remove_packages(&apt_packages, dry_run)?;
-            result.cleaned_items += apt_packages.len();
+        for (manager, items) in packages {
+            let names: Vec<String> = items.iter().map(|item| item.name.clone()).collect();
+            privilege::remove_kernels(manager, &names, dry_run)?;
+            items.iter().for_each(|item| result.item_done(item));
         }
 
         Ok(result)
     }
 }
 
-fn current_kernel_version() -> String {
-    let output = std::process::Command::new("uname").arg("-r").output();
-    if let Ok(output) = output
-        && output.status.success()
-    {
-        return String::from_utf8_lossy(&output.stdout).trim().to_string();
-    }
-    String::new()
+/// Версии ядер, которые загрузит загрузчик или к которым вернётся пользователь.
+fn pinned_kernels(current: &str) -> Vec<String> {
+    bootloader::protected_kernels(current)
+        .into_iter()
+        .map(|pin| {
+            log::info!("Keeping kernel {}: {}", pin.version, pin.reason);
+            pin.version
+        })
+        .collect()
 }
 
 fn keep_recent_kernels() -> usize {
@@ -93,9 +100,23 @@ fn keep_recent_kernels() -> usize {
         .unwrap_or(2)
 }
 
-fn scan_rpm_kernels(current: &str, keep_recent: usize) -> Result<Vec<CleanupItem>> {
+/// Ядра RPM; на системах с dnf они удаляются через dnf, чтобы вместе с
+/// версией ушли и зависящие от неё подпакеты.
+fn scan_rpm_kernels(
+    current: &str,
+    keep_recent: usize,
+    pinned: &[String],
+) -> Result<Vec<CleanupItem>> {
     let output = std::process::Command::new("rpm")
-        .args(["-q", "kernel", "kernel-core", "kernel-modules"])
+        .args([
+            "-q",
+            "kernel",
+            "kernel-core",
+            "kernel-modules",
+            "kernel-modules-core",
+            "kernel-modules-extra",
+            "kernel-devel",
+        ])
         .output();
 
     let Ok(output) = output else {
@@ -104,16 +125,22 @@ fn scan_rpm_kernels(current: &str, keep_recent: usize) -> Result<Vec<CleanupItem
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     let packages = parse_rpm_kernel_packages(&stdout);
+    let manager = if command_exists("dnf") { "dnf" } else { "rpm" };
     Ok(build_kernel_items(
         packages,
         current,
         keep_recent,
-        "rpm",
+        pinned,
+        manager,
         KernelPrefixes::Rpm,
     ))
 }
 
-fn scan_apt_kernels(current: &str, keep_recent: usize) -> Result<Vec<CleanupItem>> {
+fn scan_apt_kernels(
+    current: &str,
+    keep_recent: usize,
+    pinned: &[String],
+) -> Result<Vec<CleanupItem>> {
     let output = std::process::Command::new("dpkg")
         .args(["-l", "linux-image-*"])
         .output();
@@ -132,11 +159,152 @@ fn scan_apt_kernels(current: &str, keep_recent: usize) -> Result<Vec<CleanupItem
         packages,
         current,
         keep_recent,
+        pinned,
         "apt",
         KernelPrefixes::Apt,
     ))
 }
 
+/// Ядра Arch: у каждого варианта (`linux`, `linux-lts`, `linux-zen`) одна
+/// версия, поэтому хранятся все варианты, а предлагаются только заголовки
+/// вариантов, чьё ядро уже удалено.
+fn scan_pacman_kernels() -> Result<Vec<CleanupItem>> {
+    if !command_exists("pacman") {
+        return Ok(Vec::new());
+    }
+    let Ok(installed) = pacman::list_installed() else {
+        return Ok(Vec::new());
+    };
+    Ok(orphaned_headers(&installed)
+        .into_iter()
+        .map(|pkg| {
+            let mut item = kernel_item(pkg, "pacman");
+            item.description = "Kernel headers without their kernel (pacman)".to_string();
+            item
+        })
+        .collect())
+}
+
+fn orphaned_headers(installed: &[String]) -> Vec<String> {
+    let installed: HashSet<&str> = installed.iter().map(String::as_str).collect();
+    let mut headers: Vec<String> = installed
+        .iter()
+        .filter_map(|pkg| {
+            let flavor = pkg.strip_suffix("-headers")?;
+            (flavor.starts_with("linux") && !installed.contains(flavor)).then(|| pkg.to_string())
+        })
+        .collect();
+    headers.sort();
+    headers
+}
+
+/// Ядра openSUSE: сколько версий хранить, решает `multiversion.kernels`
+/// из zypp.conf, а не профиль.
+fn scan_zypper_kernels(current: &str) -> Result<Vec<CleanupItem>> {
+    let Ok(packages) = zypper::kernel_packages_to_remove(current) else {
+        return Ok(Vec::new());
+    };
+    Ok(packages
+        .into_iter()
+        .map(|pkg| kernel_item(pkg, "zypper"))
+        .collect())
+}
+
+/// Ядра Void: после обновления пакета `linuxX.Y` файлы прежних версий
+/// остаются в `/boot` и `/usr/lib/modules`, их удаляет `vkpurge`.
+fn scan_void_kernels(
+    current: &str,
+    keep_recent: usize,
+    pinned: &[String],
+) -> Result<Vec<CleanupItem>> {
+    let Ok(versions) = xbps::removable_kernels() else {
+        return Ok(Vec::new());
+    };
+    let mut version_times: Vec<(String, i64)> = versions
+        .iter()
+        .map(|version| (version.clone(), kernel_mtime_seconds(version)))
+        .collect();
+    // vkpurge не показывает загруженное ядро, но оно входит в число сохраняемых.
+    if !current.is_empty() {
+        version_times.push((current.to_string(), kernel_mtime_seconds(current)));
+    }
+    let keep_versions =
+        select_versions_to_keep_per_flavor(version_times, current, keep_recent, pinned);
+    Ok(versions
+        .into_iter()
+        .filter(|version| !keep_versions.contains(version))
+        .map(|version| kernel_item(version, "vkpurge"))
+        .collect())
+}
+
+/// Ядра и их файлы без пакета. Собранные вручную ядра хранятся как
+/// пакетные (`keep_recent` на вариант), остатки удалённых ядер
+/// предлагаются всегда.
+fn scan_kernel_leftovers(current: &str, keep_recent: usize, pinned: &[String]) -> Vec<CleanupItem> {
+    // Старые ядра Void после обновления тоже без пакета, их предлагает vkpurge.
+    let void_versions: HashSet<String> = xbps::removable_kernels()
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    let leftovers: Vec<KernelLeftover> = kernels::leftovers(current)
+        .into_iter()
+        .filter(|leftover| !void_versions.contains(&leftover.version))
+        .collect();
+
+    let custom_times = leftovers
+        .iter()
+        .filter(|leftover| leftover.kind == LeftoverKind::CustomKernel)
+        .map(|leftover| {
+            (
+                leftover.version.clone(),
+                kernel_mtime_seconds(&leftover.version),
+            )
+        })
+        .collect();
+    let keep_versions =
+        select_versions_to_keep_per_flavor(custom_times, current, keep_recent, pinned);
+
+    leftovers
+        .into_iter()
+        .filter(|leftover| !keep_versions.contains(&leftover.version))
+        .map(|leftover| {
+            let paths: Vec<String> = leftover
+                .paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect();
+            let kind = match leftover.kind {
+                LeftoverKind::CustomKernel => "Custom kernel without package",
+                LeftoverKind::ModuleTree => "Module tree without kernel package",
+                LeftoverKind::BootFiles => "Boot files of a removed kernel",
+            };
+            let mut item = kernel_item(leftover.version, "kernel-files");
+            item.size = leftover.size;
+            item.description = format!("{kind}: {}", paths.join(", "));
+            item
+        })
+        .collect()
+}
+
+/// Сборки DKMS для ядер, которых больше нет.
+fn scan_dkms_leftovers(current: &str) -> Vec<CleanupItem> {
+    if !command_exists("dkms") {
+        return Vec::new();
+    }
+    kernels::dkms_leftovers(current)
+        .into_iter()
+        .map(|build| {
+            let mut item = kernel_item(build.id(), "dkms");
+            item.size = allocated_size(&build.path);
+            item.description = format!(
+                "DKMS build of {} {} for removed kernel {}",
+                build.module, build.module_version, build.kernel
+            );
+            item
+        })
+        .collect()
+}
+
 enum KernelPrefixes {
     Rpm,
     Apt,
@@ -146,11 +314,19 @@ fn build_kernel_items(
     packages: Vec<String>,
     current: &str,
     keep_recent: usize,
+    pinned: &[String],
     manager: &str,
     prefixes: KernelPrefixes,
 ) -> Vec<CleanupItem> {
     let prefix_list = match prefixes {
-        KernelPrefixes::Rpm => vec!["kernel-core-", "kernel-modules-", "kernel-"],
+        KernelPrefixes::Rpm => vec![
+            "kernel-modules-extra-",
+            "kernel-modules-core-",
+            "kernel-core-",
+            "kernel-modules-",
+            "kernel-devel-",
+            "kernel-",
+        ],
         KernelPrefixes::Apt => vec!["linux-image-unsigned-", "linux-image-"],
     };
 
@@ -164,7 +340,8 @@ fn build_kernel_items(
         version_times.push((version.clone(), kernel_mtime_seconds(version)));
     }
 
-    let keep_versions = select_versions_to_keep(version_times, current, keep_recent);
+    let keep_versions =
+        select_versions_to_keep_per_flavor(version_times, current, keep_recent, pinned);
     let mut to_remove = Vec::new();
     for (version, pkgs) in versions {
         if keep_versions.contains(&version) {
@@ -177,22 +354,27 @@ fn build_kernel_items(
 
     to_remove
         .into_iter()
-        .map(|pkg| CleanupItem {
-            id: format!("{manager}:{pkg}"),
-            name: pkg.clone(),
-            path: None,
-            size: 0,
-            description: format!("Old kernel package ({manager})"),
-            category: CleanupCategory::OldKernels,
-            source: CleanupSource::PackageManager(manager.to_string()),
-            selected: false,
-            can_clean: true,
-            blocked_reason: None,
-            dependencies: Vec::new(),
-        })
+        .map(|pkg| kernel_item(pkg, manager))
         .collect()
 }
 
+fn kernel_item(pkg: String, manager: &str) -> CleanupItem {
+    CleanupItem {
+        id: format!("{manager}:{pkg}"),
+        name: pkg,
+        path: None,
+        size: 0,
+        description: format!("Old kernel package ({manager})"),
+        category: CleanupCategory::OldKernels,
+        source: CleanupSource::PackageManager(manager.to_string()),
+        selected: false,
+        can_clean: true,
+        blocked_reason: None,
+        dependencies: Vec::new(),
+        verdicts: Vec::new(),
+    }
+}
+
 fn parse_rpm_kernel_packages(output: &str) -> Vec<String> {
     output
         .lines()
@@ -225,10 +407,10 @@ fn group_by_version(packages: &[String], prefixes: &[&str]) -> HashMap<String, V
 
 fn extract_kernel_version(package: &str, prefixes: &[&str]) -> Option<String> {
     for prefix in prefixes {
-        if let Some(rest) = package.strip_prefix(prefix) {
-            if is_version_like(rest) {
-                return Some(rest.to_string());
-            }
+        if let Some(rest) = package.strip_prefix(prefix)
+            && is_version_like(rest)
+        {
+            return Some(rest.to_string());
         }
     }
     None
@@ -255,10 +437,13 @@ fn kernel_mtime_seconds(version: &str) -> i64 {
     }
 }
 
+/// Оставляет загруженное ядро, ядра, нужные загрузчику (`pinned`), и
+/// `keep_recent` самых новых.
 fn select_versions_to_keep(
     mut versions: Vec<(String, i64)>,
     current: &str,
     keep_recent: usize,
+    pinned: &[String],
 ) -> HashSet<String> {
     versions.sort_by(|(a_version, a_time), (b_version, b_time)| {
         b_time.cmp(a_time).then_with(|| b_version.cmp(a_version))
@@ -269,6 +454,8 @@ fn select_versions_to_keep(
         keep.insert(current.to_string());
     }
 
+    keep.extend(pinned.iter().cloned());
+
     for (version, _) in versions.into_iter().take(keep_recent) {
         keep.insert(version);
     }
@@ -276,9 +463,33 @@ fn select_versions_to_keep(
     keep
 }
 
+/// [`select_versions_to_keep`] отдельно для каждого варианта ядра: новое
+/// `-rt` ядро не вытесняет последние обычные.
+fn select_versions_to_keep_per_flavor(
+    versions: Vec<(String, i64)>,
+    current: &str,
+    keep_recent: usize,
+    pinned: &[String],
+) -> HashSet<String> {
+    let mut flavors: BTreeMap<String, Vec<(String, i64)>> = BTreeMap::new();
+    for (version, time) in versions {
+        flavors
+            .entry(kernels::kernel_flavor(&version))
+            .or_default()
+            .push((version, time));
+    }
+    flavors
+        .into_values()
+        .flat_map(|versions| select_versions_to_keep(versions, current, keep_recent, pinned))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{extract_kernel_version, select_versions_to_keep};
+    use super::{
+        extract_kernel_version, orphaned_headers, select_versions_to_keep,
+        select_versions_to_keep_per_flavor,
+    };
 
     #[test]
     fn test_extract_kernel_version_skips_meta() {
@@ -298,9 +509,35 @@ mod tests {
             ("6.1.0-13-amd64".to_string(), 20),
             ("6.1.0-14-amd64".to_string(), 30),
         ];
-        let keep = select_versions_to_keep(versions, "6.1.0-12-amd64", 1);
+        let keep = select_versions_to_keep(versions, "6.1.0-12-amd64", 1, &[]);
         assert!(keep.contains("6.1.0-14-amd64"));
         assert!(keep.contains("6.1.0-12-amd64"));
         assert!(!keep.contains("6.1.0-13-amd64"));
     }
+
+    #[test]
+    fn test_kernels_are_kept_per_flavor() {
+        let versions = vec![
+            ("6.1.0-12-amd64".to_string(), 10),
+            ("6.1.0-13-amd64".to_string(), 20),
+            ("6.1.0-12-rt-amd64".to_string(), 15),
+            ("6.1.0-13-rt-amd64".to_string(), 30),
+        ];
+        let keep = select_versions_to_keep_per_flavor(
+            versions,
+            "6.1.0-12-amd64",
+            1,
+            &["6.1.0-12-rt-amd64".to_string()],
+        );
+        assert!(keep.contains("6.1.0-13-amd64"));
+        assert!(keep.contains("6.1.0-13-rt-amd64"));
+        assert!(keep.contains("6.1.0-12-amd64"));
+        assert!(keep.contains("6.1.0-12-rt-amd64"));
+
+        let installed: Vec<String> = ["linux", "linux-headers", "linux-zen-headers", "linux-lts"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(orphaned_headers(&installed), vec!["linux-zen-headers"]);
+    }
 }
@@ -1,8 +1,17 @@
-use crate::backup::BackupManager;
+use crate::backup::create_backup_for_items;
 use crate::cleaner::base::Cleaner;
+use crate::cleaner::deletion::Remover;
+use crate::config::Config;
 use crate::error::Result;
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
-use crate::system::{apt, dnf, pacman, rpm};
+use crate::privilege;
+use crate::system::package_manager::simulate_removal;
+use crate::system::store::{self, Generation, StoreManager};
+use crate::system::{apk, xbps, zypper};
+use crate::utils::disk_size::SizeCounter;
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
 
 pub struct OldPackagesCleaner;
 
@@ -33,6 +42,10 @@ impl Cleaner for OldPackagesCleaner {
         items.extend(scan_apt_autoremove()?);
         items.extend(scan_dnf_unneeded()?);
         items.extend(scan_pacman_orphans()?);
+        items.extend(scan_zypper_unneeded()?);
+        items.extend(scan_apk_orphans()?);
+        items.extend(scan_xbps_orphans()?);
+        items.extend(scan_store_items());
 
         Ok(items)
     }
@@ -43,10 +56,13 @@ impl Cleaner for OldPackagesCleaner {
         let mut dnf_packages = Vec::new();
         let mut pacman_packages = Vec::new();
         let mut rpm_packages = Vec::new();
+        let mut zypper_packages = Vec::new();
+        let mut apk_packages = Vec::new();
+        let mut xbps_packages = Vec::new();
+        let mut store_items: Vec<&CleanupItem> = Vec::new();
 
         if !dry_run {
-            let manager = BackupManager::from_config()?;
-            let _backup = manager.create_backup(items)?;
+            let _backup = create_backup_for_items(items)?;
         }
 
         for item in items {
@@ -57,34 +73,122 @@ impl Cleaner for OldPackagesCleaner {
 
             match &item.source {
                 CleanupSource::PackageManager(manager) => match manager.as_str() {
-                    "apt" => apt_packages.push(item.name.clone()),
-                    "dnf" => dnf_packages.push(item.name.clone()),
-                    "pacman" => pacman_packages.push(item.name.clone()),
-                    "rpm" => rpm_packages.push(item.name.clone()),
+                    "apt" => apt_packages.push(item),
+                    "dnf" => dnf_packages.push(item),
+                    "pacman" => pacman_packages.push(item),
+                    "rpm" => rpm_packages.push(item),
+                    "zypper" => zypper_packages.push(item),
+                    "apk" => apk_packages.push(item),
+                    "xbps" => xbps_packages.push(item),
+                    "nix" | "guix" => store_items.push(item),
                     _ => result.skipped_items += 1,
                 },
                 _ => result.skipped_items += 1,
             }
         }
 
-        if !apt_packages.is_empty() {
-            apt::remove_packages(&apt_packages, dry_run)?;
-            result.cleaned_items += apt_packages.len();
+        for (manager, packages) in [
+            ("apt", apt_packages),
+            ("dnf", dnf_packages),
+            ("pacman", pacman_packages),
+            ("rpm", rpm_packages),
+            ("zypper", zypper_packages),
+            ("apk", apk_packages),
+            ("xbps", xbps_packages),
+        ] {
+            if packages.is_empty() {
+                continue;
+            }
+            let names: Vec<String> = packages.iter().map(|item| item.name.clone()).collect();
+            if !dry_run && let Some(reason) = blocked_transaction(manager, &names) {
+                result.skipped_items += packages.len();
+                result.errors.push(reason);
+                continue;
+            }
+            privilege::remove_packages(manager, &names, dry_run)?;
+            packages.iter().for_each(|item| result.item_done(item));
         }
-        if !dnf_packages.is_empty() {
-            dnf::remove_packages(&dnf_packages, dry_run)?;
-            result.cleaned_items += dnf_packages.len();
+
+        clean_store_items(&store_items, dry_run, &mut result);
+
+        Ok(result)
+    }
+}
+
+/// Удаляет старые поколения и ссылки `result`, затем собирает мусор в
+/// каждом затронутом хранилище.
+fn clean_store_items(items: &[&CleanupItem], dry_run: bool, result: &mut CleanupResult) {
+    let mut generations: BTreeMap<(&str, PathBuf), Vec<(u32, &CleanupItem)>> = BTreeMap::new();
+    let mut collect: BTreeSet<&str> = BTreeSet::new();
+    let remover = Remover::from_config();
+
+    for item in items {
+        let CleanupSource::PackageManager(manager) = &item.source else {
+            continue;
+        };
+        if let Some((_, profile, number)) = store::parse_generation_id(&item.id) {
+            generations
+                .entry((manager.as_str(), profile))
+                .or_default()
+                .push((number, *item));
+        } else if let Some(path) = &item.path {
+            if dry_run {
+                log::info!("[DRY RUN] Would remove: {}", path);
+            } else if let Err(err) = remover.remove(Path::new(path)) {
+                result.errors.push(format!("{}: {}", path, err));
+                continue;
+            }
+            result.item_done(item);
+        } else {
+            result.item_done(item);
+            result.freed_bytes += item.size;
         }
-        if !pacman_packages.is_empty() {
-            pacman::remove_packages(&pacman_packages, dry_run)?;
-            result.cleaned_items += pacman_packages.len();
+        collect.insert(manager.as_str());
+    }
+
+    for ((name, profile), entries) in generations {
+        let Some(manager) = StoreManager::from_name(name) else {
+            continue;
+        };
+        let numbers: Vec<u32> = entries.iter().map(|(number, _)| *number).collect();
+        match privilege::delete_generations(manager, &profile, &numbers, dry_run) {
+            Ok(()) => entries.iter().for_each(|(_, item)| result.item_done(item)),
+            Err(err) => {
+                result.skipped_items += numbers.len();
+                result
+                    .errors
+                    .push(format!("{}: {}", profile.display(), err));
+            }
         }
-        if !rpm_packages.is_empty() {
-            rpm::remove_packages(&rpm_packages, dry_run)?;
-            result.cleaned_items += rpm_packages.len();
+    }
+
+    // Поколения и ссылки только освобождают пакеты, место возвращает сборщик мусора.
+    for name in collect {
+        if let Some(manager) = StoreManager::from_name(name)
+            && let Err(err) = manager.collect_garbage(dry_run)
+        {
+            result
+                .errors
+                .push(format!("{name} garbage collection: {err}"));
         }
+    }
+}
 
-        Ok(result)
+/// Симулирует транзакцию и возвращает причину отказа, если она задевает
+/// удержанные или защищённые пакеты.
+///
+/// Ошибка самой симуляции не мешает удалению: для неё может не хватать прав.
+fn blocked_transaction(manager: &str, packages: &[String]) -> Option<String> {
+    match simulate_removal(manager, packages) {
+        Ok(plan) if plan.is_blocked() => Some(format!(
+            "{manager}: transaction would remove held/protected packages: {}",
+            plan.held.join(", ")
+        )),
+        Ok(_) => None,
+        Err(err) => {
+            log::warn!("Cannot simulate {manager} removal: {err}");
+            None
+        }
     }
 }
 
@@ -105,9 +209,10 @@ fn scan_apt_autoremove() -> Result<Vec<CleanupItem>> {
     for line in stdout.lines() {
         let line = line.trim();
         if let Some(rest) = line.strip_prefix("Remv ")
-            && let Some(pkg) = rest.split_whitespace().next() {
-                items.push(make_package_item(pkg, "APT autoremove candidate", "apt"));
-            }
+            && let Some(pkg) = rest.split_whitespace().next()
+        {
+            items.push(make_package_item(pkg, "APT autoremove candidate", "apt"));
+        }
     }
 
     Ok(items)
@@ -163,6 +268,113 @@ fn scan_pacman_orphans() -> Result<Vec<CleanupItem>> {
     Ok(items)
 }
 
+fn scan_zypper_unneeded() -> Result<Vec<CleanupItem>> {
+    let Ok(packages) = zypper::unneeded_packages() else {
+        return Ok(Vec::new());
+    };
+
+    Ok(packages
+        .iter()
+        .map(|pkg| make_package_item(pkg, "Zypper unneeded package", "zypper"))
+        .collect())
+}
+
+/// Пакеты, которые не нужны ни одному пакету из `/etc/apk/world`.
+fn scan_apk_orphans() -> Result<Vec<CleanupItem>> {
+    let Ok(packages) = apk::orphan_packages() else {
+        return Ok(Vec::new());
+    };
+
+    Ok(packages
+        .iter()
+        .map(|pkg| make_package_item(pkg, "APK package not required by world", "apk"))
+        .collect())
+}
+
+fn scan_xbps_orphans() -> Result<Vec<CleanupItem>> {
+    let Ok(packages) = xbps::orphan_packages() else {
+        return Ok(Vec::new());
+    };
+
+    Ok(packages
+        .iter()
+        .map(|pkg| make_package_item(pkg, "XBPS orphaned package", "xbps"))
+        .collect())
+}
+
+fn scan_store_items() -> Vec<CleanupItem> {
+    let keep = keep_generations();
+    let mut items = Vec::new();
+
+    for manager in StoreManager::detect() {
+        for profile in manager.profiles() {
+            let generations = store::list_generations(&profile);
+            for generation in store::old_generations(generations, keep) {
+                items.push(generation_item(manager, &generation));
+            }
+        }
+        for link in manager.result_links() {
+            let mut item = store_item(
+                manager,
+                format!("{}:result:{}", manager.name(), link.display()),
+                link.display().to_string(),
+                "Build result link keeping store paths alive".to_string(),
+            );
+            item.path = Some(link.to_string_lossy().to_string());
+            items.push(item);
+        }
+        match manager.dead_paths() {
+            Ok(paths) if !paths.is_empty() => {
+                let mut item = store_item(
+                    manager,
+                    format!("{}:store-garbage", manager.name()),
+                    format!("{} store garbage", manager.name()),
+                    format!("{} unreachable store paths", paths.len()),
+                );
+                item.size = SizeCounter::new().measure_all(&paths).allocated;
+                items.push(item);
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("Cannot list dead {} store paths: {}", manager.name(), err),
+        }
+    }
+
+    items
+}
+
+fn generation_item(manager: StoreManager, generation: &Generation) -> CleanupItem {
+    let created = generation
+        .created
+        .map(|time| {
+            DateTime::<Local>::from(time)
+                .format(", %Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_default();
+    store_item(
+        manager,
+        store::generation_id(manager, &generation.profile, generation.number),
+        format!(
+            "{} generation {}",
+            generation.profile.display(),
+            generation.number
+        ),
+        format!("Old {} profile generation{created}", manager.name()),
+    )
+}
+
+fn store_item(manager: StoreManager, id: String, name: String, description: String) -> CleanupItem {
+    let mut item = make_package_item(&name, &description, manager.name());
+    item.id = id;
+    item
+}
+
+fn keep_generations() -> usize {
+    Config::load(&Config::default_path())
+        .map(|config| config.current_profile().keep_generations)
+        .unwrap_or(3)
+}
+
 fn make_package_item(name: &str, description: &str, manager: &str) -> CleanupItem {
     CleanupItem {
         id: format!("{manager}:{name}"),
@@ -176,5 +388,6 @@ fn make_package_item(name: &str, description: &str, manager: &str) -> CleanupIte
         can_clean: true,
         blocked_reason: None,
         dependencies: Vec::new(),
+        verdicts: Vec::new(),
     }
 }
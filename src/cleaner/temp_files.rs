@@ -1,14 +1,22 @@
 use crate::cleaner::base::Cleaner;
-use crate::config::Config;
+use crate::cleaner::deletion::Remover;
+use crate::config::{Config, DeletionMode};
 use crate::error::Result;
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult, CleanupSource};
+use crate::privilege;
 use crate::utils::command;
-use libc;
+use crate::utils::disk_size::SizeCounter;
 use std::fs;
 use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use walkdir::WalkDir;
+
+/// Префикс идентификаторов элементов с чужими файлами в `/tmp` и
+/// `/var/tmp`; их удаляет помощник с правами root.
+pub const SYSTEM_TEMP_ID_PREFIX: &str = "system-temp:";
+
+/// Системные каталоги временных файлов.
+const SYSTEM_TEMP_DIRS: [&str; 2] = ["/tmp", "/var/tmp"];
 
 pub struct TempFilesCleaner;
 
@@ -49,25 +57,28 @@ impl Cleaner for TempFilesCleaner {
 
         for (label, path) in temp_paths {
             let entries = collect_eligible_entries(&path, &options)?;
-            let size = calculate_entries_size(&entries);
-            if size > 0 {
-                items.push(CleanupItem {
-                    id: path.to_string_lossy().to_string(),
-                    name: label.to_string(),
-                    path: Some(path.to_string_lossy().to_string()),
-                    size,
-                    description: format!(
-                        "Temporary directory: {} (older than {} days)",
-                        path.to_string_lossy(),
-                        options.max_age_days
-                    ),
-                    category: self.category(),
-                    source: CleanupSource::FileSystem,
-                    selected: false,
-                    can_clean: true,
-                    blocked_reason: None,
-                    dependencies: Vec::new(),
-                });
+            let id = path.to_string_lossy().to_string();
+            if let Some(item) = self.item(id, label, &path, &entries, &options) {
+                items.push(item);
+            }
+        }
+
+        // Файлы других пользователей удаляет помощник (класс system temp).
+        if let Owners::Mine(uid) = options.owners
+            && privilege::can_elevate()
+        {
+            let others = TempCleanupOptions {
+                owners: Owners::Others(uid),
+                ..options
+            };
+            for dir in SYSTEM_TEMP_DIRS {
+                let path = PathBuf::from(dir);
+                let entries = collect_eligible_entries(&path, &others)?;
+                let id = format!("{SYSTEM_TEMP_ID_PREFIX}{dir}");
+                let label = format!("Other users' temporary files ({dir})");
+                if let Some(item) = self.item(id, &label, &path, &entries, &others) {
+                    items.push(item);
+                }
             }
         }
 
@@ -76,7 +87,8 @@ impl Cleaner for TempFilesCleaner {
 
     fn clean(&self, items: &[CleanupItem], dry_run: bool) -> Result<CleanupResult> {
         let mut result = CleanupResult::default();
-        let options = TempCleanupOptions::from_config();
+        let remover = Remover::from_config();
+        let mine = TempCleanupOptions::from_config();
 
         for item in items {
             if !self.can_clean(item) {
@@ -92,11 +104,19 @@ impl Cleaner for TempFilesCleaner {
 
             if dry_run {
                 log::info!("[DRY RUN] Would clean: {}", path.display());
-                result.cleaned_items += 1;
+                result.item_done(item);
                 result.freed_bytes += item.size;
                 continue;
             }
 
+            let system = item.id.starts_with(SYSTEM_TEMP_ID_PREFIX);
+            let options = match mine.owners {
+                Owners::Mine(uid) if system => TempCleanupOptions {
+                    owners: Owners::Others(uid),
+                    ..mine
+                },
+                _ => mine,
+            };
             let entries = collect_eligible_entries(path, &options)?;
             if entries.is_empty() {
                 result.skipped_items += 1;
@@ -104,19 +124,23 @@ impl Cleaner for TempFilesCleaner {
             }
 
             let mut removed_bytes = 0u64;
+            let mut moved_bytes = 0u64;
             let mut removed_any = false;
+            let mut counter = SizeCounter::new();
             for entry_path in entries {
-                let entry_size = entry_size(&entry_path).unwrap_or(0);
-                if dry_run {
-                    log::info!("[DRY RUN] Would remove: {}", entry_path.display());
-                    removed_bytes = removed_bytes.saturating_add(entry_size);
-                    removed_any = true;
-                    continue;
-                }
-
-                match remove_entry(&entry_path) {
-                    Ok(()) => {
-                        removed_bytes = removed_bytes.saturating_add(entry_size);
+                let entry_size = counter.measure(&entry_path).allocated;
+                let removed = if system {
+                    privilege::remove_path(&entry_path).map(|()| false)
+                } else {
+                    remove_entry(&entry_path, &remover)
+                };
+                match removed {
+                    Ok(moved) => {
+                        if moved {
+                            moved_bytes = moved_bytes.saturating_add(entry_size);
+                        } else {
+                            removed_bytes = removed_bytes.saturating_add(entry_size);
+                        }
                         removed_any = true;
                         if let Some(info_path) = trash_info_path(&entry_path) {
                             let _ = fs::remove_file(info_path);
@@ -131,9 +155,10 @@ impl Cleaner for TempFilesCleaner {
             }
 
             if removed_any {
-                result.cleaned_items += 1;
+                result.item_done(item);
                 result.freed_bytes += removed_bytes;
-            } else if !dry_run {
+                result.moved_bytes += moved_bytes;
+            } else {
                 result.skipped_items += 1;
             }
         }
@@ -142,11 +167,63 @@ impl Cleaner for TempFilesCleaner {
     }
 }
 
+impl TempFilesCleaner {
+    fn item(
+        &self,
+        id: String,
+        label: &str,
+        path: &Path,
+        entries: &[PathBuf],
+        options: &TempCleanupOptions,
+    ) -> Option<CleanupItem> {
+        let size = calculate_entries_size(entries);
+        (size > 0).then(|| CleanupItem {
+            id,
+            name: label.to_string(),
+            path: Some(path.to_string_lossy().to_string()),
+            size,
+            description: format!(
+                "Temporary directory: {} (older than {} days)",
+                path.to_string_lossy(),
+                options.max_age_days
+            ),
+            category: self.category(),
+            source: CleanupSource::FileSystem,
+            selected: false,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
 struct TempCleanupOptions {
     cutoff: SystemTime,
     max_age_days: u64,
-    only_owner: bool,
-    owner_uid: u32,
+    owners: Owners,
+}
+
+/// Чьи записи собираются.
+#[derive(Clone, Copy)]
+enum Owners {
+    /// Все (очистка от root).
+    All,
+    /// Только записи пользователя.
+    Mine(u32),
+    /// Записи всех, кроме пользователя.
+    Others(u32),
+}
+
+impl Owners {
+    fn matches(self, uid: u32) -> bool {
+        match self {
+            Self::All => true,
+            Self::Mine(owner) => uid == owner,
+            Self::Others(owner) => uid != owner,
+        }
+    }
 }
 
 impl TempCleanupOptions {
@@ -159,8 +236,11 @@ impl TempCleanupOptions {
         Self {
             cutoff,
             max_age_days,
-            only_owner: !command::is_root(),
-            owner_uid: unsafe { libc::geteuid() } as u32,
+            owners: if command::is_root() {
+                Owners::All
+            } else {
+                Owners::Mine(unsafe { libc::geteuid() } as u32)
+            },
         }
     }
 }
@@ -179,7 +259,13 @@ fn collect_eligible_entries(path: &Path, options: &TempCleanupOptions) -> Result
             Err(_) => continue,
         };
 
-        if options.only_owner && metadata.uid() != options.owner_uid {
+        if !options.owners.matches(metadata.uid()) {
+            continue;
+        }
+
+        // Корзины раздела (`.Trash`, `.Trash-<uid>`) не временные файлы.
+        let name = entry.file_name();
+        if name == ".Trash" || name.to_string_lossy().starts_with(".Trash-") {
             continue;
         }
 
@@ -198,51 +284,26 @@ fn collect_eligible_entries(path: &Path, options: &TempCleanupOptions) -> Result
     Ok(entries)
 }
 
+/// Занятое место записей; общие жёсткие ссылки учитываются один раз.
 fn calculate_entries_size(entries: &[PathBuf]) -> u64 {
-    entries
-        .iter()
-        .filter_map(|path| entry_size(path).ok())
-        .sum()
+    SizeCounter::new().measure_all(entries).allocated
 }
 
-fn entry_size(path: &Path) -> Result<u64> {
-    let metadata = fs::symlink_metadata(path)?;
-    let file_type = metadata.file_type();
-    if file_type.is_symlink() {
-        return Ok(0);
-    }
-
-    if file_type.is_file() {
-        return Ok(metadata.len());
-    }
-
-    if !path.exists() {
-        return Ok(0);
-    }
-
-    let mut total = 0u64;
-    for entry in WalkDir::new(path).into_iter().flatten() {
-        let entry_type = entry.file_type();
-        if entry_type.is_symlink() {
-            continue;
-        }
-        if entry_type.is_file() {
-            if let Ok(metadata) = entry.metadata() {
-                total += metadata.len();
-            }
-        }
-    }
-    Ok(total)
-}
-
-fn remove_entry(path: &Path) -> Result<()> {
-    let metadata = fs::symlink_metadata(path)?;
-    if metadata.file_type().is_dir() {
-        fs::remove_dir_all(path)?;
+/// Удаляет запись; содержимое корзины всегда удаляется безвозвратно.
+///
+/// Записи `/tmp` и `/var/tmp` в корзину не переносятся: `.Trash-<uid>` на
+/// том же разделе ничего бы не освободил. Возвращает `true`, если запись
+/// перемещена в корзину или карантин.
+fn remove_entry(path: &Path, remover: &Remover) -> Result<bool> {
+    let in_system_temp = SYSTEM_TEMP_DIRS.iter().any(|dir| path.starts_with(dir));
+    let moved_to = if trash_info_path(path).is_some()
+        || (remover.mode() == DeletionMode::Trash && in_system_temp)
+    {
+        Remover::new(DeletionMode::Permanent).remove(path)?
     } else {
-        fs::remove_file(path)?;
-    }
-    Ok(())
+        remover.remove(path)?
+    };
+    Ok(moved_to.is_some())
 }
 
 fn trash_info_path(entry_path: &Path) -> Option<PathBuf> {
@@ -255,3 +316,26 @@ fn trash_info_path(entry_path: &Path) -> Option<PathBuf> {
     let info_dir = trash_dir.join("info");
     Some(info_dir.join(format!("{file_name}.trashinfo")))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_skips_partition_trash() {
+        let dir = std::env::temp_dir().join(format!("rcleaner-temp-trash-{}", std::process::id()));
+        fs::create_dir_all(dir.join(".Trash-1000/files")).unwrap();
+        fs::create_dir_all(dir.join(".Trash")).unwrap();
+        fs::write(dir.join("old.tmp"), b"x").unwrap();
+
+        let options = TempCleanupOptions {
+            cutoff: SystemTime::now() + Duration::from_secs(60),
+            max_age_days: 0,
+            owners: Owners::All,
+        };
+        let entries = collect_eligible_entries(&dir, &options).unwrap();
+
+        assert_eq!(entries, vec![dir.join("old.tmp")]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! Разбор командной строки.
+//!
+//! Без аргументов запускается интерфейс. Команды без интерфейса:
+//!
+//! - `rcleaner clean --headless [--profile P] [--if-usage-above N] [--mount PATH] [--dry-run]`
+//! - `rcleaner schedule install [--profile P] [--every WHEN] [--disk-threshold N] [--mount PATH] [--system]`
+//! - `rcleaner schedule remove [--system]`
+//! - `rcleaner explain PATH [--category C]`
+//! - `rcleaner quarantine list`
+//! - `rcleaner quarantine restore ID`
+
+use crate::error::{RcleanerError, Result};
+use crate::models::CleanupCategory;
+use crate::schedule::{HeadlessOptions, ScheduleSpec, UnitScope};
+use std::path::PathBuf;
+
+/// Подсказка по использованию.
+pub const USAGE: &str = "\
+Usage:
+  rcleaner                             start the interactive interface
+  rcleaner clean --headless [--profile safe|aggressive] [--if-usage-above N] [--mount PATH] [--dry-run]
+  rcleaner schedule install [--profile safe|aggressive] [--every daily|weekly|monthly|ONCALENDAR]
+                            [--disk-threshold N] [--mount PATH] [--system]
+  rcleaner schedule remove [--system]
+  rcleaner explain PATH [--category cache|apps|temp|logs|packages|kernels]
+  rcleaner quarantine list
+  rcleaner quarantine restore ID
+  rcleaner help";
+
+/// Команда, выбранная в командной строке.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Интерактивный интерфейс.
+    Tui,
+    /// Очистка без интерфейса.
+    Clean(HeadlessOptions),
+    /// Установка расписания.
+    ScheduleInstall(ScheduleSpec),
+    /// Удаление расписания.
+    ScheduleRemove(UnitScope),
+    /// Трассировка проверок безопасности для пути.
+    Explain {
+        /// Проверяемый путь.
+        path: PathBuf,
+        /// Категория, если её нужно задать явно.
+        category: Option<CleanupCategory>,
+    },
+    /// Список записей карантина.
+    QuarantineList,
+    /// Возврат записи карантина на исходное место.
+    QuarantineRestore(String),
+    /// Вывод подсказки.
+    Help,
+}
+
+/// Разбирает аргументы (без имени программы).
+pub fn parse(args: &[String]) -> Result<Command> {
+    let mut args = args.iter().map(String::as_str);
+    match args.next() {
+        None => Ok(Command::Tui),
+        Some("help" | "--help" | "-h") => Ok(Command::Help),
+        Some("clean") => parse_clean(args),
+        Some("explain") => parse_explain(args),
+        Some("quarantine") => parse_quarantine(args),
+        Some("schedule") => match args.next() {
+            Some("install") => parse_schedule_install(args),
+            Some("remove") => {
+                let mut scope = UnitScope::User;
+                for arg in args {
+                    match arg {
+                        "--system" => scope = UnitScope::System,
+                        other => return Err(unexpected(other)),
+                    }
+                }
+                Ok(Command::ScheduleRemove(scope))
+            }
+            Some(other) => Err(unexpected(other)),
+            None => Err(RcleanerError::InvalidInput(
+                "schedule expects install or remove".to_string(),
+            )),
+        },
+        Some(other) => Err(unexpected(other)),
+    }
+}
+
+fn parse_clean<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Command> {
+    let mut options = HeadlessOptions::default();
+    let mut headless = false;
+    while let Some(arg) = args.next() {
+        match arg {
+            "--headless" => headless = true,
+            "--dry-run" => options.dry_run = true,
+            "--profile" => options.profile = Some(value(arg, args.next())?.to_string()),
+            "--if-usage-above" => options.usage_threshold = Some(percent(arg, args.next())?),
+            "--mount" => options.mount = PathBuf::from(value(arg, args.next())?),
+            other => return Err(unexpected(other)),
+        }
+    }
+    if !headless {
+        return Err(RcleanerError::InvalidInput(
+            "clean currently runs only with --headless".to_string(),
+        ));
+    }
+    Ok(Command::Clean(options))
+}
+
+fn parse_explain<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Command> {
+    let mut path = None;
+    let mut category = None;
+    while let Some(arg) = args.next() {
+        match arg {
+            "--category" => category = Some(parse_category(value(arg, args.next())?)?),
+            other if path.is_none() && !other.starts_with("--") => {
+                path = Some(PathBuf::from(other));
+            }
+            other => return Err(unexpected(other)),
+        }
+    }
+    let path =
+        path.ok_or_else(|| RcleanerError::InvalidInput("explain expects a path".to_string()))?;
+    Ok(Command::Explain { path, category })
+}
+
+fn parse_quarantine<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Command> {
+    let command = match args.next() {
+        Some("list") => Command::QuarantineList,
+        Some("restore") => Command::QuarantineRestore(value("restore", args.next())?.to_string()),
+        Some(other) => return Err(unexpected(other)),
+        None => {
+            return Err(RcleanerError::InvalidInput(
+                "quarantine expects list or restore".to_string(),
+            ));
+        }
+    };
+    match args.next() {
+        Some(other) => Err(unexpected(other)),
+        None => Ok(command),
+    }
+}
+
+fn parse_category(raw: &str) -> Result<CleanupCategory> {
+    match raw.to_lowercase().as_str() {
+        "cache" => Ok(CleanupCategory::Cache),
+        "apps" | "applications" => Ok(CleanupCategory::Applications),
+        "temp" | "tempfiles" => Ok(CleanupCategory::TempFiles),
+        "logs" => Ok(CleanupCategory::Logs),
+        "packages" | "oldpackages" => Ok(CleanupCategory::OldPackages),
+        "kernels" | "oldkernels" => Ok(CleanupCategory::OldKernels),
+        "locales" => Ok(CleanupCategory::Locales),
+        _ => Err(RcleanerError::InvalidInput(format!(
+            "unknown category {raw}"
+        ))),
+    }
+}
+
+fn parse_schedule_install<'a>(mut args: impl Iterator<Item = &'a str>) -> Result<Command> {
+    let mut spec = ScheduleSpec::default();
+    while let Some(arg) = args.next() {
+        match arg {
+            "--system" => spec.scope = UnitScope::System,
+            "--profile" => spec.profile = value(arg, args.next())?.to_string(),
+            "--every" => spec.every = value(arg, args.next())?.to_string(),
+            "--disk-threshold" => spec.disk_threshold = Some(percent(arg, args.next())?),
+            "--mount" => spec.mount = PathBuf::from(value(arg, args.next())?),
+            other => return Err(unexpected(other)),
+        }
+    }
+    Ok(Command::ScheduleInstall(spec))
+}
+
+fn value<'a>(flag: &str, value: Option<&'a str>) -> Result<&'a str> {
+    value.ok_or_else(|| RcleanerError::InvalidInput(format!("{flag} expects a value")))
+}
+
+fn percent(flag: &str, raw: Option<&str>) -> Result<u8> {
+    let raw = value(flag, raw)?;
+    raw.trim_end_matches('%')
+        .parse::<u8>()
+        .ok()
+        .filter(|percent| (1..=99).contains(percent))
+        .ok_or_else(|| {
+            RcleanerError::InvalidInput(format!("{flag} expects a percentage 1-99, got {raw}"))
+        })
+}
+
+fn unexpected(arg: &str) -> RcleanerError {
+    RcleanerError::InvalidInput(format!("unexpected argument {arg}\n\n{USAGE}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(&[]).unwrap(), Command::Tui);
+
+        let Command::ScheduleInstall(spec) = parse(&args(
+            "schedule install --profile safe --every weekly --disk-threshold 90% --mount /home",
+        ))
+        .unwrap() else {
+            panic!("expected schedule install");
+        };
+        assert_eq!(spec.profile, "safe");
+        assert_eq!(spec.every, "weekly");
+        assert_eq!(spec.disk_threshold, Some(90));
+        assert_eq!(spec.mount, PathBuf::from("/home"));
+        assert_eq!(spec.scope, UnitScope::User);
+
+        let Command::Clean(options) = parse(&args(
+            "clean --headless --profile aggressive --if-usage-above 85",
+        ))
+        .unwrap() else {
+            panic!("expected clean");
+        };
+        assert_eq!(options.profile.as_deref(), Some("aggressive"));
+        assert_eq!(options.usage_threshold, Some(85));
+
+        assert_eq!(
+            parse(&args("schedule remove --system")).unwrap(),
+            Command::ScheduleRemove(UnitScope::System)
+        );
+        assert_eq!(
+            parse(&args("explain /var/log/old.log --category logs")).unwrap(),
+            Command::Explain {
+                path: PathBuf::from("/var/log/old.log"),
+                category: Some(CleanupCategory::Logs),
+            }
+        );
+        assert_eq!(
+            parse(&args("quarantine list")).unwrap(),
+            Command::QuarantineList
+        );
+        assert_eq!(
+            parse(&args("quarantine restore 20260101000000-42")).unwrap(),
+            Command::QuarantineRestore("20260101000000-42".to_string())
+        );
+        assert!(parse(&args("quarantine restore")).is_err());
+        assert!(parse(&args("quarantine purge")).is_err());
+        assert!(parse(&args("explain")).is_err());
+        assert!(parse(&args("explain /tmp --category nope")).is_err());
+        assert!(parse(&args("clean")).is_err());
+        assert!(parse(&args("schedule install --every")).is_err());
+        assert!(parse(&args("clean --headless --if-usage-above 120")).is_err());
+    }
+}
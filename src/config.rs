@@ -2,8 +2,13 @@
 //!
 //! Конфигурация хранится в TOML файле `~/.config/rcleaner/config.toml`.
 
+use crate::models::CleanupCategory;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Профиль, выбранный в командной строке (важнее `safety.level`).
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
 
 /// Главная структура конфигурации.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,8 +17,15 @@ pub struct Config {
     pub safety: SafetyConfig,
     /// Профили очистки.
     pub profiles: ProfilesConfig,
-    /// Правила whitelist/blacklist.
+    /// Правила безопасности allow/deny.
+    #[serde(default)]
     pub rules: RulesConfig,
+    /// Защита точек монтирования, чужих файлов, атрибутов и служб.
+    #[serde(default)]
+    pub protect: ProtectConfig,
+    /// Удаление переводов, man-страниц на других языках и документации.
+    #[serde(default)]
+    pub locales: LocalesConfig,
 }
 
 /// Настройки безопасности.
@@ -27,6 +39,70 @@ pub struct SafetyConfig {
     pub level: String,
 }
 
+/// Защита, не привязанная к конкретным путям.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectConfig {
+    /// Каталоги, все файловые системы внутри которых защищены целиком
+    /// (съёмные носители, сетевые ресурсы).
+    #[serde(default = "default_protected_mounts")]
+    pub mounts: Vec<String>,
+    /// Защищать сетевые файловые системы (nfs, cifs, sshfs) в любом месте.
+    #[serde(default = "default_true")]
+    pub network_filesystems: bool,
+    /// Защищать файлы, принадлежащие другим пользователям.
+    #[serde(default = "default_true")]
+    pub other_users: bool,
+    /// Защищать файлы с атрибутами immutable и append-only.
+    #[serde(default = "default_true")]
+    pub immutable: bool,
+    /// Службы systemd, открытые файлы и рабочие каталоги которых защищены.
+    #[serde(default)]
+    pub services: Vec<String>,
+}
+
+impl Default for ProtectConfig {
+    fn default() -> Self {
+        Self {
+            mounts: default_protected_mounts(),
+            network_filesystems: true,
+            other_users: true,
+            immutable: true,
+            services: Vec::new(),
+        }
+    }
+}
+
+/// Удаление переводов и документации (в духе localepurge).
+///
+/// Выключено по умолчанию: пока `enabled = false`, встроенная защита
+/// `/usr/share` блокирует такие элементы.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalesConfig {
+    /// Включена ли категория.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Языки, переводы которых сохраняются (`en` сохраняет и `en_GB`).
+    #[serde(default = "default_kept_locales")]
+    pub keep: Vec<String>,
+    /// Удалять также `/usr/share/doc` (кроме `copyright`) и `/usr/share/help`.
+    #[serde(default)]
+    pub docs: bool,
+    /// Настроить dpkg и dnf так, чтобы удалённые файлы не устанавливались снова.
+    #[serde(default = "default_true")]
+    pub prevent_reinstall: bool,
+}
+
+impl Default for LocalesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keep: default_kept_locales(),
+            docs: false,
+            prevent_reinstall: true,
+        }
+    }
+}
+
 /// Профили очистки (safe и aggressive).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProfilesConfig {
@@ -48,36 +124,316 @@ pub struct ProfileConfig {
     pub keep_recent_kernels: usize,
     /// Сколько последних deployments сохранять (rpm-ostree).
     pub keep_recent_deployments: usize,
-    /// Максимальный размер бэкапа в ГБ.
+    /// Сколько последних поколений профилей Nix/Guix сохранять.
+    #[serde(default = "default_keep_generations")]
+    pub keep_generations: usize,
+    /// Максимальный размер бэкапов в ГБ (считаются сжатые байты на диске).
     pub max_backup_size_gb: usize,
+    /// Сколько дней хранить бэкапы (0 — без ограничения).
+    ///
+    /// В старых конфигурациях поля нет, и ограничение выключено: иначе
+    /// обновление молча удалило бы накопленные бэкапы.
+    #[serde(default)]
+    pub backup_max_age_days: u64,
+    /// Сколько последних бэкапов хранить (0 — без ограничения).
+    #[serde(default)]
+    pub backup_max_count: usize,
     /// Минимальный возраст временных файлов (дней) для удаления.
     #[serde(default = "default_temp_max_age_days")]
     pub temp_max_age_days: u64,
+    /// Способ удаления файлов.
+    #[serde(default)]
+    pub deletion_mode: DeletionMode,
+    /// Через сколько дней карантин очищается окончательно.
+    #[serde(default = "default_quarantine_days")]
+    pub quarantine_days: u64,
+    /// Категории, которые плановая очистка берёт без подтверждения.
+    #[serde(default = "default_auto_clean_categories")]
+    pub auto_clean_categories: Vec<CleanupCategory>,
 }
 
-/// Правила whitelist и blacklist.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Способ удаления файлов при очистке.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeletionMode {
+    /// Безвозвратное удаление.
+    #[default]
+    Permanent,
+    /// Перемещение в корзину по спецификации freedesktop.
+    Trash,
+    /// Перемещение в карантин rCleaner с удалением через N дней.
+    Quarantine,
+}
+
+impl DeletionMode {
+    /// Возвращает следующий режим (для переключения в настройках).
+    pub fn next(self) -> Self {
+        match self {
+            Self::Permanent => Self::Trash,
+            Self::Trash => Self::Quarantine,
+            Self::Quarantine => Self::Permanent,
+        }
+    }
+
+    /// Возвращает название режима.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Permanent => "permanent",
+            Self::Trash => "trash",
+            Self::Quarantine => "quarantine",
+        }
+    }
+}
+
+/// Текущая версия формата правил безопасности.
+pub const RULES_VERSION: u32 = 2;
+
+/// Паттерны blacklist, которые раньше поставлялись по умолчанию.
+///
+/// Они блокировали временные файлы и логи, поэтому при миграции отбрасываются.
+const LEGACY_DEFAULT_PATTERNS: &[&str] = &["*.tmp", "*.log"];
+
+/// Приоритет правил, перенесённых из whitelist/blacklist.
+const MIGRATED_PRIORITY: i32 = 100;
+
+/// Правила безопасности.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RulesConfig {
-    /// Белый список защищённых путей.
+    /// Версия формата правил (0 — старый формат whitelist/blacklist).
+    #[serde(default)]
+    pub version: u32,
+    /// Правила allow/deny (`[[rules.rule]]`).
+    #[serde(default, rename = "rule")]
+    pub entries: Vec<RuleConfig>,
+    /// Устаревший белый список, переносится в правила `deny`.
+    #[serde(default, skip_serializing_if = "WhitelistConfig::is_empty")]
     pub whitelist: WhitelistConfig,
-    /// Чёрный список паттернов для блокировки.
+    /// Устаревший чёрный список, переносится в правила `deny`.
+    #[serde(default, skip_serializing_if = "BlacklistConfig::is_empty")]
     pub blacklist: BlacklistConfig,
 }
 
-/// Конфигурация белого списка.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Действие правила безопасности.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    /// Разрешить очистку.
+    Allow,
+    /// Запретить очистку.
+    Deny,
+}
+
+impl RuleAction {
+    /// Возвращает название действия.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+/// Правило безопасности из конфигурации.
+///
+/// Срабатывает, когда совпадают все заданные условия. Из сработавших
+/// правил побеждает правило с большим приоритетом, при равенстве — `deny`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleConfig {
+    /// Разрешить или запретить очистку.
+    pub action: RuleAction,
+    /// Приоритет (встроенная защита системы имеет приоритет 1000).
+    #[serde(default)]
+    pub priority: i32,
+    /// Путь или glob-паттерн; без него правило применяется к любому элементу.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Категории, к которым применяется правило (пусто — ко всем).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<CleanupCategory>,
+    /// Источники: `filesystem`, `package`, `container` или имя менеджера (`apt`, `flatpak`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    /// Минимальный возраст (дней с последнего изменения).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_age_days: Option<u64>,
+    /// Максимальный возраст (дней с последнего изменения).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    /// Минимальный размер в байтах.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<u64>,
+    /// Максимальный размер в байтах.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+    /// Владелец: имя пользователя, числовой UID или `current`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Пояснение, которое показывается при срабатывании.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+}
+
+impl RuleConfig {
+    /// Создаёт правило только с путём или паттерном.
+    pub fn with_pattern(action: RuleAction, pattern: &str, priority: i32) -> Self {
+        Self {
+            action,
+            priority,
+            pattern: Some(pattern.to_string()),
+            categories: Vec::new(),
+            sources: Vec::new(),
+            min_age_days: None,
+            max_age_days: None,
+            min_size: None,
+            max_size: None,
+            owner: None,
+            description: String::new(),
+        }
+    }
+
+    /// Правило задаёт только путь (его можно редактировать списком в настройках).
+    pub fn is_simple(&self) -> bool {
+        self.pattern.is_some()
+            && self.categories.is_empty()
+            && self.sources.is_empty()
+            && self.min_age_days.is_none()
+            && self.max_age_days.is_none()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+            && self.owner.is_none()
+    }
+
+    /// Краткое описание правила для интерфейса.
+    pub fn summary(&self) -> String {
+        let mut parts = vec![
+            format!("{} {}", self.action.label(), self.priority),
+            self.pattern.clone().unwrap_or_else(|| "*".to_string()),
+        ];
+        if !self.categories.is_empty() {
+            let names: Vec<_> = self.categories.iter().map(|c| format!("{c:?}")).collect();
+            parts.push(format!("[{}]", names.join(",")));
+        }
+        if !self.sources.is_empty() {
+            parts.push(format!("from {}", self.sources.join(",")));
+        }
+        if let Some(days) = self.min_age_days {
+            parts.push(format!("age>={days}d"));
+        }
+        if let Some(days) = self.max_age_days {
+            parts.push(format!("age<={days}d"));
+        }
+        if let Some(size) = self.min_size {
+            parts.push(format!("size>={size}"));
+        }
+        if let Some(size) = self.max_size {
+            parts.push(format!("size<={size}"));
+        }
+        if let Some(owner) = &self.owner {
+            parts.push(format!("owner={owner}"));
+        }
+        parts.join(" ")
+    }
+}
+
+impl RulesConfig {
+    /// Нужна ли миграция со старого формата whitelist/blacklist.
+    pub fn needs_migration(&self) -> bool {
+        self.version < RULES_VERSION || !self.whitelist.is_empty() || !self.blacklist.is_empty()
+    }
+
+    /// Переносит whitelist/blacklist в правила `deny`.
+    ///
+    /// Оба старых списка блокировали очистку, поэтому поведение сохраняется,
+    /// кроме поставлявшихся по умолчанию `*.tmp` и `*.log`. Возвращает `true`,
+    /// если конфигурация изменилась.
+    pub fn migrate(&mut self) -> bool {
+        if !self.needs_migration() {
+            return false;
+        }
+
+        let paths = std::mem::take(&mut self.whitelist.paths);
+        let patterns = std::mem::take(&mut self.blacklist.patterns);
+        for path in paths {
+            self.push_unique(RuleConfig {
+                description: "Migrated from whitelist".to_string(),
+                ..RuleConfig::with_pattern(RuleAction::Deny, path.trim(), MIGRATED_PRIORITY)
+            });
+        }
+        for pattern in patterns {
+            if LEGACY_DEFAULT_PATTERNS.contains(&pattern.trim()) {
+                continue;
+            }
+            self.push_unique(RuleConfig {
+                description: "Migrated from blacklist".to_string(),
+                ..RuleConfig::with_pattern(RuleAction::Deny, pattern.trim(), MIGRATED_PRIORITY)
+            });
+        }
+        self.version = RULES_VERSION;
+        true
+    }
+
+    /// Паттерны простых правил с указанным действием.
+    pub fn simple_patterns(&self, action: RuleAction) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|rule| rule.action == action && rule.is_simple())
+            .filter_map(|rule| rule.pattern.clone())
+            .collect()
+    }
+
+    /// Заменяет простые правила с указанным действием, не трогая остальные.
+    pub fn set_simple_patterns(&mut self, action: RuleAction, patterns: Vec<String>) {
+        self.entries
+            .retain(|rule| rule.action != action || !rule.is_simple());
+        for pattern in patterns {
+            self.push_unique(RuleConfig::with_pattern(
+                action,
+                &pattern,
+                MIGRATED_PRIORITY,
+            ));
+        }
+    }
+
+    fn push_unique(&mut self, rule: RuleConfig) {
+        let duplicate = self.entries.iter().any(|existing| {
+            existing.action == rule.action
+                && existing.pattern == rule.pattern
+                && existing.is_simple()
+        });
+        if !(duplicate && rule.is_simple()) {
+            self.entries.push(rule);
+        }
+    }
+}
+
+/// Конфигурация белого списка (устаревший формат).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct WhitelistConfig {
     /// Защищённые пути.
+    #[serde(default)]
     pub paths: Vec<String>,
 }
 
-/// Конфигурация чёрного списка.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl WhitelistConfig {
+    fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// Конфигурация чёрного списка (устаревший формат).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BlacklistConfig {
     /// Паттерны для блокировки.
+    #[serde(default)]
     pub patterns: Vec<String>,
 }
 
+impl BlacklistConfig {
+    fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -92,30 +448,46 @@ impl Default for Config {
                     dry_run: false,
                     keep_recent_kernels: 2,
                     keep_recent_deployments: 2,
+                    keep_generations: 3,
                     max_backup_size_gb: 10,
+                    backup_max_age_days: 30,
+                    backup_max_count: 10,
                     temp_max_age_days: 7,
+                    deletion_mode: DeletionMode::Permanent,
+                    quarantine_days: 14,
+                    auto_clean_categories: default_auto_clean_categories(),
                 },
                 aggressive: ProfileConfig {
                     auto_confirm: true,
                     dry_run: false,
                     keep_recent_kernels: 1,
                     keep_recent_deployments: 1,
+                    keep_generations: 1,
                     max_backup_size_gb: 5,
+                    backup_max_age_days: 7,
+                    backup_max_count: 3,
                     temp_max_age_days: 3,
+                    deletion_mode: DeletionMode::Permanent,
+                    quarantine_days: 7,
+                    auto_clean_categories: vec![
+                        CleanupCategory::Cache,
+                        CleanupCategory::TempFiles,
+                        CleanupCategory::Logs,
+                        CleanupCategory::OldPackages,
+                    ],
                 },
             },
             rules: RulesConfig {
-                whitelist: WhitelistConfig {
-                    paths: vec![
-                        "~/.config".to_string(),
-                        "~/Documents".to_string(),
-                        "~/Projects".to_string(),
-                    ],
-                },
-                blacklist: BlacklistConfig {
-                    patterns: vec!["*.tmp".to_string(), "*.log".to_string()],
-                },
+                version: RULES_VERSION,
+                entries: ["~/.config", "~/Documents", "~/Projects"]
+                    .into_iter()
+                    .map(|path| RuleConfig::with_pattern(RuleAction::Deny, path, MIGRATED_PRIORITY))
+                    .collect(),
+                whitelist: WhitelistConfig::default(),
+                blacklist: BlacklistConfig::default(),
             },
+            protect: ProtectConfig::default(),
+            locales: LocalesConfig::default(),
         }
     }
 }
@@ -128,10 +500,26 @@ impl Config {
     /// Возвращает ошибку, если файл не существует или имеет неверный формат.
     pub fn load(path: &PathBuf) -> crate::error::Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+        let mut config: Config = toml::from_str(&content)?;
+        config.rules.migrate();
         Ok(config)
     }
 
+    /// Переводит файл конфигурации на текущий формат правил.
+    ///
+    /// Старый файл сохраняется рядом с суффиксом `.v1.bak`. Возвращает `true`,
+    /// если файл был переписан.
+    pub fn migrate_file(path: &PathBuf) -> crate::error::Result<bool> {
+        let content = std::fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&content)?;
+        if !config.rules.migrate() {
+            return Ok(false);
+        }
+        std::fs::write(path.with_extension("toml.v1.bak"), &content)?;
+        config.save(path)?;
+        Ok(true)
+    }
+
     /// Сохраняет конфигурацию в файл.
     ///
     /// Создаёт родительские директории, если они не существуют.
@@ -155,20 +543,65 @@ impl Config {
             .join("config.toml")
     }
 
-    /// Возвращает текущий активный профиль на основе `safety.level`.
+    /// Возвращает текущий активный профиль на основе `safety.level`
+    /// или профиля из [`Config::override_profile`].
     pub fn current_profile(&self) -> &ProfileConfig {
-        if self.safety.level.to_lowercase() == "aggressive" {
+        let level = PROFILE_OVERRIDE.get().unwrap_or(&self.safety.level);
+        if level.to_lowercase() == "aggressive" {
             &self.profiles.aggressive
         } else {
             &self.profiles.safe
         }
     }
+
+    /// Выбирает профиль для всего процесса, не меняя файл конфигурации.
+    ///
+    /// Используется плановой очисткой (`--profile`); задаётся один раз.
+    pub fn override_profile(level: &str) -> crate::error::Result<()> {
+        let level = level.to_lowercase();
+        if level != "safe" && level != "aggressive" {
+            return Err(crate::error::RcleanerError::InvalidInput(format!(
+                "unknown profile {level}, expected safe or aggressive"
+            )));
+        }
+        PROFILE_OVERRIDE.set(level).map_err(|_| {
+            crate::error::RcleanerError::Config("profile already selected".to_string())
+        })
+    }
 }
 
 fn default_temp_max_age_days() -> u64 {
     7
 }
 
+fn default_keep_generations() -> usize {
+    3
+}
+
+fn default_quarantine_days() -> u64 {
+    14
+}
+
+fn default_protected_mounts() -> Vec<String> {
+    vec![
+        "/run/media".to_string(),
+        "/media".to_string(),
+        "/mnt".to_string(),
+    ]
+}
+
+fn default_kept_locales() -> Vec<String> {
+    vec!["en".to_string()]
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_auto_clean_categories() -> Vec<CleanupCategory> {
+    vec![CleanupCategory::Cache, CleanupCategory::TempFiles]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,4 +641,52 @@ mod tests {
         config.safety.level = "aggressive".to_string();
         assert!(config.current_profile().auto_confirm);
     }
+
+    #[test]
+    fn test_migrate_legacy_rules() {
+        let legacy = r#"
+[safety]
+enabled = true
+only_root_can_disable = true
+level = "safe"
+
+[profiles.safe]
+auto_confirm = false
+keep_recent_kernels = 2
+keep_recent_deployments = 2
+max_backup_size_gb = 10
+
+[profiles.aggressive]
+auto_confirm = true
+keep_recent_kernels = 1
+keep_recent_deployments = 1
+max_backup_size_gb = 5
+
+[rules.whitelist]
+paths = ["~/.config", "~/Work"]
+
+[rules.blacklist]
+patterns = ["*.tmp", "*.log", "*.iso"]
+"#;
+        let path = temp_config_path();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, legacy).unwrap();
+
+        assert!(Config::migrate_file(&path).unwrap());
+        assert!(!Config::migrate_file(&path).unwrap());
+        assert!(path.with_extension("toml.v1.bak").exists());
+
+        let loaded = Config::load(&path).unwrap();
+        assert_eq!(loaded.rules.version, RULES_VERSION);
+        assert!(loaded.rules.whitelist.paths.is_empty());
+        assert_eq!(
+            loaded.rules.simple_patterns(RuleAction::Deny),
+            vec!["~/.config", "~/Work", "*.iso"]
+        );
+        assert!(loaded.rules.simple_patterns(RuleAction::Allow).is_empty());
+
+        let safe = &loaded.profiles.safe;
+        assert_eq!(safe.backup_max_age_days, 0);
+        assert_eq!(safe.backup_max_count, 0);
+    }
 }
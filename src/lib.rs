@@ -9,7 +9,8 @@
 //! - Очистка журналов (logs)
 //! - Удаление старых пакетов и ядер
 //! - Поддержка Flatpak и Snap
-//! - Поддержка rpm-ostree (Atomic Desktop)
+//! - Поддержка rpm-ostree (Atomic Desktop) и transactional-update (openSUSE MicroOS)
+//! - Сборка мусора в хранилищах Nix и Guix
 //!
 //! ## Модули
 //!
@@ -19,6 +20,9 @@
 //! - [`system`] - определение системы и пакетные менеджеры
 //! - [`tui`] - терминальный интерфейс
 //! - [`backup`] - резервное копирование
+//! - [`privilege`] - разделение привилегий и root-помощник
+//! - [`schedule`] - плановая очистка через таймеры systemd
+//! - [`cli`] - разбор командной строки
 
 pub mod config;
 pub mod error;
@@ -27,8 +31,11 @@ pub use error::{RcleanerError, Result};
 
 pub mod backup;
 pub mod cleaner;
+pub mod cli;
 pub mod models;
+pub mod privilege;
 pub mod safety;
+pub mod schedule;
 pub mod system;
 pub mod tui;
 pub mod utils;
@@ -1,16 +1,92 @@
+use rcleaner::cleaner::deletion;
+use rcleaner::cli::{self, Command};
 use rcleaner::config::Config;
 use rcleaner::error::Result;
+use rcleaner::privilege::OperationClass;
+use rcleaner::schedule;
 use rcleaner::{NAME, VERSION, tui::App};
 use std::io::IsTerminal;
 
 fn main() -> Result<()> {
+    if let Some(class) = std::env::args()
+        .nth(1)
+        .and_then(|arg| OperationClass::from_helper_arg(&arg))
+    {
+        return rcleaner::privilege::helper::serve_stdio(class);
+    }
+
     env_logger::Builder::from_env(env_logger::Env::default())
         .filter_level(log::LevelFilter::Info)
         .init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(2);
+        }
+    };
+    match command {
+        Command::Tui => {}
+        Command::Help => {
+            println!("{NAME} v{VERSION}\n\n{}", cli::USAGE);
+            return Ok(());
+        }
+        Command::Clean(options) => {
+            schedule::headless::run(&options)?;
+            return Ok(());
+        }
+        Command::ScheduleInstall(spec) => {
+            for path in schedule::install(&spec)? {
+                println!("Installed {}", path.display());
+            }
+            return Ok(());
+        }
+        Command::Explain { path, category } => {
+            let item = rcleaner::safety::explain::explain_path(&path, category)?;
+            for line in rcleaner::safety::explain::format_trace(&item) {
+                println!("{line}");
+            }
+            return Ok(());
+        }
+        Command::QuarantineList => {
+            let root = deletion::default_quarantine_dir();
+            for entry in deletion::list_quarantine(&root)? {
+                let quarantined_at = chrono::DateTime::<chrono::Local>::from(entry.quarantined_at);
+                println!(
+                    "{}  {}  {}",
+                    entry.id,
+                    quarantined_at.format("%Y-%m-%d %H:%M"),
+                    entry.original_path.display()
+                );
+            }
+            return Ok(());
+        }
+        Command::QuarantineRestore(id) => {
+            let root = deletion::default_quarantine_dir();
+            let original = deletion::restore_quarantined(&root, &id)?;
+            println!("Restored {}", original.display());
+            return Ok(());
+        }
+        Command::ScheduleRemove(scope) => {
+            for path in schedule::uninstall(scope)? {
+                println!("Removed {}", path.display());
+            }
+            return Ok(());
+        }
+    }
+
     log::info!("Starting {} v{}", NAME, VERSION);
 
     let config_path = Config::default_path();
+    if config_path.exists() {
+        match Config::migrate_file(&config_path) {
+            Ok(true) => log::info!("Migrated safety rules in {:?}", config_path),
+            Ok(false) => {}
+            Err(e) => log::warn!("Failed to migrate safety rules: {}", e),
+        }
+    }
     let _config = match Config::load(&config_path) {
         Ok(config) => {
             log::info!("Loaded configuration from {:?}", config_path);
@@ -34,6 +110,7 @@ fn main() -> Result<()> {
     let mut app = App::new();
     let result = app.run(&mut terminal);
     ratatui::restore();
+    rcleaner::privilege::shutdown();
     result?;
     log::info!("rCleaner exited successfully");
 
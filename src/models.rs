@@ -1,6 +1,7 @@
 //! Основные модели данных для rCleaner.
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Элемент для очистки.
 ///
@@ -30,6 +31,82 @@ pub struct CleanupItem {
     pub blocked_reason: Option<String>,
     /// Список зависимых пакетов.
     pub dependencies: Vec<String>,
+    /// Результаты всех проверок безопасности (по порядку применения).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verdicts: Vec<Verdict>,
+}
+
+/// Вид проверки безопасности.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VerdictCheck {
+    /// Права доступа к пути.
+    Permission,
+    /// Нужны ли права root.
+    Root,
+    /// Правило безопасности.
+    Rule,
+    /// Зависимые пакеты.
+    Dependency,
+    /// Ошибка при проверке.
+    Error,
+}
+
+/// Итог одной проверки.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VerdictOutcome {
+    /// Проверка пройдена.
+    Pass,
+    /// Проверка блокирует очистку.
+    Block,
+    /// Правило совпало, но перекрыто правилом с большим приоритетом.
+    Shadowed,
+}
+
+/// Результат одной проверки безопасности элемента.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+    /// Что проверялось.
+    pub check: VerdictCheck,
+    /// Итог проверки.
+    pub outcome: VerdictOutcome,
+    /// Пояснение (для правил — паттерн и приоритет).
+    pub detail: String,
+}
+
+impl Verdict {
+    /// Создаёт результат проверки.
+    pub fn new(check: VerdictCheck, outcome: VerdictOutcome, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            outcome,
+            detail: detail.into(),
+        }
+    }
+
+    /// Блокирует ли проверка очистку.
+    pub fn is_blocking(&self) -> bool {
+        self.outcome == VerdictOutcome::Block
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let check = match self.check {
+            VerdictCheck::Permission => "permission",
+            VerdictCheck::Root => "root",
+            VerdictCheck::Rule => "rule",
+            VerdictCheck::Dependency => "dependency",
+            VerdictCheck::Error => "error",
+        };
+        let outcome = match self.outcome {
+            VerdictOutcome::Pass => "pass",
+            VerdictOutcome::Block => "BLOCK",
+            VerdictOutcome::Shadowed => "shadowed",
+        };
+        write!(f, "[{outcome}] {check}: {}", self.detail)
+    }
 }
 
 /// Категория очистки.
@@ -47,6 +124,8 @@ pub enum CleanupCategory {
     OldPackages,
     /// Старые ядра.
     OldKernels,
+    /// Переводы, man-страницы на других языках и документация.
+    Locales,
 }
 
 /// Источник элемента для очистки.
@@ -71,4 +150,42 @@ pub struct CleanupResult {
     pub skipped_items: usize,
     /// Список ошибок.
     pub errors: Vec<String>,
+    /// Освобождено байт по точкам монтирования.
+    ///
+    /// После настоящей очистки измеряется по свободному месту, при
+    /// симуляции считается по размерам элементов.
+    #[serde(default)]
+    pub freed_by_mount: BTreeMap<String, u64>,
+    /// Байты удалённых файлов, которые остаются в снимке файловой системы,
+    /// пока не удалён бэкап со снимком.
+    #[serde(default)]
+    pub held_by_snapshots: u64,
+    /// Байты, перемещённые в корзину или карантин: место освободится после
+    /// их очистки.
+    #[serde(default)]
+    pub moved_bytes: u64,
+    /// Идентификаторы успешно очищенных элементов (для журнала очистки).
+    #[serde(default)]
+    pub done_items: Vec<String>,
+}
+
+impl CleanupResult {
+    /// Отмечает элемент очищенным.
+    pub fn item_done(&mut self, item: &CleanupItem) {
+        self.cleaned_items += 1;
+        self.done_items.push(item.id.clone());
+    }
+
+    /// Учитывает удалённый элемент: его место освобождается сразу, после
+    /// очистки корзины или карантина (`moved`) или после удаления снимка, в
+    /// который он попал.
+    pub fn add_removed(&mut self, size: u64, moved: bool, in_snapshot: bool) {
+        if moved {
+            self.moved_bytes += size;
+        } else if in_snapshot {
+            self.held_by_snapshots += size;
+        } else {
+            self.freed_bytes += size;
+        }
+    }
 }
@@ -0,0 +1,85 @@
+//! Клиент root-помощника на стороне интерфейса.
+//!
+//! Для каждого класса операций помощник запускается через
+//! `pkexec <rcleaner> helper-<класс>` один раз за сессию и получает запросы
+//! через stdin.
+
+use crate::error::{RcleanerError, Result};
+use crate::privilege::protocol::{HelperRequest, HelperResponse, OperationClass};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Запущенный процесс помощника.
+pub struct HelperClient {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl HelperClient {
+    /// Запускает помощника класса через pkexec и проверяет связь.
+    ///
+    /// pkexec спрашивает пароль, если действие polkit класса этого требует.
+    pub fn spawn(class: OperationClass) -> Result<Self> {
+        let exe = std::env::current_exe()?;
+        let mut child = Command::new("pkexec")
+            .arg(exe)
+            .arg(class.helper_arg())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| RcleanerError::Command("helper stdin unavailable".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| RcleanerError::Command("helper stdout unavailable".to_string()))?;
+
+        let mut client = Self {
+            child,
+            stdin: Some(stdin),
+            stdout: BufReader::new(stdout),
+        };
+        // pkexec завершается с кодом 126, если пользователь отказал.
+        client.call(&HelperRequest::Ping).map_err(|err| {
+            RcleanerError::Permission(format!(
+                "authorization for {} was not granted: {err}",
+                class.label()
+            ))
+        })?;
+        Ok(client)
+    }
+
+    /// Отправляет запрос и ждёт ответа.
+    pub fn call(&mut self, request: &HelperRequest) -> Result<HelperResponse> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| RcleanerError::Command("helper stdin closed".to_string()))?;
+        writeln!(stdin, "{}", serde_json::to_string(request)?)?;
+        stdin.flush()?;
+
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            let status = self.child.try_wait()?;
+            return Err(RcleanerError::Permission(match status {
+                Some(status) => format!("privileged helper exited ({status})"),
+                None => "privileged helper closed the connection".to_string(),
+            }));
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+impl Drop for HelperClient {
+    fn drop(&mut self) {
+        // pkexec работает от root, поэтому сигнал ему не отправить:
+        // помощник завершается сам, получив EOF.
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,558 @@
+//! Root-помощник: принимает только фиксированный набор операций.
+//!
+//! Помощник не доверяет интерфейсу: каждая операция проверяется по
+//! белому списку (менеджеры, имена пакетов, разрешённые корни путей), а
+//! затем повторно проходит правила безопасности из конфигурации root.
+//! Помощник обслуживает один [`OperationClass`]: polkit разрешил запуск
+//! только для него, поэтому запросы других классов отклоняются.
+
+use crate::config::Config;
+use crate::error::{RcleanerError, Result};
+use crate::models::{CleanupCategory, CleanupItem, CleanupSource};
+use crate::privilege::protocol::{HelperRequest, HelperResponse, OperationClass};
+use crate::safety::SafetyChecker;
+use crate::system::package_manager::{command_failed, run_command};
+use crate::system::store::{self, StoreManager};
+use crate::system::{
+    apk, apt, dnf, flatpak, kernels, locales, pacman, rpm, rpm_ostree, snap, xbps, zypper,
+};
+use crate::utils::command::is_root;
+use crate::utils::safe_fs::remove_tree_beneath;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path};
+
+/// Источники, которые удаляют только ядра и их файлы, а не пакеты.
+const KERNEL_ONLY_MANAGERS: &[&str] = &["vkpurge", "kernel-files", "dkms"];
+
+/// Пакетные менеджеры, чей кэш пакетов помощник может очистить.
+pub const CACHE_MANAGERS: &[&str] = &["zypper", "apk", "xbps"];
+
+/// Каталоги, внутри которых помощник удаляет файлы.
+///
+/// Только те, куда помощника действительно отправляют очистители: ротированные
+/// логи и чужие временные файлы.
+pub const ALLOWED_PATH_ROOTS: &[&str] = &["/var/log", "/var/tmp", "/tmp"];
+
+/// Пакетные менеджеры, которыми помощник может удалять пакеты.
+pub const ALLOWED_MANAGERS: &[&str] = &[
+    "apt",
+    "dnf",
+    "rpm",
+    "pacman",
+    "zypper",
+    "apk",
+    "xbps",
+    "vkpurge",
+    "kernel-files",
+    "dkms",
+    "snap",
+    "flatpak",
+    "rpm-ostree",
+];
+
+const MAX_JOURNAL_AGE_DAYS: u32 = 3650;
+
+/// Обслуживает запросы класса `class` из stdin до его закрытия.
+pub fn serve_stdio(class: OperationClass) -> Result<()> {
+    if !is_root() {
+        return Err(RcleanerError::Permission(
+            "rcleaner helper must run as root".to_string(),
+        ));
+    }
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    serve(stdin.lock(), stdout.lock(), class)
+}
+
+/// Читает запросы построчно и пишет ответ на каждый.
+pub fn serve(reader: impl BufRead, mut writer: impl Write, class: OperationClass) -> Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<HelperRequest>(&line) {
+            Ok(request) if request.class().is_some_and(|other| other != class) => {
+                HelperResponse::failure(format!(
+                    "helper for {} does not accept this request",
+                    class.label()
+                ))
+            }
+            Ok(request) => match execute(&request) {
+                Ok(message) => HelperResponse::success(message),
+                Err(err) => HelperResponse::failure(err.to_string()),
+            },
+            Err(err) => HelperResponse::failure(format!("Malformed request: {err}")),
+        };
+        writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Проверяет и выполняет операцию.
+pub fn execute(request: &HelperRequest) -> Result<String> {
+    validate(request)?;
+    check_safety(request)?;
+
+    match request {
+        HelperRequest::Ping => Ok("pong".to_string()),
+        HelperRequest::RemovePackages { manager, packages }
+        | HelperRequest::RemoveKernels { manager, packages } => {
+            remove_packages(manager, packages, false)?;
+            Ok(format!("Removed {} package(s)", packages.len()))
+        }
+        HelperRequest::CleanPackageCache { manager } => {
+            clean_package_cache(manager, false)?;
+            Ok(format!("Cleaned {manager} package cache"))
+        }
+        HelperRequest::DeleteGenerations {
+            manager,
+            profile,
+            generations,
+        } => {
+            let Some(store_manager) = StoreManager::from_name(manager) else {
+                return Err(rejected(format!("unsupported store {manager}")));
+            };
+            store_manager.delete_generations(Path::new(profile), generations, false)?;
+            Ok(format!("Deleted {} generation(s)", generations.len()))
+        }
+        HelperRequest::VacuumJournal { max_age_days } => {
+            let argument = format!("--vacuum-time={max_age_days}d");
+            let output = run_command("journalctl", &[argument.as_str()])?;
+            if !output.status.success() {
+                return Err(command_failed("journalctl", &output));
+            }
+            Ok("Journal vacuumed".to_string())
+        }
+        HelperRequest::RemovePath { path } => {
+            // Проверенный путь мог измениться: от разрешённого корня удаление
+            // спускается заново и не проходит ни одной ссылки.
+            let path = Path::new(path);
+            let root = validate_path(path)?;
+            remove_tree_beneath(root, path)?;
+            Ok(format!("Removed {}", path.display()))
+        }
+        HelperRequest::PruneLocales { paths } => {
+            // Список языков и настройки dpkg/dnf берутся из конфигурации root.
+            let config = Config::load(&Config::default_path()).unwrap_or_default();
+            locales::prune(paths, &config.locales, false)?;
+            Ok(format!("Removed {} locale director(ies)", paths.len()))
+        }
+    }
+}
+
+/// Проверяет запрос по белым спискам без обращения к файловой системе,
+/// кроме разрешения родительского каталога пути.
+pub fn validate(request: &HelperRequest) -> Result<()> {
+    match request {
+        HelperRequest::Ping => Ok(()),
+        HelperRequest::RemovePackages { manager, packages }
+        | HelperRequest::RemoveKernels { manager, packages } => {
+            if !ALLOWED_MANAGERS.contains(&manager.as_str()) {
+                return Err(rejected(format!("unsupported package manager {manager}")));
+            }
+            if KERNEL_ONLY_MANAGERS.contains(&manager.as_str())
+                && matches!(request, HelperRequest::RemovePackages { .. })
+            {
+                return Err(rejected(format!("{manager} removes only kernels")));
+            }
+            if packages.is_empty() {
+                return Err(rejected("empty package list".to_string()));
+            }
+            if let Some(name) = packages.iter().find(|name| !is_valid_package_name(name)) {
+                return Err(rejected(format!("invalid package name {name:?}")));
+            }
+            Ok(())
+        }
+        HelperRequest::CleanPackageCache { manager } => {
+            if !CACHE_MANAGERS.contains(&manager.as_str()) {
+                return Err(rejected(format!("unsupported package cache {manager}")));
+            }
+            Ok(())
+        }
+        HelperRequest::DeleteGenerations {
+            manager,
+            profile,
+            generations,
+        } => {
+            if StoreManager::from_name(manager).is_none() {
+                return Err(rejected(format!("unsupported store {manager}")));
+            }
+            if !store::is_system_profile(Path::new(profile)) {
+                return Err(rejected(format!("{profile} is not a system profile")));
+            }
+            if generations.is_empty() || generations.contains(&0) {
+                return Err(rejected("invalid generation list".to_string()));
+            }
+            Ok(())
+        }
+        HelperRequest::VacuumJournal { max_age_days } => {
+            if *max_age_days == 0 || *max_age_days > MAX_JOURNAL_AGE_DAYS {
+                return Err(rejected(format!("invalid journal age {max_age_days}")));
+            }
+            Ok(())
+        }
+        HelperRequest::RemovePath { path } => validate_path(Path::new(path)).map(|_| ()),
+        HelperRequest::PruneLocales { paths } => {
+            if paths.is_empty() {
+                return Err(rejected("empty path list".to_string()));
+            }
+            paths
+                .iter()
+                .try_for_each(|path| validate_locale_path(Path::new(path)))
+        }
+    }
+}
+
+/// Допустимо ли имя пакета (без пробелов, опций и метасимволов).
+pub fn is_valid_package_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    name.len() <= 256
+        && first.is_ascii_alphanumeric()
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || "+-._:@~/".contains(ch))
+        && !name.contains("..")
+}
+
+/// Проверяет путь и возвращает разрешённый корень, внутри которого он лежит.
+fn validate_path(path: &Path) -> Result<&'static Path> {
+    if !path.is_absolute() {
+        return Err(rejected(format!("relative path {}", path.display())));
+    }
+    if path
+        .components()
+        .any(|component| matches!(component, Component::ParentDir | Component::CurDir))
+    {
+        return Err(rejected(format!("non-normalized path {}", path.display())));
+    }
+
+    let Some(root) = ALLOWED_PATH_ROOTS
+        .iter()
+        .map(|root| Path::new(*root))
+        .find(|root| path.starts_with(root) && path != *root)
+    else {
+        return Err(rejected(format!(
+            "{} is outside allowed roots",
+            path.display()
+        )));
+    };
+
+    // Родитель может быть символической ссылкой за пределы корня.
+    let parent = path
+        .parent()
+        .ok_or_else(|| rejected(format!("no parent for {}", path.display())))?;
+    let resolved_parent = fs::canonicalize(parent)?;
+    let resolved_root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    if !resolved_parent.starts_with(&resolved_root) {
+        return Err(rejected(format!(
+            "{} resolves outside {}",
+            path.display(),
+            root.display()
+        )));
+    }
+    Ok(root)
+}
+
+/// Путь должен быть каталогом языка или документации, а его родитель —
+/// настоящим каталогом, а не ссылкой в другое место.
+fn validate_locale_path(path: &Path) -> Result<()> {
+    if locales::classify(path).is_none() {
+        return Err(rejected(format!(
+            "{} is not a translation or documentation directory",
+            path.display()
+        )));
+    }
+    let parent = path
+        .parent()
+        .ok_or_else(|| rejected(format!("no parent for {}", path.display())))?;
+    if fs::canonicalize(parent)? != parent {
+        return Err(rejected(format!(
+            "{} resolves outside {}",
+            path.display(),
+            parent.display()
+        )));
+    }
+    Ok(())
+}
+
+fn check_safety(request: &HelperRequest) -> Result<()> {
+    let items = match request {
+        HelperRequest::Ping => return Ok(()),
+        HelperRequest::RemovePackages { manager, packages } => packages
+            .iter()
+            .map(|name| {
+                helper_item(
+                    name,
+                    None,
+                    CleanupCategory::OldPackages,
+                    CleanupSource::PackageManager(manager.clone()),
+                )
+            })
+            .collect(),
+        HelperRequest::RemoveKernels { manager, packages } => packages
+            .iter()
+            .map(|name| {
+                helper_item(
+                    name,
+                    None,
+                    CleanupCategory::OldKernels,
+                    CleanupSource::PackageManager(manager.clone()),
+                )
+            })
+            .collect(),
+        HelperRequest::CleanPackageCache { manager } => vec![helper_item(
+            &format!("{manager}-cache"),
+            None,
+            CleanupCategory::Cache,
+            CleanupSource::PackageManager(manager.clone()),
+        )],
+        HelperRequest::DeleteGenerations {
+            manager,
+            profile,
+            generations,
+        } => {
+            let Some(store_manager) = StoreManager::from_name(manager) else {
+                return Err(rejected(format!("unsupported store {manager}")));
+            };
+            // Окно хранения берётся из конфигурации root, а не из запроса.
+            let keep = Config::load(&Config::default_path())
+                .unwrap_or_default()
+                .current_profile()
+                .keep_generations;
+            check_generations(Path::new(profile), generations, keep)?;
+            generations
+                .iter()
+                .map(|&number| {
+                    helper_item(
+                        &store::generation_id(store_manager, Path::new(profile), number),
+                        None,
+                        CleanupCategory::OldPackages,
+                        CleanupSource::PackageManager(manager.clone()),
+                    )
+                })
+                .collect()
+        }
+        HelperRequest::VacuumJournal { .. } => vec![helper_item(
+            "systemd-journal",
+            None,
+            CleanupCategory::Logs,
+            CleanupSource::FileSystem,
+        )],
+        HelperRequest::RemovePath { path } => {
+            let category = if path.starts_with("/var/log") {
+                CleanupCategory::Logs
+            } else {
+                CleanupCategory::TempFiles
+            };
+            vec![helper_item(
+                path,
+                Some(path.clone()),
+                category,
+                CleanupSource::FileSystem,
+            )]
+        }
+        HelperRequest::PruneLocales { paths } => paths
+            .iter()
+            .map(|path| {
+                helper_item(
+                    path,
+                    Some(path.clone()),
+                    CleanupCategory::Locales,
+                    CleanupSource::FileSystem,
+                )
+            })
+            .collect(),
+    };
+
+    let mut config = Config::load(&Config::default_path()).unwrap_or_default();
+    // Чужие временные файлы и есть цель класса system temp; остальные
+    // классы по-прежнему не трогают файлы других пользователей.
+    if request.class() == Some(OperationClass::SystemTemp) {
+        config.protect.other_users = false;
+    }
+    let checker = SafetyChecker::new(config);
+    let removing = checker.removal_sets(&items);
+    for mut item in items {
+        checker.apply_with_removal(&mut item, &removing)?;
+        if !item.can_clean {
+            let reason = item
+                .blocked_reason
+                .unwrap_or_else(|| "blocked by safety rules".to_string());
+            return Err(rejected(format!("{}: {}", item.name, reason)));
+        }
+    }
+    Ok(())
+}
+
+/// Пропускает только поколения, которые сам helper считает старыми:
+/// активное поколение и `keep` последних удалить нельзя.
+fn check_generations(profile: &Path, generations: &[u32], keep: usize) -> Result<()> {
+    let removable: Vec<u32> = store::old_generations(store::list_generations(profile), keep)
+        .iter()
+        .map(|generation| generation.number)
+        .collect();
+    match generations
+        .iter()
+        .find(|number| !removable.contains(number))
+    {
+        Some(number) => Err(rejected(format!(
+            "generation {number} of {} is current or within the keep window",
+            profile.display()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn helper_item(
+    name: &str,
+    path: Option<String>,
+    category: CleanupCategory,
+    source: CleanupSource,
+) -> CleanupItem {
+    CleanupItem {
+        id: name.to_string(),
+        name: name.to_string(),
+        path,
+        size: 0,
+        description: String::new(),
+        category,
+        source,
+        selected: true,
+        can_clean: true,
+        blocked_reason: None,
+        dependencies: Vec::new(),
+        verdicts: Vec::new(),
+    }
+}
+
+/// Удаляет пакеты указанным менеджером в текущем процессе.
+pub(crate) fn remove_packages(manager: &str, packages: &[String], dry_run: bool) -> Result<()> {
+    match manager {
+        "apt" => apt::remove_packages(packages, dry_run),
+        "dnf" => dnf::remove_packages(packages, dry_run),
+        "rpm" => rpm::remove_packages(packages, dry_run),
+        "pacman" => pacman::remove_packages(packages, dry_run),
+        "zypper" => zypper::remove_packages(packages, dry_run),
+        "apk" => apk::remove_packages(packages, dry_run),
+        "xbps" => xbps::remove_packages(packages, dry_run),
+        "vkpurge" => xbps::purge_kernels(packages, dry_run),
+        "kernel-files" => kernels::remove_leftovers(packages, dry_run),
+        "dkms" => kernels::remove_dkms_builds(packages, dry_run),
+        "snap" => snap::remove_packages(packages, dry_run),
+        "flatpak" => flatpak::remove_packages(packages, dry_run),
+        "rpm-ostree" => rpm_ostree::remove_packages(packages, dry_run),
+        other => Err(rejected(format!("unsupported package manager {other}"))),
+    }
+}
+
+/// Очищает кэш пакетов указанного менеджера в текущем процессе.
+pub(crate) fn clean_package_cache(manager: &str, dry_run: bool) -> Result<()> {
+    match manager {
+        "zypper" => zypper::clean_cache(dry_run),
+        "apk" => apk::clean_cache(dry_run),
+        "xbps" => xbps::clean_cache(dry_run),
+        other => Err(rejected(format!("unsupported package cache {other}"))),
+    }
+}
+
+fn rejected(reason: String) -> RcleanerError {
+    RcleanerError::Permission(format!("helper rejected request: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_rejects_unexpected_requests() {
+        let request = |path: &str| HelperRequest::RemovePath {
+            path: path.to_string(),
+        };
+        assert!(validate(&request("/etc/passwd")).is_err());
+        assert!(validate(&request("/var/log")).is_err());
+        assert!(validate(&request("/var/log/../../etc/shadow")).is_err());
+        assert!(validate(&request("var/log/syslog.1")).is_err());
+        assert!(validate(&request("/tmp/rcleaner-helper-test")).is_ok());
+        let link =
+            std::env::temp_dir().join(format!("rcleaner-helper-link-{}", std::process::id()));
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+        let through_link = link.join("passwd").to_string_lossy().to_string();
+        assert!(validate(&request(&through_link)).is_err());
+        fs::remove_file(&link).unwrap();
+
+        let packages = |manager: &str, name: &str| HelperRequest::RemovePackages {
+            manager: manager.to_string(),
+            packages: vec![name.to_string()],
+        };
+        assert!(validate(&packages("apt", "linux-image-6.1.0-13-amd64")).is_ok());
+        assert!(validate(&packages("apt", "--purge")).is_err());
+        assert!(validate(&packages("apt", "foo; rm -rf /")).is_err());
+        assert!(validate(&packages("sh", "foo")).is_err());
+        assert!(validate(&HelperRequest::VacuumJournal { max_age_days: 0 }).is_err());
+
+        let locales = |path: &str| HelperRequest::PruneLocales {
+            paths: vec![path.to_string()],
+        };
+        assert!(validate(&locales("/usr/share/locale/..")).is_err());
+        assert!(validate(&locales("/usr/share/man/man1")).is_err());
+        assert!(validate(&locales("/usr/share/icons")).is_err());
+    }
+
+    #[test]
+    fn test_check_generations_keeps_current_and_recent() {
+        let dir = std::env::temp_dir().join(format!("rcleaner-helper-gen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for number in 1..=5 {
+            std::os::unix::fs::symlink("/nonexistent", dir.join(format!("system-{number}-link")))
+                .unwrap();
+        }
+        // Активно второе поколение, например после отката.
+        std::os::unix::fs::symlink("system-2-link", dir.join("system")).unwrap();
+        let profile = dir.join("system");
+
+        assert!(check_generations(&profile, &[1, 3], 2).is_ok());
+        assert!(check_generations(&profile, &[2], 2).is_err());
+        assert!(check_generations(&profile, &[4], 2).is_err());
+        assert!(check_generations(&profile, &[1, 9], 2).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_system_temp_accepts_other_users_files() {
+        if !is_root() {
+            return;
+        }
+        let path = format!("/tmp/rcleaner-helper-other-{}", std::process::id());
+        fs::write(&path, b"x").unwrap();
+        std::os::unix::fs::chown(&path, Some(4242), Some(4242)).unwrap();
+
+        let request = HelperRequest::RemovePath { path: path.clone() };
+        assert_eq!(request.class(), Some(OperationClass::SystemTemp));
+        let checked = check_safety(&request);
+        fs::remove_file(&path).unwrap();
+        checked.unwrap();
+    }
+
+    #[test]
+    fn test_serve_answers_each_line() {
+        let input = "{\"op\":\"ping\"}\nnot json\n{\"op\":\"vacuum_journal\",\"max_age_days\":7}\n";
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, OperationClass::Packages).unwrap();
+
+        let responses: Vec<HelperResponse> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 3);
+        assert!(responses[0].ok);
+        assert!(!responses[1].ok);
+        // Помощник для пакетов не чистит журнал.
+        assert!(!responses[2].ok);
+    }
+}
@@ -0,0 +1,335 @@
+//! Разделение привилегий между интерфейсом и root-помощником.
+//!
+//! Интерфейс работает от имени пользователя. Операции, которым нужен root
+//! (удаление пакетов, очистка журнала systemd, удаление файлов в системных
+//! каталогах), отправляются помощнику, запущенному через pkexec. Для каждого
+//! [`OperationClass`] запускается свой помощник со своим действием polkit
+//! (`data/org.rcleaner.policy`), и права запрашиваются только для классов,
+//! которые действительно нужны выбранным элементам:
+//!
+//! - [`protocol`] - запросы и ответы
+//! - [`helper`] - проверка и выполнение запросов на стороне root
+//! - [`client`] - запуск помощника и обмен с ним
+//!
+//! Если rCleaner уже запущен от root, запросы выполняются в этом же процессе
+//! с теми же проверками.
+
+pub mod client;
+pub mod helper;
+pub mod protocol;
+
+use crate::backup::journal::{self, JournalRecord};
+use crate::cleaner::temp_files::SYSTEM_TEMP_ID_PREFIX;
+use crate::config::Config;
+use crate::error::{RcleanerError, Result};
+use crate::models::{CleanupCategory, CleanupItem, CleanupSource};
+use crate::system::detection::command_exists;
+use crate::system::locales;
+use crate::system::store::{self, StoreManager};
+use crate::utils::command::is_root;
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+use self::client::HelperClient;
+use self::protocol::HelperRequest;
+pub use self::protocol::OperationClass;
+
+/// Помощники текущей сессии по классам операций.
+static CLIENTS: Mutex<BTreeMap<OperationClass, HelperClient>> = Mutex::new(BTreeMap::new());
+
+/// Можно ли выполнять операции root: процесс уже root или есть pkexec.
+pub fn can_elevate() -> bool {
+    is_root() || command_exists("pkexec")
+}
+
+/// Выполняет запрос с правами root и возвращает сообщение помощника.
+pub fn run(request: HelperRequest) -> Result<String> {
+    if is_root() {
+        return helper::execute(&request);
+    }
+    // Проверяем запрос до запуска pkexec, чтобы не спрашивать пароль зря.
+    helper::validate(&request)?;
+    let Some(class) = request.class() else {
+        return Ok("pong".to_string());
+    };
+
+    let mut clients = lock_clients()?;
+    let client = match clients.entry(class) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(HelperClient::spawn(class)?),
+    };
+
+    match client.call(&request) {
+        Ok(response) if response.ok => Ok(response.message),
+        Ok(response) => Err(RcleanerError::Permission(response.message)),
+        Err(err) => {
+            clients.remove(&class);
+            Err(err)
+        }
+    }
+}
+
+/// Класс привилегированной операции, которая понадобится для элемента.
+pub fn required_class(item: &CleanupItem) -> Option<OperationClass> {
+    match (&item.category, &item.source) {
+        (CleanupCategory::OldKernels, _) => Some(OperationClass::Kernels),
+        (CleanupCategory::Locales, _) => Some(OperationClass::Locales),
+        (CleanupCategory::TempFiles, _) if item.id.starts_with(SYSTEM_TEMP_ID_PREFIX) => {
+            Some(OperationClass::SystemTemp)
+        }
+        // Профили пользователя и сборку мусора Nix/Guix root не требуют.
+        (_, CleanupSource::PackageManager(manager))
+            if StoreManager::from_name(manager).is_some() =>
+        {
+            store::parse_generation_id(&item.id)
+                .filter(|(_, profile, _)| store::is_system_profile(profile))
+                .map(|_| OperationClass::Packages)
+        }
+        (CleanupCategory::OldPackages, _) => Some(OperationClass::Packages),
+        (CleanupCategory::Cache, CleanupSource::PackageManager(_)) => {
+            Some(OperationClass::Packages)
+        }
+        (_, CleanupSource::PackageManager(manager)) if manager == "snap" => {
+            Some(OperationClass::Packages)
+        }
+        (CleanupCategory::Logs, _)
+            if item.id == "systemd-journal"
+                || item
+                    .path
+                    .as_deref()
+                    .is_some_and(|path| Path::new(path).starts_with("/var/log")) =>
+        {
+            Some(OperationClass::SystemLogs)
+        }
+        _ => None,
+    }
+}
+
+/// Классы операций, для которых нужно повысить права перед очисткой.
+///
+/// Пусто, если процесс уже работает от root.
+pub fn required_classes<'a>(
+    items: impl IntoIterator<Item = &'a CleanupItem>,
+) -> Vec<OperationClass> {
+    if is_root() {
+        return Vec::new();
+    }
+    let mut classes: Vec<_> = items.into_iter().filter_map(required_class).collect();
+    classes.sort();
+    classes.dedup();
+    classes
+}
+
+/// Получены ли уже права для класса операций.
+pub fn is_authorized(class: OperationClass) -> bool {
+    is_root() || lock_clients().is_ok_and(|clients| clients.contains_key(&class))
+}
+
+/// Заранее запускает помощников для классов, запрашивая подтверждение polkit.
+///
+/// Вызывается после подтверждения очистки, пока терминал свободен для
+/// запроса пароля. Возвращает классы, для которых права не получены.
+pub fn authorize(classes: &[OperationClass]) -> Result<Vec<OperationClass>> {
+    let mut denied = Vec::new();
+    if is_root() {
+        return Ok(denied);
+    }
+    let mut clients = lock_clients()?;
+    for &class in classes {
+        if clients.contains_key(&class) {
+            continue;
+        }
+        match HelperClient::spawn(class) {
+            Ok(client) => {
+                clients.insert(class, client);
+            }
+            Err(err) => {
+                log::warn!("Elevation for {} failed: {}", class.label(), err);
+                denied.push(class);
+            }
+        }
+    }
+    Ok(denied)
+}
+
+/// Удаляет пакеты с правами root.
+///
+/// Симуляция выполняется без повышения прав.
+pub fn remove_packages(manager: &str, packages: &[String], dry_run: bool) -> Result<()> {
+    if dry_run {
+        return helper::remove_packages(manager, packages, true);
+    }
+    run(HelperRequest::RemovePackages {
+        manager: manager.to_string(),
+        packages: packages.to_vec(),
+    })
+    .map(|_| ())
+}
+
+/// Удаляет пакеты старых ядер с правами root.
+///
+/// Симуляция выполняется без повышения прав.
+pub fn remove_kernels(manager: &str, packages: &[String], dry_run: bool) -> Result<()> {
+    if dry_run {
+        return helper::remove_packages(manager, packages, true);
+    }
+    run(HelperRequest::RemoveKernels {
+        manager: manager.to_string(),
+        packages: packages.to_vec(),
+    })
+    .map(|_| ())
+}
+
+/// Очищает кэш скачанных пакетов с правами root.
+///
+/// Симуляция выполняется без повышения прав.
+pub fn clean_package_cache(manager: &str, dry_run: bool) -> Result<()> {
+    if dry_run {
+        return helper::clean_package_cache(manager, true);
+    }
+    run(HelperRequest::CleanPackageCache {
+        manager: manager.to_string(),
+    })
+    .map(|_| ())
+}
+
+/// Удаляет поколения профиля Nix/Guix.
+///
+/// Root нужен только для системного профиля; профили пользователя и
+/// симуляция обрабатываются в текущем процессе.
+pub fn delete_generations(
+    manager: StoreManager,
+    profile: &Path,
+    generations: &[u32],
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run || !store::is_system_profile(profile) {
+        return manager.delete_generations(profile, generations, dry_run);
+    }
+    run(HelperRequest::DeleteGenerations {
+        manager: manager.name().to_string(),
+        profile: profile.to_string_lossy().to_string(),
+        generations: generations.to_vec(),
+    })
+    .map(|_| ())
+}
+
+/// Очищает журнал systemd старше `max_age_days` дней с правами root.
+pub fn vacuum_journal(max_age_days: u32) -> Result<()> {
+    run(HelperRequest::VacuumJournal { max_age_days }).map(|_| ())
+}
+
+/// Безвозвратно удаляет путь в системном каталоге с правами root.
+pub fn remove_path(path: &Path) -> Result<()> {
+    let path = path.to_string_lossy().to_string();
+    run(HelperRequest::RemovePath { path: path.clone() })?;
+    journal::record(JournalRecord::Removed {
+        path,
+        moved_to: None,
+    });
+    Ok(())
+}
+
+/// Удаляет каталоги переводов и документации с правами root.
+///
+/// Помощник проверяет пути по конфигурации root; симуляция выполняется без
+/// повышения прав по конфигурации пользователя.
+pub fn prune_locales(paths: &[String], dry_run: bool) -> Result<()> {
+    if dry_run {
+        let config = Config::load(&Config::default_path()).unwrap_or_default();
+        return locales::prune(paths, &config.locales, true);
+    }
+    run(HelperRequest::PruneLocales {
+        paths: paths.to_vec(),
+    })?;
+    for path in paths {
+        journal::record(JournalRecord::Removed {
+            path: path.clone(),
+            moved_to: None,
+        });
+    }
+    Ok(())
+}
+
+/// Завершает помощников текущей сессии.
+pub fn shutdown() {
+    if let Ok(mut clients) = CLIENTS.lock() {
+        clients.clear();
+    }
+}
+
+fn lock_clients() -> Result<MutexGuard<'static, BTreeMap<OperationClass, HelperClient>>> {
+    CLIENTS
+        .lock()
+        .map_err(|_| RcleanerError::Command("privileged helper lock poisoned".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_declares_every_class() {
+        let policy = include_str!("../../data/org.rcleaner.policy");
+        for class in OperationClass::ALL {
+            assert!(policy.contains(&format!("<action id=\"{}\">", class.action_id())));
+            assert!(policy.contains(&format!(">{}</annotate>", class.helper_arg())));
+            assert_eq!(
+                OperationClass::from_helper_arg(class.helper_arg()),
+                Some(class)
+            );
+        }
+    }
+
+    #[test]
+    fn test_request_classes() {
+        let path = |path: &str| HelperRequest::RemovePath {
+            path: path.to_string(),
+        };
+        assert_eq!(
+            path("/var/log/syslog.1").class(),
+            Some(OperationClass::SystemLogs)
+        );
+        assert_eq!(
+            path("/var/tmp/old").class(),
+            Some(OperationClass::SystemTemp)
+        );
+        assert_eq!(
+            HelperRequest::VacuumJournal { max_age_days: 7 }.class(),
+            Some(OperationClass::SystemLogs)
+        );
+        assert_eq!(
+            HelperRequest::PruneLocales {
+                paths: vec!["/usr/share/locale/de".to_string()]
+            }
+            .class(),
+            Some(OperationClass::Locales)
+        );
+        assert_eq!(HelperRequest::Ping.class(), None);
+    }
+
+    #[test]
+    fn test_other_users_temp_files_need_system_temp() {
+        let item = |id: &str| CleanupItem {
+            id: id.to_string(),
+            name: "temp".to_string(),
+            path: Some("/tmp".to_string()),
+            size: 1,
+            description: String::new(),
+            category: CleanupCategory::TempFiles,
+            source: CleanupSource::FileSystem,
+            selected: true,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        };
+        assert_eq!(required_class(&item("/tmp")), None);
+        assert_eq!(
+            required_class(&item(&format!("{SYSTEM_TEMP_ID_PREFIX}/tmp"))),
+            Some(OperationClass::SystemTemp)
+        );
+    }
+}
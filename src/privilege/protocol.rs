@@ -0,0 +1,151 @@
+//! Протокол обмена между интерфейсом и root-помощником.
+//!
+//! Запросы и ответы передаются построчно в JSON через stdin/stdout помощника.
+
+use serde::{Deserialize, Serialize};
+
+/// Класс привилегированных операций.
+///
+/// Каждому классу соответствует отдельное действие polkit, поэтому
+/// администратор может, например, разрешить очистку журнала без права
+/// удалять пакеты.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OperationClass {
+    /// Удаление пакетов.
+    Packages,
+    /// Удаление старых ядер.
+    Kernels,
+    /// Очистка системных логов и журнала systemd.
+    SystemLogs,
+    /// Удаление чужих старых файлов в `/tmp` и `/var/tmp`.
+    SystemTemp,
+    /// Удаление переводов и документации в `/usr/share`.
+    Locales,
+}
+
+impl OperationClass {
+    pub const ALL: [Self; 5] = [
+        Self::Packages,
+        Self::Kernels,
+        Self::SystemLogs,
+        Self::SystemTemp,
+        Self::Locales,
+    ];
+
+    /// Идентификатор действия polkit.
+    pub fn action_id(self) -> &'static str {
+        match self {
+            Self::Packages => "org.rcleaner.packages",
+            Self::Kernels => "org.rcleaner.kernels",
+            Self::SystemLogs => "org.rcleaner.system-logs",
+            Self::SystemTemp => "org.rcleaner.system-temp",
+            Self::Locales => "org.rcleaner.locales",
+        }
+    }
+
+    /// Аргумент командной строки, с которым запускается помощник класса.
+    ///
+    /// По нему polkit выбирает действие (`org.freedesktop.policykit.exec.argv1`).
+    pub fn helper_arg(self) -> &'static str {
+        match self {
+            Self::Packages => "helper-packages",
+            Self::Kernels => "helper-kernels",
+            Self::SystemLogs => "helper-system-logs",
+            Self::SystemTemp => "helper-system-temp",
+            Self::Locales => "helper-locales",
+        }
+    }
+
+    pub fn from_helper_arg(arg: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.helper_arg() == arg)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Packages => "packages",
+            Self::Kernels => "kernels",
+            Self::SystemLogs => "system logs",
+            Self::SystemTemp => "system temp",
+            Self::Locales => "locales",
+        }
+    }
+}
+
+/// Операция, которую интерфейс просит выполнить с правами root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HelperRequest {
+    /// Проверка связи после запуска помощника.
+    Ping,
+    /// Удаление пакетов через пакетный менеджер.
+    RemovePackages {
+        manager: String,
+        packages: Vec<String>,
+    },
+    /// Удаление пакетов старых ядер через пакетный менеджер.
+    RemoveKernels {
+        manager: String,
+        packages: Vec<String>,
+    },
+    /// Очистка кэша скачанных пакетов пакетного менеджера.
+    CleanPackageCache { manager: String },
+    /// Удаление поколений системного профиля Nix или Guix.
+    DeleteGenerations {
+        manager: String,
+        profile: String,
+        generations: Vec<u32>,
+    },
+    /// Очистка журнала systemd старше указанного числа дней.
+    VacuumJournal { max_age_days: u32 },
+    /// Удаление файла или каталога внутри разрешённого корня.
+    RemovePath { path: String },
+    /// Удаление каталогов переводов и документации (см. [`crate::system::locales`]).
+    PruneLocales { paths: Vec<String> },
+}
+
+impl HelperRequest {
+    /// Класс операции; `None` для служебных запросов.
+    pub fn class(&self) -> Option<OperationClass> {
+        match self {
+            Self::Ping => None,
+            Self::RemovePackages { .. } => Some(OperationClass::Packages),
+            Self::RemoveKernels { .. } => Some(OperationClass::Kernels),
+            Self::CleanPackageCache { .. } => Some(OperationClass::Packages),
+            Self::DeleteGenerations { .. } => Some(OperationClass::Packages),
+            Self::VacuumJournal { .. } => Some(OperationClass::SystemLogs),
+            Self::RemovePath { path } if path.starts_with("/var/log/") => {
+                Some(OperationClass::SystemLogs)
+            }
+            Self::RemovePath { .. } => Some(OperationClass::SystemTemp),
+            Self::PruneLocales { .. } => Some(OperationClass::Locales),
+        }
+    }
+}
+
+/// Ответ помощника.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HelperResponse {
+    /// Выполнена ли операция.
+    pub ok: bool,
+    /// Описание результата или причина отказа.
+    pub message: String,
+}
+
+impl HelperResponse {
+    pub fn success(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+        }
+    }
+
+    pub fn failure(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            message: message.into(),
+        }
+    }
+}
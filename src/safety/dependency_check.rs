@@ -1,40 +1,91 @@
+//! Проверка обратных зависимостей пакетов.
+//!
+//! Граф каждого менеджера читается один раз за время жизни
+//! [`DependencyResolver`] (то есть за одно сканирование).
+
 use crate::error::Result;
-use crate::system::package_manager::PackageManager;
-use crate::system::{apt::AptManager, dnf::DnfManager, pacman::PacmanManager, rpm::RpmManager};
+use crate::system::dependency_graph::DependencyGraph;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-pub fn check_dependencies_for_manager(manager: &str, package: &str) -> Result<Vec<String>> {
-    match manager {
-        "apt" => {
-            if command_exists("apt-cache") {
-                AptManager::new().check_dependencies(package)
-            } else {
-                Ok(Vec::new())
-            }
-        }
-        "dnf" => {
-            if command_exists("dnf") {
-                DnfManager::new().check_dependencies(package)
-            } else {
-                Ok(Vec::new())
+/// Результат проверки зависимостей одного пакета.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DependencyStatus {
+    /// Все пакеты, которые напрямую требуют этот.
+    pub dependents: Vec<String>,
+    /// Зависящие пакеты, которые остаются в системе.
+    pub blocking: Vec<String>,
+}
+
+/// Кэш графов зависимостей по пакетным менеджерам.
+#[derive(Default)]
+pub struct DependencyResolver {
+    graphs: Mutex<HashMap<String, Arc<DependencyGraph>>>,
+}
+
+impl DependencyResolver {
+    /// Проверяет пакет при условии, что вместе с ним удаляются `removing`.
+    pub fn check(
+        &self,
+        manager: &str,
+        package: &str,
+        removing: &HashSet<String>,
+    ) -> Result<DependencyStatus> {
+        let graph = self.graph(manager)?;
+        Ok(DependencyStatus {
+            dependents: graph.dependents(package),
+            blocking: graph.blocking_dependents(package, removing),
+        })
+    }
+
+    /// Оставляет в наборе только пакеты, которые можно удалить вместе:
+    /// пакет, нужный оставшемуся пакету, исключается, пока набор не перестанет
+    /// меняться.
+    pub fn removable_subset(
+        &self,
+        manager: &str,
+        mut packages: HashSet<String>,
+    ) -> Result<HashSet<String>> {
+        let graph = self.graph(manager)?;
+        loop {
+            let blocked: Vec<String> = packages
+                .iter()
+                .filter(|package| !graph.blocking_dependents(package, &packages).is_empty())
+                .cloned()
+                .collect();
+            if blocked.is_empty() {
+                return Ok(packages);
             }
-        }
-        "pacman" => {
-            if command_exists("pacman") {
-                PacmanManager::new().check_dependencies(package)
-            } else {
-                Ok(Vec::new())
+            for package in blocked {
+                packages.remove(&package);
             }
         }
-        "rpm" => {
-            if command_exists("rpm") {
-                RpmManager::new().check_dependencies(package)
-            } else {
-                Ok(Vec::new())
-            }
+    }
+
+    fn graph(&self, manager: &str) -> Result<Arc<DependencyGraph>> {
+        let mut graphs = self.graphs.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(graph) = graphs.get(manager) {
+            return Ok(graph.clone());
         }
-        _ => Ok(Vec::new()),
+        let graph = if command_exists(query_command(manager)) {
+            Arc::new(DependencyGraph::load(manager)?)
+        } else {
+            Arc::default()
+        };
+        graphs.insert(manager.to_string(), graph.clone());
+        Ok(graph)
+    }
+}
+
+/// Команда, через которую читается база пакетов менеджера.
+fn query_command(manager: &str) -> &str {
+    match manager {
+        "apt" => "dpkg-query",
+        "dnf" | "zypper" => "rpm",
+        "xbps" => "xbps-query",
+        other => other,
     }
 }
 
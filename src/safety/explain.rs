@@ -0,0 +1,99 @@
+//! Объяснение решения проверок безопасности (`rcleaner explain <path>`).
+
+use super::SafetyChecker;
+use crate::config::Config;
+use crate::error::Result;
+use crate::models::{CleanupCategory, CleanupItem, CleanupSource};
+use crate::utils::cache::load_cached_items;
+use crate::utils::disk_size::allocated_size;
+use std::path::Path;
+
+/// Проверяет путь и возвращает элемент со всеми результатами проверок.
+///
+/// Относительный путь сначала дополняется текущим каталогом (последняя
+/// символическая ссылка не разыменовывается). Если путь есть в последнем
+/// сканировании, берётся найденный элемент (с его категорией и источником),
+/// иначе элемент собирается по пути.
+pub fn explain_path(path: &Path, category: Option<CleanupCategory>) -> Result<CleanupItem> {
+    let path = &std::path::absolute(path)?;
+    let path_str = path.to_string_lossy().to_string();
+    let cached = load_cached_items().ok().flatten().and_then(|items| {
+        items
+            .into_iter()
+            .find(|item| item.path.as_deref() == Some(path_str.as_str()))
+    });
+
+    let mut item = cached.unwrap_or_else(|| item_for_path(path, &path_str));
+    if let Some(category) = category {
+        item.category = category;
+    }
+    item.can_clean = true;
+    item.blocked_reason = None;
+
+    let config = Config::load(&Config::default_path()).unwrap_or_default();
+    SafetyChecker::new(config).apply_to_item(&mut item)?;
+    Ok(item)
+}
+
+/// Строки трассировки решения для вывода в терминал.
+pub fn format_trace(item: &CleanupItem) -> Vec<String> {
+    let mut lines = vec![
+        format!(
+            "{} ({:?})",
+            item.path.as_deref().unwrap_or(&item.name),
+            item.category
+        ),
+        if item.can_clean {
+            "Decision: can be cleaned".to_string()
+        } else {
+            format!(
+                "Decision: blocked ({})",
+                item.blocked_reason.as_deref().unwrap_or("safety rules")
+            )
+        },
+    ];
+    lines.extend(item.verdicts.iter().map(|verdict| format!("  {verdict}")));
+    lines
+}
+
+fn item_for_path(path: &Path, path_str: &str) -> CleanupItem {
+    let category = if path_str.starts_with("/var/log") || path_str.ends_with(".log") {
+        CleanupCategory::Logs
+    } else if path_str.starts_with("/tmp") || path_str.starts_with("/var/tmp") {
+        CleanupCategory::TempFiles
+    } else {
+        CleanupCategory::Cache
+    };
+    CleanupItem {
+        id: path_str.to_string(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path_str.to_string()),
+        path: Some(path_str.to_string()),
+        size: allocated_size(path),
+        description: String::new(),
+        category,
+        source: CleanupSource::FileSystem,
+        selected: false,
+        can_clean: true,
+        blocked_reason: None,
+        dependencies: Vec::new(),
+        verdicts: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explain_resolves_relative_path() {
+        let relative = Path::new("rcleaner-explain-missing.log");
+        let item = explain_path(relative, None).unwrap();
+
+        let expected = std::env::current_dir().unwrap().join(relative);
+        assert_eq!(item.path.as_deref(), Some(expected.to_str().unwrap()));
+        assert_eq!(item.category, CleanupCategory::Logs);
+    }
+}
@@ -0,0 +1,338 @@
+//! Glob-паттерны правил безопасности в стиле gitignore.
+//!
+//! Поддерживаются `*`, `?`, `**`, классы `[abc]`/`[!a-z]`, альтернативы
+//! `{a,b}`, экранирование `\` и отрицание `!`. Сравнение идёт по компонентам
+//! пути:
+//!
+//! - паттерн без `/` совпадает с любым компонентом (`*.log`, `node_modules`);
+//! - паттерн с `/` в начале привязан к корню (`/var/lib/*`), относительный
+//!   паттерн с `/` совпадает с любой границы компонентов (`.cache/thumbnails`);
+//! - совпадение с каталогом распространяется на всё его содержимое;
+//! - `/` в конце ограничивает совпадение каталогами.
+
+use crate::error::{RcleanerError, Result};
+use regex::{Regex, RegexSet};
+use std::path::Path;
+
+/// Имя группы с остатком пути под совпавшим каталогом.
+const REST_GROUP: &str = "rest";
+
+/// Скомпилированный glob-паттерн.
+#[derive(Debug, Clone)]
+pub struct GlobPattern {
+    regex: Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl GlobPattern {
+    /// Компилирует паттерн.
+    ///
+    /// # Errors
+    ///
+    /// Возвращает ошибку для пустого паттерна или некорректного класса символов.
+    pub fn new(pattern: &str) -> Result<Self> {
+        let (negated, body) = match pattern.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let dir_only = body.len() > 1 && body.ends_with('/');
+        let body = if dir_only {
+            body.trim_end_matches('/')
+        } else {
+            body
+        };
+        if body.is_empty() {
+            return Err(RcleanerError::Config(format!("empty pattern {pattern:?}")));
+        }
+
+        let prefix = if body.starts_with('/') {
+            "^"
+        } else {
+            "^(?:.*/)?"
+        };
+        let source = format!(
+            "{prefix}{}(?P<{REST_GROUP}>/.*)?$",
+            translate(&body.chars().collect::<Vec<_>>())
+        );
+        let regex = Regex::new(&source)
+            .map_err(|err| RcleanerError::Config(format!("invalid pattern {pattern:?}: {err}")))?;
+
+        Ok(Self {
+            regex,
+            negated,
+            dir_only,
+        })
+    }
+
+    /// Паттерн, в котором все символы понимаются буквально.
+    pub fn literal(pattern: &str) -> Self {
+        let source = format!(
+            "^{}(?P<{REST_GROUP}>/.*)?$",
+            regex::escape(pattern.trim_end_matches('/'))
+        );
+        Self {
+            regex: Regex::new(&source).expect("escaped pattern is a valid regex"),
+            negated: false,
+            dir_only: false,
+        }
+    }
+
+    /// Совпадает ли путь с паттерном.
+    pub fn is_match(&self, path: &str) -> bool {
+        self.matches_positive(path) != self.negated
+    }
+
+    fn matches_positive(&self, path: &str) -> bool {
+        let Some(captures) = self.regex.captures(path) else {
+            return false;
+        };
+        !self.dir_only || captures.name(REST_GROUP).is_some() || Path::new(path).is_dir()
+    }
+}
+
+/// Набор паттернов, проверяемых за один проход.
+#[derive(Debug, Clone)]
+pub struct GlobSet {
+    patterns: Vec<GlobPattern>,
+    set: RegexSet,
+}
+
+impl GlobSet {
+    /// Собирает набор из скомпилированных паттернов.
+    pub fn new(patterns: Vec<GlobPattern>) -> Self {
+        let set = RegexSet::new(patterns.iter().map(|pattern| pattern.regex.as_str()))
+            .expect("patterns were compiled individually");
+        Self { patterns, set }
+    }
+
+    /// Для каждого паттерна по порядку — совпадает ли он с путём.
+    pub fn matches(&self, path: &str) -> Vec<bool> {
+        let hits = self.set.matches(path);
+        self.patterns
+            .iter()
+            .enumerate()
+            .map(|(index, pattern)| {
+                (hits.matched(index) || pattern.negated) && pattern.is_match(path)
+            })
+            .collect()
+    }
+}
+
+/// Переводит glob в регулярное выражение (без якорей).
+fn translate(chars: &[char]) -> String {
+    let mut output = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let ch = chars[index];
+        match ch {
+            '\\' if index + 1 < chars.len() => {
+                output.push_str(&regex::escape(&chars[index + 1].to_string()));
+                index += 2;
+                continue;
+            }
+            '*' => {
+                let start = index;
+                while index < chars.len() && chars[index] == '*' {
+                    index += 1;
+                }
+                let at_boundary_before = start == 0 || chars[start - 1] == '/';
+                let at_end = index == chars.len();
+                let slash_after = !at_end && chars[index] == '/';
+                if index - start >= 2 && at_boundary_before && slash_after {
+                    output.push_str("(?:.*/)?");
+                    index += 1;
+                } else if index - start >= 2 && at_boundary_before && at_end {
+                    output.push_str(".*");
+                } else {
+                    output.push_str("[^/]*");
+                }
+                continue;
+            }
+            '?' => output.push_str("[^/]"),
+            '[' => {
+                if let Some((class, next)) = translate_class(chars, index) {
+                    output.push_str(&class);
+                    index = next;
+                    continue;
+                }
+                output.push_str(r"\[");
+            }
+            '{' => {
+                if let Some((alternatives, next)) = split_braces(chars, index) {
+                    let translated: Vec<_> = alternatives
+                        .iter()
+                        .map(|alternative| translate(alternative))
+                        .collect();
+                    output.push_str(&format!("(?:{})", translated.join("|")));
+                    index = next;
+                    continue;
+                }
+                output.push_str(r"\{");
+            }
+            _ => output.push_str(&regex::escape(&ch.to_string())),
+        }
+        index += 1;
+    }
+    output
+}
+
+/// Класс символов `[...]`; возвращает регулярное выражение и индекс после `]`.
+fn translate_class(chars: &[char], start: usize) -> Option<(String, usize)> {
+    let mut index = start + 1;
+    let negated = matches!(chars.get(index), Some('!' | '^'));
+    if negated {
+        index += 1;
+    }
+    let body_start = index;
+    // `]` сразу после открывающей скобки входит в класс.
+    if chars.get(index) == Some(&']') {
+        index += 1;
+    }
+    while index < chars.len() && chars[index] != ']' {
+        index += 1;
+    }
+    if index >= chars.len() {
+        return None;
+    }
+
+    let mut class = String::from(if negated { "[^/" } else { "[" });
+    for (offset, &ch) in chars[body_start..index].iter().enumerate() {
+        let position = body_start + offset;
+        let is_range = ch == '-' && position > body_start && position + 1 < index;
+        if is_range {
+            class.push('-');
+        } else if matches!(ch, '\\' | '[' | ']' | '^' | '-' | '&' | '~') {
+            class.push('\\');
+            class.push(ch);
+        } else {
+            class.push(ch);
+        }
+    }
+    class.push(']');
+    Some((class, index + 1))
+}
+
+/// Альтернативы `{a,b}` верхнего уровня; возвращает их и индекс после `}`.
+fn split_braces(chars: &[char], start: usize) -> Option<(Vec<Vec<char>>, usize)> {
+    let mut depth = 0;
+    let mut alternatives = vec![Vec::new()];
+    let mut index = start + 1;
+    while index < chars.len() {
+        let ch = chars[index];
+        match ch {
+            '\\' if index + 1 < chars.len() => {
+                let current = alternatives.last_mut()?;
+                current.push(ch);
+                current.push(chars[index + 1]);
+                index += 2;
+                continue;
+            }
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some((alternatives, index + 1)),
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                alternatives.push(Vec::new());
+                index += 1;
+                continue;
+            }
+            _ => {}
+        }
+        alternatives.last_mut()?.push(ch);
+        index += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Паттерн, путь, ожидаемый результат.
+    const CORPUS: &[(&str, &str, bool)] = &[
+        // Паттерн без `/` совпадает с любым компонентом.
+        ("*.log", "/var/log/syslog.log", true),
+        ("*.log", "/var/log/app.log/current", true),
+        ("*.log", "/var/log/catalog", false),
+        ("node_modules", "/home/u/p/node_modules/x/y.js", true),
+        ("node_modules", "/home/u/p/node_modules_old", false),
+        // Абсолютный паттерн привязан к корню и сравнивается по компонентам.
+        ("/tmp/foo", "/tmp/foo", true),
+        ("/tmp/foo", "/tmp/foo/bar", true),
+        ("/tmp/foo", "/tmp/foobar", false),
+        ("/tmp/foo", "/var/tmp/foo", false),
+        ("/usr/bin/*", "/usr/bin/ls", true),
+        ("/usr/bin/*", "/usr/bin", false),
+        ("/usr/bin/*", "/usr/local/bin/x", false),
+        // `*` и `?` не пересекают `/`.
+        ("/var/*/cache", "/var/lib/cache", true),
+        ("/var/*/cache", "/var/lib/x/cache", false),
+        ("/tmp/file?.txt", "/tmp/file1.txt", true),
+        ("/tmp/file?.txt", "/tmp/file10.txt", false),
+        // `**` пересекает любое число каталогов.
+        ("/home/**/Cache", "/home/Cache", true),
+        ("/home/**/Cache", "/home/u/.mozilla/Cache/1", true),
+        ("**/thumbnails", "/home/u/.cache/thumbnails", true),
+        ("/srv/**", "/srv/a/b", true),
+        ("/srv/**", "/srv", false),
+        // Относительный паттерн с `/` совпадает с любой границы компонентов.
+        (".cache/thumbnails", "/home/u/.cache/thumbnails/x.png", true),
+        (".cache/thumbnails", "/home/u/x.cache/thumbnails", false),
+        // Классы символов.
+        ("/tmp/[abc].txt", "/tmp/b.txt", true),
+        ("/tmp/[abc].txt", "/tmp/d.txt", false),
+        ("/tmp/[!abc].txt", "/tmp/d.txt", true),
+        ("/tmp/[!abc].txt", "/tmp/a.txt", false),
+        ("/tmp/[0-9]*", "/tmp/7zip", true),
+        ("/tmp/[0-9]*", "/tmp/a7", false),
+        ("/tmp/[!x]", "/tmp//", false),
+        // Альтернативы.
+        ("*.{tmp,bak}", "/tmp/a.bak", true),
+        ("*.{tmp,bak}", "/tmp/a.log", false),
+        ("/var/{log,cache}/*.{gz,xz}", "/var/cache/a.xz", true),
+        ("/var/{log,cache}/*.{gz,xz}", "/var/lib/a.xz", false),
+        // Экранирование.
+        (r"/tmp/\*", "/tmp/*", true),
+        (r"/tmp/\*", "/tmp/a", false),
+        ("/tmp/a+b(1)", "/tmp/a+b(1)", true),
+        // Отрицание.
+        ("!*.log", "/tmp/a.txt", true),
+        ("!*.log", "/tmp/a.log", false),
+    ];
+
+    #[test]
+    fn test_glob_corpus() {
+        for &(pattern, path, expected) in CORPUS {
+            let glob = GlobPattern::new(pattern).unwrap();
+            assert_eq!(glob.is_match(path), expected, "{pattern} vs {path}");
+        }
+
+        let set = GlobSet::new(
+            CORPUS
+                .iter()
+                .map(|(pattern, _, _)| GlobPattern::new(pattern).unwrap())
+                .collect(),
+        );
+        for (index, &(_, path, expected)) in CORPUS.iter().enumerate() {
+            assert_eq!(set.matches(path)[index], expected, "set entry {index}");
+        }
+    }
+
+    #[test]
+    fn test_dir_only_and_invalid_patterns() {
+        let dir = std::env::temp_dir();
+        let dir_pattern = GlobPattern::new(&format!("{}/", dir.display())).unwrap();
+        assert!(dir_pattern.is_match(&dir.to_string_lossy()));
+        assert!(dir_pattern.is_match(&format!("{}/child", dir.display())));
+        assert!(
+            !GlobPattern::new("/etc/hostname/")
+                .unwrap()
+                .is_match("/etc/hostname")
+        );
+
+        assert!(GlobPattern::new("").is_err());
+        assert!(GlobPattern::new("!").is_err());
+        assert!(GlobPattern::new("/tmp/[abc").unwrap().is_match("/tmp/[abc"));
+        assert!(GlobPattern::literal("/tmp/[x").is_match("/tmp/[x/y"));
+    }
+}
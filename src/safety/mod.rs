@@ -2,132 +2,354 @@
 //!
 //! Проверяет:
 //! - Права доступа к файлам
-//! - Правила whitelist/blacklist
+//! - Правила allow/deny с приоритетами, областью и условиями
 //! - Зависимости пакетов
 //! - Защищённые системные пути
+//! - Съёмные носители и сетевые ресурсы, чужие файлы, атрибуты
+//!   immutable/append-only и файлы, открытые указанными службами
+//!
+//! Все результаты проверок сохраняются в [`CleanupItem::verdicts`],
+//! [`explain`] показывает их для произвольного пути.
 
 mod dependency_check;
+pub mod explain;
+mod glob;
 mod permissions;
+mod protections;
 mod rules;
 
-use self::dependency_check::check_dependencies_for_manager;
+use self::dependency_check::DependencyResolver;
 use self::permissions::{can_clean_path, is_root};
-use self::rules::SafetyRules;
+use self::protections::Protections;
+use self::rules::{SafetyRules, format_rule_reason};
 use crate::config::Config;
+use crate::config::RuleAction;
 use crate::error::Result;
-use crate::models::{CleanupItem, CleanupSource};
+use crate::models::{
+    CleanupCategory, CleanupItem, CleanupSource, Verdict, VerdictCheck, VerdictOutcome,
+};
+use crate::privilege::can_elevate;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::store;
+use std::collections::{HashMap, HashSet};
 
 /// Проверяет безопасность очистки элементов.
 pub struct SafetyChecker {
     config: Config,
     rules: SafetyRules,
+    protections: Protections,
+    dependencies: DependencyResolver,
 }
 
 impl SafetyChecker {
     /// Создаёт новый экземпляр с указанной конфигурацией.
     pub fn new(config: Config) -> Self {
         let rules = SafetyRules::from_config(&config);
-        Self { config, rules }
+        let protections = Protections::new(&config.protect);
+        Self {
+            config,
+            rules,
+            protections,
+            dependencies: DependencyResolver::default(),
+        }
     }
 
     /// Проверяет, безопасно ли очистить элемент.
     ///
     /// Возвращает `true`, если элемент можно безопасно удалить.
     pub fn is_safe_to_clean(&self, item: &CleanupItem) -> Result<bool> {
-        let safety_disabled =
-            !self.config.safety.enabled && (!self.config.safety.only_root_can_disable || is_root());
+        let verdicts = self.evaluate(item)?;
+        Ok(!verdicts.iter().any(Verdict::is_blocking))
+    }
 
-        if item.id == "systemd-journal" && !is_root() {
-            return Ok(false);
-        }
+    /// Применяет проверки безопасности к элементу.
+    ///
+    /// Сохраняет все результаты в `verdicts`. Если хотя бы одна проверка
+    /// блокирует очистку, устанавливает `can_clean = false` и `blocked_reason`
+    /// по первой такой проверке.
+    pub fn apply_to_item(&self, item: &mut CleanupItem) -> Result<()> {
+        self.apply_with_removal(item, &HashMap::new())
+    }
 
-        if let CleanupSource::PackageManager(manager) = &item.source
-            && requires_root(manager)
-            && !is_root()
-        {
-            return Ok(false);
+    /// Как [`Self::apply_to_item`], но пакет не считается нужным пакетам,
+    /// которые удаляются вместе с ним (`removing`, см. [`Self::removal_sets`]).
+    pub fn apply_with_removal(
+        &self,
+        item: &mut CleanupItem,
+        removing: &HashMap<String, HashSet<String>>,
+    ) -> Result<()> {
+        let (verdicts, dependencies) = self.run_checks(item, removing)?;
+        if let Some(dependencies) = dependencies {
+            item.dependencies = dependencies;
         }
+        apply_verdicts(item, verdicts);
+        Ok(())
+    }
 
-        if let Some(ref path) = item.path
-            && !can_clean_path(path)
-        {
-            return Ok(false);
-        }
+    /// Выполняет все проверки и возвращает их результаты по порядку.
+    ///
+    /// В отличие от первой причины блокировки, здесь видны и пройденные
+    /// проверки, и правила, перекрытые правилом с большим приоритетом.
+    pub fn evaluate(&self, item: &CleanupItem) -> Result<Vec<Verdict>> {
+        self.run_checks(item, &HashMap::new())
+            .map(|(verdicts, _)| verdicts)
+    }
 
-        if !safety_disabled {
-            if !self.rules.check_item(item) {
-                return Ok(false);
+    /// Наборы пакетов по менеджерам, которые можно удалить вместе.
+    ///
+    /// В набор входят пакеты, не заблокированные другими проверками, из
+    /// которых исключены пакеты, нужные остающимся. Так цепочка осиротевших
+    /// пакетов проходит проверку зависимостей целиком.
+    pub fn removal_sets(&self, items: &[CleanupItem]) -> HashMap<String, HashSet<String>> {
+        let mut candidates: HashMap<String, HashSet<String>> = HashMap::new();
+        for item in items {
+            if let CleanupSource::PackageManager(manager) = &item.source
+                && item.can_clean
+                && DependencyGraph::supports(manager)
+                && !self.base_checks(item).iter().any(Verdict::is_blocking)
+            {
+                candidates
+                    .entry(manager.clone())
+                    .or_default()
+                    .insert(item.name.clone());
             }
+        }
 
-            if let CleanupSource::PackageManager(manager) = &item.source {
-                let deps = check_dependencies_for_manager(manager, &item.name)?;
-                if !deps.is_empty() {
-                    return Ok(false);
+        candidates
+            .into_iter()
+            .filter_map(|(manager, packages)| {
+                match self.dependencies.removable_subset(&manager, packages) {
+                    Ok(packages) => Some((manager, packages)),
+                    Err(err) => {
+                        log::warn!("Cannot resolve {manager} dependencies: {err}");
+                        None
+                    }
                 }
-            }
+            })
+            .collect()
+    }
+
+    /// Результаты проверок и найденные зависимые пакеты.
+    fn run_checks(
+        &self,
+        item: &CleanupItem,
+        removing: &HashMap<String, HashSet<String>>,
+    ) -> Result<(Vec<Verdict>, Option<Vec<String>>)> {
+        let mut verdicts = self.base_checks(item);
+        if self.safety_disabled() {
+            return Ok((verdicts, None));
+        }
+        let CleanupSource::PackageManager(manager) = &item.source else {
+            return Ok((verdicts, None));
+        };
+        // Кэш пакетов очищает сам менеджер, пакеты при этом не удаляются.
+        // Для остальных источников (Nix/Guix, snap, vkpurge) зависимости
+        // учитывает сам менеджер.
+        if item.category == CleanupCategory::Cache || !DependencyGraph::supports(manager) {
+            return Ok((verdicts, None));
         }
 
-        Ok(true)
+        let mut removing = removing.get(manager).cloned().unwrap_or_default();
+        removing.insert(item.name.clone());
+        let status = self.dependencies.check(manager, &item.name, &removing)?;
+        verdicts.push(if !status.blocking.is_empty() {
+            Verdict::new(
+                VerdictCheck::Dependency,
+                VerdictOutcome::Block,
+                format!("Package has dependents: {}", status.blocking.join(", ")),
+            )
+        } else if status.dependents.is_empty() {
+            Verdict::new(
+                VerdictCheck::Dependency,
+                VerdictOutcome::Pass,
+                "No installed package depends on it",
+            )
+        } else {
+            Verdict::new(
+                VerdictCheck::Dependency,
+                VerdictOutcome::Pass,
+                format!(
+                    "Required only by packages removed with it: {}",
+                    status.dependents.join(", ")
+                ),
+            )
+        });
+        Ok((verdicts, Some(status.dependents)))
     }
 
-    /// Применяет проверки безопасности к элементу.
-    ///
-    /// Устанавливает `can_clean = false` и `blocked_reason`, если элемент заблокирован.
-    pub fn apply_to_item(&self, item: &mut CleanupItem) -> Result<()> {
-        let safety_disabled =
-            !self.config.safety.enabled && (!self.config.safety.only_root_can_disable || is_root());
+    /// Все проверки, кроме зависимостей пакетов.
+    fn base_checks(&self, item: &CleanupItem) -> Vec<Verdict> {
+        let mut verdicts = Vec::new();
 
-        if item.id == "systemd-journal" && !is_root() {
-            mark_blocked(item, "Root required to manage systemd journal");
-            return Ok(());
+        if item.id == "systemd-journal" {
+            verdicts.push(root_verdict("Root required to manage systemd journal"));
         }
 
         if let CleanupSource::PackageManager(manager) = &item.source
             && requires_root(manager)
-            && !is_root()
         {
-            mark_blocked(item, "Root required to manage packages");
-            return Ok(());
+            verdicts.push(root_verdict("Root required to manage packages"));
         }
 
-        if let Some(ref path) = item.path
-            && !can_clean_path(path)
+        if store::parse_generation_id(&item.id)
+            .is_some_and(|(_, profile, _)| store::is_system_profile(&profile))
         {
-            mark_blocked(item, "Insufficient permissions to clean path");
-            if safety_disabled {
-                return Ok(());
-            }
+            verdicts.push(root_verdict("Root required to delete system generations"));
         }
 
-        if !safety_disabled {
-            if let Some(reason) = self.rules.check_item_reason(item) {
-                mark_blocked(item, &reason);
-            }
+        if let Some(ref path) = item.path {
+            verdicts.push(if can_clean_path(path) {
+                Verdict::new(
+                    VerdictCheck::Permission,
+                    VerdictOutcome::Pass,
+                    format!("{path} is writable by this user or the helper"),
+                )
+            } else {
+                Verdict::new(
+                    VerdictCheck::Permission,
+                    VerdictOutcome::Block,
+                    "Insufficient permissions to clean path",
+                )
+            });
+        }
 
-            if let CleanupSource::PackageManager(manager) = &item.source {
-                let deps = check_dependencies_for_manager(manager, &item.name)?;
-                if !deps.is_empty() {
-                    item.dependencies = deps;
-                    mark_blocked(item, "Package has dependents");
-                }
-            }
+        if self.safety_disabled() {
+            verdicts.push(Verdict::new(
+                VerdictCheck::Rule,
+                VerdictOutcome::Pass,
+                "Safety rules are disabled",
+            ));
+            return verdicts;
         }
 
-        Ok(())
+        let matches = self.rules.matching_rules(item);
+        if matches.is_empty() {
+            verdicts.push(Verdict::new(
+                VerdictCheck::Rule,
+                VerdictOutcome::Pass,
+                "No rule matched",
+            ));
+        }
+        for (index, rule) in matches.iter().enumerate() {
+            let outcome = match (index, rule.action) {
+                (0, RuleAction::Deny) => VerdictOutcome::Block,
+                (0, RuleAction::Allow) => VerdictOutcome::Pass,
+                _ => VerdictOutcome::Shadowed,
+            };
+            verdicts.push(Verdict::new(VerdictCheck::Rule, outcome, rule.explain()));
+        }
+
+        for (rule_type, detail) in self.protections.check(item) {
+            verdicts.push(Verdict::new(
+                VerdictCheck::Rule,
+                VerdictOutcome::Block,
+                format_rule_reason(rule_type, &detail),
+            ));
+        }
+
+        verdicts
     }
+
+    /// Отключена ли система правил (с учётом ограничения только для root).
+    fn safety_disabled(&self) -> bool {
+        !self.config.safety.enabled && (!self.config.safety.only_root_can_disable || is_root())
+    }
+}
+
+/// Сохраняет результаты проверок в элементе.
+pub fn apply_verdicts(item: &mut CleanupItem, verdicts: Vec<Verdict>) {
+    if let Some(blocking) = verdicts.iter().find(|verdict| verdict.is_blocking()) {
+        item.can_clean = false;
+        if item.blocked_reason.is_none() {
+            item.blocked_reason = Some(blocking.detail.clone());
+        }
+    }
+    item.verdicts = verdicts;
 }
 
-/// Помечает элемент как заблокированный.
-fn mark_blocked(item: &mut CleanupItem, reason: &str) {
-    item.can_clean = false;
-    if item.blocked_reason.is_none() {
-        item.blocked_reason = Some(reason.to_string());
+/// Проверка прав root: помощник из [`crate::privilege`] снимает ограничение.
+fn root_verdict(blocked: &str) -> Verdict {
+    if can_elevate() {
+        Verdict::new(
+            VerdictCheck::Root,
+            VerdictOutcome::Pass,
+            "Root available (running as root or via pkexec helper)",
+        )
+    } else {
+        Verdict::new(VerdictCheck::Root, VerdictOutcome::Block, blocked)
     }
 }
 
+/// Нужен ли root для удаления пакетов менеджера.
+///
+/// Без root такие операции выполняет помощник из [`crate::privilege`].
 fn requires_root(manager: &str) -> bool {
     matches!(
         manager,
-        "apt" | "dnf" | "rpm" | "pacman" | "snap" | "rpm-ostree"
+        "apt"
+            | "dnf"
+            | "rpm"
+            | "pacman"
+            | "zypper"
+            | "apk"
+            | "xbps"
+            | "vkpurge"
+            | "kernel-files"
+            | "dkms"
+            | "snap"
+            | "rpm-ostree"
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RuleConfig;
+    use crate::models::CleanupCategory;
+
+    #[test]
+    fn test_apply_collects_every_verdict() {
+        let mut config = Config::default();
+        config.rules.entries = vec![
+            RuleConfig::with_pattern(RuleAction::Deny, "/tmp/rcleaner-trace", 10),
+            RuleConfig::with_pattern(RuleAction::Allow, "/tmp/rcleaner-trace/a", 5),
+        ];
+        let checker = SafetyChecker::new(config);
+        let mut item = CleanupItem {
+            id: "trace".to_string(),
+            name: "trace".to_string(),
+            path: Some("/tmp/rcleaner-trace/a".to_string()),
+            size: 0,
+            description: String::new(),
+            category: CleanupCategory::TempFiles,
+            source: CleanupSource::FileSystem,
+            selected: false,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        };
+
+        checker.apply_to_item(&mut item).unwrap();
+
+        assert!(!item.can_clean);
+        let outcomes: Vec<_> = item
+            .verdicts
+            .iter()
+            .map(|verdict| (verdict.check, verdict.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (VerdictCheck::Permission, VerdictOutcome::Pass),
+                (VerdictCheck::Rule, VerdictOutcome::Block),
+                (VerdictCheck::Rule, VerdictOutcome::Shadowed),
+            ]
+        );
+        assert!(item.verdicts[1].detail.contains("/tmp/rcleaner-trace"));
+        assert_eq!(
+            item.blocked_reason.as_deref(),
+            Some(item.verdicts[1].detail.as_str())
+        );
+    }
+}
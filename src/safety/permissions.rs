@@ -1,3 +1,8 @@
+use crate::privilege::can_elevate;
+use crate::privilege::helper::ALLOWED_PATH_ROOTS;
+use crate::system::locales;
+use std::path::Path;
+
 pub fn is_root() -> bool {
     crate::utils::command::is_root()
 }
@@ -7,14 +12,42 @@ pub fn can_clean_path(path: &str) -> bool {
         return true;
     }
 
+    if can_elevate()
+        && (ALLOWED_PATH_ROOTS
+            .iter()
+            .any(|root| Path::new(path).starts_with(root))
+            || locales::classify(Path::new(path)).is_some())
+    {
+        return true;
+    }
+
     if path.starts_with("/tmp") || path.starts_with("/var/tmp") {
         return true;
     }
 
     if let Ok(home) = std::env::var("HOME")
-        && path.starts_with(&home) {
-            return true;
-        }
+        && path.starts_with(&home)
+    {
+        return true;
+    }
 
     false
 }
+
+/// Возвращает UID по имени пользователя, числовому UID или `current`.
+pub fn resolve_uid(owner: &str) -> Option<u32> {
+    if owner == "current" {
+        return Some(unsafe { libc::geteuid() });
+    }
+    if let Ok(uid) = owner.parse() {
+        return Some(uid);
+    }
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? != owner {
+            return None;
+        }
+        fields.nth(1)?.parse().ok()
+    })
+}
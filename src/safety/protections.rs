@@ -0,0 +1,323 @@
+//! Защита, не привязанная к путям из правил.
+//!
+//! Проверяет, на какой файловой системе лежит элемент, кому он принадлежит,
+//! какие у него атрибуты и не открыт ли он одной из указанных служб.
+
+use super::rules::SafetyRuleType;
+use crate::config::ProtectConfig;
+use crate::models::{CleanupCategory, CleanupItem};
+use crate::system::bootloader::{self, BootPin};
+use crate::system::kernels;
+use crate::system::mounts::{self, MountEntry};
+use crate::system::package_manager::run_command;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Сетевые файловые системы, защищаемые в любом месте.
+const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "sshfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "9p",
+    "afs",
+    "ceph",
+    "glusterfs",
+];
+
+/// Атрибут immutable (`chattr +i`).
+const FS_IMMUTABLE_FL: libc::c_long = 0x0000_0010;
+/// Атрибут append-only (`chattr +a`).
+const FS_APPEND_FL: libc::c_long = 0x0000_0020;
+
+/// Первый UID обычных пользователей, если `/etc/login.defs` не задаёт иначе.
+const DEFAULT_UID_MIN: u32 = 1000;
+/// UID пользователя `nobody`.
+const NOBODY_UID: u32 = 65534;
+
+/// Проверки защиты с данными, прочитанными один раз на весь набор элементов.
+pub struct Protections {
+    config: ProtectConfig,
+    mounts: Vec<MountEntry>,
+    uid_min: u32,
+    service_files: OnceLock<Vec<(PathBuf, String)>>,
+    boot_pins: OnceLock<Vec<BootPin>>,
+}
+
+impl Protections {
+    /// Создаёт проверки по конфигурации.
+    pub fn new(config: &ProtectConfig) -> Self {
+        Self {
+            config: config.clone(),
+            mounts: mounts::read_mounts(),
+            uid_min: login_uid_min(),
+            service_files: OnceLock::new(),
+            boot_pins: OnceLock::new(),
+        }
+    }
+
+    /// Возвращает все сработавшие защиты для элемента.
+    pub fn check(&self, item: &CleanupItem) -> Vec<(SafetyRuleType, String)> {
+        let mut hits = Vec::new();
+
+        // Имена пакетов и файлов ядер заканчиваются версией ядра.
+        if item.category == CleanupCategory::OldKernels {
+            let pins = self
+                .boot_pins
+                .get_or_init(|| bootloader::protected_kernels(&kernels::running_version()));
+            if let Some(pin) = pins.iter().find(|pin| item.name.ends_with(&pin.version)) {
+                hits.push((
+                    SafetyRuleType::ProtectBootloader,
+                    format!("{} is the {}", pin.version, pin.reason),
+                ));
+            }
+        }
+
+        let Some(path) = item.path.as_deref().map(Path::new) else {
+            return hits;
+        };
+
+        if let Some(mount) = mounts::find_mount(&self.mounts, path)
+            && let Some(reason) = mount_reason(&mount, &self.config)
+        {
+            hits.push((SafetyRuleType::ProtectMountPoint, reason));
+        }
+
+        if self.config.other_users
+            && let Ok(metadata) = std::fs::symlink_metadata(path)
+            && let Some(reason) =
+                other_user_reason(metadata.uid(), unsafe { libc::geteuid() }, self.uid_min)
+        {
+            hits.push((SafetyRuleType::ProtectOtherUsers, reason));
+        }
+
+        if self.config.immutable
+            && let Some(reason) = attribute_reason(path)
+        {
+            hits.push((SafetyRuleType::ProtectFileAttributes, reason));
+        }
+
+        if !self.config.services.is_empty() {
+            let open = self
+                .service_files
+                .get_or_init(|| collect_service_files(&self.config.services));
+            if let Some((file, service)) = open.iter().find(|(file, _)| file.starts_with(path)) {
+                hits.push((
+                    SafetyRuleType::ProtectServiceFiles,
+                    format!("{} is in use by {service}", file.display()),
+                ));
+            }
+        }
+
+        hits
+    }
+}
+
+/// Защищена ли файловая система элемента целиком.
+fn mount_reason(mount: &MountEntry, config: &ProtectConfig) -> Option<String> {
+    if config
+        .mounts
+        .iter()
+        .any(|root| root != "/" && mount.mount_point.starts_with(root))
+    {
+        return Some(format!(
+            "{} is a protected mount ({})",
+            mount.mount_point.display(),
+            mount.source
+        ));
+    }
+    if config.network_filesystems && NETWORK_FS_TYPES.contains(&mount.fs_type.as_str()) {
+        return Some(format!(
+            "{} is a network share ({})",
+            mount.mount_point.display(),
+            mount.fs_type
+        ));
+    }
+    None
+}
+
+/// Принадлежит ли файл другому обычному пользователю.
+///
+/// Системные учётные записи (root, службы) не считаются: их файлы в
+/// `/var/log` и `/var/tmp` очищаются через помощника.
+fn other_user_reason(owner: u32, current: u32, uid_min: u32) -> Option<String> {
+    if owner == current || owner < uid_min || owner == NOBODY_UID {
+        return None;
+    }
+    let name = user_name(owner).unwrap_or_else(|| owner.to_string());
+    Some(format!("owned by another user ({name})"))
+}
+
+/// Атрибуты immutable/append-only у элемента или его каталога.
+fn attribute_reason(path: &Path) -> Option<String> {
+    if let Some(reason) = flags_reason(path, "") {
+        return Some(reason);
+    }
+    path.parent()
+        .and_then(|parent| flags_reason(parent, "parent directory "))
+}
+
+fn flags_reason(path: &Path, prefix: &str) -> Option<String> {
+    let flags = file_flags(path)?;
+    let attribute = if flags & FS_IMMUTABLE_FL != 0 {
+        "immutable"
+    } else if flags & FS_APPEND_FL != 0 {
+        "append-only"
+    } else {
+        return None;
+    };
+    Some(format!("{prefix}{} is {attribute}", path.display()))
+}
+
+/// Флаги inode (`lsattr`); `None` для ссылок, устройств и ошибок.
+fn file_flags(path: &Path) -> Option<libc::c_long> {
+    let metadata = std::fs::symlink_metadata(path).ok()?;
+    if !metadata.is_file() && !metadata.is_dir() {
+        return None;
+    }
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let fd = unsafe {
+        libc::open(
+            c_path.as_ptr(),
+            libc::O_RDONLY | libc::O_NONBLOCK | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+        )
+    };
+    if fd < 0 {
+        return None;
+    }
+    let mut flags: libc::c_long = 0;
+    let result = unsafe { libc::ioctl(fd, libc::FS_IOC_GETFLAGS, &mut flags) };
+    unsafe { libc::close(fd) };
+    (result == 0).then_some(flags)
+}
+
+/// Файлы, открытые процессами служб, и рабочие каталоги этих процессов.
+fn collect_service_files(services: &[String]) -> Vec<(PathBuf, String)> {
+    let mut files = Vec::new();
+    for service in services {
+        let unit = if service.contains('.') {
+            service.clone()
+        } else {
+            format!("{service}.service")
+        };
+        for pid in service_pids(&unit) {
+            for file in open_paths(pid) {
+                files.push((file, unit.clone()));
+            }
+        }
+    }
+    files
+}
+
+/// PID процессов службы из её cgroup (системной или пользовательской).
+fn service_pids(unit: &str) -> Vec<u32> {
+    for scope in [None, Some("--user")] {
+        let mut args: Vec<&str> = scope.into_iter().collect();
+        args.extend(["show", "-p", "ControlGroup", "--value", unit]);
+        let Ok(output) = run_command("systemctl", &args) else {
+            continue;
+        };
+        let group = output.stdout.trim();
+        if !output.status.success() || group.is_empty() {
+            continue;
+        }
+        let procs = format!("/sys/fs/cgroup{group}/cgroup.procs");
+        let pids: Vec<u32> = std::fs::read_to_string(&procs)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.trim().parse().ok())
+            .collect();
+        if !pids.is_empty() {
+            return pids;
+        }
+    }
+    Vec::new()
+}
+
+/// Открытые файлы и рабочий каталог процесса (нужны права на `/proc/PID`).
+fn open_paths(pid: u32) -> Vec<PathBuf> {
+    let proc_dir = PathBuf::from(format!("/proc/{pid}"));
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(proc_dir.join("fd"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| std::fs::read_link(entry.path()).ok())
+                .filter(|target| target.is_absolute())
+                .collect()
+        })
+        .unwrap_or_else(|err| {
+            log::debug!("Cannot inspect open files of {pid}: {err}");
+            Vec::new()
+        });
+    if let Ok(cwd) = std::fs::read_link(proc_dir.join("cwd")) {
+        paths.push(cwd);
+    }
+    paths
+}
+
+/// `UID_MIN` из `/etc/login.defs`.
+fn login_uid_min() -> u32 {
+    std::fs::read_to_string("/etc/login.defs")
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                let mut fields = line.split_whitespace();
+                (fields.next()? == "UID_MIN").then(|| fields.next()?.parse().ok())?
+            })
+        })
+        .unwrap_or(DEFAULT_UID_MIN)
+}
+
+fn user_name(uid: u32) -> Option<String> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<_> = line.split(':').collect();
+        (fields.get(2)?.parse::<u32>().ok()? == uid).then(|| fields[0].to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mount(point: &str, fs_type: &str) -> MountEntry {
+        MountEntry {
+            mount_point: PathBuf::from(point),
+            root: "/".to_string(),
+            fs_type: fs_type.to_string(),
+            source: "/dev/sdb1".to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_mount_and_owner_protection() {
+        let config = ProtectConfig::default();
+        assert!(mount_reason(&mount("/run/media/u/USB", "vfat"), &config).is_some());
+        assert!(mount_reason(&mount("/mnt/backup", "ext4"), &config).is_some());
+        assert!(mount_reason(&mount("/srv/share", "nfs4"), &config).is_some());
+        assert!(mount_reason(&mount("/", "ext4"), &config).is_none());
+        assert!(mount_reason(&mount("/home", "btrfs"), &config).is_none());
+
+        let config = ProtectConfig {
+            mounts: Vec::new(),
+            network_filesystems: false,
+            ..ProtectConfig::default()
+        };
+        assert!(mount_reason(&mount("/mnt/backup", "ext4"), &config).is_none());
+        assert!(mount_reason(&mount("/srv/share", "nfs4"), &config).is_none());
+
+        assert!(other_user_reason(1001, 1000, 1000).is_some());
+        assert!(other_user_reason(1000, 1000, 1000).is_none());
+        assert!(other_user_reason(0, 1000, 1000).is_none());
+        assert!(other_user_reason(NOBODY_UID, 1000, 1000).is_none());
+        assert!(other_user_reason(1000, 0, 1000).is_some());
+    }
+}
@@ -1,20 +1,77 @@
-use crate::config::Config;
-use crate::models::CleanupItem;
-use regex::Regex;
+use super::glob::{GlobPattern, GlobSet};
+use super::permissions::resolve_uid;
+use crate::config::{Config, RuleAction, RuleConfig};
+use crate::models::{CleanupCategory, CleanupItem, CleanupSource};
+use crate::system::locales;
+use std::cell::OnceCell;
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::time::SystemTime;
 
+/// Приоритет встроенной защиты системы.
+pub const BUILTIN_PRIORITY: i32 = 1000;
+
+/// Приоритет области удаления переводов: выше встроенной защиты
+/// `/usr/share`, поэтому её перекрывают только правила с приоритетом выше.
+pub const LOCALE_SCOPE_PRIORITY: i32 = BUILTIN_PRIORITY + 1;
+
+/// Набор правил безопасности в порядке применения.
 #[derive(Debug, Clone)]
 pub struct SafetyRules {
     pub enabled: bool,
     pub only_root_can_disable: bool,
-    pub whitelist: Vec<SafetyRule>,
-    pub blacklist: Vec<SafetyRule>,
+    /// Правила, отсортированные по убыванию приоритета (`deny` раньше `allow`).
+    pub rules: Vec<SafetyRule>,
+    /// Паттерны всех правил, скомпилированные один раз.
+    patterns: GlobSet,
 }
 
+/// Правило безопасности: действие, приоритет, область и условия.
 #[derive(Debug, Clone)]
 pub struct SafetyRule {
-    pub pattern: String,
+    pub action: RuleAction,
+    pub priority: i32,
+    pub pattern: Option<String>,
+    /// Индекс паттерна в [`SafetyRules::patterns`].
+    glob: Option<usize>,
     pub description: String,
     pub rule_type: SafetyRuleType,
+    pub categories: Vec<CleanupCategory>,
+    pub sources: Vec<String>,
+    pub min_age_days: Option<u64>,
+    pub max_age_days: Option<u64>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    /// Требуемый владелец; `Some(None)` — владелец из конфигурации не найден.
+    pub owner: Option<Option<u32>>,
+}
+
+/// Сработавшее правило с объяснением.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleMatch {
+    pub action: RuleAction,
+    pub priority: i32,
+    pub rule_type: SafetyRuleType,
+    pub pattern: Option<String>,
+    pub description: String,
+}
+
+impl RuleMatch {
+    /// Причина блокировки в формате `тип: описание`.
+    pub fn reason(&self) -> String {
+        format_rule_reason(self.rule_type, &self.description)
+    }
+
+    /// Подробное объяснение: действие, приоритет и паттерн правила.
+    pub fn explain(&self) -> String {
+        let pattern = self.pattern.as_deref().unwrap_or("*");
+        format!(
+            "{} (rule: {} {pattern}, priority {})",
+            self.reason(),
+            self.action.label(),
+            self.priority
+        )
+    }
 }
 
 /// Тип правила безопасности, определяющий категорию защиты.
@@ -31,6 +88,18 @@ pub enum SafetyRuleType {
     ProtectUserHome,
     /// Защита активных приложений
     ProtectActiveApplications,
+    /// Правило из конфигурации пользователя
+    ConfiguredRule,
+    /// Защита съёмных носителей и сетевых ресурсов целиком
+    ProtectMountPoint,
+    /// Защита файлов других пользователей
+    ProtectOtherUsers,
+    /// Защита файлов с атрибутами immutable/append-only
+    ProtectFileAttributes,
+    /// Защита файлов, открытых указанными службами
+    ProtectServiceFiles,
+    /// Область удаления переводов и документации (`[locales]`)
+    LocaleScope,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -111,6 +180,11 @@ const DEFAULT_RULES: &[SafetyRuleTemplate] = &[
         description: "Защита системного состояния",
         rule_type: SafetyRuleType::ProtectSystemPackages,
     },
+    SafetyRuleTemplate {
+        pattern: "/usr/share/*",
+        description: "Защита системных данных",
+        rule_type: SafetyRuleType::ProtectSystemPackages,
+    },
 ];
 
 impl SafetyRules {
@@ -118,8 +192,8 @@ impl SafetyRules {
         Self {
             enabled: true,
             only_root_can_disable: true,
-            whitelist: Vec::new(),
-            blacklist: Vec::new(),
+            rules: Vec::new(),
+            patterns: GlobSet::new(Vec::new()),
         }
     }
 
@@ -128,83 +202,321 @@ impl SafetyRules {
         rules.enabled = config.safety.enabled;
         rules.only_root_can_disable = config.safety.only_root_can_disable;
 
-        for path in &config.rules.whitelist.paths {
-            let normalized = normalize_pattern(path);
-            rules.whitelist.push(SafetyRule {
-                pattern: normalized.clone(),
-                description: format!("Whitelist: {}", normalized),
-                rule_type: SafetyRuleType::ProtectUserHome,
-            });
+        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR")
+            && !runtime_dir.is_empty()
+        {
+            rules.rules.push(SafetyRule::builtin(
+                &runtime_dir,
+                "Защита активных приложений",
+                SafetyRuleType::ProtectActiveApplications,
+            ));
         }
 
-        for pattern in &config.rules.blacklist.patterns {
-            let normalized = normalize_pattern(pattern);
-            rules.blacklist.push(SafetyRule {
-                pattern: normalized.clone(),
-                description: format!("Blacklist: {}", normalized),
-                rule_type: SafetyRuleType::ProtectSystemPackages,
-            });
+        for template in DEFAULT_RULES {
+            rules.rules.push(SafetyRule::builtin(
+                template.pattern,
+                template.description,
+                template.rule_type,
+            ));
         }
 
+        if config.locales.enabled {
+            rules.rules.extend(locale_scope(config));
+        }
+
+        let mut rules_config = config.rules.clone();
+        rules_config.migrate();
+        for (index, entry) in rules_config.entries.iter().enumerate() {
+            rules.rules.push(SafetyRule::from_config(entry, index));
+        }
+
+        rules.rules.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| deny_first(a.action).cmp(&deny_first(b.action)))
+        });
+
+        let mut globs = Vec::new();
+        for rule in &mut rules.rules {
+            if let Some(ref pattern) = rule.pattern {
+                let glob = GlobPattern::new(pattern).unwrap_or_else(|err| {
+                    log::warn!("{err}; matching {pattern} literally");
+                    GlobPattern::literal(pattern)
+                });
+                rule.glob = Some(globs.len());
+                globs.push(glob);
+            }
+        }
+        rules.patterns = GlobSet::new(globs);
         rules
     }
 
+    #[allow(dead_code)]
     pub fn check_item(&self, item: &CleanupItem) -> bool {
         self.check_item_reason(item).is_none()
     }
 
+    #[allow(dead_code)]
     pub fn check_item_reason(&self, item: &CleanupItem) -> Option<String> {
-        if let Some(ref path) = item.path {
-            if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR")
-                && path.starts_with(&runtime_dir)
+        self.decide(item)
+            .filter(|decision| decision.action == RuleAction::Deny)
+            .map(|decision| decision.explain())
+    }
+
+    /// Возвращает правило, которое решает судьбу элемента.
+    ///
+    /// Побеждает сработавшее правило с наибольшим приоритетом,
+    /// при равном приоритете `deny` важнее `allow`.
+    pub fn decide(&self, item: &CleanupItem) -> Option<RuleMatch> {
+        self.matching_rules(item).into_iter().next()
+    }
+
+    /// Все сработавшие правила в порядке применения.
+    pub fn matching_rules(&self, item: &CleanupItem) -> Vec<RuleMatch> {
+        let facts = ItemFacts::new(item);
+        let hits = item.path.as_deref().map(|path| self.patterns.matches(path));
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(item, &facts, hits.as_deref()))
+            .map(SafetyRule::to_match)
+            .collect()
+    }
+}
+
+impl Default for SafetyRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SafetyRule {
+    fn builtin(pattern: &str, description: &str, rule_type: SafetyRuleType) -> Self {
+        Self {
+            action: RuleAction::Deny,
+            priority: BUILTIN_PRIORITY,
+            pattern: Some(pattern.to_string()),
+            glob: None,
+            description: description.to_string(),
+            rule_type,
+            categories: Vec::new(),
+            sources: Vec::new(),
+            min_age_days: None,
+            max_age_days: None,
+            min_size: None,
+            max_size: None,
+            owner: None,
+        }
+    }
+
+    fn from_config(entry: &RuleConfig, index: usize) -> Self {
+        let pattern = entry.pattern.as_deref().map(normalize_pattern);
+        let description = if entry.description.is_empty() {
+            format!(
+                "rule #{} {} {}",
+                index + 1,
+                entry.action.label(),
+                pattern.as_deref().unwrap_or("*")
+            )
+        } else {
+            entry.description.clone()
+        };
+        let rule_type = configured_rule_type(entry.action, pattern.as_deref());
+        Self {
+            action: entry.action,
+            priority: entry.priority,
+            pattern,
+            glob: None,
+            description,
+            rule_type,
+            categories: entry.categories.clone(),
+            sources: entry
+                .sources
+                .iter()
+                .map(|source| source.to_lowercase())
+                .collect(),
+            min_age_days: entry.min_age_days,
+            max_age_days: entry.max_age_days,
+            min_size: entry.min_size,
+            max_size: entry.max_size,
+            owner: entry.owner.as_deref().map(resolve_uid),
+        }
+    }
+
+    fn matches(&self, item: &CleanupItem, facts: &ItemFacts, hits: Option<&[bool]>) -> bool {
+        if !self.categories.is_empty() && !self.categories.contains(&item.category) {
+            return false;
+        }
+        if !self.sources.is_empty()
+            && !self
+                .sources
+                .iter()
+                .any(|source| source_matches(source, &item.source))
+        {
+            return false;
+        }
+        if let Some(index) = self.glob
+            && !hits.is_some_and(|hits| hits[index])
+        {
+            return false;
+        }
+        if self.min_size.is_some_and(|min| item.size < min)
+            || self.max_size.is_some_and(|max| item.size > max)
+        {
+            return false;
+        }
+        if self.min_age_days.is_some() || self.max_age_days.is_some() {
+            let Some(age) = facts.age_days() else {
+                return false;
+            };
+            if self.min_age_days.is_some_and(|min| age < min)
+                || self.max_age_days.is_some_and(|max| age > max)
             {
-                return Some(format_rule_reason(
-                    SafetyRuleType::ProtectActiveApplications,
-                    "Защита активных приложений",
-                ));
+                return false;
             }
-
-            for rule in DEFAULT_RULES.iter() {
-                if self.matches_rule(path, rule.pattern) {
-                    return Some(format_rule_reason(rule.rule_type, rule.description));
-                }
+        }
+        if let Some(owner) = self.owner {
+            let Some(expected) = owner else {
+                return false;
+            };
+            if facts.owner_uid() != Some(expected) {
+                return false;
             }
+        }
+        true
+    }
 
-            for rule in &self.whitelist {
-                if self.matches_rule(path, &rule.pattern) {
-                    return Some(format_rule_reason(rule.rule_type, &rule.description));
-                }
-            }
+    fn to_match(&self) -> RuleMatch {
+        RuleMatch {
+            action: self.action,
+            priority: self.priority,
+            rule_type: self.rule_type,
+            pattern: self.pattern.clone(),
+            description: self.description.clone(),
+        }
+    }
+}
 
-            for rule in &self.blacklist {
-                if self.matches_rule(path, &rule.pattern) {
-                    return Some(format_rule_reason(rule.rule_type, &rule.description));
-                }
-            }
+/// Сведения о файле элемента, читаемые только при необходимости.
+struct ItemFacts<'a> {
+    path: Option<&'a str>,
+    metadata: OnceCell<Option<Metadata>>,
+}
+
+impl<'a> ItemFacts<'a> {
+    fn new(item: &'a CleanupItem) -> Self {
+        Self {
+            path: item.path.as_deref(),
+            metadata: OnceCell::new(),
         }
+    }
+
+    fn metadata(&self) -> Option<&Metadata> {
+        self.metadata
+            .get_or_init(|| {
+                self.path
+                    .and_then(|path| std::fs::symlink_metadata(path).ok())
+            })
+            .as_ref()
+    }
+
+    fn age_days(&self) -> Option<u64> {
+        let modified = self.metadata()?.modified().ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        Some(age.as_secs() / 86_400)
+    }
 
-        None
+    fn owner_uid(&self) -> Option<u32> {
+        self.metadata().map(MetadataExt::uid)
     }
+}
 
-    fn matches_rule(&self, path: &str, pattern: &str) -> bool {
-        let expanded = normalize_pattern(pattern);
+/// Явная область, в которой категория `Locales` может удалять файлы из
+/// `/usr/share`: каталоги языков, а при `docs = true` документация и
+/// справка. Сохраняемые языки и man-страницы без перевода запрещены с тем
+/// же приоритетом, а при равенстве `deny` важнее.
+fn locale_scope(config: &Config) -> Vec<SafetyRule> {
+    let mut allowed = vec![
+        "/usr/share/locale/*",
+        "/usr/share/man/*",
+        "/usr/share/help/*",
+    ];
+    if config.locales.docs {
+        allowed.extend([locales::DOC_DIR, locales::HELP_DIR]);
+    }
+    let mut denied = vec![(
+        "/usr/share/man/man*".to_string(),
+        "Man-страницы без перевода",
+    )];
+    let kept = locales::ALWAYS_KEPT
+        .iter()
+        .copied()
+        .chain(locales::kept_languages(&config.locales));
+    for language in kept {
+        for pattern in [
+            format!("/usr/share/{{locale,man,help}}/{language}"),
+            format!("/usr/share/{{locale,man,help}}/{language}[_@.]*"),
+        ] {
+            denied.push((pattern, "Сохраняемый язык"));
+        }
+    }
 
-        if has_glob(&expanded)
-            && let Ok(re) = Regex::new(&glob_to_regex(&expanded))
+    let scoped = |action: RuleAction, pattern: &str, description: &str| SafetyRule {
+        action,
+        priority: LOCALE_SCOPE_PRIORITY,
+        categories: vec![CleanupCategory::Locales],
+        sources: vec!["filesystem".to_string()],
+        ..SafetyRule::builtin(pattern, description, SafetyRuleType::LocaleScope)
+    };
+    allowed
+        .into_iter()
+        .map(|pattern| scoped(RuleAction::Allow, pattern, "Удаление переводов включено"))
+        .chain(
+            denied
+                .iter()
+                .map(|(pattern, description)| scoped(RuleAction::Deny, pattern, description)),
+        )
+        .collect()
+}
+
+/// Запреты внутри домашнего каталога считаются защитой данных пользователя.
+fn configured_rule_type(action: RuleAction, pattern: Option<&str>) -> SafetyRuleType {
+    let home = std::env::var("HOME").unwrap_or_default();
+    match pattern {
+        Some(pattern)
+            if action == RuleAction::Deny && !home.is_empty() && pattern.starts_with(&home) =>
         {
-            return re.is_match(path);
+            SafetyRuleType::ProtectUserHome
         }
+        _ => SafetyRuleType::ConfiguredRule,
+    }
+}
 
-        if expanded.starts_with('/') {
-            path.starts_with(&expanded)
-        } else {
-            path.contains(&expanded)
+fn deny_first(action: RuleAction) -> u8 {
+    match action {
+        RuleAction::Deny => 0,
+        RuleAction::Allow => 1,
+    }
+}
+
+/// Совпадает ли источник элемента с источником из правила.
+fn source_matches(spec: &str, source: &CleanupSource) -> bool {
+    match source {
+        CleanupSource::FileSystem => spec == "filesystem",
+        CleanupSource::PackageManager(manager) => {
+            spec == "package" || spec == manager.to_lowercase()
         }
+        CleanupSource::Container(runtime) => spec == "container" || spec == runtime.to_lowercase(),
     }
 }
 
 fn normalize_pattern(pattern: &str) -> String {
-    expand_tilde(pattern.trim())
+    let pattern = pattern.trim();
+    match pattern.strip_prefix('!') {
+        Some(rest) => format!("!{}", expand_tilde(rest)),
+        None => expand_tilde(pattern),
+    }
 }
 
 fn expand_tilde(value: &str) -> String {
@@ -219,6 +531,26 @@ fn expand_tilde(value: &str) -> String {
     value.to_string()
 }
 
+pub(super) fn format_rule_reason(rule_type: SafetyRuleType, description: &str) -> String {
+    format!("{}: {}", rule_type_label(rule_type), description)
+}
+
+fn rule_type_label(rule_type: SafetyRuleType) -> &'static str {
+    match rule_type {
+        SafetyRuleType::ProtectSystemPackages => "system",
+        SafetyRuleType::ProtectKernel => "kernel",
+        SafetyRuleType::ProtectBootloader => "bootloader",
+        SafetyRuleType::ProtectUserHome => "user",
+        SafetyRuleType::ProtectActiveApplications => "active",
+        SafetyRuleType::ConfiguredRule => "config",
+        SafetyRuleType::ProtectMountPoint => "mount",
+        SafetyRuleType::ProtectOtherUsers => "owner",
+        SafetyRuleType::ProtectFileAttributes => "attributes",
+        SafetyRuleType::ProtectServiceFiles => "service",
+        SafetyRuleType::LocaleScope => "locales",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,6 +569,7 @@ mod tests {
             can_clean: true,
             blocked_reason: None,
             dependencies: Vec::new(),
+            verdicts: Vec::new(),
         }
     }
 
@@ -260,48 +593,111 @@ mod tests {
     #[test]
     fn test_blacklist_blocks_pattern() {
         let mut config = Config::default();
-        config.rules.blacklist.patterns = vec!["*.log".to_string()];
+        config.rules.blacklist.patterns = vec!["*.keep".to_string()];
         let rules = SafetyRules::from_config(&config);
-        let item = item_with_path("/tmp/rcleaner-test.log");
+        let item = item_with_path("/tmp/rcleaner-test.keep");
         assert!(!rules.check_item(&item));
     }
 
     #[test]
-    fn test_safe_path_allowed() {
-        let config = Config::default();
+    fn test_legacy_default_patterns_do_not_block() {
+        let mut config = Config::default();
+        config.rules.blacklist.patterns = vec!["*.tmp".to_string(), "*.log".to_string()];
         let rules = SafetyRules::from_config(&config);
-        let item = item_with_path("/tmp/rcleaner-ok");
-        assert!(rules.check_item(&item));
+        assert!(rules.check_item(&item_with_path("/tmp/rcleaner-test.log")));
+        assert!(rules.check_item(&item_with_path("/tmp/rcleaner-test.tmp")));
     }
-}
 
-fn has_glob(pattern: &str) -> bool {
-    pattern.contains('*') || pattern.contains('?')
-}
+    #[test]
+    fn test_priority_and_scope() {
+        let mut config = Config::default();
+        config.rules.entries = vec![
+            RuleConfig::with_pattern(RuleAction::Deny, "/tmp/rcleaner-scope", 10),
+            RuleConfig::with_pattern(RuleAction::Allow, "/tmp/rcleaner-scope/cache", 20),
+            RuleConfig::with_pattern(RuleAction::Allow, "/tmp/rcleaner-scope/tie", 10),
+            RuleConfig {
+                categories: vec![CleanupCategory::Logs],
+                ..RuleConfig::with_pattern(RuleAction::Deny, "/tmp/rcleaner-logs", 10)
+            },
+            RuleConfig {
+                min_size: Some(1024),
+                ..RuleConfig::with_pattern(RuleAction::Deny, "/tmp/rcleaner-big", 10)
+            },
+        ];
+        let rules = SafetyRules::from_config(&config);
 
-fn glob_to_regex(pattern: &str) -> String {
-    let mut output = String::from("^");
-    for ch in pattern.chars() {
-        match ch {
-            '*' => output.push_str(".*"),
-            '?' => output.push('.'),
-            _ => output.push_str(&regex::escape(&ch.to_string())),
-        }
+        let allowed = rules
+            .decide(&item_with_path("/tmp/rcleaner-scope/cache/x"))
+            .unwrap();
+        assert_eq!(allowed.action, RuleAction::Allow);
+        assert_eq!(allowed.priority, 20);
+        assert!(!rules.check_item(&item_with_path("/tmp/rcleaner-scope/other")));
+        assert!(!rules.check_item(&item_with_path("/tmp/rcleaner-scope/tie/x")));
+
+        assert!(rules.check_item(&item_with_path("/tmp/rcleaner-logs/a")));
+        let mut log_item = item_with_path("/tmp/rcleaner-logs/a");
+        log_item.category = CleanupCategory::Logs;
+        assert!(!rules.check_item(&log_item));
+
+        assert!(rules.check_item(&item_with_path("/tmp/rcleaner-big/a")));
+        let mut big = item_with_path("/tmp/rcleaner-big/a");
+        big.size = 4096;
+        let reason = rules.decide(&big).unwrap().explain();
+        assert!(reason.contains("/tmp/rcleaner-big"));
+        assert!(reason.contains("priority 10"));
     }
-    output.push('$');
-    output
-}
 
-fn format_rule_reason(rule_type: SafetyRuleType, description: &str) -> String {
-    format!("{}: {}", rule_type_label(rule_type), description)
-}
+    #[test]
+    fn test_builtin_rule_explained() {
+        let rules = SafetyRules::from_config(&Config::default());
+        let decision = rules
+            .decide(&item_with_path("/usr/bin/rcleaner-test"))
+            .unwrap();
+        assert_eq!(decision.action, RuleAction::Deny);
+        assert_eq!(decision.priority, BUILTIN_PRIORITY);
+        assert_eq!(decision.pattern.as_deref(), Some("/usr/bin/*"));
+    }
 
-fn rule_type_label(rule_type: SafetyRuleType) -> &'static str {
-    match rule_type {
-        SafetyRuleType::ProtectSystemPackages => "system",
-        SafetyRuleType::ProtectKernel => "kernel",
-        SafetyRuleType::ProtectBootloader => "bootloader",
-        SafetyRuleType::ProtectUserHome => "user",
-        SafetyRuleType::ProtectActiveApplications => "active",
+    #[test]
+    fn test_locale_scope_is_opt_in() {
+        let mut item = item_with_path("/usr/share/locale/de");
+        item.category = CleanupCategory::Locales;
+        let mut config = Config::default();
+        assert!(!SafetyRules::from_config(&config).check_item(&item));
+
+        config.locales.enabled = true;
+        config.locales.keep = vec!["en".to_string(), "pt_BR".to_string()];
+        let rules = SafetyRules::from_config(&config);
+        assert!(rules.check_item(&item));
+        assert!(!rules.check_item(&item_with_path("/usr/share/locale/de")));
+
+        let mut kept = item.clone();
+        for path in [
+            "/usr/share/locale/en_GB",
+            "/usr/share/man/pt_BR",
+            "/usr/share/help/C",
+            "/usr/share/man/man1",
+            "/usr/share/doc",
+            "/usr/share/icons/hicolor",
+        ] {
+            kept.path = Some(path.to_string());
+            assert!(!rules.check_item(&kept), "{path} must stay protected");
+        }
+        let decision = rules
+            .decide(&CleanupItem {
+                path: Some("/usr/share/locale/en".to_string()),
+                ..item
+            })
+            .unwrap();
+        assert_eq!(decision.action, RuleAction::Deny);
+        assert_eq!(decision.rule_type, SafetyRuleType::LocaleScope);
+    }
+
+    #[test]
+    fn test_safe_path_allowed() {
+        let config = Config::default();
+        let rules = SafetyRules::from_config(&config);
+        let item = item_with_path("/tmp/rcleaner-ok");
+        assert!(rules.check_item(&item));
     }
 }
@@ -0,0 +1,220 @@
+//! Очистка без интерфейса для запуска из таймера systemd.
+//!
+//! Берёт только элементы, которые профиль разрешает очищать без
+//! подтверждения (`auto_clean_categories`), и пишет отчёт в журнал systemd.
+//! `auto_confirm` относится к интерактивному подтверждению: без присмотра
+//! даже профиль safe очищает только эти категории.
+
+use crate::backup::journal;
+use crate::cleaner;
+use crate::config::{Config, ProfileConfig};
+use crate::error::Result;
+use crate::models::{CleanupItem, CleanupResult};
+use crate::privilege;
+use crate::system::mounts;
+use crate::utils::command::is_root;
+use crate::utils::size_format::format_size;
+use std::path::PathBuf;
+
+/// Параметры очистки без интерфейса.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadlessOptions {
+    /// Профиль вместо `safety.level` из конфигурации.
+    pub profile: Option<String>,
+    /// Очищать, только если файловая система заполнена больше чем на N%.
+    pub usage_threshold: Option<u8>,
+    /// Файловая система для проверки заполненности.
+    pub mount: PathBuf,
+    /// Только симуляция.
+    pub dry_run: bool,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            usage_threshold: None,
+            mount: PathBuf::from("/"),
+            dry_run: false,
+        }
+    }
+}
+
+/// Выполняет плановую очистку.
+///
+/// Возвращает `None`, если очистка не понадобилась (диск заполнен меньше
+/// порога) или была отложена из-за прерванной сессии.
+pub fn run(options: &HeadlessOptions) -> Result<Option<CleanupResult>> {
+    if let Some(profile) = &options.profile {
+        Config::override_profile(profile)?;
+    }
+    let config = Config::load(&Config::default_path()).unwrap_or_default();
+    let profile = config.current_profile();
+
+    if let Some(threshold) = options.usage_threshold {
+        let usage = mounts::fs_usage(&options.mount)?;
+        let percent = usage.used_percent();
+        if percent < f64::from(threshold) {
+            report_info(&format!(
+                "{} is {percent:.0}% full, below {threshold}%: nothing to do",
+                options.mount.display()
+            ));
+            return Ok(None);
+        }
+    }
+
+    if let Some(session) = journal::find_interrupted()? {
+        report_warning(&format!(
+            "Interrupted cleanup from {} is pending; open rCleaner to finish or roll it back",
+            session.started_at.format("%Y-%m-%d %H:%M")
+        ));
+        return Ok(None);
+    }
+
+    let mut items = cleaner::scan_all()?;
+    let selected = auto_select(&mut items, profile, is_root());
+    if selected == 0 {
+        report_info("Scheduled cleanup: nothing to clean");
+        return Ok(None);
+    }
+
+    let dry_run = options.dry_run || profile.dry_run;
+    let items: Vec<CleanupItem> = items.into_iter().filter(|item| item.selected).collect();
+    let result = cleaner::clean_selected(&items, dry_run)?;
+    report_result(&result, dry_run);
+    Ok(Some(result))
+}
+
+/// Отмечает элементы, которые можно очистить без подтверждения.
+///
+/// Без root пропускаются элементы, требующие повышения прав: таймер не
+/// может показать запрос пароля. Возвращает число отмеченных элементов.
+pub fn auto_select(items: &mut [CleanupItem], profile: &ProfileConfig, root: bool) -> usize {
+    let mut count = 0;
+    for item in items.iter_mut() {
+        item.selected = item.can_clean
+            && profile.auto_clean_categories.contains(&item.category)
+            && (root || privilege::required_class(item).is_none());
+        if item.selected {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn report_result(result: &CleanupResult, dry_run: bool) {
+    let message = format!(
+        "Scheduled cleanup{}: {} item(s) cleaned, {} freed, {} skipped, {} error(s)",
+        if dry_run { " (dry run)" } else { "" },
+        result.cleaned_items,
+        format_size(result.freed_bytes),
+        result.skipped_items,
+        result.errors.len()
+    );
+    let fields = [
+        ("RCLEANER_CLEANED_ITEMS", result.cleaned_items.to_string()),
+        ("RCLEANER_FREED_BYTES", result.freed_bytes.to_string()),
+        ("RCLEANER_MOVED_BYTES", result.moved_bytes.to_string()),
+        ("RCLEANER_SKIPPED_ITEMS", result.skipped_items.to_string()),
+        ("RCLEANER_ERRORS", result.errors.len().to_string()),
+        ("RCLEANER_DRY_RUN", dry_run.to_string()),
+    ];
+    send(false, &message, &fields);
+    for (mount_point, bytes) in &result.freed_by_mount {
+        report_info(&format!("Freed {} on {mount_point}", format_size(*bytes)));
+    }
+    if result.moved_bytes > 0 {
+        report_info(&format!(
+            "Moved {} to trash/quarantine",
+            format_size(result.moved_bytes)
+        ));
+    }
+    if result.held_by_snapshots > 0 {
+        report_info(&format!(
+            "{} stays in backup snapshots until the backup is deleted",
+            format_size(result.held_by_snapshots)
+        ));
+    }
+    for error in &result.errors {
+        report_warning(error);
+    }
+}
+
+fn report_info(message: &str) {
+    send(false, message, &[]);
+}
+
+fn report_warning(message: &str) {
+    send(true, message, &[]);
+}
+
+/// Пишет запись в журнал systemd, а без него - в лог.
+fn send(warning: bool, message: &str, fields: &[(&str, String)]) {
+    #[cfg(feature = "systemd")]
+    {
+        use libsystemd::logging::{Priority, journal_send};
+        let priority = if warning {
+            Priority::Warning
+        } else {
+            Priority::Info
+        };
+        let vars = fields
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .chain(std::iter::once(("SYSLOG_IDENTIFIER", "rcleaner")));
+        if journal_send(priority, message, vars).is_ok() {
+            return;
+        }
+    }
+    let _ = fields;
+    if warning {
+        log::warn!("{}", message);
+    } else {
+        log::info!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CleanupCategory, CleanupSource};
+
+    fn item(id: &str, category: CleanupCategory, path: Option<&str>) -> CleanupItem {
+        CleanupItem {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: path.map(String::from),
+            size: 1,
+            description: String::new(),
+            category,
+            source: CleanupSource::FileSystem,
+            selected: false,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_auto_select_respects_profile_and_privileges() {
+        let config = Config::default();
+        let mut items = vec![
+            item("cache", CleanupCategory::Cache, Some("/home/u/.cache/x")),
+            item("apps", CleanupCategory::Applications, None),
+            item("var-log", CleanupCategory::Logs, Some("/var/log")),
+            item("old", CleanupCategory::OldPackages, None),
+        ];
+
+        assert_eq!(auto_select(&mut items, &config.profiles.safe, true), 1);
+        assert!(items[0].selected);
+
+        let aggressive = &config.profiles.aggressive;
+        assert_eq!(auto_select(&mut items, aggressive, true), 3);
+        // Без root таймер не может запросить пароль.
+        assert_eq!(auto_select(&mut items, aggressive, false), 1);
+
+        items[0].can_clean = false;
+        assert_eq!(auto_select(&mut items, &config.profiles.safe, true), 0);
+    }
+}
@@ -0,0 +1,108 @@
+//! Плановая очистка через таймеры systemd.
+//!
+//! - [`units`] - генерация service/timer для пользователя или системы
+//! - [`headless`] - очистка без интерфейса, которую запускают таймеры
+//!
+//! `rcleaner schedule install --profile safe --every weekly` записывает units
+//! и включает таймеры; `--disk-threshold 90` добавляет ежечасную проверку
+//! заполненности диска.
+
+pub mod headless;
+pub mod units;
+
+use crate::error::{RcleanerError, Result};
+use crate::system::package_manager::{command_failed, run_command};
+use crate::utils::command::is_root;
+use std::fs;
+use std::path::PathBuf;
+
+pub use self::headless::HeadlessOptions;
+pub use self::units::{CLEAN_UNIT, PRESSURE_UNIT, ScheduleSpec, UnitScope};
+
+/// Записывает units расписания и включает таймеры.
+///
+/// Возвращает пути записанных файлов.
+pub fn install(spec: &ScheduleSpec) -> Result<Vec<PathBuf>> {
+    units::validate(spec)?;
+    check_scope(spec.scope)?;
+
+    let exe = std::env::current_exe()?;
+    let dir = spec.scope.unit_dir();
+    fs::create_dir_all(&dir)?;
+
+    // Старый таймер давления мог остаться от прошлой установки.
+    if spec.disk_threshold.is_none() {
+        remove_units(spec.scope, &[PRESSURE_UNIT])?;
+    }
+
+    let mut written = Vec::new();
+    for unit in units::render_units(spec, &exe) {
+        let path = dir.join(&unit.name);
+        fs::write(&path, unit.content)?;
+        written.push(path);
+    }
+
+    systemctl(spec.scope, &["daemon-reload"])?;
+    let mut enable = vec!["enable", "--now"];
+    let clean_timer = format!("{CLEAN_UNIT}.timer");
+    let pressure_timer = format!("{PRESSURE_UNIT}.timer");
+    enable.push(&clean_timer);
+    if spec.disk_threshold.is_some() {
+        enable.push(&pressure_timer);
+    }
+    systemctl(spec.scope, &enable)?;
+    Ok(written)
+}
+
+/// Отключает таймеры и удаляет units расписания.
+///
+/// Возвращает пути удалённых файлов.
+pub fn uninstall(scope: UnitScope) -> Result<Vec<PathBuf>> {
+    check_scope(scope)?;
+    let removed = remove_units(scope, &[CLEAN_UNIT, PRESSURE_UNIT])?;
+    if !removed.is_empty() {
+        systemctl(scope, &["daemon-reload"])?;
+    }
+    Ok(removed)
+}
+
+fn remove_units(scope: UnitScope, names: &[&str]) -> Result<Vec<PathBuf>> {
+    let dir = scope.unit_dir();
+    let mut removed = Vec::new();
+    for name in names {
+        let timer = dir.join(format!("{name}.timer"));
+        if timer.exists() {
+            let timer_name = format!("{name}.timer");
+            if let Err(err) = systemctl(scope, &["disable", "--now", &timer_name]) {
+                log::warn!("Failed to disable {}: {}", timer_name, err);
+            }
+        }
+        for extension in ["timer", "service"] {
+            let path = dir.join(format!("{name}.{extension}"));
+            if path.exists() {
+                fs::remove_file(&path)?;
+                removed.push(path);
+            }
+        }
+    }
+    Ok(removed)
+}
+
+fn check_scope(scope: UnitScope) -> Result<()> {
+    if scope == UnitScope::System && !is_root() {
+        return Err(RcleanerError::Permission(
+            "system units require root; run the command with sudo".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn systemctl(scope: UnitScope, args: &[&str]) -> Result<()> {
+    let mut full_args = scope.systemctl_args().to_vec();
+    full_args.extend_from_slice(args);
+    let output = run_command("systemctl", &full_args)?;
+    if !output.status.success() {
+        return Err(command_failed("systemctl", &output));
+    }
+    Ok(())
+}
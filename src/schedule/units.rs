@@ -0,0 +1,263 @@
+//! Генерация unit-файлов systemd для плановой очистки.
+
+use crate::error::{RcleanerError, Result};
+use std::path::{Path, PathBuf};
+
+/// Имя service/timer плановой очистки.
+pub const CLEAN_UNIT: &str = "rcleaner-clean";
+/// Имя service/timer проверки заполненности диска.
+pub const PRESSURE_UNIT: &str = "rcleaner-pressure";
+
+/// Как часто проверяется заполненность диска.
+const PRESSURE_INTERVAL: &str = "hourly";
+
+/// Где устанавливаются units: у пользователя или в системе.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitScope {
+    /// `systemctl --user`, units в `~/.config/systemd/user`.
+    User,
+    /// Системный менеджер, units в `/etc/systemd/system` (нужен root).
+    System,
+}
+
+impl UnitScope {
+    /// Каталог unit-файлов.
+    pub fn unit_dir(self) -> PathBuf {
+        match self {
+            Self::User => {
+                let config_home = std::env::var("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| {
+                        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+                        PathBuf::from(home).join(".config")
+                    });
+                config_home.join("systemd").join("user")
+            }
+            Self::System => PathBuf::from("/etc/systemd/system"),
+        }
+    }
+
+    /// Аргументы `systemctl` для этой области.
+    pub fn systemctl_args(self) -> &'static [&'static str] {
+        match self {
+            Self::User => &["--user"],
+            Self::System => &[],
+        }
+    }
+}
+
+/// Параметры расписания.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleSpec {
+    /// Профиль очистки: `safe` или `aggressive`.
+    pub profile: String,
+    /// Периодичность (`daily`, `weekly`, `monthly` или выражение OnCalendar).
+    pub every: String,
+    /// Запускать очистку, когда файловая система заполнена больше чем на N%.
+    pub disk_threshold: Option<u8>,
+    /// Файловая система, заполненность которой проверяется.
+    pub mount: PathBuf,
+    /// Пользовательские или системные units.
+    pub scope: UnitScope,
+}
+
+impl Default for ScheduleSpec {
+    fn default() -> Self {
+        Self {
+            profile: "safe".to_string(),
+            every: "weekly".to_string(),
+            disk_threshold: None,
+            mount: PathBuf::from("/"),
+            scope: UnitScope::User,
+        }
+    }
+}
+
+/// Содержимое одного unit-файла.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitFile {
+    /// Имя файла, например `rcleaner-clean.timer`.
+    pub name: String,
+    pub content: String,
+}
+
+/// Проверяет параметры расписания.
+pub fn validate(spec: &ScheduleSpec) -> Result<()> {
+    let profile = spec.profile.to_lowercase();
+    if profile != "safe" && profile != "aggressive" {
+        return Err(RcleanerError::InvalidInput(format!(
+            "unknown profile {}, expected safe or aggressive",
+            spec.profile
+        )));
+    }
+    if spec.every.trim().is_empty() || spec.every.chars().any(|ch| ch.is_control()) {
+        return Err(RcleanerError::InvalidInput(format!(
+            "invalid schedule {:?}",
+            spec.every
+        )));
+    }
+    if let Some(threshold) = spec.disk_threshold
+        && !(1..=99).contains(&threshold)
+    {
+        return Err(RcleanerError::InvalidInput(format!(
+            "disk threshold must be between 1 and 99, got {threshold}"
+        )));
+    }
+    if !spec.mount.is_absolute() {
+        return Err(RcleanerError::InvalidInput(format!(
+            "mount point must be absolute: {}",
+            spec.mount.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Строит unit-файлы для расписания.
+///
+/// Всегда создаётся периодическая пара `rcleaner-clean.{service,timer}`;
+/// при заданном пороге добавляется `rcleaner-pressure.{service,timer}`,
+/// которая каждый час запускает очистку только при заполненном диске.
+pub fn render_units(spec: &ScheduleSpec, exe: &Path) -> Vec<UnitFile> {
+    let profile = spec.profile.to_lowercase();
+    let command = format!(
+        "{} clean --headless --profile {profile}",
+        quote_exec(&exe.to_string_lossy())
+    );
+
+    let mut units = vec![
+        service_unit(
+            CLEAN_UNIT,
+            &format!("rCleaner scheduled cleanup ({profile} profile)"),
+            &command,
+        ),
+        timer_unit(
+            CLEAN_UNIT,
+            &format!("Run rCleaner cleanup {}", spec.every),
+            &spec.every,
+            true,
+        ),
+    ];
+
+    if let Some(threshold) = spec.disk_threshold {
+        let mount = spec.mount.to_string_lossy();
+        units.push(service_unit(
+            PRESSURE_UNIT,
+            &format!("rCleaner cleanup when {mount} is over {threshold}% full"),
+            &format!(
+                "{command} --if-usage-above {threshold} --mount {}",
+                quote_exec(&mount)
+            ),
+        ));
+        units.push(timer_unit(
+            PRESSURE_UNIT,
+            &format!("Check {mount} usage for rCleaner"),
+            PRESSURE_INTERVAL,
+            false,
+        ));
+    }
+    units
+}
+
+fn service_unit(name: &str, description: &str, command: &str) -> UnitFile {
+    UnitFile {
+        name: format!("{name}.service"),
+        content: format!(
+            "[Unit]\n\
+             Description={description}\n\
+             \n\
+             [Service]\n\
+             Type=oneshot\n\
+             ExecStart={command}\n\
+             Nice=19\n\
+             IOSchedulingClass=idle\n"
+        ),
+    }
+}
+
+fn timer_unit(name: &str, description: &str, calendar: &str, persistent: bool) -> UnitFile {
+    UnitFile {
+        name: format!("{name}.timer"),
+        content: format!(
+            "[Unit]\n\
+             Description={description}\n\
+             \n\
+             [Timer]\n\
+             OnCalendar={calendar}\n\
+             Persistent={persistent}\n\
+             RandomizedDelaySec=10min\n\
+             \n\
+             [Install]\n\
+             WantedBy=timers.target\n"
+        ),
+    }
+}
+
+/// Экранирует аргумент для `ExecStart`.
+fn quote_exec(value: &str) -> String {
+    if value
+        .chars()
+        .any(|ch| ch.is_whitespace() || "\"'\\;$%".contains(ch))
+    {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('$', "$$")
+            .replace('%', "%%");
+        format!("\"{escaped}\"")
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_units_with_pressure_trigger() {
+        let spec = ScheduleSpec {
+            disk_threshold: Some(90),
+            ..ScheduleSpec::default()
+        };
+        let units = render_units(&spec, Path::new("/usr/bin/rcleaner"));
+        let names: Vec<_> = units.iter().map(|unit| unit.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "rcleaner-clean.service",
+                "rcleaner-clean.timer",
+                "rcleaner-pressure.service",
+                "rcleaner-pressure.timer"
+            ]
+        );
+        assert!(
+            units[0]
+                .content
+                .contains("ExecStart=/usr/bin/rcleaner clean --headless --profile safe\n")
+        );
+        assert!(units[1].content.contains("OnCalendar=weekly\n"));
+        assert!(units[2].content.contains("--if-usage-above 90 --mount /\n"));
+        assert!(units[3].content.contains("OnCalendar=hourly\n"));
+        assert!(units[3].content.contains("Persistent=false\n"));
+    }
+
+    #[test]
+    fn test_validate_and_quote() {
+        let mut spec = ScheduleSpec::default();
+        assert!(validate(&spec).is_ok());
+        spec.every = "weekly\nExecStart=/bin/sh".to_string();
+        assert!(validate(&spec).is_err());
+        spec.every = "daily".to_string();
+        spec.profile = "reckless".to_string();
+        assert!(validate(&spec).is_err());
+        spec.profile = "safe".to_string();
+        spec.disk_threshold = Some(100);
+        assert!(validate(&spec).is_err());
+
+        assert_eq!(
+            quote_exec("/home/me/My Apps/rcleaner"),
+            "\"/home/me/My Apps/rcleaner\""
+        );
+        assert_eq!(quote_exec("/usr/bin/rcleaner"), "/usr/bin/rcleaner");
+    }
+}
@@ -0,0 +1,317 @@
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::package_manager::{PackageManager, RemovalPlan, command_failed, run_command};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// База установленных пакетов apk.
+pub const INSTALLED_DB: &str = "/lib/apk/db/installed";
+
+/// Явно установленные пакеты (`apk add`).
+pub const WORLD_FILE: &str = "/etc/apk/world";
+
+/// Ссылка на каталог кэша; без неё apk пакеты не кэширует.
+const CACHE_LINK: &str = "/etc/apk/cache";
+
+pub struct ApkManager;
+
+impl Default for ApkManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApkManager {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl PackageManager for ApkManager {
+    fn name(&self) -> &str {
+        "apk"
+    }
+
+    fn version(&self) -> Result<String> {
+        let output = run_command("apk", &["--version"])?;
+        if !output.status.success() {
+            return Err(command_failed("apk", &output));
+        }
+        Ok(first_line(&output.stdout))
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let output = run_command("apk", &["info"])?;
+        if !output.status.success() {
+            return Err(command_failed("apk", &output));
+        }
+        Ok(split_lines(&output.stdout))
+    }
+
+    fn check_dependencies(&self, package: &str) -> Result<Vec<String>> {
+        let output = run_command("apk", &["info", "--rdepends", package])?;
+        if !output.status.success() {
+            return Err(command_failed("apk", &output));
+        }
+        Ok(parse_required_by(&output.stdout))
+    }
+
+    fn remove_packages(&self, packages: &[String], dry_run: bool) -> Result<()> {
+        if packages.is_empty() {
+            return Ok(());
+        }
+
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
+
+        let mut args = vec!["del"];
+        if dry_run {
+            args.push("--simulate");
+        }
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("apk", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("apk", &output));
+        }
+
+        Ok(())
+    }
+
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        let mut args = vec!["del", "--simulate"];
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("apk", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("apk", &output));
+        }
+
+        let mut plan = RemovalPlan::new("apk", packages);
+        plan.removed = parse_apk_simulation(&output.stdout);
+        if let Ok(content) = fs::read_to_string(INSTALLED_DB) {
+            let installed = parse_installed(&content);
+            plan.freed_bytes = Some(
+                plan.removed
+                    .iter()
+                    .filter_map(|name| installed.get(name))
+                    .map(|package| package.size)
+                    .sum(),
+            );
+        }
+        Ok(plan)
+    }
+}
+
+/// Установленный пакет из [`INSTALLED_DB`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ApkPackage {
+    version: String,
+    /// Установленный размер в байтах.
+    size: u64,
+}
+
+pub fn list_installed() -> Result<Vec<String>> {
+    ApkManager::new().list_installed()
+}
+
+pub fn remove_packages(packages: &[String], dry_run: bool) -> Result<()> {
+    ApkManager::new().remove_packages(packages, dry_run)
+}
+
+/// Пакеты, до которых нельзя дойти по зависимостям от пакетов из world.
+pub fn orphan_packages() -> Result<Vec<String>> {
+    let world = parse_world(&fs::read_to_string(WORLD_FILE)?);
+    Ok(DependencyGraph::load("apk")?.unreachable_from(&world))
+}
+
+/// Каталог кэша пакетов, если кэш включён.
+pub fn cache_dir() -> Option<PathBuf> {
+    fs::canonicalize(CACHE_LINK)
+        .ok()
+        .filter(|path| path.is_dir())
+}
+
+/// Размер пакетов в кэше, которые не соответствуют установленным версиям
+/// (их удалит `apk cache clean`).
+pub fn obsolete_cache_size() -> u64 {
+    let Some(dir) = cache_dir() else {
+        return 0;
+    };
+    let installed: HashSet<String> = fs::read_to_string(INSTALLED_DB)
+        .map(|content| {
+            parse_installed(&content)
+                .into_iter()
+                .map(|(name, package)| format!("{name}-{}", package.version))
+                .collect()
+        })
+        .unwrap_or_default();
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| {
+            cached_pkgver(&entry.file_name().to_string_lossy())
+                .is_some_and(|pkgver| !installed.contains(pkgver))
+        })
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Удаляет из кэша устаревшие пакеты (`apk cache clean`).
+pub fn clean_cache(dry_run: bool) -> Result<()> {
+    if dry_run {
+        log::info!(
+            "[DRY RUN] apk cache clean ({})",
+            cache_dir()
+                .as_deref()
+                .unwrap_or(Path::new(CACHE_LINK))
+                .display()
+        );
+        return Ok(());
+    }
+    let output = run_command("apk", &["cache", "clean"])?;
+    if !output.status.success() {
+        return Err(command_failed("apk", &output));
+    }
+    Ok(())
+}
+
+/// Имя пакета из `имя-версия-rN`.
+pub fn package_name(pkgver: &str) -> &str {
+    let mut parts = pkgver.rsplitn(3, '-');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(release), Some(_version), Some(name)) if release.starts_with('r') => name,
+        _ => pkgver,
+    }
+}
+
+/// `имя-версия-rN` из имени файла кэша `имя-версия-rN.<хэш>.apk`.
+fn cached_pkgver(file_name: &str) -> Option<&str> {
+    let (pkgver, _hash) = file_name.strip_suffix(".apk")?.rsplit_once('.')?;
+    Some(pkgver)
+}
+
+/// Поля `P:`, `V:` и `I:` из базы установленных пакетов.
+fn parse_installed(content: &str) -> HashMap<String, ApkPackage> {
+    let mut packages = HashMap::new();
+    let mut name: Option<String> = None;
+    let mut package = ApkPackage {
+        version: String::new(),
+        size: 0,
+    };
+    // Записи разделены пустой строкой; в конце файла её может не быть.
+    for line in content.lines().chain(std::iter::once("")) {
+        if line.is_empty() {
+            if let Some(name) = name.take() {
+                packages.insert(name, package.clone());
+            }
+            package.version.clear();
+            package.size = 0;
+            continue;
+        }
+        match line.split_once(':') {
+            Some(("P", value)) => name = Some(value.to_string()),
+            Some(("V", value)) => package.version = value.to_string(),
+            Some(("I", value)) => package.size = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+    packages
+}
+
+/// Имена из world без версий, тегов репозиториев и запретов `!`.
+fn parse_world(content: &str) -> Vec<String> {
+    content
+        .split_whitespace()
+        .filter(|entry| !entry.starts_with('!'))
+        .filter_map(|entry| entry.split(['<', '>', '=', '~', '@']).next())
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Пакеты из строк `(1/2) Purging foo (1.0-r0)` вывода `apk del --simulate`.
+fn parse_apk_simulation(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix('('))
+        .filter_map(|rest| rest.split_once(") "))
+        .filter_map(|(_, action)| action.strip_prefix("Purging "))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+/// Пакеты из вывода `apk info --rdepends` (`foo-1.0-r0 is required by:`).
+fn parse_required_by(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .map(|pkgver| package_name(pkgver).to_string())
+        .collect()
+}
+
+fn first_line(output: &str) -> String {
+    output.lines().next().unwrap_or_default().trim().to_string()
+}
+
+fn split_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_apk_output() {
+        let installed = "\
+C:Q1abc=
+P:musl
+V:1.2.4-r2
+I:647168
+D:
+
+C:Q1def=
+P:curl
+V:8.5.0-r0
+I:311296
+D:ca-certificates-bundle so:libc.musl-x86_64.so.1 so:libcurl.so.4";
+        let packages = parse_installed(installed);
+        assert_eq!(packages["curl"].version, "8.5.0-r0");
+        assert_eq!(packages["musl"].size, 647168);
+
+        assert_eq!(
+            parse_world("alpine-base\ncurl@edge\nbusybox>=1.36\n!sudo\n"),
+            vec!["alpine-base", "curl", "busybox"]
+        );
+        assert_eq!(
+            cached_pkgver("curl-8.4.0-r0.0f1e2d3c.apk"),
+            Some("curl-8.4.0-r0")
+        );
+        assert_eq!(cached_pkgver("APKINDEX.66df4ba4.tar.gz"), None);
+
+        let simulation = "\
+(1/3) Purging curl (8.5.0-r0)
+(2/3) Purging libcurl (8.5.0-r0)
+(3/3) Purging nghttp2-libs (1.58.0-r0)
+OK: 9 MiB in 15 packages
+";
+        assert_eq!(
+            parse_apk_simulation(simulation),
+            vec!["curl", "libcurl", "nghttp2-libs"]
+        );
+        assert_eq!(
+            parse_required_by("libcurl-8.5.0-r0 is required by:\ncurl-8.5.0-r0\n\n"),
+            vec!["curl"]
+        );
+        assert_eq!(package_name("py3-foo-bar-1.0-r3"), "py3-foo-bar");
+    }
+}
@@ -1,5 +1,7 @@
-use crate::error::{RcleanerError, Result};
-use crate::system::package_manager::{PackageManager, command_failed, run_command};
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::package_manager::{PackageManager, RemovalPlan, command_failed, run_command};
+use crate::utils::size_format::parse_size_string;
 
 pub struct AptManager;
 
@@ -51,15 +53,7 @@ impl PackageManager for AptManager {
             return Ok(());
         }
 
-        for package in packages {
-            let deps = self.check_dependencies(package)?;
-            if !deps.is_empty() {
-                return Err(RcleanerError::Dependency(format!(
-                    "Package {package} is required by: {}",
-                    deps.join(", ")
-                )));
-            }
-        }
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
 
         let mut args = vec!["remove"];
         if dry_run {
@@ -78,6 +72,25 @@ impl PackageManager for AptManager {
 
         Ok(())
     }
+
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        let mut args = vec!["-s", "remove"];
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("apt-get", &args)?;
+        let mut plan = parse_apt_simulation(&output.stdout, packages);
+        if !output.status.success() && plan.held.is_empty() {
+            return Err(command_failed("apt-get", &output));
+        }
+
+        if let Ok(holds) = run_command("apt-mark", &["showhold"]) {
+            for package in split_lines(&holds.stdout) {
+                if plan.removed.contains(&package) && !plan.held.contains(&package) {
+                    plan.held.push(package);
+                }
+            }
+        }
+        Ok(plan)
+    }
 }
 
 pub fn list_installed() -> Result<Vec<String>> {
@@ -101,6 +114,39 @@ fn split_lines(output: &str) -> Vec<String> {
         .collect()
 }
 
+/// Разбирает вывод `apt-get -s remove`: строки `Remv`, освобождаемое место
+/// и список essential-пакетов из предупреждения.
+fn parse_apt_simulation(output: &str, requested: &[String]) -> RemovalPlan {
+    let mut plan = RemovalPlan::new("apt", requested);
+    plan.removed.clear();
+    let mut in_essential = false;
+    for line in output.lines() {
+        if line.starts_with("WARNING: The following essential packages") {
+            in_essential = true;
+            continue;
+        }
+        if in_essential {
+            if line.starts_with(' ') {
+                plan.held.extend(line.split_whitespace().map(String::from));
+                continue;
+            }
+            if !line.starts_with("This should NOT") {
+                in_essential = false;
+            }
+        }
+        if let Some(rest) = line.strip_prefix("Remv ")
+            && let Some(package) = rest.split_whitespace().next()
+        {
+            plan.removed.push(package.to_string());
+        } else if let Some(rest) = line.strip_prefix("After this operation, ")
+            && let Some(amount) = rest.strip_suffix(" disk space will be freed.")
+        {
+            plan.freed_bytes = parse_size_string(&amount.replace(',', ""));
+        }
+    }
+    plan
+}
+
 fn parse_apt_rdepends(output: &str) -> Vec<String> {
     output
         .lines()
@@ -120,3 +166,31 @@ fn parse_apt_rdepends(output: &str) -> Vec<String> {
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_apt_simulation() {
+        let output = "\
+Reading package lists...
+The following packages will be REMOVED:
+  foo libfoo1 login
+WARNING: The following essential packages will be removed.
+This should NOT be done unless you know exactly what you are doing!
+  login
+0 upgraded, 0 newly installed, 3 to remove and 0 not upgraded.
+After this operation, 1,536 kB disk space will be freed.
+Remv foo [1.0-1]
+Remv libfoo1 [1.0-1]
+Remv login [1:4.13]
+";
+        let plan = parse_apt_simulation(output, &["foo".to_string()]);
+        assert_eq!(plan.removed, vec!["foo", "libfoo1", "login"]);
+        assert_eq!(plan.extra(), vec!["libfoo1", "login"]);
+        assert_eq!(plan.held, vec!["login"]);
+        assert_eq!(plan.freed_bytes, Some(1536 * 1024));
+        assert!(plan.is_blocked());
+    }
+}
@@ -0,0 +1,464 @@
+//! Какие ядра нужны загрузчику: запись по умолчанию GRUB и `saved_entry`,
+//! запись по умолчанию systemd-boot, последняя подтверждённая запись BLS
+//! (`systemd-bless-boot`) и ядро предыдущей загрузки из журнала.
+//!
+//! Такие ядра не удаляются, даже если они старше `keep_recent_kernels`.
+
+use crate::system::package_manager::run_command;
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Каталоги GRUB с `grub.cfg` и `grubenv`.
+pub const GRUB_DIRS: &[&str] = &["/boot/grub", "/boot/grub2"];
+
+/// Настройки GRUB с `GRUB_DEFAULT`.
+pub const GRUB_DEFAULTS: &str = "/etc/default/grub";
+
+/// Каталоги загрузчика с `loader.conf` и `entries/*.conf` (Boot Loader Specification).
+pub const LOADER_DIRS: &[&str] = &["/boot/loader", "/efi/loader", "/boot/efi/loader"];
+
+/// Переменные EFI systemd-boot.
+const EFIVARS_DIR: &str = "/sys/firmware/efi/efivars";
+const LOADER_VENDOR_GUID: &str = "4a67b082-0a4c-41cf-b6c7-440b29bb8c4f";
+
+/// Сколько предыдущих загрузок просматривать в журнале.
+const MAX_PREVIOUS_BOOTS: usize = 10;
+
+/// Ядро, которое нельзя удалять, и почему.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootPin {
+    pub version: String,
+    pub reason: String,
+}
+
+/// Пункт меню GRUB или запись BLS.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootEntry {
+    /// `$menuentry_id_option` GRUB или имя файла BLS без `.conf` и счётчика попыток.
+    pub id: String,
+    pub title: String,
+    pub version: Option<String>,
+    /// Номер пункта в меню GRUB (`1>2` внутри подменю).
+    pub position: String,
+    /// Запись BLS со счётчиком попыток (`+3-0`) ещё не подтверждена.
+    pub counting: bool,
+    /// Время последнего изменения записи (подтверждение переименовывает файл).
+    pub changed: i64,
+}
+
+/// Ядра, которые загрузит загрузчик или к которым пользователь вернётся,
+/// если текущее не загрузится.
+pub fn protected_kernels(current: &str) -> Vec<BootPin> {
+    let grub = GRUB_DIRS
+        .iter()
+        .find_map(|dir| fs::read_to_string(Path::new(dir).join("grub.cfg")).ok())
+        .map(|content| parse_grub_cfg(&content))
+        .unwrap_or_default();
+    let bls = bls_entries();
+    let mut pins: Vec<BootPin> = Vec::new();
+    let mut pin = |version: Option<String>, reason: &str| {
+        if let Some(version) = version
+            && !pins.iter().any(|pin| pin.version == version)
+        {
+            pins.push(BootPin {
+                version,
+                reason: reason.to_string(),
+            });
+        }
+    };
+
+    let env = GRUB_DIRS
+        .iter()
+        .find_map(|dir| fs::read_to_string(Path::new(dir).join("grubenv")).ok())
+        .map(|content| parse_key_values(&content, '='))
+        .unwrap_or_default();
+    if !grub.is_empty() {
+        let default = fs::read_to_string(GRUB_DEFAULTS)
+            .ok()
+            .and_then(|content| parse_key_values(&content, '=').remove("GRUB_DEFAULT"))
+            .unwrap_or_else(|| "0".to_string());
+        if default != "saved" {
+            pin(resolve_entry(&default, &grub, &bls), "GRUB default entry");
+        }
+    }
+    for key in ["saved_entry", "next_entry"] {
+        if let Some(reference) = env.get(key).filter(|value| !value.is_empty()) {
+            pin(
+                resolve_entry(reference, &grub, &bls),
+                &format!("GRUB {key}"),
+            );
+        }
+    }
+
+    if let Some(entry) = systemd_boot_default(&bls) {
+        pin(entry.version.clone(), "systemd-boot default entry");
+    }
+    if let Some(entry) = last_known_good(&bls, current) {
+        pin(
+            entry.version.clone(),
+            "last known-good boot entry (systemd-bless-boot)",
+        );
+    }
+    pin(previous_boot_kernel(current), "kernel of the previous boot");
+
+    pins
+}
+
+/// Записи BLS из всех каталогов загрузчика.
+pub fn bls_entries() -> Vec<BootEntry> {
+    let mut entries = Vec::new();
+    for dir in LOADER_DIRS {
+        let Ok(files) = fs::read_dir(Path::new(dir).join("entries")) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+            let (Some(stem), Ok(content)) =
+                (name.strip_suffix(".conf"), fs::read_to_string(file.path()))
+            else {
+                continue;
+            };
+            let changed = file
+                .metadata()
+                .map(|metadata| metadata.ctime())
+                .unwrap_or(0);
+            entries.push(parse_bls_entry(stem, &content, changed));
+        }
+    }
+    entries.sort_by(|a, b| a.id.cmp(&b.id));
+    entries
+}
+
+/// Запись по умолчанию systemd-boot: переменная EFI `LoaderEntryDefault`
+/// или `default` из `loader.conf`.
+fn systemd_boot_default(entries: &[BootEntry]) -> Option<&BootEntry> {
+    let pattern = efi_variable("LoaderEntryDefault").or_else(|| {
+        LOADER_DIRS.iter().find_map(|dir| {
+            let content = fs::read_to_string(Path::new(dir).join("loader.conf")).ok()?;
+            parse_key_values(&content, ' ').remove("default")
+        })
+    })?;
+    let pattern = if pattern == "@saved" {
+        efi_variable("LoaderEntryLastBooted")?
+    } else {
+        pattern
+    };
+    let pattern = pattern.strip_suffix(".conf").unwrap_or(&pattern);
+    // Из подходящих записей systemd-boot выбирает последнюю по имени.
+    entries
+        .iter()
+        .rfind(|entry| wildcard_match(pattern, &entry.id))
+}
+
+/// Последняя подтверждённая запись BLS с другим ядром. Имеет смысл, только
+/// если используется подсчёт попыток: иначе подтверждены все записи.
+fn last_known_good<'a>(entries: &'a [BootEntry], current: &str) -> Option<&'a BootEntry> {
+    if !entries.iter().any(|entry| entry.counting) {
+        return None;
+    }
+    entries
+        .iter()
+        .filter(|entry| !entry.counting && entry.version.as_deref() != Some(current))
+        .max_by_key(|entry| entry.changed)
+}
+
+/// Ядро последней из предыдущих загрузок, которое отличается от текущего.
+fn previous_boot_kernel(current: &str) -> Option<String> {
+    let boots = run_command("journalctl", &["--list-boots", "--no-pager", "-q"]).ok()?;
+    for offset in parse_boot_offsets(&boots.stdout)
+        .into_iter()
+        .take(MAX_PREVIOUS_BOOTS)
+    {
+        let offset = offset.to_string();
+        let Ok(log) = run_command(
+            "journalctl",
+            &["-k", "-b", &offset, "-o", "cat", "-q", "--no-pager"],
+        ) else {
+            continue;
+        };
+        if let Some(version) = kernel_from_log(&log.stdout)
+            && version != current
+        {
+            return Some(version);
+        }
+    }
+    None
+}
+
+/// Ядро пункта меню: номер (`0`, `1>2`), идентификатор или заголовок GRUB,
+/// идентификатор записи BLS или версия внутри ссылки.
+fn resolve_entry(reference: &str, grub: &[BootEntry], bls: &[BootEntry]) -> Option<String> {
+    let reference = reference.trim_matches('"');
+    // В подменю ссылка — путь `подменю>пункт` из заголовков или идентификаторов.
+    let last = reference.rsplit('>').next().unwrap_or(reference);
+    let found = grub
+        .iter()
+        .find(|entry| entry.position == reference || entry.id == last || entry.title == last)
+        .or_else(|| {
+            let id = reference.strip_suffix(".conf").unwrap_or(reference);
+            bls.iter().find(|entry| entry.id == id)
+        });
+    if let Some(entry) = found {
+        return entry.version.clone();
+    }
+    grub.iter()
+        .chain(bls)
+        .filter_map(|entry| entry.version.as_deref())
+        .filter(|version| reference.contains(version))
+        .max_by_key(|version| version.len())
+        .map(String::from)
+}
+
+/// Пункты меню из `grub.cfg` с номерами и ядрами из команды `linux`.
+fn parse_grub_cfg(content: &str) -> Vec<BootEntry> {
+    enum Block {
+        Entry(usize),
+        Submenu,
+        Other,
+    }
+
+    let mut entries: Vec<BootEntry> = Vec::new();
+    let mut blocks: Vec<Block> = Vec::new();
+    // Номер следующего пункта на каждом уровне меню и номера открытых подменю.
+    let mut counters = vec![0usize];
+    let mut submenus: Vec<usize> = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        let is_entry = line.starts_with("menuentry ");
+        if is_entry || line.starts_with("submenu ") {
+            let level = counters.len() - 1;
+            let index = counters[level];
+            counters[level] += 1;
+            let position = submenus
+                .iter()
+                .chain([&index])
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(">");
+            if is_entry {
+                entries.push(BootEntry {
+                    id: line
+                        .split_once("$menuentry_id_option")
+                        .and_then(|(_, rest)| quoted(rest))
+                        .unwrap_or_default(),
+                    title: quoted(line).unwrap_or_default(),
+                    version: None,
+                    position,
+                    counting: false,
+                    changed: 0,
+                });
+                blocks.push(Block::Entry(entries.len() - 1));
+            } else {
+                submenus.push(index);
+                counters.push(0);
+                blocks.push(Block::Submenu);
+            }
+        } else if line == "}" {
+            if let Some(Block::Submenu) = blocks.pop() {
+                submenus.pop();
+                counters.pop();
+            }
+        } else if line.ends_with('{') {
+            blocks.push(Block::Other);
+        } else if let Some(Block::Entry(index)) = blocks.last() {
+            let mut tokens = line.split_whitespace();
+            if matches!(tokens.next(), Some("linux" | "linuxefi" | "linux16"))
+                && let Some(image) = tokens.next()
+            {
+                entries[*index].version = version_from_image(image);
+            }
+        }
+    }
+    entries
+}
+
+/// Запись BLS: `title`, `version` и `linux`; счётчик попыток в имени
+/// файла (`fedora-6.5+2-1`) означает, что загрузка ещё не подтверждена.
+fn parse_bls_entry(stem: &str, content: &str, changed: i64) -> BootEntry {
+    let (id, counting) = match stem.split_once('+') {
+        Some((id, _)) => (id, true),
+        None => (stem, false),
+    };
+    let fields = parse_key_values(content, ' ');
+    BootEntry {
+        id: id.to_string(),
+        title: fields.get("title").cloned().unwrap_or_default(),
+        version: fields.get("version").cloned().or_else(|| {
+            fields
+                .get("linux")
+                .and_then(|image| version_from_image(image))
+        }),
+        position: String::new(),
+        counting,
+        changed,
+    }
+}
+
+/// Версия из пути образа: `/vmlinuz-6.1.0-13-amd64` или
+/// `/<machine-id>/6.5.0-1.fc39.x86_64/linux` (раскладка kernel-install).
+fn version_from_image(image: &str) -> Option<String> {
+    let path = Path::new(image);
+    let name = path.file_name()?.to_string_lossy();
+    if let Some(version) = name
+        .strip_prefix("vmlinuz-")
+        .or_else(|| name.strip_prefix("vmlinux-"))
+    {
+        return Some(version.to_string());
+    }
+    (name == "linux")
+        .then(|| path.parent()?.file_name())
+        .flatten()
+        .map(|version| version.to_string_lossy().to_string())
+}
+
+/// Смещения предыдущих загрузок из `journalctl --list-boots`, от ближайшей.
+fn parse_boot_offsets(output: &str) -> Vec<i64> {
+    let mut offsets: Vec<i64> = output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next()?.parse().ok())
+        .filter(|offset| *offset < 0)
+        .collect();
+    offsets.sort_unstable_by(|a, b| b.cmp(a));
+    offsets
+}
+
+/// Версия из строки ядра `Linux version 6.1.0-13-amd64 (…)`.
+fn kernel_from_log(log: &str) -> Option<String> {
+    log.lines()
+        .find_map(|line| line.trim().strip_prefix("Linux version "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .map(String::from)
+}
+
+/// Пары `ключ<разделитель>значение` без комментариев и кавычек.
+fn parse_key_values(content: &str, separator: char) -> HashMap<String, String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(separator))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value.trim().trim_matches(['"', '\'']).to_string(),
+            )
+        })
+        .collect()
+}
+
+fn quoted(text: &str) -> Option<String> {
+    let start = text.find(['\'', '"'])?;
+    let quote = text[start..].chars().next()?;
+    let rest = &text[start + 1..];
+    Some(rest[..rest.find(quote)?].to_string())
+}
+
+/// Строка из переменной EFI systemd-boot: 4 байта атрибутов, затем UTF-16LE.
+fn efi_variable(name: &str) -> Option<String> {
+    let data =
+        fs::read(Path::new(EFIVARS_DIR).join(format!("{name}-{LOADER_VENDOR_GUID}"))).ok()?;
+    let units: Vec<u16> = data
+        .get(4..)?
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16(&units)
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+/// Шаблон `default` из `loader.conf`: `*` и `?`.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    match pattern.chars().next() {
+        None => text.is_empty(),
+        Some('*') => (0..=text.len())
+            .filter(|index| text.is_char_boundary(*index))
+            .any(|index| wildcard_match(&pattern[1..], &text[index..])),
+        Some(ch) => text.chars().next().is_some_and(|first| {
+            (ch == '?' || ch == first)
+                && wildcard_match(&pattern[ch.len_utf8()..], &text[first.len_utf8()..])
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_boot_entries() {
+        let grub_cfg = r#"
+function load_video {
+  insmod all_video
+}
+menuentry 'Debian GNU/Linux' --class debian $menuentry_id_option 'gnulinux-simple-abcd' {
+	load_video
+	linux	/vmlinuz-6.1.0-14-amd64 root=UUID=abcd ro quiet
+}
+submenu 'Advanced options for Debian GNU/Linux' $menuentry_id_option 'gnulinux-advanced-abcd' {
+	menuentry 'Debian GNU/Linux, with Linux 6.1.0-14-amd64' $menuentry_id_option 'gnulinux-6.1.0-14-amd64-advanced-abcd' {
+		linux	/vmlinuz-6.1.0-14-amd64 root=UUID=abcd ro quiet
+	}
+	menuentry 'Debian GNU/Linux, with Linux 6.1.0-13-amd64' $menuentry_id_option 'gnulinux-6.1.0-13-amd64-advanced-abcd' {
+		linux	/vmlinuz-6.1.0-13-amd64 root=UUID=abcd ro quiet
+	}
+}
+"#;
+        let grub = parse_grub_cfg(grub_cfg);
+        assert_eq!(grub.len(), 3);
+        assert_eq!(grub[2].position, "1>1");
+        let resolve = |reference: &str| resolve_entry(reference, &grub, &[]);
+        assert_eq!(resolve("0").as_deref(), Some("6.1.0-14-amd64"));
+        assert_eq!(resolve("1>1").as_deref(), Some("6.1.0-13-amd64"));
+        assert_eq!(
+            resolve("gnulinux-advanced-abcd>gnulinux-6.1.0-13-amd64-advanced-abcd").as_deref(),
+            Some("6.1.0-13-amd64")
+        );
+        assert_eq!(
+            resolve(
+                "Advanced options for Debian GNU/Linux>Debian GNU/Linux, with Linux 6.1.0-13-amd64"
+            )
+            .as_deref(),
+            Some("6.1.0-13-amd64")
+        );
+
+        let bls = vec![
+            parse_bls_entry(
+                "0123-6.5.0-1.fc39.x86_64",
+                "title Fedora (6.5.0-1)\nversion 6.5.0-1.fc39.x86_64\nlinux /vmlinuz-6.5.0-1.fc39.x86_64\n",
+                10,
+            ),
+            parse_bls_entry(
+                "0123-6.6.0-2.fc39.x86_64+2-1",
+                "title Fedora (6.6.0-2)\nlinux /0123/6.6.0-2.fc39.x86_64/linux\n",
+                20,
+            ),
+        ];
+        assert_eq!(bls[1].version.as_deref(), Some("6.6.0-2.fc39.x86_64"));
+        assert_eq!(
+            resolve_entry("0123-6.5.0-1.fc39.x86_64", &[], &bls).as_deref(),
+            Some("6.5.0-1.fc39.x86_64")
+        );
+        assert_eq!(
+            last_known_good(&bls, "6.6.0-2.fc39.x86_64").map(|entry| entry.id.as_str()),
+            Some("0123-6.5.0-1.fc39.x86_64")
+        );
+        assert!(wildcard_match("0123-*", "0123-6.5.0-1.fc39.x86_64"));
+        assert!(!wildcard_match("arch-*", "0123-6.5.0-1.fc39.x86_64"));
+
+        assert_eq!(
+            parse_boot_offsets(
+                "IDX BOOT ID FIRST ENTRY LAST ENTRY\n -2 aa Mon\n -1 bb Tue\n  0 cc Wed\n"
+            ),
+            vec![-1, -2]
+        );
+        assert_eq!(
+            kernel_from_log("Linux version 6.1.0-13-amd64 (debian-kernel@lists.debian.org) #1 SMP\nCommand line: …\n")
+                .as_deref(),
+            Some("6.1.0-13-amd64")
+        );
+    }
+}
@@ -0,0 +1,666 @@
+//! Граф обратных зависимостей установленных пакетов.
+//!
+//! Граф строится одним-тремя запросами к пакетной базе (apk и xbps читают
+//! файл базы напрямую) вместо вызова
+//! `apt-cache rdepends`/`rpm -q --whatrequires` на каждый пакет. Требование
+//! пакета — группа альтернатив (`a | b`, виртуальные пакеты, `Provides`),
+//! которая удовлетворена, пока установлен хотя бы один пакет из группы.
+//! Поэтому цепочку осиротевших пакетов можно удалить целиком: пакет мешает
+//! удалению, только если от него зависит пакет, который остаётся в системе.
+
+use crate::error::{RcleanerError, Result};
+use crate::system::package_manager::{command_failed, run_command};
+use crate::system::{apk, xbps};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+/// База установленных пакетов xbps (plist в XML).
+const XBPS_PKGDB: &str = "/var/db/xbps/pkgdb-0.38.plist";
+
+/// Пакет, его `Provides` и требования до разрешения имён.
+#[derive(Debug, Default)]
+struct PackageEntry {
+    name: String,
+    provides: Vec<String>,
+    /// Группы альтернативных возможностей.
+    requires: Vec<Vec<String>>,
+}
+
+/// Граф зависимостей установленных пакетов одного менеджера.
+#[derive(Debug, Default)]
+pub struct DependencyGraph {
+    /// Пакет → группы пакетов, каждая из которых удовлетворяет требование.
+    requirements: HashMap<String, Vec<Vec<String>>>,
+    /// Пакет → (зависящий пакет, индекс группы в его требованиях).
+    dependents: HashMap<String, Vec<(String, usize)>>,
+    /// Возможность (имя, `Provides`) → пакеты, которые её предоставляют.
+    providers: HashMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    /// Читает граф из базы пакетного менеджера.
+    ///
+    /// # Errors
+    ///
+    /// Возвращает ошибку, если запрос к базе пакетов завершился неудачно.
+    pub fn load(manager: &str) -> Result<Self> {
+        let entries = match manager {
+            "apt" => load_dpkg()?,
+            "dnf" | "rpm" | "zypper" => load_rpm()?,
+            "pacman" => load_pacman()?,
+            "apk" => parse_apk(&fs::read_to_string(apk::INSTALLED_DB)?),
+            "xbps" => parse_xbps_pkgdb(&fs::read_to_string(XBPS_PKGDB)?),
+            _ => Vec::new(),
+        };
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Умеет ли граф читать базу пакетов менеджера.
+    pub fn supports(manager: &str) -> bool {
+        matches!(
+            manager,
+            "apt" | "dnf" | "rpm" | "zypper" | "pacman" | "apk" | "xbps"
+        )
+    }
+
+    fn from_entries(entries: Vec<PackageEntry>) -> Self {
+        let mut providers: HashMap<&str, Vec<&str>> = HashMap::new();
+        for entry in &entries {
+            providers.entry(&entry.name).or_default().push(&entry.name);
+            for capability in &entry.provides {
+                providers.entry(capability).or_default().push(&entry.name);
+            }
+        }
+
+        let mut graph = Self::default();
+        for entry in &entries {
+            let mut groups: Vec<Vec<String>> = Vec::new();
+            for alternatives in &entry.requires {
+                let mut group: Vec<String> = Vec::new();
+                for capability in alternatives {
+                    for provider in providers.get(capability.as_str()).into_iter().flatten() {
+                        if !group.iter().any(|name| name == provider) {
+                            group.push(provider.to_string());
+                        }
+                    }
+                }
+                // Требования к самому себе и к отсутствующим пакетам не связывают пакеты.
+                if group.is_empty() || group.contains(&entry.name) {
+                    continue;
+                }
+                for provider in &group {
+                    graph
+                        .dependents
+                        .entry(provider.clone())
+                        .or_default()
+                        .push((entry.name.clone(), groups.len()));
+                }
+                groups.push(group);
+            }
+            graph
+                .requirements
+                .entry(entry.name.clone())
+                .or_default()
+                .extend(groups);
+        }
+        graph.providers = providers
+            .into_iter()
+            .map(|(capability, packages)| {
+                let packages = packages.into_iter().map(String::from).collect();
+                (capability.to_string(), packages)
+            })
+            .collect();
+        graph
+    }
+
+    /// Все установленные пакеты, которые напрямую требуют пакет.
+    pub fn dependents(&self, package: &str) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (dependent, _) in self.dependents.get(package).into_iter().flatten() {
+            if !names.contains(dependent) {
+                names.push(dependent.clone());
+            }
+        }
+        names
+    }
+
+    /// Пакеты, которые остаются в системе и перестанут работать без `package`,
+    /// если вместе с ним удалить `removing`.
+    pub fn blocking_dependents(&self, package: &str, removing: &HashSet<String>) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (dependent, group) in self.dependents.get(package).into_iter().flatten() {
+            if removing.contains(dependent) || names.contains(dependent) {
+                continue;
+            }
+            let satisfied = self.requirements[dependent][*group]
+                .iter()
+                .any(|provider| provider != package && !removing.contains(provider));
+            if !satisfied {
+                names.push(dependent.clone());
+            }
+        }
+        names
+    }
+
+    /// Установленные пакеты, которые не нужны ни одному пакету из `roots`
+    /// (явно установленных) ни напрямую, ни через цепочку требований.
+    ///
+    /// Требование с альтернативами считается нужным целиком.
+    pub fn unreachable_from(&self, roots: &[String]) -> Vec<String> {
+        let mut reached: HashSet<&str> = HashSet::new();
+        let mut queue: Vec<&str> = roots
+            .iter()
+            .filter_map(|root| self.providers.get(root))
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        while let Some(package) = queue.pop() {
+            if !reached.insert(package) {
+                continue;
+            }
+            for group in self.requirements.get(package).into_iter().flatten() {
+                queue.extend(group.iter().map(String::as_str));
+            }
+        }
+
+        let mut names: Vec<String> = self
+            .requirements
+            .keys()
+            .filter(|name| !reached.contains(name.as_str()))
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Проверяет, что удаление набора пакетов ничего не ломает.
+    ///
+    /// # Errors
+    ///
+    /// Возвращает [`RcleanerError::Dependency`] для первого пакета, от которого
+    /// зависит пакет вне набора.
+    pub fn check_removal(&self, packages: &[String]) -> Result<()> {
+        let removing: HashSet<String> = packages.iter().cloned().collect();
+        for package in packages {
+            let dependents = self.blocking_dependents(package, &removing);
+            if !dependents.is_empty() {
+                return Err(RcleanerError::Dependency(format!(
+                    "Package {package} is required by: {}",
+                    dependents.join(", ")
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `dpkg-query` по всем пакетам: `Provides`, `Pre-Depends`, `Depends` и
+/// `Recommends` (apt по умолчанию не удаляет рекомендованные пакеты).
+fn load_dpkg() -> Result<Vec<PackageEntry>> {
+    let output = run_command(
+        "dpkg-query",
+        &[
+            "-W",
+            "-f=${Package}\t${db:Status-Abbrev}\t${Provides}\t${Pre-Depends}, ${Depends}, ${Recommends}\n",
+        ],
+    )?;
+    if !output.status.success() {
+        return Err(command_failed("dpkg-query", &output));
+    }
+    Ok(parse_dpkg(&output.stdout))
+}
+
+fn parse_dpkg(output: &str) -> Vec<PackageEntry> {
+    let mut entries: Vec<PackageEntry> = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [name, status, provides, depends] = fields[..] else {
+            continue;
+        };
+        // Второй символ статуса: `n` — не установлен, `c` — остались только настройки.
+        if matches!(status.chars().nth(1), None | Some('n' | 'c')) {
+            continue;
+        }
+        let provides = provides
+            .split(',')
+            .filter_map(dpkg_capability)
+            .collect::<Vec<_>>();
+        let requires = depends
+            .split(',')
+            .map(|group| {
+                group
+                    .split('|')
+                    .filter_map(dpkg_capability)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| !group.is_empty())
+            .collect();
+
+        // Пакеты нескольких архитектур объединяются под одним именем.
+        match entries.iter_mut().find(|entry| entry.name == name) {
+            Some(entry) => {
+                entry.provides.extend(provides);
+                entry.requires.extend(requires);
+            }
+            None => entries.push(PackageEntry {
+                name: name.to_string(),
+                provides,
+                requires,
+            }),
+        }
+    }
+    entries
+}
+
+/// Имя из `libfoo:any (>= 1.0)`.
+fn dpkg_capability(spec: &str) -> Option<String> {
+    let name = spec.split(['(', ' ']).find(|part| !part.is_empty())?;
+    let name = name.split(':').next().unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Требования и `Provides` из базы rpm; файловые требования разрешаются
+/// одним `rpm -qf` по всем путям.
+fn load_rpm() -> Result<Vec<PackageEntry>> {
+    let requires = run_command("rpm", &["-qa", "--qf", "[%{NAME}\t%{REQUIRENAME}\n]"])?;
+    if !requires.status.success() {
+        return Err(command_failed("rpm", &requires));
+    }
+    let provides = run_command("rpm", &["-qa", "--qf", "[%{NAME}\t%{PROVIDENAME}\n]"])?;
+    if !provides.status.success() {
+        return Err(command_failed("rpm", &provides));
+    }
+    let names = run_command("rpm", &["-qa", "--qf", "%{NAME}\n"])?;
+    if !names.status.success() {
+        return Err(command_failed("rpm", &names));
+    }
+
+    let mut entries = parse_rpm(&names.stdout, &provides.stdout, &requires.stdout);
+    let mut paths: Vec<&str> = Vec::new();
+    for entry in &entries {
+        for group in &entry.requires {
+            for capability in group {
+                if capability.starts_with('/') && !paths.contains(&capability.as_str()) {
+                    paths.push(capability);
+                }
+            }
+        }
+    }
+    if paths.is_empty() {
+        return Ok(entries);
+    }
+
+    let mut args = vec!["-qf", "--qf", "[%{FILENAMES}\t%{NAME}\n]"];
+    args.extend(paths.iter().copied());
+    // Неизвестные пути дают ненулевой код возврата, но остальные строки верны.
+    let owners = run_command("rpm", &args)?;
+    let wanted: HashSet<String> = paths.iter().map(|path| path.to_string()).collect();
+    for line in owners.stdout.lines() {
+        let Some((file, owner)) = line.split_once('\t') else {
+            continue;
+        };
+        if wanted.contains(file)
+            && let Some(entry) = entries.iter_mut().find(|entry| entry.name == owner)
+        {
+            entry.provides.push(file.to_string());
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_rpm(names: &str, provides: &str, requires: &str) -> Vec<PackageEntry> {
+    let mut entries: Vec<PackageEntry> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for name in names.lines().map(str::trim).filter(|name| !name.is_empty()) {
+        if !index.contains_key(name) {
+            index.insert(name.to_string(), entries.len());
+            entries.push(PackageEntry {
+                name: name.to_string(),
+                ..PackageEntry::default()
+            });
+        }
+    }
+
+    for line in provides.lines() {
+        if let Some((name, capability)) = line.split_once('\t')
+            && let Some(&position) = index.get(name)
+        {
+            entries[position].provides.push(capability.to_string());
+        }
+    }
+    for line in requires.lines() {
+        let Some((name, capability)) = line.split_once('\t') else {
+            continue;
+        };
+        let Some(&position) = index.get(name) else {
+            continue;
+        };
+        if capability.starts_with("rpmlib(") {
+            continue;
+        }
+        let group = if capability.starts_with('(') {
+            rich_dependency_names(capability)
+        } else {
+            vec![capability.to_string()]
+        };
+        if !group.is_empty() {
+            entries[position].requires.push(group);
+        }
+    }
+    entries
+}
+
+/// Имена из rich-зависимости `(a or b >= 2)`: считается, что её
+/// удовлетворяет любой из упомянутых пакетов.
+fn rich_dependency_names(capability: &str) -> Vec<String> {
+    const KEYWORDS: &[&str] = &["and", "or", "if", "else", "with", "without", "unless"];
+    capability
+        .split(|ch: char| ch.is_whitespace() || ch == '(' || ch == ')')
+        .filter(|token| !token.is_empty() && !KEYWORDS.contains(token))
+        .filter(|token| !token.starts_with(['<', '>', '=']))
+        .filter(|token| !token.starts_with(|ch: char| ch.is_ascii_digit()))
+        .map(String::from)
+        .collect()
+}
+
+/// `pacman -Qi` по всем пакетам.
+fn load_pacman() -> Result<Vec<PackageEntry>> {
+    let output = run_command("pacman", &["-Qi"])?;
+    if !output.status.success() {
+        return Err(command_failed("pacman", &output));
+    }
+    Ok(parse_pacman(&output.stdout))
+}
+
+fn parse_pacman(output: &str) -> Vec<PackageEntry> {
+    let mut entries: Vec<PackageEntry> = Vec::new();
+    let mut field = String::new();
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = if line.starts_with(char::is_whitespace) {
+            // Продолжение перенесённого значения.
+            line.trim()
+        } else {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            field = key.trim().to_string();
+            value.trim()
+        };
+
+        match field.as_str() {
+            "Name" => entries.push(PackageEntry {
+                name: value.to_string(),
+                ..PackageEntry::default()
+            }),
+            "Provides" | "Depends On" if value != "None" => {
+                let Some(entry) = entries.last_mut() else {
+                    continue;
+                };
+                let names = value.split_whitespace().map(|spec| {
+                    spec.split(['<', '>', '='])
+                        .next()
+                        .unwrap_or(spec)
+                        .to_string()
+                });
+                if field == "Provides" {
+                    entry.provides.extend(names);
+                } else {
+                    entry.requires.extend(names.map(|name| vec![name]));
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// База apk: записи `P:` (имя), `p:` (provides) и `D:` (зависимости),
+/// разделённые пустыми строками.
+fn parse_apk(content: &str) -> Vec<PackageEntry> {
+    let mut entries: Vec<PackageEntry> = Vec::new();
+    for line in content.lines() {
+        let Some((field, value)) = line.split_once(':') else {
+            continue;
+        };
+        if field == "P" {
+            entries.push(PackageEntry {
+                name: value.to_string(),
+                ..PackageEntry::default()
+            });
+            continue;
+        }
+        let Some(entry) = entries.last_mut() else {
+            continue;
+        };
+        // `!foo` — конфликт, а не требование.
+        let names = value
+            .split_whitespace()
+            .filter(|spec| !spec.starts_with('!'))
+            .filter_map(|spec| spec.split(['<', '>', '=', '~']).next())
+            .filter(|name| !name.is_empty())
+            .map(String::from);
+        match field {
+            "p" => entry.provides.extend(names),
+            "D" => entry.requires.extend(names.map(|name| vec![name])),
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// pkgdb xbps: словарь «имя пакета → свойства», каждый элемент plist на
+/// отдельной строке.
+fn parse_xbps_pkgdb(content: &str) -> Vec<PackageEntry> {
+    let mut entries: Vec<PackageEntry> = Vec::new();
+    let mut depth = 0usize;
+    let mut key = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        match line {
+            "<dict>" => depth += 1,
+            "</dict>" => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if let Some(value) = plist_value(line, "key") {
+            // Служебные ключи верхнего уровня (`_XBPS_ALTERNATIVES_`) не пакеты.
+            if depth == 1 && !value.starts_with('_') {
+                entries.push(PackageEntry {
+                    name: value.clone(),
+                    ..PackageEntry::default()
+                });
+            }
+            key = value;
+            continue;
+        }
+        if depth != 2 {
+            continue;
+        }
+        let (Some(value), Some(entry)) = (plist_value(line, "string"), entries.last_mut()) else {
+            continue;
+        };
+        match key.as_str() {
+            "run_depends" => entry.requires.push(vec![xbps_pattern_name(&value)]),
+            "shlib-requires" => entry.requires.push(vec![value]),
+            "provides" => entry.provides.push(xbps_pattern_name(&value)),
+            "shlib-provides" => entry.provides.push(value),
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// Значение `<tag>…</tag>` с раскрытыми сущностями XML.
+fn plist_value(line: &str, tag: &str) -> Option<String> {
+    let value = line
+        .strip_prefix(&format!("<{tag}>"))?
+        .strip_suffix(&format!("</{tag}>"))?;
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Имя из шаблона xbps: `glibc>=2.36_1`, `foo-1.0_1`, `bar<2`.
+fn xbps_pattern_name(pattern: &str) -> String {
+    match pattern.find(['<', '>', '=']) {
+        Some(position) => pattern[..position].to_string(),
+        None => xbps::package_name(pattern).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn removing(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_orphan_chains_and_alternatives() {
+        let dpkg = "\
+app\tii \t\t, libchain1 (>= 1.0), libui:any | libui-compat, \n\
+libchain1\tii \t\t, libchain2, \n\
+libchain2\tii \t\t, , \n\
+libui\tii \t\t, , \n\
+libui-qt\tii \tlibui-compat\t, , \n\
+old\trc \t\t, libchain2, \n";
+        let graph = DependencyGraph::from_entries(parse_dpkg(dpkg));
+
+        assert_eq!(graph.dependents("libchain2"), vec!["libchain1"]);
+        assert!(graph.dependents("app").is_empty());
+
+        // Вся цепочка удаляется вместе, по отдельности её части нужны.
+        let chain = removing(&["app", "libchain1", "libchain2"]);
+        assert!(graph.blocking_dependents("libchain2", &chain).is_empty());
+        assert!(graph.blocking_dependents("libchain1", &chain).is_empty());
+        assert_eq!(
+            graph.blocking_dependents("libchain2", &removing(&["libchain2"])),
+            vec!["libchain1"]
+        );
+        assert!(
+            graph
+                .check_removal(&["libchain1".to_string(), "libchain2".to_string()])
+                .is_err()
+        );
+        assert!(
+            graph
+                .check_removal(&["app".to_string(), "libchain1".to_string()])
+                .is_ok()
+        );
+
+        // Альтернатива через Provides продолжает удовлетворять требование.
+        assert!(
+            graph
+                .blocking_dependents("libui", &removing(&["libui"]))
+                .is_empty()
+        );
+        assert_eq!(
+            graph.blocking_dependents("libui", &removing(&["libui", "libui-qt"])),
+            vec!["app"]
+        );
+
+        let pacman = "\
+Name            : foo
+Provides        : libfoo.so=1-64
+Depends On      : glibc  bar>=2
+Optional Deps   : baz: extra
+                  qux: more
+
+Name            : bar
+Provides        : None
+Depends On      : None
+
+Name            : baz
+Provides        : None
+Depends On      : libfoo.so=1-64
+";
+        let graph = DependencyGraph::from_entries(parse_pacman(pacman));
+        assert_eq!(graph.dependents("bar"), vec!["foo"]);
+        assert_eq!(graph.dependents("foo"), vec!["baz"]);
+
+        let names = "base\nfoo\nbar\n";
+        let provides = "foo\tfoo\nfoo\tlibfoo.so.1()(64bit)\nbar\tbar\n";
+        let requires = "\
+foo\trpmlib(CompressedFileNames)\n\
+foo\t(bar or base)\n\
+base\tlibfoo.so.1()(64bit)\n";
+        let graph = DependencyGraph::from_entries(parse_rpm(names, provides, requires));
+        assert_eq!(graph.dependents("foo"), vec!["base"]);
+        assert!(
+            graph
+                .blocking_dependents("bar", &removing(&["bar"]))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_apk_and_xbps_databases() {
+        let installed = "\
+P:musl
+V:1.2.4-r2
+p:so:libc.musl-x86_64.so.1=1
+
+P:libcurl
+D:so:libc.musl-x86_64.so.1 !libcurl-old
+p:so:libcurl.so.4=4.8.0
+
+P:curl
+D:libcurl>=8 so:libc.musl-x86_64.so.1
+
+P:build-tool
+D:musl
+";
+        let graph = DependencyGraph::from_entries(parse_apk(installed));
+        assert_eq!(graph.dependents("libcurl"), vec!["curl"]);
+        assert_eq!(
+            graph.unreachable_from(&["curl".to_string()]),
+            vec!["build-tool"]
+        );
+
+        let pkgdb = "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<plist version=\"1.0\">
+<dict>
+	<key>_XBPS_ALTERNATIVES_</key>
+	<dict>
+		<key>pager</key>
+		<array>
+			<string>less</string>
+		</array>
+	</dict>
+	<key>glibc</key>
+	<dict>
+		<key>pkgver</key>
+		<string>glibc-2.36_1</string>
+		<key>shlib-provides</key>
+		<array>
+			<string>libc.so.6</string>
+		</array>
+	</dict>
+	<key>less</key>
+	<dict>
+		<key>run_depends</key>
+		<array>
+			<string>glibc&gt;=2.36_1</string>
+		</array>
+		<key>shlib-requires</key>
+		<array>
+			<string>libc.so.6</string>
+		</array>
+	</dict>
+</dict>
+</plist>
+";
+        let entries = parse_xbps_pkgdb(pkgdb);
+        assert_eq!(entries.len(), 2);
+        let graph = DependencyGraph::from_entries(entries);
+        assert_eq!(graph.dependents("glibc"), vec!["less"]);
+        assert_eq!(xbps_pattern_name("foo-1.0_1"), "foo");
+    }
+}
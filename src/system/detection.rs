@@ -1,8 +1,11 @@
 use crate::error::Result;
+use crate::system::mounts::{MountEntry, find_mount, read_mounts};
+use crate::utils::command::is_root;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 
 pub fn detect_system() -> Result<SystemInfo> {
     let os_release = read_os_release();
@@ -19,6 +22,8 @@ pub fn detect_system() -> Result<SystemInfo> {
 
     let system_type = if is_atomic_rpm_ostree() {
         SystemType::AtomicRpmOstree
+    } else if is_transactional_update() {
+        SystemType::AtomicTransactional
     } else {
         SystemType::Desktop(detect_desktop_type(&os_release, &available_managers))
     };
@@ -46,6 +51,8 @@ pub struct SystemInfo {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SystemType {
     AtomicRpmOstree,
+    /// Read-only корень с transactional-update (openSUSE MicroOS, Aeon, Kalpa).
+    AtomicTransactional,
     Desktop(DesktopType),
 }
 
@@ -56,6 +63,11 @@ pub enum DesktopType {
     AptDebian,
     AptUbuntu,
     PacmanArch,
+    ZypperSuse,
+    ApkAlpine,
+    XbpsVoid,
+    NixOS,
+    GuixSystem,
     Unknown,
 }
 
@@ -66,6 +78,11 @@ pub enum PackageManagerType {
     Dnf,
     Apt,
     Pacman,
+    Zypper,
+    Apk,
+    Xbps,
+    Nix,
+    Guix,
     Flatpak,
     Snap,
 }
@@ -76,6 +93,121 @@ pub enum ContainerType {
     Podman,
 }
 
+/// Способ создания резервной копии перед очисткой.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupBackendType {
+    /// Копирование файлов в хранилище блоков rCleaner.
+    FileCopy,
+    /// Read-only снимок подтома Btrfs.
+    BtrfsSnapshot,
+    /// Снимок ZFS dataset.
+    ZfsSnapshot,
+    /// Снимок через snapper.
+    Snapper,
+    /// Снимок через timeshift.
+    Timeshift,
+}
+
+/// Выбирает способ резервного копирования для указанных путей.
+///
+/// Снимки файловой системы требуют root и того, чтобы все пути лежали на
+/// одной поддерживаемой ФС; иначе используется копирование файлов.
+pub fn detect_backup_backend(paths: &[PathBuf]) -> BackupBackendType {
+    if paths.is_empty() || !is_root() {
+        return BackupBackendType::FileCopy;
+    }
+
+    let mounts = read_mounts();
+    let targets: Vec<MountEntry> = paths
+        .iter()
+        .filter_map(|path| find_mount(&mounts, path))
+        .collect();
+    if targets.len() != paths.len() {
+        return BackupBackendType::FileCopy;
+    }
+
+    choose_backup_backend(
+        paths,
+        &targets,
+        &command_exists,
+        &|subvolume| snapper_config_for(subvolume).is_some(),
+        Path::new(TIMESHIFT_CONFIG).exists(),
+    )
+}
+
+const TIMESHIFT_CONFIG: &str = "/etc/timeshift/timeshift.json";
+
+/// Выбирает способ по файловым системам путей и установленным программам.
+fn choose_backup_backend(
+    paths: &[PathBuf],
+    targets: &[MountEntry],
+    has_command: &dyn Fn(&str) -> bool,
+    has_snapper_config: &dyn Fn(&Path) -> bool,
+    timeshift_configured: bool,
+) -> BackupBackendType {
+    if targets.iter().all(|mount| mount.fs_type == "btrfs") {
+        if has_command("snapper")
+            && targets
+                .iter()
+                .all(|mount| has_snapper_config(&mount.mount_point))
+        {
+            return BackupBackendType::Snapper;
+        }
+        if has_command("btrfs") {
+            return BackupBackendType::BtrfsSnapshot;
+        }
+    }
+
+    if targets.iter().all(|mount| mount.fs_type == "zfs") && has_command("zfs") {
+        return BackupBackendType::ZfsSnapshot;
+    }
+
+    if has_command("timeshift")
+        && timeshift_configured
+        && paths.iter().all(|path| !path.starts_with("/home"))
+    {
+        return BackupBackendType::Timeshift;
+    }
+
+    BackupBackendType::FileCopy
+}
+
+/// Возвращает имя конфигурации snapper, которая обслуживает подтом.
+pub fn snapper_config_for(subvolume: &Path) -> Option<String> {
+    let entries = fs::read_dir("/etc/snapper/configs").ok()?;
+    for entry in entries.flatten() {
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        let configured = content.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix("SUBVOLUME=")
+                .map(|value| value.trim_matches('"').to_string())
+        });
+        if configured.as_deref().map(Path::new) == Some(subvolume) {
+            return Some(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    None
+}
+
+/// Возвращает корень подтома Btrfs, которому принадлежит путь.
+///
+/// Подтома Btrfs имеют собственный номер устройства, поэтому корнем
+/// считается самый верхний предок с тем же `st_dev`.
+pub fn btrfs_subvolume_root(path: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(path).ok()?;
+    let device = fs::metadata(&path).ok()?.dev();
+    let mut root = path.clone();
+    for ancestor in path.ancestors().skip(1) {
+        match fs::metadata(ancestor) {
+            Ok(metadata) if metadata.dev() == device => root = ancestor.to_path_buf(),
+            _ => break,
+        }
+    }
+    Some(root)
+}
+
 fn read_os_release() -> HashMap<String, String> {
     let mut values = HashMap::new();
     let paths = [
@@ -114,6 +246,14 @@ fn is_atomic_rpm_ostree() -> bool {
     Path::new("/run/ostree-booted").exists() || command_exists("rpm-ostree")
 }
 
+/// Обновляется ли система через transactional-update: корень смонтирован
+/// только для чтения, пакеты меняются в новом снимке Btrfs.
+pub fn is_transactional_update() -> bool {
+    command_exists("transactional-update")
+        && find_mount(&read_mounts(), Path::new("/"))
+            .is_some_and(|mount| mount.options.iter().any(|option| option == "ro"))
+}
+
 fn detect_available_managers() -> Vec<PackageManagerType> {
     let mut managers = Vec::new();
 
@@ -132,6 +272,21 @@ fn detect_available_managers() -> Vec<PackageManagerType> {
     if command_exists("pacman") {
         managers.push(PackageManagerType::Pacman);
     }
+    if command_exists("zypper") {
+        managers.push(PackageManagerType::Zypper);
+    }
+    if command_exists("apk") {
+        managers.push(PackageManagerType::Apk);
+    }
+    if command_exists("xbps-query") {
+        managers.push(PackageManagerType::Xbps);
+    }
+    if Path::new("/nix/store").is_dir() && command_exists("nix-env") {
+        managers.push(PackageManagerType::Nix);
+    }
+    if Path::new("/gnu/store").is_dir() && command_exists("guix") {
+        managers.push(PackageManagerType::Guix);
+    }
     if command_exists("flatpak") {
         managers.push(PackageManagerType::Flatpak);
     }
@@ -207,6 +362,21 @@ fn detect_desktop_type(
     if id == "arch" || id == "manjaro" || id == "endeavouros" {
         return DesktopType::PacmanArch;
     }
+    if id.starts_with("opensuse") || id == "sles" || id == "sled" {
+        return DesktopType::ZypperSuse;
+    }
+    if id == "alpine" || id == "postmarketos" {
+        return DesktopType::ApkAlpine;
+    }
+    if id == "void" {
+        return DesktopType::XbpsVoid;
+    }
+    if id == "nixos" {
+        return DesktopType::NixOS;
+    }
+    if id == "guix" {
+        return DesktopType::GuixSystem;
+    }
 
     if id_like_values.contains(&"fedora") {
         return DesktopType::RpmFedora;
@@ -223,6 +393,9 @@ fn detect_desktop_type(
     if id_like_values.contains(&"arch") {
         return DesktopType::PacmanArch;
     }
+    if id_like_values.contains(&"suse") || id_like_values.contains(&"opensuse") {
+        return DesktopType::ZypperSuse;
+    }
 
     if managers.contains(&PackageManagerType::Pacman) {
         return DesktopType::PacmanArch;
@@ -230,14 +403,29 @@ fn detect_desktop_type(
     if managers.contains(&PackageManagerType::Apt) {
         return DesktopType::AptDebian;
     }
+    if managers.contains(&PackageManagerType::Zypper) {
+        return DesktopType::ZypperSuse;
+    }
     if managers.contains(&PackageManagerType::Dnf) || managers.contains(&PackageManagerType::Rpm) {
         return DesktopType::RpmFedora;
     }
+    if managers.contains(&PackageManagerType::Apk) {
+        return DesktopType::ApkAlpine;
+    }
+    if managers.contains(&PackageManagerType::Xbps) {
+        return DesktopType::XbpsVoid;
+    }
+    if managers.contains(&PackageManagerType::Nix) {
+        return DesktopType::NixOS;
+    }
+    if managers.contains(&PackageManagerType::Guix) {
+        return DesktopType::GuixSystem;
+    }
 
     DesktopType::Unknown
 }
 
-fn command_exists(command: &str) -> bool {
+pub(crate) fn command_exists(command: &str) -> bool {
     if command.contains('/') {
         return is_executable(Path::new(command));
     }
@@ -269,3 +457,84 @@ fn is_executable(path: &Path) -> bool {
 fn is_executable(path: &Path) -> bool {
     path.is_file()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mount(mount_point: &str, fs_type: &str) -> MountEntry {
+        MountEntry {
+            mount_point: PathBuf::from(mount_point),
+            root: "/".to_string(),
+            fs_type: fs_type.to_string(),
+            source: "/dev/test".to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    fn choose(
+        paths: &[&str],
+        targets: &[MountEntry],
+        commands: &[&str],
+        snapper: bool,
+        timeshift: bool,
+    ) -> BackupBackendType {
+        let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+        choose_backup_backend(
+            &paths,
+            targets,
+            &|command| commands.contains(&command),
+            &|_| snapper,
+            timeshift,
+        )
+    }
+
+    #[test]
+    fn test_detect_backup_backend_picks_snapshots_by_filesystem() {
+        assert_eq!(detect_backup_backend(&[]), BackupBackendType::FileCopy);
+
+        let btrfs = [mount("/home", "btrfs")];
+        let home = ["/home/user/.cache"];
+        assert_eq!(
+            choose(&home, &btrfs, &["btrfs", "snapper"], true, false),
+            BackupBackendType::Snapper
+        );
+        assert_eq!(
+            choose(&home, &btrfs, &["btrfs", "snapper"], false, false),
+            BackupBackendType::BtrfsSnapshot
+        );
+        assert_eq!(
+            choose(&home, &btrfs, &[], false, false),
+            BackupBackendType::FileCopy
+        );
+
+        let zfs = [mount("/var", "zfs")];
+        assert_eq!(
+            choose(&["/var/cache/x"], &zfs, &["zfs"], false, false),
+            BackupBackendType::ZfsSnapshot
+        );
+
+        let mixed = [mount("/home", "btrfs"), mount("/var", "zfs")];
+        assert_eq!(
+            choose(
+                &["/home/user/.cache", "/var/cache/x"],
+                &mixed,
+                &["btrfs", "zfs"],
+                false,
+                false
+            ),
+            BackupBackendType::FileCopy
+        );
+
+        let ext4 = [mount("/", "ext4")];
+        assert_eq!(
+            choose(&["/var/cache/x"], &ext4, &["timeshift"], false, true),
+            BackupBackendType::Timeshift
+        );
+        // timeshift не сохраняет /home по умолчанию.
+        assert_eq!(
+            choose(&home, &ext4, &["timeshift"], false, true),
+            BackupBackendType::FileCopy
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Заполненность файловых систем и привязка элементов к ним.
+//!
+//! Каждый элемент очистки относится к файловой системе, на которой лежат
+//! его данные, чтобы показывать, сколько места освободится на каждом
+//! разделе, а после очистки - сколько освободилось на самом деле.
+
+use crate::models::{CleanupCategory, CleanupItem, CleanupSource};
+use crate::system::mounts::{self, FsUsage, MountEntry};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Типы файловых систем, которые не показываются в обзоре.
+const PSEUDO_FS_TYPES: &[&str] = &[
+    "proc",
+    "sysfs",
+    "devtmpfs",
+    "devpts",
+    "cgroup",
+    "cgroup2",
+    "securityfs",
+    "pstore",
+    "efivarfs",
+    "bpf",
+    "debugfs",
+    "tracefs",
+    "configfs",
+    "fusectl",
+    "mqueue",
+    "hugetlbfs",
+    "autofs",
+    "binfmt_misc",
+    "rpc_pipefs",
+    "nsfs",
+    "ramfs",
+    "squashfs",
+    "overlay",
+    "fuse.portal",
+    "fuse.gvfsd-fuse",
+];
+
+/// Файловая система и её заполненность.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilesystemUsage {
+    /// Точка монтирования.
+    pub mount_point: PathBuf,
+    /// Тип файловой системы.
+    pub fs_type: String,
+    /// Источник монтирования.
+    pub source: String,
+    /// Заполненность.
+    pub usage: FsUsage,
+}
+
+/// Сколько освободит очистка на файловой системе.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MountEstimate {
+    pub filesystem: FilesystemUsage,
+    /// Сумма размеров элементов на этой файловой системе.
+    pub bytes: u64,
+    /// Число элементов.
+    pub items: usize,
+}
+
+impl MountEstimate {
+    /// "3.2 GB on /home (now 94% full)".
+    pub fn describe(&self) -> String {
+        format!(
+            "{} on {} (now {:.0}% full)",
+            crate::utils::size_format::format_size(self.bytes),
+            self.filesystem.mount_point.display(),
+            self.filesystem.usage.used_percent()
+        )
+    }
+}
+
+/// Возвращает реальные файловые системы с их заполненностью.
+pub fn list_filesystems() -> Vec<FilesystemUsage> {
+    filesystems_from(&mounts::read_mounts(), |path| mounts::fs_usage(path).ok())
+}
+
+/// Отбирает реальные файловые системы из списка монтирований.
+///
+/// Повторные монтирования одной точки и файловые системы нулевого размера
+/// пропускаются.
+pub fn filesystems_from(
+    entries: &[MountEntry],
+    usage_of: impl Fn(&Path) -> Option<FsUsage>,
+) -> Vec<FilesystemUsage> {
+    let mut filesystems: Vec<FilesystemUsage> = Vec::new();
+    for entry in entries {
+        if PSEUDO_FS_TYPES.contains(&entry.fs_type.as_str()) {
+            continue;
+        }
+        let Some(usage) = usage_of(&entry.mount_point) else {
+            continue;
+        };
+        if usage.total == 0 {
+            continue;
+        }
+        let filesystem = FilesystemUsage {
+            mount_point: entry.mount_point.clone(),
+            fs_type: entry.fs_type.clone(),
+            source: entry.source.clone(),
+            usage,
+        };
+        // Поздние монтирования перекрывают ранние в той же точке.
+        match filesystems
+            .iter_mut()
+            .find(|existing| existing.mount_point == filesystem.mount_point)
+        {
+            Some(existing) => *existing = filesystem,
+            None => filesystems.push(filesystem),
+        }
+    }
+    filesystems.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    filesystems
+}
+
+/// Путь, по которому определяется файловая система элемента.
+///
+/// Для элементов без пути (пакеты, журнал systemd) берётся каталог, где
+/// соответствующий менеджер хранит данные.
+pub fn item_location(item: &CleanupItem) -> PathBuf {
+    if let Some(path) = &item.path {
+        return PathBuf::from(path);
+    }
+    let location = match (&item.category, &item.source) {
+        (CleanupCategory::Logs, _) => "/var/log/journal",
+        (CleanupCategory::OldKernels, _) => "/boot",
+        (_, CleanupSource::PackageManager(manager)) if manager == "flatpak" => "/var/lib/flatpak",
+        (_, CleanupSource::PackageManager(manager)) if manager == "snap" => "/var/lib/snapd",
+        (_, CleanupSource::Container(_)) => "/var/lib/containers",
+        _ => "/usr",
+    };
+    PathBuf::from(location)
+}
+
+/// Файловая система, которой принадлежит элемент.
+pub fn filesystem_for<'a>(
+    item: &CleanupItem,
+    filesystems: &'a [FilesystemUsage],
+) -> Option<&'a FilesystemUsage> {
+    let location = item_location(item);
+    filesystems
+        .iter()
+        .filter(|filesystem| location.starts_with(&filesystem.mount_point))
+        .max_by_key(|filesystem| filesystem.mount_point.as_os_str().len())
+}
+
+/// Группирует размеры элементов по файловым системам.
+pub fn estimate_by_mount<'a>(
+    items: impl IntoIterator<Item = &'a CleanupItem>,
+    filesystems: &[FilesystemUsage],
+) -> Vec<MountEstimate> {
+    let mut estimates: Vec<MountEstimate> = Vec::new();
+    for item in items {
+        let Some(filesystem) = filesystem_for(item, filesystems) else {
+            continue;
+        };
+        match estimates
+            .iter_mut()
+            .find(|estimate| estimate.filesystem.mount_point == filesystem.mount_point)
+        {
+            Some(estimate) => {
+                estimate.bytes = estimate.bytes.saturating_add(item.size);
+                estimate.items += 1;
+            }
+            None => estimates.push(MountEstimate {
+                filesystem: filesystem.clone(),
+                bytes: item.size,
+                items: 1,
+            }),
+        }
+    }
+    estimates.sort_by_key(|estimate| std::cmp::Reverse(estimate.bytes));
+    estimates
+}
+
+/// Снимок свободного места до очистки.
+#[derive(Debug, Clone, Default)]
+pub struct FreeSpaceSnapshot {
+    available: BTreeMap<PathBuf, u64>,
+}
+
+impl FreeSpaceSnapshot {
+    /// Запоминает свободное место на файловых системах элементов.
+    pub fn capture(items: &[CleanupItem]) -> Self {
+        let filesystems = list_filesystems();
+        let available = estimate_by_mount(items, &filesystems)
+            .into_iter()
+            .map(|estimate| {
+                (
+                    estimate.filesystem.mount_point,
+                    estimate.filesystem.usage.available,
+                )
+            })
+            .collect();
+        Self { available }
+    }
+
+    /// Сколько места освободилось на каждой файловой системе с момента снимка.
+    pub fn freed_since(&self) -> BTreeMap<String, u64> {
+        self.available
+            .iter()
+            .map(|(mount_point, before)| {
+                let after = mounts::fs_usage(mount_point)
+                    .map(|usage| usage.available)
+                    .unwrap_or(*before);
+                (
+                    mount_point.to_string_lossy().to_string(),
+                    after.saturating_sub(*before),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mounts::parse_mountinfo;
+
+    const SAMPLE: &str = "\
+22 1 0:21 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+23 22 0:5 / /proc rw - proc proc rw
+40 22 0:22 / /home rw,relatime shared:2 - ext4 /dev/nvme0n1p3 rw
+41 22 0:23 / /tmp rw - tmpfs tmpfs rw
+42 22 0:24 / /snap/core/1 ro - squashfs /dev/loop0 ro";
+
+    fn usage(total: u64, used: u64) -> FsUsage {
+        FsUsage {
+            total,
+            available: total - used,
+            used,
+        }
+    }
+
+    fn filesystems() -> Vec<FilesystemUsage> {
+        filesystems_from(&parse_mountinfo(SAMPLE), |path| match path.to_str() {
+            Some("/") => Some(usage(100, 50)),
+            Some("/home") => Some(usage(1000, 940)),
+            Some("/tmp") => Some(usage(10, 1)),
+            _ => Some(usage(5, 5)),
+        })
+    }
+
+    fn item(path: Option<&str>, category: CleanupCategory, size: u64) -> CleanupItem {
+        CleanupItem {
+            id: path.unwrap_or("pkg").to_string(),
+            name: path.unwrap_or("pkg").to_string(),
+            path: path.map(String::from),
+            size,
+            description: String::new(),
+            category,
+            source: CleanupSource::FileSystem,
+            selected: true,
+            can_clean: true,
+            blocked_reason: None,
+            dependencies: Vec::new(),
+            verdicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_filesystems_skip_pseudo() {
+        let mounts: Vec<_> = filesystems()
+            .into_iter()
+            .map(|filesystem| filesystem.mount_point)
+            .collect();
+        assert_eq!(
+            mounts,
+            [
+                PathBuf::from("/"),
+                PathBuf::from("/home"),
+                PathBuf::from("/tmp")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_estimate_by_mount() {
+        let filesystems = filesystems();
+        let items = [
+            item(Some("/home/u/.cache/a"), CleanupCategory::Cache, 300),
+            item(Some("/home/u/.cache/b"), CleanupCategory::Cache, 200),
+            item(Some("/tmp"), CleanupCategory::TempFiles, 5),
+            item(None, CleanupCategory::OldPackages, 40),
+        ];
+        let estimates = estimate_by_mount(&items, &filesystems);
+        assert_eq!(estimates.len(), 3);
+        assert_eq!(estimates[0].filesystem.mount_point, PathBuf::from("/home"));
+        assert_eq!(estimates[0].bytes, 500);
+        assert_eq!(estimates[0].items, 2);
+        assert_eq!(estimates[0].describe(), "500.00 B on /home (now 94% full)");
+        assert_eq!(estimates[1].filesystem.mount_point, PathBuf::from("/"));
+        assert_eq!(estimates[1].bytes, 40);
+    }
+}
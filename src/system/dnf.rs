@@ -1,6 +1,8 @@
-use crate::error::{RcleanerError, Result};
-use crate::system::package_manager::{PackageManager, command_failed, run_command};
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::package_manager::{PackageManager, RemovalPlan, command_failed, run_command};
 use crate::system::rpm::is_no_requires_message;
+use crate::utils::size_format::parse_size_string;
 
 pub struct DnfManager;
 
@@ -55,15 +57,7 @@ impl PackageManager for DnfManager {
             return Ok(());
         }
 
-        for package in packages {
-            let deps = self.check_dependencies(package)?;
-            if !deps.is_empty() {
-                return Err(RcleanerError::Dependency(format!(
-                    "Package {package} is required by: {}",
-                    deps.join(", ")
-                )));
-            }
-        }
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
 
         let mut args = vec!["remove", "-y"];
         if dry_run {
@@ -80,6 +74,19 @@ impl PackageManager for DnfManager {
 
         Ok(())
     }
+
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        let mut args = vec!["remove", "--assumeno"];
+        args.extend(packages.iter().map(String::as_str));
+        // `--assumeno` всегда завершается с ошибкой, поэтому смотрим на вывод.
+        let output = run_command("dnf", &args)?;
+        let combined = format!("{}\n{}", output.stdout, output.stderr);
+        let plan = parse_dnf_simulation(&combined, packages);
+        if plan.removed.is_empty() && plan.held.is_empty() {
+            return Err(command_failed("dnf", &output));
+        }
+        Ok(plan)
+    }
 }
 
 pub fn list_installed() -> Result<Vec<String>> {
@@ -109,6 +116,41 @@ fn parse_dnf_list(output: &str) -> Vec<String> {
         .collect()
 }
 
+/// Разбирает таблицу транзакции `dnf remove --assumeno` (dnf4 и dnf5).
+fn parse_dnf_simulation(output: &str, requested: &[String]) -> RemovalPlan {
+    let mut plan = RemovalPlan::new("dnf", requested);
+    plan.removed.clear();
+    let mut in_removing = false;
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if !line.starts_with(' ') {
+            in_removing = trimmed.starts_with("Removing") && trimmed.ends_with(':');
+        } else if in_removing
+            && let Some(package) = trimmed.split_whitespace().next()
+            && trimmed.split_whitespace().count() >= 4
+        {
+            plan.removed.push(package.to_string());
+        }
+
+        if let Some(amount) = trimmed.strip_prefix("Freed space:") {
+            plan.freed_bytes = parse_size_string(amount);
+        } else if let Some(rest) = trimmed.strip_prefix("After this operation, ")
+            && let Some((amount, _)) = rest.split_once(" will be freed")
+        {
+            plan.freed_bytes = parse_size_string(amount);
+        }
+        if let Some((_, names)) = trimmed.split_once("protected packages:") {
+            plan.held.extend(
+                names
+                    .split([' ', ','])
+                    .filter(|name| !name.is_empty())
+                    .map(String::from),
+            );
+        }
+    }
+    plan
+}
+
 fn split_lines(output: &str) -> Vec<String> {
     output
         .lines()
@@ -117,3 +159,40 @@ fn split_lines(output: &str) -> Vec<String> {
         .map(String::from)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dnf_simulation() {
+        let dnf4 = "\
+Dependencies resolved.
+================================================================================
+ Package            Arch      Version             Repository          Size
+================================================================================
+Removing:
+ foo                x86_64    1.0-1.fc39          @fedora             1.0 M
+Removing unused dependencies:
+ libfoo             x86_64    1.0-1.fc39          @fedora             512 k
+
+Transaction Summary
+================================================================================
+Remove  2 Packages
+
+Freed space: 1.5 M
+Operation aborted.
+";
+        let plan = parse_dnf_simulation(dnf4, &["foo".to_string()]);
+        assert_eq!(plan.removed, vec!["foo", "libfoo"]);
+        assert_eq!(plan.extra(), vec!["libfoo"]);
+        assert_eq!(plan.freed_bytes, Some(1572864));
+        assert!(!plan.is_blocked());
+
+        let dnf5 = "\
+Problem: The operation would result in removing of the following protected packages: systemd, dnf
+";
+        let plan = parse_dnf_simulation(dnf5, &["systemd".to_string()]);
+        assert_eq!(plan.held, vec!["systemd", "dnf"]);
+    }
+}
@@ -0,0 +1,430 @@
+//! Файлы ядер, которые не принадлежат ни одному пакету: деревья модулей в
+//! `/usr/lib/modules`, образы и initramfs в `/boot`, сборки DKMS для
+//! удалённых ядер.
+//!
+//! Принадлежность проверяется по всем найденным базам пакетов (dpkg,
+//! pacman, rpm, xbps, apk). Если ни одной базы нет, ничто не считается
+//! брошенным.
+
+use crate::cleaner::deletion::remove_permanently;
+use crate::error::{RcleanerError, Result};
+use crate::system::package_manager::{command_failed, run_command};
+use crate::utils::disk_size::SizeCounter;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Каталоги деревьев модулей; на системах с объединённым `/usr` это один каталог.
+pub const MODULE_DIRS: &[&str] = &["/usr/lib/modules", "/lib/modules"];
+
+pub const BOOT_DIR: &str = "/boot";
+
+/// Состояние DKMS: `<модуль>/<версия>/<ядро>/<архитектура>`.
+pub const DKMS_DIR: &str = "/var/lib/dkms";
+
+/// Префиксы файлов ядра в `/boot` и признак образа ядра.
+const BOOT_PREFIXES: &[(&str, bool)] = &[
+    ("vmlinuz-", true),
+    ("vmlinux-", true),
+    ("initramfs-", false),
+    ("initrd.img-", false),
+    ("initrd-", false),
+    ("System.map-", false),
+    ("config-", false),
+];
+
+/// Базы пакетов и команда, которая отвечает, кому принадлежит путь.
+const OWNER_QUERIES: &[(&str, &str, &[&str])] = &[
+    ("/var/lib/dpkg/status", "dpkg-query", &["-S"]),
+    ("/var/lib/pacman/local", "pacman", &["-Qo"]),
+    ("/var/lib/rpm", "rpm", &["-qf"]),
+    ("/usr/lib/sysimage/rpm", "rpm", &["-qf"]),
+    ("/var/db/xbps", "xbps-query", &["-o"]),
+    ("/lib/apk/db/installed", "apk", &["info", "--who-owns"]),
+];
+
+/// Установленное дерево модулей.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleTree {
+    pub version: String,
+    pub path: PathBuf,
+    /// Пакет ядра из файла `pkgbase` (Arch: `linux`, `linux-lts`, `linux-zen`).
+    pub pkgbase: Option<String>,
+}
+
+/// Что осталось от ядра без пакета.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeftoverKind {
+    /// Собранное вручную ядро: есть образ в `/boot`, пакета нет.
+    CustomKernel,
+    /// Дерево модулей без образа ядра (например, модули DKMS после удаления пакета).
+    ModuleTree,
+    /// initramfs, System.map и config от удалённого ядра.
+    BootFiles,
+}
+
+/// Файлы одной версии ядра, которые не принадлежат пакетам.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelLeftover {
+    /// Версия ядра или имя варианта из имён файлов `/boot` (`linux-zen`).
+    pub version: String,
+    pub kind: LeftoverKind,
+    pub paths: Vec<PathBuf>,
+    pub size: u64,
+}
+
+/// Сборка DKMS для ядра, которого больше нет.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DkmsBuild {
+    pub module: String,
+    pub module_version: String,
+    pub kernel: String,
+    pub path: PathBuf,
+}
+
+impl DkmsBuild {
+    /// Имя элемента очистки: `модуль/версия/ядро`.
+    pub fn id(&self) -> String {
+        format!("{}/{}/{}", self.module, self.module_version, self.kernel)
+    }
+}
+
+/// Версия загруженного ядра.
+pub fn running_version() -> String {
+    if let Ok(release) = fs::read_to_string("/proc/sys/kernel/osrelease") {
+        return release.trim().to_string();
+    }
+    run_command("uname", &["-r"])
+        .map(|output| output.stdout.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Вариант ядра для раздельного хранения версий: `amd64`, `rt-amd64`,
+/// `generic`, `lts`, `zen`, `debug` или пустая строка.
+///
+/// Это хвост версии из компонентов, которые начинаются с буквы:
+/// `6.1.0-13-rt-amd64` → `rt-amd64`, `6.6.1-zen1-1-zen` → `zen`,
+/// `6.5.0-1.fc39.x86_64+debug` → `debug`.
+pub fn kernel_flavor(version: &str) -> String {
+    if let Some((_, variant)) = version.split_once('+') {
+        return variant.to_string();
+    }
+    let parts: Vec<&str> = version.split('-').collect();
+    let start = parts
+        .iter()
+        .rposition(|part| !part.starts_with(|ch: char| ch.is_ascii_alphabetic()))
+        .map_or(0, |index| index + 1);
+    parts[start..].join("-")
+}
+
+/// Деревья модулей из [`MODULE_DIRS`] без повторов.
+pub fn module_trees() -> Vec<ModuleTree> {
+    let mut seen = HashSet::new();
+    let mut trees = Vec::new();
+    for dir in MODULE_DIRS {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() || !seen.insert(fs::canonicalize(&path).unwrap_or(path.clone())) {
+                continue;
+            }
+            trees.push(ModuleTree {
+                version: entry.file_name().to_string_lossy().to_string(),
+                pkgbase: fs::read_to_string(path.join("pkgbase"))
+                    .ok()
+                    .map(|content| content.trim().to_string())
+                    .filter(|pkgbase| !pkgbase.is_empty()),
+                path,
+            });
+        }
+    }
+    trees.sort_by(|a, b| a.version.cmp(&b.version));
+    trees
+}
+
+/// Есть ли база пакетов, которую можно спросить о владельце файла.
+pub fn has_package_database() -> bool {
+    OWNER_QUERIES
+        .iter()
+        .any(|(database, _, _)| Path::new(database).exists())
+}
+
+/// Принадлежит ли путь пакету; `None`, если спросить некого.
+pub fn is_owned(path: &Path) -> Option<bool> {
+    let mut answered = false;
+    for (database, program, args) in OWNER_QUERIES {
+        if !Path::new(database).exists() {
+            continue;
+        }
+        let mut full_args = args.to_vec();
+        let path = path.to_string_lossy();
+        full_args.push(&path);
+        let Ok(output) = run_command(program, &full_args) else {
+            continue;
+        };
+        if output.status.success() && !output.stdout.trim().is_empty() {
+            return Some(true);
+        }
+        answered = true;
+    }
+    answered.then_some(false)
+}
+
+/// Файлы ядер без пакета, кроме загруженного ядра `current`.
+///
+/// Ничего не возвращает, если нет дерева модулей загруженного ядра или
+/// базы пакетов: в контейнере или на незнакомой системе брошенным
+/// оказалось бы всё.
+pub fn leftovers(current: &str) -> Vec<KernelLeftover> {
+    let trees = module_trees();
+    if current.is_empty()
+        || !trees.iter().any(|tree| tree.version == current)
+        || !has_package_database()
+    {
+        return Vec::new();
+    }
+
+    // Файлы `/boot` с ключом живого ядра (версия, pkgbase или вариант,
+    // как `vmlinuz-lts` в Alpine) не трогаются.
+    let mut claimed: HashSet<String> = HashSet::from([current.to_string()]);
+    let mut unowned_trees = Vec::new();
+    for tree in trees {
+        if tree.version == current || tree_owned(&tree.version) != Some(false) {
+            claimed.insert(kernel_flavor(&tree.version));
+            claimed.extend(tree.pkgbase.clone());
+            claimed.insert(tree.version);
+        } else {
+            unowned_trees.push(tree);
+        }
+    }
+
+    let mut boot_files: BTreeMap<String, Vec<(PathBuf, bool)>> = BTreeMap::new();
+    for (key, path, image) in boot_files_in(Path::new(BOOT_DIR)) {
+        if !claimed.contains(&key) {
+            boot_files.entry(key).or_default().push((path, image));
+        }
+    }
+
+    let mut leftovers = Vec::new();
+    for tree in unowned_trees {
+        let files = boot_files.remove(&tree.version).unwrap_or_default();
+        let kind = if files.iter().any(|(_, image)| *image) || tree.path.join("vmlinuz").exists() {
+            LeftoverKind::CustomKernel
+        } else {
+            LeftoverKind::ModuleTree
+        };
+        let mut paths = vec![tree.path];
+        paths.extend(unowned(files.into_iter().map(|(path, _)| path)));
+        leftovers.push(leftover(tree.version, kind, paths));
+    }
+    for (key, files) in boot_files {
+        let kind = if files.iter().any(|(_, image)| *image) {
+            LeftoverKind::CustomKernel
+        } else {
+            LeftoverKind::BootFiles
+        };
+        let paths = unowned(files.into_iter().map(|(path, _)| path));
+        if !paths.is_empty() {
+            leftovers.push(leftover(key, kind, paths));
+        }
+    }
+    leftovers
+}
+
+/// Удаляет файлы указанных версий, заново проверив, что они брошены.
+pub fn remove_leftovers(versions: &[String], dry_run: bool) -> Result<()> {
+    let current = running_version();
+    let found = leftovers(&current);
+    for version in versions {
+        let Some(leftover) = found.iter().find(|leftover| &leftover.version == version) else {
+            return Err(RcleanerError::Permission(format!(
+                "{version} is not an unowned kernel"
+            )));
+        };
+        for path in &leftover.paths {
+            if dry_run {
+                log::info!("[DRY RUN] Would remove: {}", path.display());
+            } else {
+                remove_permanently(path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Сборки DKMS для ядер, у которых не осталось дерева модулей.
+pub fn dkms_leftovers(current: &str) -> Vec<DkmsBuild> {
+    let installed: HashSet<String> = module_trees()
+        .into_iter()
+        .map(|tree| tree.version)
+        .collect();
+    if !installed.contains(current) {
+        return Vec::new();
+    }
+    let mut builds = Vec::new();
+    for module in list_dirs(Path::new(DKMS_DIR)) {
+        for module_version in list_dirs(&module) {
+            for kernel in list_dirs(&module_version) {
+                let name = file_name(&kernel);
+                if matches!(name.as_str(), "source" | "build") || installed.contains(&name) {
+                    continue;
+                }
+                builds.push(DkmsBuild {
+                    module: file_name(&module),
+                    module_version: file_name(&module_version),
+                    kernel: name,
+                    path: kernel,
+                });
+            }
+        }
+    }
+    builds.sort_by_key(DkmsBuild::id);
+    builds
+}
+
+/// Снимает сборки DKMS с учёта (`dkms remove <модуль>/<версия> -k <ядро>`).
+pub fn remove_dkms_builds(ids: &[String], dry_run: bool) -> Result<()> {
+    let found = dkms_leftovers(&running_version());
+    for id in ids {
+        let Some(build) = found.iter().find(|build| &build.id() == id) else {
+            return Err(RcleanerError::Permission(format!(
+                "{id} is not a DKMS build for a removed kernel"
+            )));
+        };
+        let module = format!("{}/{}", build.module, build.module_version);
+        if dry_run {
+            log::info!("[DRY RUN] dkms remove {module} -k {}", build.kernel);
+            continue;
+        }
+        let output = run_command("dkms", &["remove", &module, "-k", &build.kernel])?;
+        if !output.status.success() {
+            return Err(command_failed("dkms", &output));
+        }
+    }
+    Ok(())
+}
+
+/// Принадлежит ли пакету дерево модулей: сам каталог или поставляемый
+/// пакетом `modules.builtin` под любым из путей [`MODULE_DIRS`].
+fn tree_owned(version: &str) -> Option<bool> {
+    let mut answer = None;
+    for dir in MODULE_DIRS {
+        let tree = Path::new(dir).join(version);
+        for path in [tree.join("modules.builtin"), tree] {
+            match is_owned(&path) {
+                Some(true) => return Some(true),
+                Some(false) => answer = Some(false),
+                None => {}
+            }
+        }
+    }
+    answer
+}
+
+/// Файлы ядер в `/boot`: ключ (версия или вариант), путь и признак образа.
+fn boot_files_in(boot: &Path) -> Vec<(String, PathBuf, bool)> {
+    let Ok(entries) = fs::read_dir(boot) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(String, PathBuf, bool)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_file()))
+        .filter_map(|entry| {
+            let (key, image) = boot_file_key(&entry.file_name().to_string_lossy())?;
+            Some((key, entry.path(), image))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Ключ ядра из имени файла в `/boot`: `initramfs-linux-fallback.img` → `linux`,
+/// `initramfs-6.5.0-1.fc39.x86_64kdump.img` → `6.5.0-1.fc39.x86_64`.
+///
+/// Спасательные ядра (`vmlinuz-0-rescue-…`) не учитываются.
+fn boot_file_key(file_name: &str) -> Option<(String, bool)> {
+    let (key, image) = BOOT_PREFIXES
+        .iter()
+        .find_map(|(prefix, image)| file_name.strip_prefix(prefix).map(|key| (key, *image)))?;
+    let key = key.strip_suffix(".img").unwrap_or(key);
+    let key = key.strip_suffix("-fallback").unwrap_or(key);
+    let key = key.strip_suffix("kdump").unwrap_or(key);
+    if key.is_empty() || key.contains("rescue") {
+        return None;
+    }
+    Some((key.to_string(), image))
+}
+
+fn leftover(version: String, kind: LeftoverKind, paths: Vec<PathBuf>) -> KernelLeftover {
+    KernelLeftover {
+        version,
+        kind,
+        size: SizeCounter::new().measure_all(&paths).allocated,
+        paths,
+    }
+}
+
+fn unowned(paths: impl IntoIterator<Item = PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter(|path| is_owned(path) == Some(false))
+        .collect()
+}
+
+fn list_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel_flavor_and_boot_keys() {
+        assert_eq!(kernel_flavor("6.1.0-13-amd64"), "amd64");
+        assert_eq!(kernel_flavor("6.1.0-13-rt-amd64"), "rt-amd64");
+        assert_eq!(kernel_flavor("6.5.0-14-generic"), "generic");
+        assert_eq!(kernel_flavor("6.1.62-1-lts"), "lts");
+        assert_eq!(kernel_flavor("6.6.1-zen1-1-zen"), "zen");
+        assert_eq!(kernel_flavor("6.6.1-arch1-1"), "");
+        assert_eq!(kernel_flavor("6.5.0-1.fc39.x86_64"), "");
+        assert_eq!(kernel_flavor("6.5.0-1.fc39.x86_64+debug"), "debug");
+
+        assert_eq!(
+            boot_file_key("initramfs-linux-zen-fallback.img"),
+            Some(("linux-zen".to_string(), false))
+        );
+        assert_eq!(
+            boot_file_key("vmlinuz-6.1.0-13-amd64"),
+            Some(("6.1.0-13-amd64".to_string(), true))
+        );
+        assert_eq!(
+            boot_file_key("initramfs-6.5.0-1.fc39.x86_64kdump.img"),
+            Some(("6.5.0-1.fc39.x86_64".to_string(), false))
+        );
+        assert_eq!(
+            boot_file_key("initrd.img-6.1.0-13-amd64"),
+            Some(("6.1.0-13-amd64".to_string(), false))
+        );
+        assert_eq!(boot_file_key("vmlinuz-0-rescue-0123abcd"), None);
+        assert_eq!(boot_file_key("vmlinuz"), None);
+        assert_eq!(boot_file_key("grubenv"), None);
+    }
+}
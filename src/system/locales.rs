@@ -0,0 +1,481 @@
+//! Переводы, man-страницы на других языках и документация в `/usr/share`
+//! (в духе localepurge).
+//!
+//! Переводы удаляются каталогами языков, которых нет в списке сохраняемых,
+//! документация — целиком, кроме файлов `copyright`. Чтобы файлы не
+//! вернулись с обновлениями пакетов, dpkg получает правила `path-exclude`,
+//! а dnf — `install_langs` и `tsflags=nodocs`.
+
+use crate::cleaner::deletion::remove_permanently;
+use crate::config::LocalesConfig;
+use crate::error::{RcleanerError, Result};
+use crate::utils::disk_size::{SizeCounter, allocated_size};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Каталоги с подкаталогом на каждый язык.
+pub const TRANSLATION_DIRS: &[&str] = &["/usr/share/locale", "/usr/share/man", "/usr/share/help"];
+
+/// Документация пакетов.
+pub const DOC_DIR: &str = "/usr/share/doc";
+
+/// Справка GNOME; `C` — её исходный язык.
+pub const HELP_DIR: &str = "/usr/share/help";
+
+/// Языки, которые не удаляются никогда.
+pub const ALWAYS_KEPT: &[&str] = &["C", "POSIX"];
+
+/// Файл лицензии в каталоге документации пакета (Debian требует его хранить).
+const COPYRIGHT_FILE: &str = "copyright";
+
+/// Каталог настроек dpkg и файл с правилами rCleaner в нём.
+const DPKG_CONFIG_DIR: &str = "/etc/dpkg/dpkg.cfg.d";
+const DPKG_EXCLUDES_FILE: &str = "rcleaner-locales";
+
+pub const DNF_CONF: &str = "/etc/dnf/dnf.conf";
+
+/// Что лежит в каталоге.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocaleDirKind {
+    /// Переводы программ (`/usr/share/locale/<язык>`).
+    Translations,
+    /// Man-страницы на другом языке (`/usr/share/man/<язык>`).
+    ManPages,
+    /// Справка GNOME на одном языке или целиком.
+    Help,
+    /// Документация пакетов целиком.
+    Docs,
+}
+
+impl LocaleDirKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Translations => "translations",
+            Self::ManPages => "man pages",
+            Self::Help => "help",
+            Self::Docs => "documentation",
+        }
+    }
+}
+
+/// Каталог, который можно удалить.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleDir {
+    pub kind: LocaleDirKind,
+    /// Язык каталога; `None` для документации и справки целиком.
+    pub language: Option<String>,
+    pub path: PathBuf,
+}
+
+/// Можно ли подставить язык из конфигурации в паттерны и файлы настроек.
+pub fn is_valid_language(language: &str) -> bool {
+    language.len() <= 32
+        && language
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_alphabetic())
+        && language
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "_@.-".contains(ch))
+}
+
+/// Языки из списка сохраняемых, пригодные для паттернов.
+pub fn kept_languages(config: &LocalesConfig) -> Vec<&str> {
+    config
+        .keep
+        .iter()
+        .map(|language| language.trim())
+        .filter(|language| {
+            let valid = is_valid_language(language);
+            if !valid {
+                log::warn!("Ignoring invalid language {language:?} in locales.keep");
+            }
+            valid
+        })
+        .collect()
+}
+
+/// Сохраняется ли язык: `en` сохраняет `en`, `en_GB`, `en@quot` и `en.UTF-8`.
+pub fn is_kept(language: &str, keep: &[&str]) -> bool {
+    ALWAYS_KEPT.contains(&language)
+        || keep.iter().any(|kept| {
+            language
+                .strip_prefix(kept)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['_', '@', '.']))
+        })
+}
+
+/// Определяет, что за каталог перед нами; `None`, если это не каталог
+/// языка, документации или справки.
+pub fn classify(path: &Path) -> Option<LocaleDir> {
+    if path == Path::new(DOC_DIR) {
+        return Some(LocaleDir {
+            kind: LocaleDirKind::Docs,
+            language: None,
+            path: path.to_path_buf(),
+        });
+    }
+    if path == Path::new(HELP_DIR) {
+        return Some(LocaleDir {
+            kind: LocaleDirKind::Help,
+            language: None,
+            path: path.to_path_buf(),
+        });
+    }
+    let parent = path.parent()?.to_str()?;
+    let kind = match parent {
+        "/usr/share/locale" => LocaleDirKind::Translations,
+        "/usr/share/man" => LocaleDirKind::ManPages,
+        "/usr/share/help" => LocaleDirKind::Help,
+        _ => return None,
+    };
+    let name = path.file_name()?.to_str()?;
+    is_language_dir(name).then(|| LocaleDir {
+        kind,
+        language: Some(name.to_string()),
+        path: path.to_path_buf(),
+    })
+}
+
+/// Каталоги, которые удалит очистка с этой конфигурацией.
+pub fn prunable(config: &LocalesConfig) -> Vec<LocaleDir> {
+    let keep = kept_languages(config);
+    let mut dirs = Vec::new();
+    for root in TRANSLATION_DIRS {
+        // Справка удаляется целиком вместе с документацией.
+        if config.docs && *root == HELP_DIR {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(root) else {
+            continue;
+        };
+        let mut found: Vec<LocaleDir> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .filter_map(|entry| classify(&entry.path()))
+            .filter(|dir| {
+                dir.language
+                    .as_deref()
+                    .is_some_and(|language| !is_kept(language, &keep))
+            })
+            .collect();
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        dirs.extend(found);
+    }
+    if config.docs {
+        for root in [DOC_DIR, HELP_DIR] {
+            if let Some(dir) = classify(Path::new(root)).filter(|dir| dir.path.is_dir()) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// Место, которое освободит удаление каталога.
+pub fn size(dir: &LocaleDir) -> u64 {
+    match dir.kind {
+        LocaleDirKind::Docs => {
+            SizeCounter::new()
+                .measure_all(&doc_removals(&dir.path))
+                .allocated
+        }
+        _ => allocated_size(&dir.path),
+    }
+}
+
+/// Удаляет каталоги и настраивает пакетные менеджеры, если это разрешено.
+///
+/// Каждый путь заново проверяется по конфигурации: без `enabled = true`
+/// ничего не удаляется, каталог языка из списка сохраняемых или
+/// документация при `docs = false` тоже.
+pub fn prune(paths: &[String], config: &LocalesConfig, dry_run: bool) -> Result<()> {
+    if !config.enabled {
+        return Err(RcleanerError::Permission(
+            "locales cleanup is disabled in the configuration".to_string(),
+        ));
+    }
+    let keep = kept_languages(config);
+    let mut dirs = Vec::new();
+    for path in paths {
+        let Some(dir) = classify(Path::new(path)) else {
+            return Err(RcleanerError::Permission(format!(
+                "{path} is not a translation or documentation directory"
+            )));
+        };
+        if dir.language.is_none() && !config.docs {
+            return Err(RcleanerError::Permission(format!(
+                "{path} is documentation, but locales.docs is disabled"
+            )));
+        }
+        if let Some(language) = dir.language.as_deref()
+            && is_kept(language, &keep)
+        {
+            return Err(RcleanerError::Permission(format!(
+                "{language} is in the locales keep list"
+            )));
+        }
+        dirs.push(dir);
+    }
+
+    for dir in &dirs {
+        let removals = match dir.kind {
+            LocaleDirKind::Docs => doc_removals(&dir.path),
+            _ => vec![dir.path.clone()],
+        };
+        for path in removals {
+            if dry_run {
+                log::info!("[DRY RUN] Would remove: {}", path.display());
+            } else {
+                remove_permanently(&path)?;
+            }
+        }
+    }
+
+    if config.prevent_reinstall {
+        prevent_reinstall(config, dry_run)?;
+    }
+    Ok(())
+}
+
+/// Запрещает dpkg и dnf устанавливать то, что удаляет очистка.
+pub fn prevent_reinstall(config: &LocalesConfig, dry_run: bool) -> Result<()> {
+    if Path::new(DPKG_CONFIG_DIR).is_dir() {
+        let path = Path::new(DPKG_CONFIG_DIR).join(DPKG_EXCLUDES_FILE);
+        if dry_run {
+            log::info!("[DRY RUN] Would write {}", path.display());
+        } else {
+            fs::write(&path, dpkg_excludes(config))?;
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(DNF_CONF) {
+        let updated = update_dnf_conf(&content, &kept_languages(config), config.docs);
+        if updated == content {
+            return Ok(());
+        }
+        if dry_run {
+            log::info!("[DRY RUN] Would update {DNF_CONF}");
+        } else {
+            fs::write(DNF_CONF, updated)?;
+        }
+    }
+    Ok(())
+}
+
+/// Похоже ли имя на код языка (`de`, `pt_BR`, `sr@latin`, `zh_CN.UTF-8`).
+///
+/// Каталоги `man1`…`man9` и `locale.alias` под это не подходят.
+fn is_language_dir(name: &str) -> bool {
+    if ALWAYS_KEPT.contains(&name) {
+        return true;
+    }
+    let letters = name
+        .chars()
+        .take_while(|ch| ch.is_ascii_lowercase())
+        .count();
+    (2..=3).contains(&letters)
+        && name[letters..]
+            .chars()
+            .next()
+            .is_none_or(|ch| "_@.".contains(ch))
+        && is_valid_language(name)
+}
+
+/// Файлы и каталоги документации, кроме файлов `copyright`.
+///
+/// Символические ссылки на каталоги других пакетов остаются.
+fn doc_removals(root: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut removals = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        if kind.is_symlink() {
+            continue;
+        }
+        if !kind.is_dir() {
+            removals.push(entry.path());
+            continue;
+        }
+        let Ok(children) = fs::read_dir(entry.path()) else {
+            continue;
+        };
+        removals.extend(
+            children
+                .flatten()
+                .filter(|child| child.file_name() != COPYRIGHT_FILE)
+                .map(|child| child.path()),
+        );
+    }
+    removals.sort();
+    removals
+}
+
+/// Правила dpkg: исключить переводы и документацию, вернуть сохраняемые языки.
+///
+/// dpkg применяет последнее совпавшее правило, поэтому `path-include` идут
+/// после `path-exclude`.
+fn dpkg_excludes(config: &LocalesConfig) -> String {
+    let mut excludes = vec![
+        "/usr/share/locale/*".to_string(),
+        "/usr/share/man/*".to_string(),
+        "/usr/share/help/*".to_string(),
+    ];
+    let mut includes = vec![
+        "/usr/share/locale/locale.alias".to_string(),
+        "/usr/share/man/man*".to_string(),
+    ];
+    let roots: Vec<&str> = if config.docs {
+        excludes.push(format!("{DOC_DIR}/*"));
+        includes.push(format!("{DOC_DIR}/*/{COPYRIGHT_FILE}"));
+        vec!["/usr/share/locale", "/usr/share/man"]
+    } else {
+        includes.push(format!("{HELP_DIR}/C/*"));
+        TRANSLATION_DIRS.to_vec()
+    };
+    for language in kept_languages(config) {
+        for root in &roots {
+            includes.push(format!("{root}/{language}/*"));
+            includes.push(format!("{root}/{language}[_@.]*"));
+        }
+    }
+
+    let mut content = String::from(
+        "# Written by rcleaner from [locales] in config.toml; changes are overwritten.\n",
+    );
+    for pattern in excludes {
+        content.push_str(&format!("path-exclude={pattern}\n"));
+    }
+    for pattern in includes {
+        content.push_str(&format!("path-include={pattern}\n"));
+    }
+    content
+}
+
+/// Задаёт `install_langs` и, если документация удаляется, `tsflags=nodocs`
+/// в секции `[main]` настроек dnf. Остальные строки не меняются.
+fn update_dnf_conf(content: &str, languages: &[&str], nodocs: bool) -> String {
+    let install_langs = format!("install_langs={}", languages.join(","));
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_main = false;
+    let mut main_end = None;
+    let mut has_langs = false;
+    let mut has_tsflags = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_main = trimmed == "[main]";
+            lines.push(line.to_string());
+            if in_main {
+                main_end = Some(lines.len());
+            }
+            continue;
+        }
+        let key = trimmed.split('=').next().unwrap_or_default().trim();
+        let line = match key {
+            "install_langs" if in_main => {
+                has_langs = true;
+                install_langs.clone()
+            }
+            "tsflags" if in_main => {
+                has_tsflags = true;
+                let mut flags: Vec<&str> = trimmed
+                    .split_once('=')
+                    .map(|(_, value)| value)
+                    .unwrap_or_default()
+                    .split([',', ' '])
+                    .filter(|flag| !flag.is_empty())
+                    .collect();
+                if nodocs && !flags.contains(&"nodocs") {
+                    flags.push("nodocs");
+                }
+                format!("tsflags={}", flags.join(","))
+            }
+            _ => line.to_string(),
+        };
+        lines.push(line);
+        if in_main && !trimmed.is_empty() {
+            main_end = Some(lines.len());
+        }
+    }
+
+    let mut missing = Vec::new();
+    if !has_langs {
+        missing.push(install_langs);
+    }
+    if nodocs && !has_tsflags {
+        missing.push("tsflags=nodocs".to_string());
+    }
+    match main_end {
+        Some(index) => {
+            lines.splice(index..index, missing);
+        }
+        None if !missing.is_empty() => {
+            lines.push("[main]".to_string());
+            lines.extend(missing);
+        }
+        None => {}
+    }
+
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_and_package_manager_rules() {
+        let keep = ["en", "pt_BR"];
+        assert!(is_kept("en_GB", &keep));
+        assert!(is_kept("en@quot", &keep));
+        assert!(is_kept("pt_BR.UTF-8", &keep));
+        assert!(is_kept("C", &keep));
+        assert!(!is_kept("pt", &keep));
+        assert!(!is_kept("eo", &keep));
+
+        assert!(is_language_dir("sr@latin"));
+        assert!(is_language_dir("ast"));
+        assert!(!is_language_dir("man1"));
+        assert!(!is_language_dir("locale.alias"));
+        assert_eq!(
+            classify(Path::new("/usr/share/man/de")).map(|dir| dir.kind),
+            Some(LocaleDirKind::ManPages)
+        );
+        assert_eq!(classify(Path::new("/usr/share/man/man8")), None);
+        assert_eq!(classify(Path::new("/usr/share/locale/../../etc")), None);
+
+        let config = LocalesConfig {
+            enabled: true,
+            keep: vec!["ru".to_string(), "{evil}".to_string()],
+            docs: true,
+            prevent_reinstall: true,
+        };
+        let rules = dpkg_excludes(&config);
+        assert!(rules.contains("path-exclude=/usr/share/doc/*\n"));
+        assert!(rules.contains("path-include=/usr/share/doc/*/copyright\n"));
+        assert!(rules.contains("path-include=/usr/share/locale/ru[_@.]*\n"));
+        assert!(!rules.contains("evil"));
+        assert!(
+            rules.find("path-exclude=/usr/share/man/*")
+                < rules.find("path-include=/usr/share/man/ru/*")
+        );
+
+        let dnf = "[main]\ngpgcheck=True\ntsflags=test\n\n[updates]\nenabled=1\n";
+        assert_eq!(
+            update_dnf_conf(dnf, &["en", "ru"], true),
+            "[main]\ngpgcheck=True\ntsflags=test,nodocs\ninstall_langs=en,ru\n\n[updates]\nenabled=1\n"
+        );
+        assert_eq!(
+            update_dnf_conf("", &["en"], false),
+            "[main]\ninstall_langs=en\n"
+        );
+    }
+}
@@ -1,21 +1,45 @@
 //! Модули для работы с системой и пакетными менеджерами.
 //!
 //! - [`detection`] - определение типа системы
+//! - [`mounts`] - точки монтирования и типы файловых систем
+//! - [`disk_usage`] - заполненность файловых систем и привязка элементов к ним
 //! - [`package_manager`] - базовый trait для пакетных менеджеров
+//! - [`dependency_graph`] - граф обратных зависимостей установленных пакетов
 //! - [`rpm_ostree`] - поддержка rpm-ostree (Atomic Desktop)
 //! - [`rpm`] - поддержка RPM
 //! - [`dnf`] - поддержка DNF
 //! - [`apt`] - поддержка APT
 //! - [`pacman`] - поддержка Pacman
+//! - [`zypper`] - поддержка Zypper (openSUSE, в том числе transactional-update)
+//! - [`apk`] - поддержка apk (Alpine Linux)
+//! - [`xbps`] - поддержка XBPS и vkpurge (Void Linux)
+//! - [`kernels`] - файлы ядер без пакета и сборки DKMS для удалённых ядер
+//! - [`bootloader`] - ядра, нужные загрузчику (GRUB, systemd-boot, последняя удачная загрузка)
 //! - [`flatpak`] - поддержка Flatpak
 //! - [`snap`] - поддержка Snap
+//! - [`user_packages`] - пакеты пользователя: Homebrew, pipx, cargo, npm, AppImage
+//! - [`usage`] - оценка времени последнего использования приложений
+//! - [`locales`] - переводы, man-страницы и документация в `/usr/share`
+//! - [`store`] - хранилища Nix и Guix: поколения профилей и сборка мусора
 
+pub mod apk;
 pub mod apt;
+pub mod bootloader;
+pub mod dependency_graph;
 pub mod detection;
+pub mod disk_usage;
 pub mod dnf;
 pub mod flatpak;
+pub mod kernels;
+pub mod locales;
+pub mod mounts;
 pub mod package_manager;
 pub mod pacman;
 pub mod rpm;
 pub mod rpm_ostree;
 pub mod snap;
+pub mod store;
+pub mod usage;
+pub mod user_packages;
+pub mod xbps;
+pub mod zypper;
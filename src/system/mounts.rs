@@ -0,0 +1,159 @@
+//! Информация о смонтированных файловых системах из `/proc/self/mountinfo`.
+
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Точка монтирования.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountEntry {
+    /// Путь, куда смонтирована файловая система.
+    pub mount_point: PathBuf,
+    /// Корень внутри файловой системы (для bind-mount и подтомов).
+    pub root: String,
+    /// Тип файловой системы (`btrfs`, `zfs`, `ext4`, ...).
+    pub fs_type: String,
+    /// Источник монтирования (устройство или dataset).
+    pub source: String,
+    /// Опции монтирования.
+    pub options: Vec<String>,
+}
+
+/// Заполненность файловой системы по данным `statvfs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FsUsage {
+    /// Размер файловой системы в байтах.
+    pub total: u64,
+    /// Свободно для непривилегированного пользователя.
+    pub available: u64,
+    /// Занято (без учёта зарезервированных блоков).
+    pub used: u64,
+}
+
+impl FsUsage {
+    /// Процент заполнения так, как его показывает `df`.
+    pub fn used_percent(&self) -> f64 {
+        let usable = self.used + self.available;
+        if usable == 0 {
+            return 0.0;
+        }
+        self.used as f64 * 100.0 / usable as f64
+    }
+}
+
+/// Возвращает заполненность файловой системы, содержащей путь.
+pub fn fs_usage(path: &Path) -> io::Result<FsUsage> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let block = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block;
+    let free = stat.f_bfree as u64 * block;
+    Ok(FsUsage {
+        total,
+        available: stat.f_bavail as u64 * block,
+        used: total.saturating_sub(free),
+    })
+}
+
+/// Возвращает все точки монтирования текущего процесса.
+pub fn read_mounts() -> Vec<MountEntry> {
+    fs::read_to_string("/proc/self/mountinfo")
+        .map(|content| parse_mountinfo(&content))
+        .unwrap_or_default()
+}
+
+/// Возвращает точку монтирования, которой принадлежит путь.
+pub fn mount_for_path(path: &Path) -> Option<MountEntry> {
+    find_mount(&read_mounts(), path)
+}
+
+/// Ищет самую длинную точку монтирования, являющуюся префиксом пути.
+pub fn find_mount(mounts: &[MountEntry], path: &Path) -> Option<MountEntry> {
+    let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    mounts
+        .iter()
+        .filter(|mount| resolved.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.as_os_str().len())
+        .cloned()
+}
+
+/// Разбирает содержимое `mountinfo`.
+pub fn parse_mountinfo(content: &str) -> Vec<MountEntry> {
+    let mut mounts = Vec::new();
+    for line in content.lines() {
+        let Some((left, right)) = line.split_once(" - ") else {
+            continue;
+        };
+        let left: Vec<&str> = left.split_whitespace().collect();
+        let right: Vec<&str> = right.split_whitespace().collect();
+        if left.len() < 6 || right.len() < 2 {
+            continue;
+        }
+
+        mounts.push(MountEntry {
+            mount_point: PathBuf::from(unescape(left[4])),
+            root: unescape(left[3]),
+            fs_type: right[0].to_string(),
+            source: unescape(right[1]),
+            options: left[5].split(',').map(String::from).collect(),
+        });
+    }
+    mounts
+}
+
+fn unescape(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            let code: String = chars.clone().take(3).collect();
+            if code.len() == 3
+                && let Ok(byte) = u8::from_str_radix(&code, 8)
+            {
+                output.push(byte as char);
+                for _ in 0..3 {
+                    chars.next();
+                }
+                continue;
+            }
+        }
+        output.push(ch);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "\
+22 1 0:21 / / rw,relatime shared:1 - btrfs /dev/nvme0n1p3 rw,subvol=/root
+40 22 0:21 /home /home rw,relatime shared:2 - btrfs /dev/nvme0n1p3 rw,subvol=/home
+55 40 0:45 / /home/user/My\\040Drive rw,nosuid - fuse.rclone drive: rw
+61 22 0:50 / /tank rw,xattr - zfs tank/data rw,xattr";
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let mounts = parse_mountinfo(SAMPLE);
+        assert_eq!(mounts.len(), 4);
+        assert_eq!(mounts[3].source, "tank/data");
+        assert_eq!(mounts[1].mount_point, PathBuf::from("/home"));
+        assert_eq!(mounts[1].root, "/home");
+        assert_eq!(mounts[1].fs_type, "btrfs");
+        assert_eq!(mounts[2].mount_point, PathBuf::from("/home/user/My Drive"));
+    }
+
+    #[test]
+    fn test_find_mount_prefers_longest_prefix() {
+        let mounts = parse_mountinfo(SAMPLE);
+        let mount = find_mount(&mounts, Path::new("/home/rcleaner-missing/.cache")).unwrap();
+        assert_eq!(mount.mount_point, PathBuf::from("/home"));
+    }
+}
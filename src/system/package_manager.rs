@@ -1,11 +1,18 @@
 //! Trait и утилиты для работы с пакетными менеджерами.
 
 use crate::error::{RcleanerError, Result};
+use crate::system::{
+    apk::ApkManager, apt::AptManager, dnf::DnfManager, pacman::PacmanManager, xbps::XbpsManager,
+    zypper::ZypperManager,
+};
+use crate::utils::size_format::format_size;
+use serde::{Deserialize, Serialize};
 use std::process::{Command, ExitStatus};
 
 /// Trait для пакетных менеджеров.
 ///
-/// Реализуется для rpm, dnf, apt, pacman, flatpak, snap и rpm-ostree.
+/// Реализуется для rpm, dnf, apt, pacman, zypper, apk, xbps, flatpak, snap и
+/// rpm-ostree.
 pub trait PackageManager {
     /// Возвращает имя пакетного менеджера.
     fn name(&self) -> &str;
@@ -28,6 +35,87 @@ pub trait PackageManager {
     /// * `packages` - список пакетов для удаления
     /// * `dry_run` - если `true`, только симуляция
     fn remove_packages(&self, packages: &[String], dry_run: bool) -> Result<()>;
+
+    /// Симулирует удаление пакетов без изменений в системе.
+    ///
+    /// Возвращает все пакеты, которые удалит транзакция, освобождаемое
+    /// место и удержанные/защищённые пакеты. По умолчанию считается,
+    /// что удаляются ровно запрошенные пакеты.
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        Ok(RemovalPlan::new(self.name(), packages))
+    }
+}
+
+/// Результат симуляции удаления пакетов.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovalPlan {
+    /// Пакетный менеджер.
+    pub manager: String,
+    /// Пакеты, выбранные пользователем.
+    pub requested: Vec<String>,
+    /// Все пакеты, которые удалит транзакция.
+    pub removed: Vec<String>,
+    /// Освобождаемое место в байтах, если менеджер его сообщает.
+    pub freed_bytes: Option<u64>,
+    /// Удержанные (hold) или защищённые пакеты, затронутые транзакцией.
+    pub held: Vec<String>,
+}
+
+impl RemovalPlan {
+    /// План, удаляющий ровно запрошенные пакеты.
+    pub fn new(manager: &str, packages: &[String]) -> Self {
+        Self {
+            manager: manager.to_string(),
+            requested: packages.to_vec(),
+            removed: packages.to_vec(),
+            freed_bytes: None,
+            held: Vec::new(),
+        }
+    }
+
+    /// Пакеты, которые удалятся помимо выбранных.
+    pub fn extra(&self) -> Vec<&str> {
+        self.removed
+            .iter()
+            .filter(|package| !self.requested.contains(package))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Мешают ли удалению удержанные или защищённые пакеты.
+    pub fn is_blocked(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    /// Краткое описание для экрана подтверждения.
+    pub fn summary(&self) -> String {
+        let extra = self.extra();
+        let mut summary = format!(
+            "{}: removes {} package(s)",
+            self.manager,
+            self.removed.len()
+        );
+        if !extra.is_empty() {
+            summary.push_str(&format!(", also {}", extra.join(", ")));
+        }
+        if let Some(bytes) = self.freed_bytes {
+            summary.push_str(&format!(", frees {}", format_size(bytes)));
+        }
+        summary
+    }
+}
+
+/// Симулирует удаление пакетов менеджером с указанным именем.
+pub fn simulate_removal(manager: &str, packages: &[String]) -> Result<RemovalPlan> {
+    match manager {
+        "apt" => AptManager::new().simulate_removal(packages),
+        "dnf" => DnfManager::new().simulate_removal(packages),
+        "pacman" => PacmanManager::new().simulate_removal(packages),
+        "zypper" => ZypperManager::new().simulate_removal(packages),
+        "apk" => ApkManager::new().simulate_removal(packages),
+        "xbps" => XbpsManager::new().simulate_removal(packages),
+        other => Ok(RemovalPlan::new(other, packages)),
+    }
 }
 
 /// Результат выполнения команды.
@@ -1,5 +1,11 @@
-use crate::error::{RcleanerError, Result};
-use crate::system::package_manager::{PackageManager, command_failed, run_command};
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::package_manager::{PackageManager, RemovalPlan, command_failed, run_command};
+use crate::utils::size_format::parse_size_string;
+
+/// Удаление вместе со ставшими ненужными зависимостями; симуляция и
+/// настоящее удаление должны использовать одни и те же флаги.
+const REMOVE_FLAGS: &str = "-Rs";
 
 pub struct PacmanManager;
 
@@ -49,22 +55,14 @@ impl PackageManager for PacmanManager {
             return Ok(());
         }
 
-        for package in packages {
-            let deps = self.check_dependencies(package)?;
-            if !deps.is_empty() {
-                return Err(RcleanerError::Dependency(format!(
-                    "Package {package} is required by: {}",
-                    deps.join(", ")
-                )));
-            }
-        }
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
 
         if dry_run {
-            log::info!("[DRY RUN] pacman -R {:?}", packages);
+            log::info!("[DRY RUN] pacman {} {:?}", REMOVE_FLAGS, packages);
             return Ok(());
         }
 
-        let args = vec!["-R", "--noconfirm"];
+        let args = vec![REMOVE_FLAGS, "--noconfirm"];
         let package_args = packages.iter().map(String::as_str).collect::<Vec<_>>();
         let mut combined = args;
         combined.extend(package_args);
@@ -76,6 +74,35 @@ impl PackageManager for PacmanManager {
 
         Ok(())
     }
+
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        let mut args = vec![REMOVE_FLAGS, "--print", "--print-format", "%n"];
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("pacman", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("pacman", &output));
+        }
+
+        let mut plan = RemovalPlan::new("pacman", packages);
+        plan.removed = split_lines(&output.stdout);
+        if !plan.removed.is_empty() {
+            let mut args = vec!["-Qi"];
+            args.extend(plan.removed.iter().map(String::as_str));
+            if let Ok(info) = run_command("pacman", &args) {
+                plan.freed_bytes = Some(sum_installed_sizes(&info.stdout));
+            }
+        }
+        let holds = std::fs::read_to_string("/etc/pacman.conf")
+            .map(|content| parse_hold_packages(&content))
+            .unwrap_or_default();
+        plan.held = plan
+            .removed
+            .iter()
+            .filter(|package| holds.contains(package))
+            .cloned()
+            .collect();
+        Ok(plan)
+    }
 }
 
 pub fn list_installed() -> Result<Vec<String>> {
@@ -99,17 +126,38 @@ fn split_lines(output: &str) -> Vec<String> {
         .collect()
 }
 
+/// Сумма `Installed Size` из вывода `pacman -Qi`.
+fn sum_installed_sizes(output: &str) -> u64 {
+    output
+        .lines()
+        .filter(|line| line.trim_start().starts_with("Installed Size"))
+        .filter_map(|line| line.split_once(':'))
+        .filter_map(|(_, value)| parse_size_string(value))
+        .sum()
+}
+
+/// Пакеты из `HoldPkg` в `pacman.conf`.
+fn parse_hold_packages(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("HoldPkg"))
+        .filter_map(|rest| rest.trim_start().strip_prefix('='))
+        .flat_map(|value| value.split_whitespace().map(String::from))
+        .collect()
+}
+
 fn parse_pacman_required_by(output: &str) -> Vec<String> {
     for line in output.lines() {
         let line = line.trim();
         if line.starts_with("Required By")
-            && let Some((_, value)) = line.split_once(':') {
-                let value = value.trim();
-                if value == "None" || value.is_empty() {
-                    return Vec::new();
-                }
-                return value.split_whitespace().map(String::from).collect();
+            && let Some((_, value)) = line.split_once(':')
+        {
+            let value = value.trim();
+            if value == "None" || value.is_empty() {
+                return Vec::new();
             }
+            return value.split_whitespace().map(String::from).collect();
+        }
     }
     Vec::new()
 }
@@ -1,4 +1,5 @@
-use crate::error::{RcleanerError, Result};
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
 use crate::system::package_manager::{PackageManager, command_failed, run_command};
 
 pub struct RpmManager;
@@ -54,15 +55,7 @@ impl PackageManager for RpmManager {
             return Ok(());
         }
 
-        for package in packages {
-            let deps = self.check_dependencies(package)?;
-            if !deps.is_empty() {
-                return Err(RcleanerError::Dependency(format!(
-                    "Package {package} is required by: {}",
-                    deps.join(", ")
-                )));
-            }
-        }
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
 
         let mut args: Vec<String> = vec!["-e".to_string()];
         if dry_run {
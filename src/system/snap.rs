@@ -1,6 +1,6 @@
 use crate::error::Result;
 use crate::system::package_manager::{PackageManager, command_failed, run_command};
-use std::fs;
+use crate::utils::disk_size::allocated_size;
 use std::path::Path;
 
 pub struct SnapManager;
@@ -117,7 +117,7 @@ fn parse_snap_list_with_sizes(output: &str) -> Vec<(String, u64)> {
         };
 
         let snap_path = format!("/var/lib/snapd/snaps/{}_{}.snap", name, rev);
-        let size = fs::metadata(&snap_path).map(|meta| meta.len()).unwrap_or(0);
+        let size = allocated_size(Path::new(&snap_path));
         items.push((name.to_string(), size));
     }
     items
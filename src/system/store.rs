@@ -0,0 +1,380 @@
+//! Функциональные хранилища пакетов Nix и Guix.
+//!
+//! Пакеты в `/nix/store` и `/gnu/store` удаляет только сборщик мусора, и
+//! только если на них не ссылается ни одно поколение профиля и ни один
+//! gcroot. Поэтому очистка состоит из удаления старых поколений, забытых
+//! ссылок `result` и последующего запуска сборщика.
+
+use crate::error::Result;
+use crate::system::detection::command_exists;
+use crate::system::package_manager::{command_failed, run_command};
+use crate::utils::command::is_root;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Системные профили: их поколения удаляются только с правами root.
+pub const SYSTEM_PROFILES: &[&str] = &["/nix/var/nix/profiles/system", "/var/guix/profiles/system"];
+
+/// Хранилище пакетов.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreManager {
+    Nix,
+    Guix,
+}
+
+/// Поколение профиля (`<profile>-<N>-link`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generation {
+    /// Профиль, которому принадлежит поколение.
+    pub profile: PathBuf,
+    /// Номер поколения.
+    pub number: u32,
+    /// Ссылка на поколение.
+    pub link: PathBuf,
+    /// Активно ли поколение сейчас.
+    pub current: bool,
+    /// Когда поколение создано.
+    pub created: Option<SystemTime>,
+}
+
+impl StoreManager {
+    /// Установленные хранилища.
+    pub fn detect() -> Vec<Self> {
+        let mut managers = Vec::new();
+        if Path::new("/nix/store").is_dir() && command_exists("nix-env") {
+            managers.push(Self::Nix);
+        }
+        if Path::new("/gnu/store").is_dir() && command_exists("guix") {
+            managers.push(Self::Guix);
+        }
+        managers
+    }
+
+    /// Хранилище по имени источника элемента.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nix" => Some(Self::Nix),
+            "guix" => Some(Self::Guix),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nix => "nix",
+            Self::Guix => "guix",
+        }
+    }
+
+    /// Существующие профили: системный и профили текущего пользователя.
+    pub fn profiles(self) -> Vec<PathBuf> {
+        let user = std::env::var("USER").unwrap_or_default();
+        let home = std::env::var("HOME").map(PathBuf::from).ok();
+        let mut candidates: Vec<PathBuf> = Vec::new();
+        match self {
+            Self::Nix => {
+                candidates.push(PathBuf::from("/nix/var/nix/profiles/system"));
+                if is_root() {
+                    candidates.push(PathBuf::from("/nix/var/nix/profiles/default"));
+                }
+                let state = std::env::var("XDG_STATE_HOME")
+                    .map(PathBuf::from)
+                    .ok()
+                    .or_else(|| home.as_ref().map(|home| home.join(".local/state")));
+                for name in ["profile", "home-manager"] {
+                    if let Some(state) = &state {
+                        candidates.push(state.join("nix/profiles").join(name));
+                    }
+                    if !user.is_empty() {
+                        candidates.push(
+                            Path::new("/nix/var/nix/profiles/per-user")
+                                .join(&user)
+                                .join(name),
+                        );
+                    }
+                }
+            }
+            Self::Guix => {
+                candidates.push(PathBuf::from("/var/guix/profiles/system"));
+                if !user.is_empty() {
+                    for name in ["guix-profile", "guix-home", "current-guix"] {
+                        candidates.push(
+                            Path::new("/var/guix/profiles/per-user")
+                                .join(&user)
+                                .join(name),
+                        );
+                    }
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .filter(|profile| {
+                fs::symlink_metadata(profile).is_ok_and(|metadata| metadata.is_symlink())
+            })
+            .collect()
+    }
+
+    /// Пути хранилища, которые соберёт сборщик мусора.
+    pub fn dead_paths(self) -> Result<Vec<PathBuf>> {
+        let (command, args): (&str, &[&str]) = match self {
+            Self::Nix => ("nix-store", &["--gc", "--print-dead"]),
+            Self::Guix => ("guix", &["gc", "--list-dead"]),
+        };
+        let output = run_command(command, args)?;
+        if !output.status.success() {
+            return Err(command_failed(command, &output));
+        }
+        Ok(output
+            .stdout
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with('/'))
+            .map(PathBuf::from)
+            .collect())
+    }
+
+    /// Ссылки `result` текущего пользователя, которые держат пакеты через
+    /// автоматические gcroots (`nix-build`, `guix build -r`).
+    pub fn result_links(self) -> Vec<PathBuf> {
+        let auto = match self {
+            Self::Nix => "/nix/var/nix/gcroots/auto",
+            Self::Guix => "/var/guix/gcroots/auto",
+        };
+        let Ok(home) = std::env::var("HOME") else {
+            return Vec::new();
+        };
+        let Ok(entries) = fs::read_dir(auto) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| fs::read_link(entry.path()).ok())
+            .filter(|target| target.starts_with(&home))
+            .filter(|target| {
+                target
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("result"))
+            })
+            .filter(|target| {
+                fs::symlink_metadata(target).is_ok_and(|metadata| metadata.is_symlink())
+            })
+            .collect()
+    }
+
+    /// Удаляет поколения профиля.
+    ///
+    /// После удаления системных поколений NixOS пересобирает меню загрузчика,
+    /// Guix делает это сам.
+    pub fn delete_generations(self, profile: &Path, numbers: &[u32], dry_run: bool) -> Result<()> {
+        if numbers.is_empty() {
+            return Ok(());
+        }
+        if dry_run {
+            log::info!(
+                "[DRY RUN] {} delete generations {:?} of {}",
+                self.name(),
+                numbers,
+                profile.display()
+            );
+            return Ok(());
+        }
+
+        let profile_arg = profile.to_string_lossy().to_string();
+        let listed: Vec<String> = numbers.iter().map(u32::to_string).collect();
+        let joined = listed.join(",");
+        let (command, args): (&str, Vec<String>) = match self {
+            Self::Nix => {
+                let mut args = vec![
+                    "--profile".to_string(),
+                    profile_arg,
+                    "--delete-generations".to_string(),
+                ];
+                args.extend(listed);
+                ("nix-env", args)
+            }
+            Self::Guix if is_system_profile(profile) => (
+                "guix",
+                vec![
+                    "system".to_string(),
+                    "delete-generations".to_string(),
+                    joined,
+                ],
+            ),
+            Self::Guix => (
+                "guix",
+                vec![
+                    "package".to_string(),
+                    format!("--profile={profile_arg}"),
+                    format!("--delete-generations={joined}"),
+                ],
+            ),
+        };
+        let args_ref: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = run_command(command, &args_ref)?;
+        if !output.status.success() {
+            return Err(command_failed(command, &output));
+        }
+
+        if self == Self::Nix && is_system_profile(profile) {
+            let switch = profile.join("bin/switch-to-configuration");
+            if switch.exists() {
+                let switch = switch.to_string_lossy().to_string();
+                let output = run_command(&switch, &["boot"])?;
+                if !output.status.success() {
+                    return Err(command_failed("switch-to-configuration", &output));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Запускает сборщик мусора (`nix-collect-garbage`, `guix gc`).
+    pub fn collect_garbage(self, dry_run: bool) -> Result<()> {
+        let (command, args): (&str, &[&str]) = match self {
+            Self::Nix => ("nix-collect-garbage", &[]),
+            Self::Guix => ("guix", &["gc"]),
+        };
+        if dry_run {
+            log::info!("[DRY RUN] {command} {}", args.join(" "));
+            return Ok(());
+        }
+        let output = run_command(command, args)?;
+        if !output.status.success() {
+            return Err(command_failed(command, &output));
+        }
+        Ok(())
+    }
+}
+
+/// Системный ли профиль (нужны права root).
+pub fn is_system_profile(profile: &Path) -> bool {
+    SYSTEM_PROFILES
+        .iter()
+        .any(|system| profile == Path::new(system))
+}
+
+/// Поколения профиля по ссылкам `<profile>-<N>-link` рядом с ним.
+pub fn list_generations(profile: &Path) -> Vec<Generation> {
+    let (Some(dir), Some(base)) = (profile.parent(), profile.file_name()) else {
+        return Vec::new();
+    };
+    let base = base.to_string_lossy().to_string();
+    let current = fs::read_link(profile).ok().and_then(|target| {
+        target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+    });
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut generations: Vec<Generation> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let number = parse_generation_link(&name, &base)?;
+            Some(Generation {
+                profile: profile.to_path_buf(),
+                number,
+                link: entry.path(),
+                current: current.as_deref() == Some(name.as_str()),
+                created: fs::symlink_metadata(entry.path())
+                    .and_then(|metadata| metadata.modified())
+                    .ok(),
+            })
+        })
+        .collect();
+    generations.sort_by_key(|generation| generation.number);
+    generations
+}
+
+/// Поколения, которые можно удалить: кроме активного и `keep` последних.
+pub fn old_generations(mut generations: Vec<Generation>, keep: usize) -> Vec<Generation> {
+    generations.sort_by_key(|generation| generation.number);
+    let removable = generations.len().saturating_sub(keep);
+    generations.truncate(removable);
+    generations.retain(|generation| !generation.current);
+    generations
+}
+
+fn parse_generation_link(name: &str, base: &str) -> Option<u32> {
+    name.strip_prefix(base)?
+        .strip_prefix('-')?
+        .strip_suffix("-link")?
+        .parse()
+        .ok()
+}
+
+/// Идентификатор элемента для поколения.
+pub fn generation_id(manager: StoreManager, profile: &Path, number: u32) -> String {
+    format!(
+        "{}:generation:{}:{number}",
+        manager.name(),
+        profile.display()
+    )
+}
+
+/// Разбирает идентификатор из [`generation_id`].
+pub fn parse_generation_id(id: &str) -> Option<(StoreManager, PathBuf, u32)> {
+    let (manager, rest) = id.split_once(":generation:")?;
+    let (profile, number) = rest.rsplit_once(':')?;
+    Some((
+        StoreManager::from_name(manager)?,
+        PathBuf::from(profile),
+        number.parse().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generation(number: u32, current: bool) -> Generation {
+        Generation {
+            profile: PathBuf::from("/nix/var/nix/profiles/system"),
+            number,
+            link: PathBuf::from(format!("/nix/var/nix/profiles/system-{number}-link")),
+            current,
+            created: None,
+        }
+    }
+
+    #[test]
+    fn test_old_generations_keep_newest_and_current() {
+        assert_eq!(parse_generation_link("system-42-link", "system"), Some(42));
+        assert_eq!(parse_generation_link("system-profiles", "system"), None);
+        assert_eq!(parse_generation_link("profile-3-link", "system"), None);
+
+        let generations = vec![
+            generation(10, false),
+            generation(11, true),
+            generation(12, false),
+            generation(13, false),
+            generation(14, false),
+        ];
+        let old: Vec<u32> = old_generations(generations.clone(), 2)
+            .iter()
+            .map(|generation| generation.number)
+            .collect();
+        assert_eq!(old, vec![10, 12]);
+        assert_eq!(old_generations(generations.clone(), 0).len(), 4);
+        assert!(old_generations(generations, 10).is_empty());
+
+        let id = generation_id(
+            StoreManager::Nix,
+            Path::new("/nix/var/nix/profiles/system"),
+            12,
+        );
+        assert_eq!(
+            parse_generation_id(&id),
+            Some((
+                StoreManager::Nix,
+                PathBuf::from("/nix/var/nix/profiles/system"),
+                12
+            ))
+        );
+        assert!(is_system_profile(Path::new("/var/guix/profiles/system")));
+    }
+}
@@ -0,0 +1,357 @@
+//! Оценка времени последнего использования приложений.
+//!
+//! Точного журнала запусков в Linux нет, поэтому используются косвенные
+//! признаки: время доступа к исполняемым файлам, записи о запусках через
+//! desktop-файлы (`~/.local/share/gnome-shell/application_state`), список
+//! недавних документов `~/.local/share/recently-used.xbel` и время изменения
+//! каталогов данных Flatpak (`~/.var/app`) и Snap (`~/snap`).
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Самое позднее время доступа к путям (ссылки разыменовываются).
+pub fn last_access<'a>(paths: impl IntoIterator<Item = &'a Path>) -> Option<SystemTime> {
+    paths
+        .into_iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .filter_map(|metadata| metadata.accessed().ok())
+        .max()
+}
+
+/// Текст для описания элемента: «last used 12 days ago».
+pub fn describe_last_used(last_used: Option<SystemTime>) -> String {
+    let Some(time) = last_used else {
+        return "no recorded use".to_string();
+    };
+    let days = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs()
+        / 86_400;
+    match days {
+        0 => "last used today".to_string(),
+        1 => "last used yesterday".to_string(),
+        days => format!("last used {days} days ago"),
+    }
+}
+
+/// Оценка последнего использования по всем доступным признакам.
+#[derive(Debug, Default)]
+pub struct UsageAnalyzer {
+    home: Option<PathBuf>,
+    launches: LaunchRecords,
+    /// Имя приложения или программы → последнее открытие документа.
+    recent: HashMap<String, SystemTime>,
+}
+
+impl UsageAnalyzer {
+    pub fn load() -> Self {
+        let home = std::env::var("HOME").map(PathBuf::from).ok();
+        let recent = home
+            .as_ref()
+            .and_then(|home| fs::read_to_string(home.join(".local/share/recently-used.xbel")).ok())
+            .map(|content| parse_recently_used(&content))
+            .unwrap_or_default();
+        Self {
+            home,
+            launches: LaunchRecords::load(),
+            recent,
+        }
+    }
+
+    /// Последнее использование программы по её исполняемым файлам.
+    pub fn executables(&self, paths: &[PathBuf]) -> Option<SystemTime> {
+        let accessed = last_access(paths.iter().map(PathBuf::as_path));
+        let launched = paths
+            .iter()
+            .filter_map(|path| self.launches.for_executable(path))
+            .max();
+        let opened = paths
+            .iter()
+            .filter_map(|path| path.file_name())
+            .filter_map(|name| self.recent(&name.to_string_lossy()))
+            .max();
+        accessed.max(launched).max(opened)
+    }
+
+    /// Последнее использование приложения Flatpak (`org.gimp.GIMP`).
+    pub fn flatpak_app(&self, id: &str) -> Option<SystemTime> {
+        let binaries: Vec<PathBuf> = self
+            .flatpak_installations()
+            .iter()
+            .map(|root| root.join("app").join(id).join("current/active/files/bin"))
+            .flat_map(|bin_dir| list_dir(&bin_dir))
+            .collect();
+        let short_name = id.rsplit('.').next().unwrap_or(id);
+        [
+            self.launches.for_desktop_id(&format!("{id}.desktop")),
+            self.home
+                .as_ref()
+                .and_then(|home| last_modified(&home.join(".var/app").join(id))),
+            self.recent(id),
+            self.recent(short_name),
+            self.executables(&binaries),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    /// Последнее использование snap-пакета.
+    pub fn snap_app(&self, name: &str) -> Option<SystemTime> {
+        [
+            self.launches
+                .for_desktop_id(&format!("{name}_{name}.desktop")),
+            self.home
+                .as_ref()
+                .and_then(|home| last_modified(&home.join("snap").join(name))),
+            self.recent(name),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    fn recent(&self, name: &str) -> Option<SystemTime> {
+        self.recent.get(&name.to_lowercase()).copied()
+    }
+
+    fn flatpak_installations(&self) -> Vec<PathBuf> {
+        let mut roots = vec![PathBuf::from("/var/lib/flatpak")];
+        roots.extend(
+            self.home
+                .as_ref()
+                .map(|home| home.join(".local/share/flatpak")),
+        );
+        roots
+    }
+}
+
+/// Самое позднее время изменения каталога и его непосредственных
+/// подкаталогов (`config`, `data`, `cache` и т.п.).
+fn last_modified(dir: &Path) -> Option<SystemTime> {
+    std::iter::once(dir.to_path_buf())
+        .chain(list_dir(dir))
+        .filter_map(|path| fs::metadata(path).ok())
+        .filter_map(|metadata| metadata.modified().ok())
+        .max()
+}
+
+fn list_dir(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+/// Запуски приложений через desktop-файлы.
+#[derive(Debug, Default)]
+pub struct LaunchRecords {
+    /// Идентификатор desktop-файла → время последнего запуска.
+    launched: HashMap<String, SystemTime>,
+    /// Идентификатор desktop-файла → исполняемый файл из `Exec`.
+    executables: Vec<(String, String)>,
+}
+
+impl LaunchRecords {
+    /// Читает записи о запусках и desktop-файлы текущего пользователя и системы.
+    pub fn load() -> Self {
+        let Ok(home) = std::env::var("HOME").map(PathBuf::from) else {
+            return Self::default();
+        };
+        let launched = fs::read_to_string(home.join(".local/share/gnome-shell/application_state"))
+            .map(|content| parse_application_state(&content))
+            .unwrap_or_default();
+
+        let mut executables = Vec::new();
+        for dir in [
+            home.join(".local/share/applications"),
+            PathBuf::from("/usr/share/applications"),
+            PathBuf::from("/usr/local/share/applications"),
+        ] {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !name.ends_with(".desktop") {
+                    continue;
+                }
+                if let Some(exec) = fs::read_to_string(entry.path())
+                    .ok()
+                    .and_then(|content| desktop_executable(&content))
+                {
+                    executables.push((name, exec));
+                }
+            }
+        }
+
+        Self {
+            launched,
+            executables,
+        }
+    }
+
+    /// Последний запуск по идентификатору desktop-файла (`org.gnome.Maps.desktop`).
+    pub fn for_desktop_id(&self, id: &str) -> Option<SystemTime> {
+        self.launched.get(id).copied()
+    }
+
+    /// Последний запуск через desktop-файлы, которые запускают `executable`.
+    ///
+    /// `Exec` сравнивается с полным путём и с именем файла.
+    pub fn for_executable(&self, executable: &Path) -> Option<SystemTime> {
+        let full = executable.to_string_lossy();
+        let file_name = executable.file_name()?.to_string_lossy();
+        self.executables
+            .iter()
+            .filter(|(_, exec)| *exec == full || *exec == file_name)
+            .filter_map(|(id, _)| self.for_desktop_id(id))
+            .max()
+    }
+}
+
+/// Время запуска приложений из `application_state` GNOME Shell:
+/// `<application id="firefox.desktop" score="12" last-seen="1700000000"/>`.
+fn parse_application_state(content: &str) -> HashMap<String, SystemTime> {
+    let mut launched = HashMap::new();
+    for element in content.split("<application ").skip(1) {
+        let (Some(id), Some(seconds)) = (
+            xml_attribute(element, "id"),
+            xml_attribute(element, "last-seen").and_then(|value| value.parse::<u64>().ok()),
+        ) else {
+            continue;
+        };
+        launched.insert(id.to_string(), UNIX_EPOCH + Duration::from_secs(seconds));
+    }
+    launched
+}
+
+/// Приложения из `recently-used.xbel`:
+/// `<bookmark:application name="Krita" exec="&apos;krita %u&apos;" modified="…"/>`.
+///
+/// Ключи — имя приложения и программа из `exec` в нижнем регистре; для
+/// `flatpak run` программой считается идентификатор приложения.
+fn parse_recently_used(content: &str) -> HashMap<String, SystemTime> {
+    let mut recent: HashMap<String, SystemTime> = HashMap::new();
+    for element in content.split("<bookmark:application ").skip(1) {
+        let Some(modified) = xml_attribute(element, "modified")
+            .and_then(|value| chrono::DateTime::parse_from_rfc3339(value).ok())
+            .map(SystemTime::from)
+        else {
+            continue;
+        };
+        let exec = xml_attribute(element, "exec")
+            .map(|exec| exec.replace("&apos;", "").replace("&quot;", ""))
+            .and_then(|exec| recent_exec_key(&exec));
+        let keys = xml_attribute(element, "name")
+            .map(str::to_string)
+            .into_iter()
+            .chain(exec);
+        for key in keys {
+            let entry = recent.entry(key.to_lowercase()).or_insert(modified);
+            *entry = (*entry).max(modified);
+        }
+    }
+    recent
+}
+
+fn recent_exec_key(exec: &str) -> Option<String> {
+    let tokens: Vec<&str> = exec
+        .split_whitespace()
+        .map(|token| token.trim_matches(['\'', '"']))
+        .filter(|token| !token.starts_with('%'))
+        .collect();
+    let program = Path::new(tokens.first()?)
+        .file_name()?
+        .to_string_lossy()
+        .to_string();
+    if program == "flatpak" {
+        return tokens
+            .iter()
+            .rev()
+            .find(|token| !token.starts_with('-') && **token != "run")
+            .map(|id| id.to_string());
+    }
+    Some(program)
+}
+
+fn xml_attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let start = element.find(&format!("{name}=\""))? + name.len() + 2;
+    let rest = &element[start..];
+    Some(&rest[..rest.find('"')?])
+}
+
+/// Исполняемый файл из первой строки `Exec=` (без аргументов и `env VAR=…`).
+fn desktop_executable(content: &str) -> Option<String> {
+    let exec = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Exec="))?;
+    let mut tokens = exec.split_whitespace().map(|token| token.trim_matches('"'));
+    let mut program = tokens.next()?;
+    if program == "env" {
+        program = tokens.find(|token| !token.contains('='))?;
+    }
+    Some(program.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_records_match_executables() {
+        let state = r#"<?xml version="1.0"?>
+<application-state>
+  <context id="">
+    <application id="firefox.desktop" score="40" last-seen="1700000000"/>
+    <application id="appimagekit-krita.desktop" score="3" last-seen="1600000000"/>
+  </context>
+</application-state>
+"#;
+        let records = LaunchRecords {
+            launched: parse_application_state(state),
+            executables: vec![
+                (
+                    "firefox.desktop".to_string(),
+                    desktop_executable("[Desktop Entry]\nExec=firefox %u\n").unwrap(),
+                ),
+                (
+                    "appimagekit-krita.desktop".to_string(),
+                    desktop_executable(
+                        "[Desktop Entry]\nExec=env DESKTOPINTEGRATION=1 \"/home/u/Applications/krita.AppImage\" %F\n",
+                    )
+                    .unwrap(),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            records.for_executable(Path::new("/usr/bin/firefox")),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(
+            records.for_executable(Path::new("/home/u/Applications/krita.AppImage")),
+            Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000))
+        );
+        assert_eq!(records.for_executable(Path::new("/usr/bin/vim")), None);
+
+        let xbel = r#"<bookmark href="file:///home/u/a.kra" modified="2024-03-01T10:00:00Z">
+  <info><metadata owner="http://freedesktop.org"><bookmark:applications>
+    <bookmark:application name="Krita" exec="&apos;krita %u&apos;" modified="2024-03-01T10:00:00Z" count="2"/>
+    <bookmark:application name="GNU Image Manipulation Program" exec="&apos;flatpak run --branch=stable org.gimp.GIMP %u&apos;" modified="2023-11-14T22:13:20Z" count="1"/>
+  </bookmark:applications></metadata></info>
+</bookmark>"#;
+        let recent = parse_recently_used(xbel);
+        assert_eq!(
+            recent["krita"],
+            UNIX_EPOCH + Duration::from_secs(1_709_287_200)
+        );
+        assert_eq!(
+            recent["org.gimp.gimp"],
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+        assert!(recent.contains_key("gnu image manipulation program"));
+    }
+}
@@ -0,0 +1,387 @@
+//! Пакеты, установленные пользователем без системного менеджера:
+//! Homebrew (Linuxbrew), pipx, `cargo install`, глобальные пакеты npm и
+//! AppImage в `~/Applications`.
+//!
+//! Для каждого пакета считается размер и оценивается время последнего
+//! использования (см. [`crate::system::usage`]).
+
+use crate::error::Result;
+use crate::system::detection::command_exists;
+use crate::system::package_manager::{command_failed, run_command};
+use crate::system::usage::UsageAnalyzer;
+use crate::utils::disk_size::{SizeCounter, allocated_size};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Откуда установлен пакет.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserPackageKind {
+    /// Формула Homebrew.
+    Brew,
+    /// Старые версии формулы Homebrew (удаляет `brew cleanup`).
+    BrewOldKegs,
+    Pipx,
+    Cargo,
+    Npm,
+    AppImage,
+}
+
+impl UserPackageKind {
+    /// Имя источника элемента очистки.
+    pub fn source(self) -> &'static str {
+        match self {
+            Self::Brew | Self::BrewOldKegs => "brew",
+            Self::Pipx => "pipx",
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::AppImage => "appimage",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Brew => "Homebrew formula",
+            Self::BrewOldKegs => "Old Homebrew versions",
+            Self::Pipx => "pipx application",
+            Self::Cargo => "cargo-installed binary",
+            Self::Npm => "Global npm package",
+            Self::AppImage => "AppImage",
+        }
+    }
+}
+
+/// Пакет пользователя.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserPackage {
+    pub kind: UserPackageKind,
+    /// Имя, которое принимает команда удаления.
+    pub name: String,
+    /// Основной каталог или файл пакета.
+    pub path: PathBuf,
+    pub size: u64,
+    /// Последнее использование, если его удалось оценить.
+    pub last_used: Option<SystemTime>,
+}
+
+/// Пакеты из всех найденных источников.
+pub fn inventory() -> Vec<UserPackage> {
+    let Ok(home) = std::env::var("HOME").map(PathBuf::from) else {
+        return Vec::new();
+    };
+    let usage = UsageAnalyzer::load();
+    let mut packages = Vec::new();
+    packages.extend(brew_packages(&home, &usage));
+    packages.extend(pipx_packages(&home, &usage));
+    packages.extend(cargo_packages(&home, &usage));
+    packages.extend(npm_packages(&home, &usage));
+    packages.extend(appimages(&home, &usage));
+    packages
+}
+
+/// Удаляет пакеты командой их менеджера.
+///
+/// AppImage удаляются как обычные файлы и сюда не передаются.
+pub fn remove(kind: UserPackageKind, names: &[String], dry_run: bool) -> Result<()> {
+    let (program, args): (&str, &[&str]) = match kind {
+        UserPackageKind::Brew => ("brew", &["uninstall"]),
+        UserPackageKind::BrewOldKegs => ("brew", &["cleanup"]),
+        UserPackageKind::Pipx => ("pipx", &["uninstall"]),
+        UserPackageKind::Cargo => ("cargo", &["uninstall"]),
+        UserPackageKind::Npm => ("npm", &["uninstall", "--global"]),
+        UserPackageKind::AppImage => return Ok(()),
+    };
+    for name in names {
+        if dry_run {
+            log::info!("[DRY RUN] {program} {} {name}", args.join(" "));
+            continue;
+        }
+        let mut full_args = args.to_vec();
+        full_args.push(name);
+        let output = run_command(program, &full_args)?;
+        if !output.status.success() {
+            return Err(command_failed(program, &output));
+        }
+    }
+    Ok(())
+}
+
+/// Формулы из `Cellar`: текущая версия — та, на которую указывает `opt/<формула>`.
+fn brew_packages(home: &Path, usage: &UsageAnalyzer) -> Vec<UserPackage> {
+    let Some(prefix) = std::env::var("HOMEBREW_PREFIX")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain([
+            PathBuf::from("/home/linuxbrew/.linuxbrew"),
+            home.join(".linuxbrew"),
+        ])
+        .find(|prefix| prefix.join("Cellar").is_dir())
+    else {
+        return Vec::new();
+    };
+    let Ok(formulas) = fs::read_dir(prefix.join("Cellar")) else {
+        return Vec::new();
+    };
+
+    let mut packages = Vec::new();
+    for formula in formulas.flatten() {
+        let name = formula.file_name().to_string_lossy().to_string();
+        let current = fs::read_link(prefix.join("opt").join(&name))
+            .ok()
+            .and_then(|target| target.file_name().map(|version| version.to_os_string()));
+        let Ok(kegs) = fs::read_dir(formula.path()) else {
+            continue;
+        };
+        let kegs: Vec<PathBuf> = kegs.flatten().map(|keg| keg.path()).collect();
+        let (current_kegs, old_kegs) = split_current_keg(kegs, current.as_deref());
+
+        for keg in current_kegs {
+            packages.push(UserPackage {
+                kind: UserPackageKind::Brew,
+                name: name.clone(),
+                size: allocated_size(&keg),
+                last_used: binaries_last_used(&keg.join("bin"), usage),
+                path: keg,
+            });
+        }
+        if !old_kegs.is_empty() {
+            packages.push(UserPackage {
+                kind: UserPackageKind::BrewOldKegs,
+                name: name.clone(),
+                path: formula.path(),
+                size: SizeCounter::new().measure_all(&old_kegs).allocated,
+                last_used: None,
+            });
+        }
+    }
+    packages
+}
+
+/// Делит версии формулы на текущую и старые. Если `opt`-ссылки нет,
+/// текущей считается самая новая по имени.
+fn split_current_keg(
+    mut kegs: Vec<PathBuf>,
+    current: Option<&std::ffi::OsStr>,
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    kegs.sort();
+    let position = current
+        .and_then(|current| kegs.iter().position(|keg| keg.file_name() == Some(current)))
+        .or_else(|| kegs.len().checked_sub(1));
+    match position {
+        Some(position) => {
+            let current = kegs.remove(position);
+            (vec![current], kegs)
+        }
+        None => (Vec::new(), kegs),
+    }
+}
+
+fn pipx_packages(home: &Path, usage: &UsageAnalyzer) -> Vec<UserPackage> {
+    let Some(venvs) = std::env::var("PIPX_HOME")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain([home.join(".local/share/pipx"), home.join(".local/pipx")])
+        .map(|pipx_home| pipx_home.join("venvs"))
+        .find(|venvs| venvs.is_dir())
+    else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(venvs) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|venv| {
+            let path = venv.path();
+            UserPackage {
+                kind: UserPackageKind::Pipx,
+                name: venv.file_name().to_string_lossy().to_string(),
+                size: allocated_size(&path),
+                last_used: binaries_last_used(&path.join("bin"), usage),
+                path,
+            }
+        })
+        .collect()
+}
+
+fn cargo_packages(home: &Path, usage: &UsageAnalyzer) -> Vec<UserPackage> {
+    let cargo_home = std::env::var("CARGO_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".cargo"));
+    let Ok(content) = fs::read_to_string(cargo_home.join(".crates2.json")) else {
+        return Vec::new();
+    };
+    let bin_dir = cargo_home.join("bin");
+    parse_crates2(&content)
+        .into_iter()
+        .filter_map(|(name, bins)| {
+            let bins: Vec<PathBuf> = bins.iter().map(|bin| bin_dir.join(bin)).collect();
+            let path = bins.first()?.clone();
+            Some(UserPackage {
+                kind: UserPackageKind::Cargo,
+                name,
+                size: SizeCounter::new().measure_all(&bins).allocated,
+                last_used: usage.executables(&bins),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Крейты и их бинарники из `~/.cargo/.crates2.json`.
+fn parse_crates2(content: &str) -> Vec<(String, Vec<String>)> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let Some(installs) = value
+        .get("installs")
+        .and_then(|installs| installs.as_object())
+    else {
+        return Vec::new();
+    };
+    installs
+        .iter()
+        .filter_map(|(key, install)| {
+            // Ключ: `ripgrep 14.1.0 (registry+https://...)`.
+            let name = key.split_whitespace().next()?.to_string();
+            let bins = install
+                .get("bins")?
+                .as_array()?
+                .iter()
+                .filter_map(|bin| bin.as_str().map(String::from))
+                .collect();
+            Some((name, bins))
+        })
+        .collect()
+}
+
+/// Глобальные пакеты npm, если префикс находится в домашнем каталоге
+/// (системный префикс требует root и управляется пакетным менеджером).
+fn npm_packages(home: &Path, usage: &UsageAnalyzer) -> Vec<UserPackage> {
+    if !command_exists("npm") {
+        return Vec::new();
+    }
+    let Ok(output) = run_command("npm", &["prefix", "--global"]) else {
+        return Vec::new();
+    };
+    let prefix = PathBuf::from(output.stdout.trim());
+    if !output.status.success() || !prefix.starts_with(home) {
+        return Vec::new();
+    }
+    let modules = prefix.join("lib/node_modules");
+    let bins: Vec<PathBuf> = fs::read_dir(prefix.join("bin"))
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+
+    let mut packages = Vec::new();
+    for (name, path) in npm_module_dirs(&modules) {
+        // Ссылки из `bin` указывают на файлы внутри пакета.
+        let package_bins: Vec<PathBuf> = bins
+            .iter()
+            .filter(|bin| {
+                fs::canonicalize(bin).is_ok_and(|target| {
+                    fs::canonicalize(&path).is_ok_and(|package| target.starts_with(package))
+                })
+            })
+            .cloned()
+            .collect();
+        packages.push(UserPackage {
+            kind: UserPackageKind::Npm,
+            size: allocated_size(&path),
+            last_used: usage.executables(&package_bins),
+            name,
+            path,
+        });
+    }
+    packages
+}
+
+/// Каталоги пакетов в `node_modules`, включая `@scope/name`.
+fn npm_module_dirs(modules: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(modules) else {
+        return Vec::new();
+    };
+    let mut dirs = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        if name.starts_with('@') {
+            for (scoped, path) in npm_module_dirs(&entry.path()) {
+                dirs.push((format!("{name}/{scoped}"), path));
+            }
+        } else {
+            dirs.push((name, entry.path()));
+        }
+    }
+    dirs
+}
+
+fn appimages(home: &Path, usage: &UsageAnalyzer) -> Vec<UserPackage> {
+    let Ok(entries) = fs::read_dir(home.join("Applications")) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("appimage"))
+                && path.is_file()
+        })
+        .map(|path| UserPackage {
+            kind: UserPackageKind::AppImage,
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size: allocated_size(&path),
+            last_used: usage.executables(std::slice::from_ref(&path)),
+            path,
+        })
+        .collect()
+}
+
+fn binaries_last_used(bin_dir: &Path, usage: &UsageAnalyzer) -> Option<SystemTime> {
+    let bins: Vec<PathBuf> = fs::read_dir(bin_dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    usage.executables(&bins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_user_package_metadata() {
+        let crates2 = r#"{"installs":{
+            "ripgrep 14.1.0 (registry+https://github.com/rust-lang/crates.io-index)":{"bins":["rg"],"profile":"release"},
+            "cargo-edit 0.12.2 (registry+https://github.com/rust-lang/crates.io-index)":{"bins":["cargo-add","cargo-rm"]}
+        }}"#;
+        let mut crates = parse_crates2(crates2);
+        crates.sort();
+        assert_eq!(
+            crates,
+            vec![
+                (
+                    "cargo-edit".to_string(),
+                    vec!["cargo-add".to_string(), "cargo-rm".to_string()]
+                ),
+                ("ripgrep".to_string(), vec!["rg".to_string()]),
+            ]
+        );
+
+        let kegs = vec![
+            PathBuf::from("/brew/Cellar/git/2.43.0"),
+            PathBuf::from("/brew/Cellar/git/2.42.1"),
+            PathBuf::from("/brew/Cellar/git/2.44.0"),
+        ];
+        let (current, old) = split_current_keg(kegs.clone(), Some("2.43.0".as_ref()));
+        assert_eq!(current, vec![PathBuf::from("/brew/Cellar/git/2.43.0")]);
+        assert_eq!(old.len(), 2);
+        let (current, _) = split_current_keg(kegs, None);
+        assert_eq!(current, vec![PathBuf::from("/brew/Cellar/git/2.44.0")]);
+    }
+}
@@ -0,0 +1,280 @@
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::package_manager::{PackageManager, RemovalPlan, command_failed, run_command};
+use std::path::Path;
+
+/// Кэш скачанных пакетов xbps.
+pub const PACKAGE_CACHE_DIR: &str = "/var/cache/xbps";
+
+pub struct XbpsManager;
+
+impl Default for XbpsManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XbpsManager {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl PackageManager for XbpsManager {
+    fn name(&self) -> &str {
+        "xbps"
+    }
+
+    fn version(&self) -> Result<String> {
+        let output = run_command("xbps-query", &["-V"])?;
+        if !output.status.success() {
+            return Err(command_failed("xbps-query", &output));
+        }
+        Ok(first_line(&output.stdout))
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let output = run_command("xbps-query", &["-l"])?;
+        if !output.status.success() {
+            return Err(command_failed("xbps-query", &output));
+        }
+        Ok(output
+            .stdout
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .map(|pkgver| package_name(pkgver).to_string())
+            .collect())
+    }
+
+    fn check_dependencies(&self, package: &str) -> Result<Vec<String>> {
+        let output = run_command("xbps-query", &["-X", package])?;
+        if !output.status.success() {
+            return Err(command_failed("xbps-query", &output));
+        }
+        Ok(output
+            .stdout
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|pkgver| package_name(pkgver).to_string())
+            .collect())
+    }
+
+    fn remove_packages(&self, packages: &[String], dry_run: bool) -> Result<()> {
+        if packages.is_empty() {
+            return Ok(());
+        }
+
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
+
+        if dry_run {
+            log::info!("[DRY RUN] xbps-remove {:?}", packages);
+            return Ok(());
+        }
+
+        let mut args = vec!["-y"];
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("xbps-remove", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("xbps-remove", &output));
+        }
+
+        Ok(())
+    }
+
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        let mut args = vec!["-n"];
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("xbps-remove", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("xbps-remove", &output));
+        }
+
+        let transaction = parse_xbps_transaction(&output.stdout);
+        let mut plan = RemovalPlan::new("xbps", packages);
+        plan.removed = transaction.iter().map(|(name, _)| name.clone()).collect();
+        plan.freed_bytes = Some(transaction.iter().map(|(_, size)| size).sum());
+        if let Ok(held) = run_command("xbps-query", &["-H"]) {
+            let held: Vec<&str> = held
+                .stdout
+                .lines()
+                .map(|line| package_name(line.trim()))
+                .collect();
+            plan.held = plan
+                .removed
+                .iter()
+                .filter(|package| held.contains(&package.as_str()))
+                .cloned()
+                .collect();
+        }
+        Ok(plan)
+    }
+}
+
+pub fn list_installed() -> Result<Vec<String>> {
+    XbpsManager::new().list_installed()
+}
+
+pub fn remove_packages(packages: &[String], dry_run: bool) -> Result<()> {
+    XbpsManager::new().remove_packages(packages, dry_run)
+}
+
+/// Осиротевшие пакеты, которые удалил бы `xbps-remove -o`.
+pub fn orphan_packages() -> Result<Vec<String>> {
+    let output = run_command("xbps-remove", &["-n", "-o"])?;
+    if !output.status.success() {
+        return Err(command_failed("xbps-remove", &output));
+    }
+    Ok(parse_xbps_transaction(&output.stdout)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Размер устаревших пакетов в кэше, которые удалил бы `xbps-remove -O`.
+pub fn obsolete_cache_size() -> u64 {
+    let Ok(output) = run_command("xbps-remove", &["-n", "-O"]) else {
+        return 0;
+    };
+    parse_obsolete_cache(&output.stdout)
+        .into_iter()
+        .map(|file| Path::new(PACKAGE_CACHE_DIR).join(file))
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Удаляет устаревшие пакеты из кэша (`xbps-remove -O`).
+pub fn clean_cache(dry_run: bool) -> Result<()> {
+    if dry_run {
+        log::info!("[DRY RUN] xbps-remove -O ({PACKAGE_CACHE_DIR})");
+        return Ok(());
+    }
+    let output = run_command("xbps-remove", &["-y", "-O"])?;
+    if !output.status.success() {
+        return Err(command_failed("xbps-remove", &output));
+    }
+    Ok(())
+}
+
+/// Версии ядер, которые `vkpurge` может удалить (загруженное ядро он не
+/// предлагает).
+pub fn removable_kernels() -> Result<Vec<String>> {
+    let output = run_command("vkpurge", &["list"])?;
+    if !output.status.success() {
+        return Err(command_failed("vkpurge", &output));
+    }
+    Ok(split_lines(&output.stdout))
+}
+
+/// Удаляет файлы старых ядер в `/boot` и их модули (`vkpurge rm`).
+pub fn purge_kernels(versions: &[String], dry_run: bool) -> Result<()> {
+    if versions.is_empty() {
+        return Ok(());
+    }
+    if dry_run {
+        log::info!("[DRY RUN] vkpurge rm {:?}", versions);
+        return Ok(());
+    }
+    let mut args = vec!["rm"];
+    args.extend(versions.iter().map(String::as_str));
+    let output = run_command("vkpurge", &args)?;
+    if !output.status.success() {
+        return Err(command_failed("vkpurge", &output));
+    }
+    Ok(())
+}
+
+/// Имя пакета из `имя-версия_ревизия`.
+pub fn package_name(pkgver: &str) -> &str {
+    match pkgver.rsplit_once('-') {
+        Some((name, version))
+            if version.contains('_') && version.starts_with(|ch: char| ch.is_ascii_digit()) =>
+        {
+            name
+        }
+        _ => pkgver,
+    }
+}
+
+/// Пакеты и установленный размер из вывода `xbps-remove -n`:
+/// `pkgver действие архитектура репозиторий размер`.
+fn parse_xbps_transaction(output: &str) -> Vec<(String, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(1) != Some(&"remove") {
+                return None;
+            }
+            let size = fields
+                .get(4)
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(0);
+            Some((package_name(fields[0]).to_string(), size))
+        })
+        .collect()
+}
+
+/// Файлы из строк `Removed foo-1.0_1.x86_64.xbps from cachedir (obsolete)`.
+fn parse_obsolete_cache(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Removed "))
+        .filter_map(|rest| rest.split_once(" from cachedir"))
+        .map(|(file, _)| file.to_string())
+        .collect()
+}
+
+fn first_line(output: &str) -> String {
+    output.lines().next().unwrap_or_default().trim().to_string()
+}
+
+fn split_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xbps_output() {
+        let transaction = "\
+Name                Action    Version           New version            Download size
+libfoo-1.2_1 remove x86_64 /var/db/xbps 524288
+python3-bar-0.9_2 remove noarch /var/db/xbps 8192
+
+Size freed on disk:        520 KB
+";
+        assert_eq!(
+            parse_xbps_transaction(transaction),
+            vec![
+                ("libfoo".to_string(), 524288),
+                ("python3-bar".to_string(), 8192)
+            ]
+        );
+
+        let cache = "\
+Removed firefox-118.0_1.x86_64.xbps from cachedir (obsolete)
+Removed firefox-118.0_1.x86_64.xbps.sig2 from cachedir (obsolete)
+";
+        assert_eq!(
+            parse_obsolete_cache(cache),
+            vec![
+                "firefox-118.0_1.x86_64.xbps",
+                "firefox-118.0_1.x86_64.xbps.sig2"
+            ]
+        );
+        assert_eq!(
+            package_name("linux-firmware-network-20231030_1"),
+            "linux-firmware-network"
+        );
+        assert_eq!(package_name("base-system"), "base-system");
+    }
+}
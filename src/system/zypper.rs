@@ -0,0 +1,474 @@
+use crate::error::Result;
+use crate::system::dependency_graph::DependencyGraph;
+use crate::system::detection::is_transactional_update;
+use crate::system::package_manager::{PackageManager, RemovalPlan, command_failed, run_command};
+use crate::system::rpm::is_no_requires_message;
+use crate::utils::size_format::parse_size_string;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Кэш скачанных пакетов zypper.
+pub const PACKAGE_CACHE_DIR: &str = "/var/cache/zypp/packages";
+
+/// Значение `multiversion.kernels`, если zypp.conf его не задаёт.
+const DEFAULT_MULTIVERSION_KERNELS: &str = "latest,latest-1,running";
+
+/// Расположение zypp.conf: в `/etc` администратор переопределяет
+/// поставляемый в `/usr/etc` файл.
+const ZYPP_CONF_PATHS: &[&str] = &["/etc/zypp/zypp.conf", "/usr/etc/zypp/zypp.conf"];
+
+pub struct ZypperManager;
+
+impl Default for ZypperManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ZypperManager {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl PackageManager for ZypperManager {
+    fn name(&self) -> &str {
+        "zypper"
+    }
+
+    fn version(&self) -> Result<String> {
+        let output = run_command("zypper", &["--version"])?;
+        if !output.status.success() {
+            return Err(command_failed("zypper", &output));
+        }
+        Ok(first_line(&output.stdout))
+    }
+
+    fn list_installed(&self) -> Result<Vec<String>> {
+        let output = run_command("rpm", &["-qa", "--qf", "%{NAME}\n"])?;
+        if !output.status.success() {
+            return Err(command_failed("rpm", &output));
+        }
+        Ok(split_lines(&output.stdout))
+    }
+
+    fn check_dependencies(&self, package: &str) -> Result<Vec<String>> {
+        let output = run_command("rpm", &["-q", "--whatrequires", package])?;
+        if !output.status.success() {
+            if is_no_requires_message(&output.stderr, package)
+                || is_no_requires_message(&output.stdout, package)
+            {
+                return Ok(Vec::new());
+            }
+            return Err(command_failed("rpm", &output));
+        }
+        Ok(split_lines(&output.stdout))
+    }
+
+    fn remove_packages(&self, packages: &[String], dry_run: bool) -> Result<()> {
+        if packages.is_empty() {
+            return Ok(());
+        }
+
+        DependencyGraph::load(self.name())?.check_removal(packages)?;
+
+        // На системах с read-only корнем изменения попадают в новый снимок,
+        // который станет активным после перезагрузки.
+        if is_transactional_update() {
+            if dry_run {
+                log::info!("[DRY RUN] transactional-update pkg remove {:?}", packages);
+                return Ok(());
+            }
+            let mut args = vec!["--non-interactive", "pkg", "remove"];
+            args.extend(packages.iter().map(String::as_str));
+            let output = run_command("transactional-update", &args)?;
+            if !output.status.success() {
+                return Err(command_failed("transactional-update", &output));
+            }
+            return Ok(());
+        }
+
+        let mut args = vec!["--non-interactive", "remove"];
+        if dry_run {
+            args.push("--dry-run");
+        }
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("zypper", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("zypper", &output));
+        }
+
+        Ok(())
+    }
+
+    fn simulate_removal(&self, packages: &[String]) -> Result<RemovalPlan> {
+        let mut args = vec!["--non-interactive", "remove", "--dry-run"];
+        args.extend(packages.iter().map(String::as_str));
+        let output = run_command("zypper", &args)?;
+        if !output.status.success() {
+            return Err(command_failed("zypper", &output));
+        }
+
+        let mut plan = parse_zypper_simulation(&output.stdout, packages);
+        if let Ok(locks) = run_command("zypper", &["--quiet", "locks"]) {
+            let locked = parse_table_column(&locks.stdout, "Name");
+            plan.held = plan
+                .removed
+                .iter()
+                .filter(|package| locked.contains(package))
+                .cloned()
+                .collect();
+        }
+        Ok(plan)
+    }
+}
+
+/// Установленный пакет ядра с поддержкой нескольких версий.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZypperKernel {
+    /// Имя пакета (`kernel-default`).
+    pub name: String,
+    /// Версия и релиз (`6.5.9-1.1`).
+    pub edition: String,
+    /// Время установки (секунды Unix).
+    pub install_time: i64,
+}
+
+/// Элемент `multiversion.kernels`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KernelSpec {
+    /// `latest`, `latest-N`: N-я версия от самой новой.
+    Latest(usize),
+    /// `oldest`, `oldest+N`: N-я версия от самой старой.
+    Oldest(usize),
+    /// `running`: загруженное ядро.
+    Running,
+    /// Конкретная версия.
+    Version(String),
+}
+
+pub fn list_installed() -> Result<Vec<String>> {
+    ZypperManager::new().list_installed()
+}
+
+pub fn remove_packages(packages: &[String], dry_run: bool) -> Result<()> {
+    ZypperManager::new().remove_packages(packages, dry_run)
+}
+
+/// Пакеты, которые больше не нужны ни одному пакету (`zypper packages --unneeded`).
+pub fn unneeded_packages() -> Result<Vec<String>> {
+    let output = run_command(
+        "zypper",
+        &["--quiet", "--no-refresh", "packages", "--unneeded"],
+    )?;
+    if !output.status.success() {
+        return Err(command_failed("zypper", &output));
+    }
+    Ok(parse_table_column(&output.stdout, "Name"))
+}
+
+/// Очищает кэш скачанных пакетов (`zypper clean`).
+pub fn clean_cache(dry_run: bool) -> Result<()> {
+    if dry_run {
+        log::info!("[DRY RUN] zypper clean ({PACKAGE_CACHE_DIR})");
+        return Ok(());
+    }
+    let output = run_command("zypper", &["--non-interactive", "clean"])?;
+    if !output.status.success() {
+        return Err(command_failed("zypper", &output));
+    }
+    Ok(())
+}
+
+/// Ядра, которые можно удалить, в виде `имя-версия-релиз`.
+///
+/// Сохраняются версии, перечисленные в `multiversion.kernels`, и всегда
+/// загруженное ядро.
+pub fn kernel_packages_to_remove(running: &str) -> Result<Vec<String>> {
+    let kernels = installed_kernels()?;
+    let specs = parse_multiversion_kernels(&read_zypp_conf());
+    Ok(select_kernels_to_remove(&kernels, &specs, running))
+}
+
+/// Пакеты ядер, установленные в нескольких версиях.
+fn installed_kernels() -> Result<Vec<ZypperKernel>> {
+    let output = run_command(
+        "rpm",
+        &[
+            "-q",
+            "--whatprovides",
+            "multiversion(kernel)",
+            "--qf",
+            "%{NAME}\t%{VERSION}-%{RELEASE}\t%{INSTALLTIME}\n",
+        ],
+    )?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_rpm_kernels(&output.stdout))
+}
+
+fn parse_rpm_kernels(output: &str) -> Vec<ZypperKernel> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(ZypperKernel {
+                name: fields.next()?.to_string(),
+                edition: fields.next()?.to_string(),
+                install_time: fields.next()?.trim().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn read_zypp_conf() -> String {
+    ZYPP_CONF_PATHS
+        .iter()
+        .find_map(|path| std::fs::read_to_string(Path::new(path)).ok())
+        .unwrap_or_default()
+}
+
+/// Разбирает `multiversion.kernels` из zypp.conf.
+pub fn parse_multiversion_kernels(content: &str) -> Vec<KernelSpec> {
+    let value = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && !line.starts_with(';'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "multiversion.kernels")
+        .map(|(_, value)| value.trim())
+        .unwrap_or(DEFAULT_MULTIVERSION_KERNELS);
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry {
+            "latest" => KernelSpec::Latest(0),
+            "oldest" => KernelSpec::Oldest(0),
+            "running" => KernelSpec::Running,
+            _ => {
+                if let Some(offset) = entry
+                    .strip_prefix("latest-")
+                    .and_then(|rest| rest.parse().ok())
+                {
+                    KernelSpec::Latest(offset)
+                } else if let Some(offset) = entry
+                    .strip_prefix("oldest+")
+                    .and_then(|rest| rest.parse().ok())
+                {
+                    KernelSpec::Oldest(offset)
+                } else {
+                    KernelSpec::Version(entry.to_string())
+                }
+            }
+        })
+        .collect()
+}
+
+/// Выбирает пакеты ядер вне `multiversion.kernels`.
+fn select_kernels_to_remove(
+    kernels: &[ZypperKernel],
+    specs: &[KernelSpec],
+    running: &str,
+) -> Vec<String> {
+    let mut editions: Vec<(&str, i64)> = Vec::new();
+    for kernel in kernels {
+        match editions
+            .iter_mut()
+            .find(|(edition, _)| *edition == kernel.edition)
+        {
+            Some((_, time)) => *time = (*time).max(kernel.install_time),
+            None => editions.push((&kernel.edition, kernel.install_time)),
+        }
+    }
+    editions.sort_by(|(a_edition, a_time), (b_edition, b_time)| {
+        a_time.cmp(b_time).then_with(|| a_edition.cmp(b_edition))
+    });
+
+    let mut keep: HashSet<&str> = HashSet::new();
+    for spec in specs {
+        match spec {
+            KernelSpec::Latest(offset) => {
+                if let Some(index) = editions.len().checked_sub(offset + 1) {
+                    keep.insert(editions[index].0);
+                }
+            }
+            KernelSpec::Oldest(offset) => {
+                if let Some((edition, _)) = editions.get(*offset) {
+                    keep.insert(edition);
+                }
+            }
+            KernelSpec::Running => {}
+            KernelSpec::Version(version) => {
+                keep.extend(
+                    editions
+                        .iter()
+                        .map(|(edition, _)| *edition)
+                        .filter(|edition| edition.starts_with(version.as_str())),
+                );
+            }
+        }
+    }
+    // Загруженное ядро не удаляется, даже если `running` не указан.
+    keep.extend(
+        editions
+            .iter()
+            .map(|(edition, _)| *edition)
+            .filter(|edition| is_running_edition(edition, running)),
+    );
+
+    kernels
+        .iter()
+        .filter(|kernel| !keep.contains(kernel.edition.as_str()))
+        .map(|kernel| format!("{}-{}", kernel.name, kernel.edition))
+        .collect()
+}
+
+/// Соответствует ли версия пакета `uname -r` (`6.5.9-1-default` ↔ `6.5.9-1.1`).
+fn is_running_edition(edition: &str, running: &str) -> bool {
+    let Some((release, _flavor)) = running.rsplit_once('-') else {
+        return false;
+    };
+    edition
+        .strip_prefix(release)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Разбирает вывод `zypper remove --dry-run`.
+fn parse_zypper_simulation(output: &str, requested: &[String]) -> RemovalPlan {
+    let mut plan = RemovalPlan::new("zypper", requested);
+    plan.removed.clear();
+    let mut in_removed = false;
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("The following") {
+            in_removed = trimmed.ends_with("REMOVED:");
+            continue;
+        }
+        if trimmed.is_empty() {
+            in_removed = false;
+            continue;
+        }
+        if in_removed && line.starts_with(' ') {
+            plan.removed
+                .extend(trimmed.split_whitespace().map(String::from));
+        } else if let Some(rest) = trimmed.strip_prefix("After the operation, ")
+            && let Some(amount) = rest.strip_suffix(" will be freed.")
+        {
+            plan.freed_bytes = parse_size_string(amount);
+        }
+    }
+    plan
+}
+
+/// Значения столбца из таблицы zypper (`S | Repository | Name | ...`).
+fn parse_table_column(output: &str, column: &str) -> Vec<String> {
+    let mut index = None;
+    let mut values: Vec<String> = Vec::new();
+    for line in output.lines() {
+        if !line.contains('|') || line.trim_start().starts_with('-') {
+            continue;
+        }
+        let cells: Vec<&str> = line.split('|').map(str::trim).collect();
+        let Some(position) = index else {
+            index = cells.iter().position(|cell| *cell == column);
+            continue;
+        };
+        if let Some(value) = cells.get(position)
+            && !value.is_empty()
+            && !values.iter().any(|existing| existing == value)
+        {
+            values.push(value.to_string());
+        }
+    }
+    values
+}
+
+fn first_line(output: &str) -> String {
+    output.lines().next().unwrap_or_default().trim().to_string()
+}
+
+fn split_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kernel(edition: &str, install_time: i64) -> ZypperKernel {
+        ZypperKernel {
+            name: "kernel-default".to_string(),
+            edition: edition.to_string(),
+            install_time,
+        }
+    }
+
+    #[test]
+    fn test_multiversion_kernels_retention() {
+        assert_eq!(
+            parse_multiversion_kernels("# multiversion.kernels = oldest\n"),
+            vec![
+                KernelSpec::Latest(0),
+                KernelSpec::Latest(1),
+                KernelSpec::Running
+            ]
+        );
+        let specs = parse_multiversion_kernels("multiversion.kernels = latest,oldest+1,6.1.\n");
+        assert_eq!(
+            specs,
+            vec![
+                KernelSpec::Latest(0),
+                KernelSpec::Oldest(1),
+                KernelSpec::Version("6.1.".to_string())
+            ]
+        );
+
+        let kernels = vec![
+            kernel("6.1.10-1.1", 10),
+            kernel("6.4.1-1.1", 20),
+            kernel("6.5.2-1.1", 30),
+            kernel("6.5.9-2.1", 40),
+            kernel("6.6.1-1.1", 50),
+        ];
+        // latest, oldest+1 (6.4.1), 6.1.* и загруженное ядро 6.5.2.
+        assert_eq!(
+            select_kernels_to_remove(&kernels, &specs, "6.5.2-1-default"),
+            vec!["kernel-default-6.5.9-2.1"]
+        );
+        assert!(is_running_edition("6.5.9-1.1", "6.5.9-1-default"));
+        assert!(!is_running_edition("6.5.9-10.1", "6.5.9-1-default"));
+    }
+
+    #[test]
+    fn test_parse_zypper_output() {
+        let table = "\
+S  | Repository | Name    | Version | Arch
+---+------------+---------+---------+-------
+i  | @System    | libfoo1 | 1.0-1.1 | x86_64
+i  | @System    | bar     | 2.0-1.1 | noarch
+";
+        assert_eq!(parse_table_column(table, "Name"), vec!["libfoo1", "bar"]);
+
+        let simulation = "\
+Reading installed packages...
+Resolving package dependencies...
+
+The following 2 packages are going to be REMOVED:
+  bar libfoo1
+
+2 packages to remove.
+After the operation, 1.5 MiB will be freed.
+";
+        let plan = parse_zypper_simulation(simulation, &["bar".to_string()]);
+        assert_eq!(plan.removed, vec!["bar", "libfoo1"]);
+        assert_eq!(plan.freed_bytes, Some(1572864));
+    }
+}
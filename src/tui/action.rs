@@ -1,6 +1,10 @@
 //! Действия для Flux-архитектуры.
 
+use crate::backup::BackupSummary;
+use crate::backup::journal::JournalSession;
 use crate::models::{CleanupItem, CleanupResult};
+use crate::system::disk_usage::FilesystemUsage;
+use crate::system::package_manager::RemovalPlan;
 use serde::{Deserialize, Serialize};
 
 /// Экраны приложения.
@@ -16,15 +20,19 @@ pub enum Screen {
     Settings,
     /// Экран результатов.
     Results,
+    /// Экран управления бэкапами.
+    Backups,
+    /// Экран восстановления после прерванной очистки.
+    Recovery,
 }
 
 /// Тип редактирования в настройках.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SettingsEdit {
-    /// Редактирование whitelist.
-    Whitelist,
-    /// Редактирование blacklist.
-    Blacklist,
+    /// Редактирование защищённых путей (правила `deny`).
+    Protect,
+    /// Редактирование разрешённых путей (правила `allow`).
+    Allow,
 }
 
 /// Действия пользователя и системы.
@@ -39,6 +47,8 @@ pub enum Action {
     Refresh,
     /// Установить элементы.
     SetItems(Vec<CleanupItem>),
+    /// Установить заполненность файловых систем.
+    SetFilesystems(Vec<FilesystemUsage>),
     /// Установить статусное сообщение.
     SetStatus(Option<String>),
 
@@ -66,6 +76,8 @@ pub enum Action {
     ToggleSelection,
     /// Переключить выбор всех видимых элементов.
     ToggleAllVisible,
+    /// Показать или скрыть трассировку проверок безопасности.
+    ToggleTrace,
     /// Начать поиск.
     StartSearch,
     /// Завершить поиск.
@@ -85,10 +97,26 @@ pub enum Action {
     /// Удалить последний символ из ввода настроек.
     BackspaceSettingsInput,
 
+    /// Установить симуляции транзакций пакетных менеджеров.
+    SetRemovalPlans(Vec<RemovalPlan>),
     /// Открыть экран подтверждения.
     OpenConfirm,
     /// Открыть настройки.
     OpenSettings,
+    /// Открыть экран бэкапов.
+    OpenBackups,
+    /// Установить список бэкапов.
+    SetBackups(Vec<BackupSummary>),
+    /// Выбрать следующий бэкап.
+    SelectNextBackup,
+    /// Выбрать предыдущий бэкап.
+    SelectPrevBackup,
+    /// Запросить подтверждение удаления бэкапа или снять запрос.
+    SetBackupDeletePending(Option<String>),
+    /// Показать прерванную сессию очистки.
+    OpenRecovery(JournalSession),
+    /// Закрыть экран восстановления.
+    CloseRecovery,
     /// Вернуться на главный экран.
     BackToMain,
 
@@ -1,11 +1,16 @@
+use crate::backup::BackupManager;
+use crate::backup::journal;
 use crate::cleaner;
-use crate::config::Config;
+use crate::config::{Config, RuleAction};
 use crate::error::{RcleanerError, Result};
-use crate::models::CleanupItem;
+use crate::models::{CleanupItem, CleanupSource};
+use crate::privilege;
 use crate::system::detection::{SystemInfo, SystemType, detect_system};
+use crate::system::disk_usage;
+use crate::system::package_manager;
 use crate::tui::action::{Action, SafetyLevel, Screen, SettingsEdit};
 use crate::tui::dispatcher::Dispatcher;
-use crate::tui::screens::{confirm, main, progress, results, settings};
+use crate::tui::screens::{backups, confirm, main, progress, recovery, results, settings};
 use crate::tui::state::State;
 use crate::utils::cache;
 use crate::utils::command;
@@ -59,6 +64,7 @@ impl App {
             app.dispatcher.dispatch(Action::SetStatus(Some(message)));
         }
         app.load_cached_items();
+        app.check_interrupted_cleanup();
         app.request_scan("Startup scan");
 
         app
@@ -106,11 +112,11 @@ impl App {
                 self.config.current_profile().auto_confirm,
                 self.config.current_profile().dry_run,
                 self.config.current_profile().temp_max_age_days,
+                self.config.current_profile().deletion_mode,
                 &self.config_path.to_string_lossy(),
                 self.config.safety.enabled,
                 self.config.safety.only_root_can_disable,
-                &self.config.rules.whitelist.paths,
-                &self.config.rules.blacklist.patterns,
+                &self.config.rules.entries,
             ),
             Screen::Progress => {
                 progress::render_progress_screen(frame, area, state, &self.system_label)
@@ -118,6 +124,16 @@ impl App {
             Screen::Results => {
                 results::render_results_screen(frame, area, state, &self.system_label)
             }
+            Screen::Backups => backups::render_backups_screen(
+                frame,
+                area,
+                state,
+                &self.system_label,
+                &retention_label(&self.config),
+            ),
+            Screen::Recovery => {
+                recovery::render_recovery_screen(frame, area, state, &self.system_label)
+            }
         }
     }
 
@@ -133,6 +149,8 @@ impl App {
             Screen::Confirm => self.handle_confirm_keys(key, terminal)?,
             Screen::Settings => self.handle_settings_keys(key),
             Screen::Results => self.handle_results_keys(key),
+            Screen::Backups => self.handle_backups_keys(key),
+            Screen::Recovery => self.handle_recovery_keys(key, terminal)?,
             Screen::Progress => {}
         }
 
@@ -152,10 +170,8 @@ impl App {
                 KeyCode::Backspace => {
                     self.dispatcher.dispatch(Action::BackspaceSearch);
                 }
-                KeyCode::Char(ch) => {
-                    if !ch.is_control() {
-                        self.dispatcher.dispatch(Action::AppendSearch(ch));
-                    }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    self.dispatcher.dispatch(Action::AppendSearch(ch));
                 }
                 _ => {}
             }
@@ -174,6 +190,7 @@ impl App {
                     if self.config.current_profile().auto_confirm {
                         self.perform_cleanup(terminal)?;
                     } else {
+                        self.simulate_package_removal();
                         self.dispatcher.dispatch(Action::OpenConfirm);
                     }
                 } else {
@@ -184,10 +201,8 @@ impl App {
             KeyCode::Char('/') => {
                 self.dispatcher.dispatch(Action::StartSearch);
             }
-            KeyCode::Esc => {
-                if !self.dispatcher.store().state().search_query.is_empty() {
-                    self.dispatcher.dispatch(Action::ClearSearch);
-                }
+            KeyCode::Esc if !self.dispatcher.store().state().search_query.is_empty() => {
+                self.dispatcher.dispatch(Action::ClearSearch);
             }
             KeyCode::Tab => {
                 self.dispatcher.dispatch(Action::NextTab);
@@ -207,9 +222,16 @@ impl App {
             KeyCode::Char('a') | KeyCode::Char('A') => {
                 self.dispatcher.dispatch(Action::ToggleAllVisible);
             }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                self.dispatcher.dispatch(Action::ToggleTrace);
+            }
             KeyCode::Char('s') | KeyCode::Char('S') => {
                 self.dispatcher.dispatch(Action::OpenSettings);
             }
+            KeyCode::Char('b') | KeyCode::Char('B') => {
+                self.dispatcher.dispatch(Action::OpenBackups);
+                self.reload_backups();
+            }
             // Числовые клавиши для вкладок
             KeyCode::Char('1') => self.dispatcher.dispatch(Action::ChangeTab(0)),
             KeyCode::Char('2') => self.dispatcher.dispatch(Action::ChangeTab(1)),
@@ -217,6 +239,7 @@ impl App {
             KeyCode::Char('4') => self.dispatcher.dispatch(Action::ChangeTab(3)),
             KeyCode::Char('5') => self.dispatcher.dispatch(Action::ChangeTab(4)),
             KeyCode::Char('6') => self.dispatcher.dispatch(Action::ChangeTab(5)),
+            KeyCode::Char('7') => self.dispatcher.dispatch(Action::ChangeTab(6)),
             // Навигация по страницам
             KeyCode::PageDown => self.dispatcher.dispatch(Action::SelectPageDown),
             KeyCode::PageUp => self.dispatcher.dispatch(Action::SelectPageUp),
@@ -257,10 +280,8 @@ impl App {
                 KeyCode::Backspace => {
                     self.dispatcher.dispatch(Action::BackspaceSettingsInput);
                 }
-                KeyCode::Char(ch) => {
-                    if !ch.is_control() {
-                        self.dispatcher.dispatch(Action::AppendSettingsInput(ch));
-                    }
+                KeyCode::Char(ch) if !ch.is_control() => {
+                    self.dispatcher.dispatch(Action::AppendSettingsInput(ch));
                 }
                 _ => {}
             }
@@ -284,11 +305,14 @@ impl App {
             KeyCode::Char('d') | KeyCode::Char('D') => {
                 self.toggle_dry_run();
             }
-            KeyCode::Char('w') | KeyCode::Char('W') => {
-                self.begin_settings_edit(SettingsEdit::Whitelist);
+            KeyCode::Char('m') | KeyCode::Char('M') => {
+                self.cycle_deletion_mode();
             }
-            KeyCode::Char('b') | KeyCode::Char('B') => {
-                self.begin_settings_edit(SettingsEdit::Blacklist);
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                self.begin_settings_edit(SettingsEdit::Protect);
+            }
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                self.begin_settings_edit(SettingsEdit::Allow);
             }
             KeyCode::Enter | KeyCode::Esc => {
                 self.dispatcher.dispatch(Action::BackToMain);
@@ -307,6 +331,149 @@ impl App {
         }
     }
 
+    fn handle_backups_keys(&mut self, key: event::KeyEvent) {
+        let pending = self
+            .dispatcher
+            .store()
+            .state()
+            .backup_delete_pending
+            .clone();
+        if let Some(backup_id) = pending {
+            self.dispatcher
+                .dispatch(Action::SetBackupDeletePending(None));
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.delete_backup(&backup_id),
+                _ => self.dispatcher.dispatch(Action::SetStatus(Some(
+                    "Backup deletion cancelled.".to_string(),
+                ))),
+            }
+            return;
+        }
+
+        match key.code {
+            KeyCode::Down => self.dispatcher.dispatch(Action::SelectNextBackup),
+            KeyCode::Up => self.dispatcher.dispatch(Action::SelectPrevBackup),
+            KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete => {
+                self.request_backup_delete();
+            }
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                self.toggle_selected_backup_pin();
+            }
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                self.apply_backup_retention();
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.reload_backups();
+            }
+            KeyCode::Enter | KeyCode::Esc => {
+                self.dispatcher.dispatch(Action::BackToMain);
+            }
+            _ => {}
+        }
+    }
+
+    fn reload_backups(&mut self) {
+        let summaries = BackupManager::from_config().and_then(|manager| manager.list_summaries());
+        match summaries {
+            Ok(summaries) => self.dispatcher.dispatch(Action::SetBackups(summaries)),
+            Err(err) => {
+                log::warn!("Failed to list backups: {}", err);
+                self.dispatcher.dispatch(Action::SetStatus(Some(
+                    "Failed to list backups.".to_string(),
+                )));
+            }
+        }
+    }
+
+    /// Удаление бэкапа необратимо, поэтому сначала спрашиваем подтверждение.
+    fn request_backup_delete(&mut self) {
+        let Some(backup) = self.dispatcher.store().state().selected_backup().cloned() else {
+            return;
+        };
+        self.dispatcher
+            .dispatch(Action::SetBackupDeletePending(Some(backup.id)));
+    }
+
+    fn delete_backup(&mut self, backup_id: &str) {
+        let result =
+            BackupManager::from_config().and_then(|manager| manager.delete_backup(backup_id));
+        let message = match result {
+            Ok(()) => format!("Deleted backup {backup_id}."),
+            Err(err) => {
+                log::warn!("Failed to delete backup {}: {}", backup_id, err);
+                "Failed to delete backup.".to_string()
+            }
+        };
+        self.reload_backups();
+        self.dispatcher.dispatch(Action::SetStatus(Some(message)));
+    }
+
+    fn toggle_selected_backup_pin(&mut self) {
+        let Some(backup) = self.dispatcher.store().state().selected_backup().cloned() else {
+            return;
+        };
+        let result = BackupManager::from_config()
+            .and_then(|manager| manager.set_pinned(&backup.id, !backup.pinned));
+        let message = match result {
+            Ok(()) if backup.pinned => "Backup unpinned.",
+            Ok(()) => "Backup pinned.",
+            Err(err) => {
+                log::warn!("Failed to pin backup {}: {}", backup.id, err);
+                "Failed to update backup."
+            }
+        };
+        self.reload_backups();
+        self.dispatcher
+            .dispatch(Action::SetStatus(Some(message.to_string())));
+    }
+
+    fn apply_backup_retention(&mut self) {
+        let result = BackupManager::from_config().and_then(|manager| manager.apply_retention(None));
+        let message = match result {
+            Ok(removed) => format!("Retention removed {} backup(s).", removed.len()),
+            Err(err) => {
+                log::warn!("Failed to apply backup retention: {}", err);
+                "Failed to apply retention.".to_string()
+            }
+        };
+        self.reload_backups();
+        self.dispatcher.dispatch(Action::SetStatus(Some(message)));
+    }
+
+    /// Симулирует удаление выбранных пакетов, чтобы показать полную
+    /// транзакцию на экране подтверждения.
+    fn simulate_package_removal(&mut self) {
+        let mut by_manager: Vec<(String, Vec<String>)> = Vec::new();
+        for item in self.dispatcher.store().state().items.iter() {
+            let CleanupSource::PackageManager(manager) = &item.source else {
+                continue;
+            };
+            if !item.selected || item.blocked_reason.is_some() {
+                continue;
+            }
+            match by_manager.iter_mut().find(|(name, _)| name == manager) {
+                Some((_, packages)) => packages.push(item.name.clone()),
+                None => by_manager.push((manager.clone(), vec![item.name.clone()])),
+            }
+        }
+
+        let mut plans = Vec::new();
+        let mut failures = Vec::new();
+        for (manager, packages) in by_manager {
+            match package_manager::simulate_removal(&manager, &packages) {
+                Ok(plan) => plans.push(plan),
+                Err(err) => failures.push(format!("{manager}: {err}")),
+            }
+        }
+        self.dispatcher.dispatch(Action::SetRemovalPlans(plans));
+        if !failures.is_empty() {
+            self.dispatcher.dispatch(Action::SetStatus(Some(format!(
+                "Cannot simulate package removal: {}",
+                failures.join("; ")
+            ))));
+        }
+    }
+
     fn perform_cleanup(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
         let selected_items = self.dispatcher.store().state().selected_items();
         if selected_items.is_empty() {
@@ -316,31 +483,77 @@ impl App {
             return Ok(());
         }
 
+        let dry_run = self.config.current_profile().dry_run;
+        if !dry_run && !self.request_elevation(terminal, &selected_items) {
+            return Ok(());
+        }
+        self.run_cleanup_with_progress(terminal, |on_progress| {
+            cleaner::clean_selected_with_progress(&selected_items, dry_run, on_progress)
+        });
+        Ok(())
+    }
+
+    /// Запрашивает права root для выбранных элементов, которым они нужны.
+    ///
+    /// pkexec спрашивает пароль в терминале, поэтому интерфейс на это время
+    /// приостанавливается. Возвращает `false`, если права не получены.
+    fn request_elevation(&mut self, terminal: &mut DefaultTerminal, items: &[CleanupItem]) -> bool {
+        let classes = privilege::required_classes(items);
+        if classes.iter().all(|class| privilege::is_authorized(*class)) {
+            return true;
+        }
+
+        ratatui::restore();
+        let result = privilege::authorize(&classes);
+        *terminal = ratatui::init();
+        if let Err(err) = terminal.clear() {
+            log::warn!("Failed to clear terminal: {}", err);
+        }
+
+        let denied = match result {
+            Ok(denied) if denied.is_empty() => return true,
+            Ok(denied) => denied,
+            Err(err) => {
+                log::warn!("Failed to request elevation: {}", err);
+                classes
+            }
+        };
+        let labels: Vec<_> = denied.iter().map(|class| class.label()).collect();
+        self.dispatcher.dispatch(Action::SetStatus(Some(format!(
+            "Administrator rights not granted for: {}. Deselect those items or retry.",
+            labels.join(", ")
+        ))));
+        self.dispatcher.dispatch(Action::BackToMain);
+        false
+    }
+
+    fn run_cleanup_with_progress<R>(&mut self, terminal: &mut DefaultTerminal, run: R)
+    where
+        R: FnOnce(&mut dyn FnMut(f64, &str)) -> Result<crate::models::CleanupResult>,
+    {
         self.dispatcher.dispatch(Action::StartCleanup);
         self.draw_current(terminal);
 
         let mut last_error = None;
-        let result = cleaner::clean_selected_with_progress(
-            &selected_items,
-            self.config.current_profile().dry_run,
-            |progress, step| {
-                self.dispatcher.dispatch(Action::CleanupProgress {
-                    progress,
-                    step: Some(step.to_string()),
-                });
-                if let Err(err) = terminal.draw(|frame| {
-                    let state = self.dispatcher.store().state().clone();
-                    self.draw(frame, &state);
-                }) {
-                    last_error = Some(err.to_string());
-                }
-            },
-        );
+        let result = run(&mut |progress, step| {
+            self.dispatcher.dispatch(Action::CleanupProgress {
+                progress,
+                step: Some(step.to_string()),
+            });
+            if let Err(err) = terminal.draw(|frame| {
+                let state = self.dispatcher.store().state().clone();
+                self.draw(frame, &state);
+            }) {
+                last_error = Some(err.to_string());
+            }
+        });
 
         if let Some(message) = last_error {
             log::warn!("Failed to render progress: {}", message);
         }
 
+        self.dispatcher
+            .dispatch(Action::SetFilesystems(disk_usage::list_filesystems()));
         match result {
             Ok(result) => self.dispatcher.dispatch(Action::FinishCleanup(result)),
             Err(err) => {
@@ -349,7 +562,71 @@ impl App {
                 self.dispatcher.dispatch(Action::FinishCleanup(failed));
             }
         }
+    }
+
+    fn check_interrupted_cleanup(&mut self) {
+        match journal::find_interrupted() {
+            Ok(Some(session)) => self.dispatcher.dispatch(Action::OpenRecovery(session)),
+            Ok(None) => {}
+            Err(err) => log::warn!("Failed to read cleanup journal: {}", err),
+        }
+    }
+
+    fn handle_recovery_keys(
+        &mut self,
+        key: event::KeyEvent,
+        terminal: &mut DefaultTerminal,
+    ) -> Result<()> {
+        let Some(session) = self.dispatcher.store().state().interrupted_session.clone() else {
+            self.dispatcher.dispatch(Action::BackToMain);
+            return Ok(());
+        };
 
+        match key.code {
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                self.dispatcher.dispatch(Action::CloseRecovery);
+                self.run_cleanup_with_progress(terminal, |on_progress| {
+                    cleaner::resume_session(&session, on_progress)
+                });
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                let message = match journal::rollback_session(&session) {
+                    Ok(report) if report.errors.is_empty() => format!(
+                        "Rolled back: {} backup(s), {} moved path(s) restored.",
+                        report.restored_backups, report.restored_paths
+                    ),
+                    Ok(report) => {
+                        for err in &report.errors {
+                            log::warn!("Rollback error: {}", err);
+                        }
+                        format!(
+                            "Rollback finished with {} error(s). See logs.",
+                            report.errors.len()
+                        )
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to roll back cleanup: {}", err);
+                        "Rollback failed. See logs.".to_string()
+                    }
+                };
+                self.dispatcher.dispatch(Action::CloseRecovery);
+                self.dispatcher.dispatch(Action::SetStatus(Some(message)));
+                self.request_scan("Post-rollback refresh");
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                if let Err(err) = journal::discard_interrupted() {
+                    log::warn!("Failed to discard cleanup journal: {}", err);
+                }
+                self.dispatcher.dispatch(Action::CloseRecovery);
+                self.dispatcher.dispatch(Action::SetStatus(Some(
+                    "Cleanup journal discarded.".to_string(),
+                )));
+            }
+            KeyCode::Esc => {
+                self.dispatcher.dispatch(Action::BackToMain);
+            }
+            _ => {}
+        }
         Ok(())
     }
 
@@ -357,6 +634,8 @@ impl App {
         match cache::load_cached_items() {
             Ok(Some(items)) => {
                 self.dispatcher.dispatch(Action::SetItems(items));
+                self.dispatcher
+                    .dispatch(Action::SetFilesystems(disk_usage::list_filesystems()));
                 self.dispatcher.dispatch(Action::SetStatus(Some(
                     "Loaded cached results.".to_string(),
                 )));
@@ -398,6 +677,8 @@ impl App {
                         log::warn!("Failed to save cache: {}", err);
                     }
                     self.dispatcher.dispatch(Action::SetItems(items));
+                    self.dispatcher
+                        .dispatch(Action::SetFilesystems(disk_usage::list_filesystems()));
                     self.dispatcher
                         .dispatch(Action::SetStatus(Some("Scan complete.".to_string())));
                 }
@@ -434,20 +715,20 @@ impl App {
     }
 
     fn begin_settings_edit(&mut self, target: SettingsEdit) {
-        let input = match target {
-            SettingsEdit::Whitelist => self.config.rules.whitelist.paths.join(", "),
-            SettingsEdit::Blacklist => self.config.rules.blacklist.patterns.join(", "),
-        };
+        let input = self
+            .config
+            .rules
+            .simple_patterns(settings_action(target))
+            .join(", ");
         self.dispatcher
             .dispatch(Action::BeginSettingsEdit(target, input));
     }
 
     fn apply_settings_edit(&mut self, target: SettingsEdit, input: &str) {
         let values = parse_rules_input(input);
-        match target {
-            SettingsEdit::Whitelist => self.config.rules.whitelist.paths = values,
-            SettingsEdit::Blacklist => self.config.rules.blacklist.patterns = values,
-        }
+        self.config
+            .rules
+            .set_simple_patterns(settings_action(target), values);
 
         if self.save_config("Rules updated.") {
             self.request_scan("Rules updated");
@@ -494,6 +775,16 @@ impl App {
         self.save_config("Dry-run updated.");
     }
 
+    fn cycle_deletion_mode(&mut self) {
+        let profile = if self.config.safety.level.to_lowercase() == "aggressive" {
+            &mut self.config.profiles.aggressive
+        } else {
+            &mut self.config.profiles.safe
+        };
+        profile.deletion_mode = profile.deletion_mode.next();
+        self.save_config("Deletion mode updated.");
+    }
+
     fn save_config(&mut self, message: &str) -> bool {
         if let Err(err) = self.config.save(&self.config_path) {
             log::warn!("Failed to save config: {}", err);
@@ -521,6 +812,14 @@ impl App {
     }
 }
 
+/// Действие правил, которые редактируются списком.
+fn settings_action(target: SettingsEdit) -> RuleAction {
+    match target {
+        SettingsEdit::Protect => RuleAction::Deny,
+        SettingsEdit::Allow => RuleAction::Allow,
+    }
+}
+
 fn parse_rules_input(input: &str) -> Vec<String> {
     input
         .split([',', '\n'])
@@ -539,7 +838,10 @@ fn build_system_label() -> String {
 
 fn format_system_label(info: &SystemInfo) -> String {
     let mut label = format!("{} {}", info.os_name, info.os_version);
-    if matches!(info.system_type, SystemType::AtomicRpmOstree) {
+    if matches!(
+        info.system_type,
+        SystemType::AtomicRpmOstree | SystemType::AtomicTransactional
+    ) {
         label.push_str(" Atomic");
     }
     if let Some(desktop) = info.desktop_environment.as_deref()
@@ -585,3 +887,20 @@ fn safety_level_from_config(config: &Config) -> SafetyLevel {
         SafetyLevel::Safe
     }
 }
+
+fn retention_label(config: &Config) -> String {
+    let profile = config.current_profile();
+    let limit = |value: u64, unit: &str| {
+        if value == 0 {
+            "unlimited".to_string()
+        } else {
+            format!("{value}{unit}")
+        }
+    };
+    format!(
+        "age {}, count {}, size {}",
+        limit(profile.backup_max_age_days, "d"),
+        limit(profile.backup_max_count as u64, ""),
+        limit(profile.max_backup_size_gb as u64, " GB")
+    )
+}
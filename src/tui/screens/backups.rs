@@ -0,0 +1,130 @@
+use crate::tui::screens::common::render_header;
+use crate::tui::state::State;
+use crate::tui::widgets::status_bar::render_status_bar;
+use crate::utils::size_format::format_size;
+use chrono::Local;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph};
+
+/// Отрисовывает экран управления бэкапами.
+pub fn render_backups_screen(
+    frame: &mut ratatui::Frame,
+    area: ratatui::layout::Rect,
+    state: &State,
+    system_label: &str,
+    retention_label: &str,
+) {
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Backups");
+    let inner = outer.inner(area);
+    frame.render_widget(outer, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(inner);
+
+    render_header(frame, chunks[0], system_label, state.safety_level);
+
+    let body = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(0)])
+        .split(chunks[1]);
+
+    let stored_total: u64 = state.backups.iter().map(|backup| backup.stored_size).sum();
+    let pinned = state.backups.iter().filter(|backup| backup.pinned).count();
+    let snapshots = state
+        .backups
+        .iter()
+        .filter(|backup| backup.format.is_snapshot())
+        .count();
+    let snapshot_note = if snapshots > 0 {
+        format!(", {snapshots} snapshot(s) keep cleaned files on disk until deleted")
+    } else {
+        String::new()
+    };
+    let summary = Paragraph::new(format!(
+        "Backups: {} ({} pinned), stored: {}{}\nRetention: {}",
+        state.backups.len(),
+        pinned,
+        format_size(stored_total),
+        snapshot_note,
+        retention_label
+    ))
+    .block(Block::default().borders(Borders::ALL).title("Summary"));
+    frame.render_widget(summary, body[0]);
+
+    let list_items: Vec<ListItem> = if state.backups.is_empty() {
+        vec![ListItem::new("No backups found.")]
+    } else {
+        state
+            .backups
+            .iter()
+            .map(|backup| {
+                let marker = if backup.pinned { "[P]" } else { "   " };
+                let timestamp = backup
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M");
+                let style = if backup.pinned {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(format!(
+                    "{marker} {timestamp}  {:>4} items  {:>10}  {:>10}  {:<9} {}",
+                    backup.item_count,
+                    format_size(backup.size),
+                    format_size(backup.stored_size),
+                    backup.format.label(),
+                    backup.id
+                ))
+                .style(style)
+            })
+            .collect()
+    };
+
+    let mut list_state = ListState::default();
+    if !state.backups.is_empty() {
+        list_state.select(Some(state.backup_selected));
+    }
+    let list = List::new(list_items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Date / Items / Size / Stored / Format"),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, body[1], &mut list_state);
+
+    if let Some(backup_id) = state.backup_delete_pending.as_deref() {
+        let keys = vec![
+            format!("Delete backup {backup_id}?"),
+            "[Y] Yes".to_string(),
+            "[any key] No".to_string(),
+        ];
+        render_status_bar(frame, chunks[2], &keys);
+        return;
+    }
+
+    let mut keys = vec![
+        "[Up/Down] Move".to_string(),
+        "[D] Delete".to_string(),
+        "[P] Pin".to_string(),
+        "[X] Apply retention".to_string(),
+        "[R] Refresh".to_string(),
+        "[Esc] Back".to_string(),
+    ];
+    if let Some(message) = state.status_message.as_deref() {
+        keys.push(message.to_string());
+    }
+    render_status_bar(frame, chunks[2], &keys);
+}
@@ -1,3 +1,5 @@
+use crate::privilege;
+use crate::system::package_manager::RemovalPlan;
 use crate::tui::screens::common::render_header;
 use crate::tui::state::State;
 use crate::tui::widgets::status_bar::render_status_bar;
@@ -36,13 +38,19 @@ pub fn render_confirm_screen(
     let selected_count = selected_items.len();
     let selected_size = format_size(state.selected_size);
 
+    let estimates = state.selected_by_mount();
     let content_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(5), Constraint::Min(0)])
+        .constraints([
+            Constraint::Length(
+                4 + estimates.len().min(4) as u16 + plan_lines(&state.removal_plans),
+            ),
+            Constraint::Min(0),
+        ])
         .split(chunks[1]);
 
     let mode_label = if dry_run { "Dry run" } else { "Execute" };
-    let summary = Paragraph::new(Line::from(vec![
+    let mut summary_lines = vec![Line::from(vec![
         Span::styled(
             "Selected items: ",
             Style::default().add_modifier(Modifier::BOLD),
@@ -57,8 +65,40 @@ pub fn render_confirm_screen(
         Span::raw(" | "),
         Span::styled("Mode: ", Style::default().add_modifier(Modifier::BOLD)),
         Span::raw(mode_label),
-    ]))
-    .block(Block::default().borders(Borders::ALL).title("Summary"));
+    ])];
+    let classes = privilege::required_classes(selected_items.iter().copied());
+    if !dry_run && !classes.is_empty() {
+        let labels: Vec<_> = classes.iter().map(|class| class.label()).collect();
+        summary_lines.push(Line::from(vec![
+            Span::styled(
+                "Administrator rights: ",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(labels.join(", ")),
+        ]));
+    }
+    for estimate in estimates.iter().take(4) {
+        summary_lines.push(Line::from(format!("Will free {}", estimate.describe())));
+    }
+    for plan in &state.removal_plans {
+        summary_lines.push(Line::from(vec![
+            Span::styled(
+                "Package transaction: ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(plan.summary()),
+        ]));
+        if plan.is_blocked() {
+            summary_lines.push(Line::from(Span::styled(
+                format!("Held/protected, will be skipped: {}", plan.held.join(", ")),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            )));
+        }
+    }
+    let summary = Paragraph::new(summary_lines)
+        .block(Block::default().borders(Borders::ALL).title("Summary"));
     frame.render_widget(summary, content_chunks[0]);
 
     let max_items = content_chunks[1].height.saturating_sub(2) as usize;
@@ -90,3 +130,11 @@ pub fn render_confirm_screen(
     ];
     render_status_bar(frame, chunks[2], &keys);
 }
+
+/// Число строк сводки, занимаемых симуляциями транзакций.
+fn plan_lines(plans: &[RemovalPlan]) -> u16 {
+    plans
+        .iter()
+        .map(|plan| if plan.is_blocked() { 2 } else { 1 })
+        .sum()
+}
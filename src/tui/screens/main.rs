@@ -1,3 +1,5 @@
+use crate::models::VerdictOutcome;
+use crate::system::disk_usage;
 use crate::tui::screens::common::render_header;
 use crate::tui::state::State;
 use crate::tui::widgets::info_panel::render_info_panel;
@@ -7,7 +9,8 @@ use crate::tui::widgets::tabs::render_tabs;
 use crate::utils::size_format::{format_percentage, format_size};
 use ratatui::layout::{Constraint, Direction, Layout};
 use ratatui::style::{Color, Style};
-use ratatui::widgets::{Block, BorderType, Borders, Paragraph};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Wrap};
 
 pub fn render_main_screen(
     frame: &mut ratatui::Frame,
@@ -65,7 +68,11 @@ pub fn render_main_screen(
 
     let right_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(8), Constraint::Min(0)])
+        .constraints([
+            Constraint::Length(9),
+            Constraint::Length(6),
+            Constraint::Min(0),
+        ])
         .split(body_chunks[1]);
 
     let info_text = match state.selected_item() {
@@ -84,11 +91,22 @@ pub fn render_main_screen(
                 format!("Dependencies: {}\n", item.dependencies.join(", "))
             };
 
+            let filesystem = match disk_usage::filesystem_for(item, &state.filesystems) {
+                Some(filesystem) => format!(
+                    "Filesystem: {} ({:.0}% full, {} free)",
+                    filesystem.mount_point.display(),
+                    filesystem.usage.used_percent(),
+                    format_size(filesystem.usage.available)
+                ),
+                None => "Filesystem: unknown".to_string(),
+            };
+
             format!(
-                "Name: {}\nSize: {}\nSource: {}\n{}\n{}{}\n",
+                "Name: {}\nSize: {}\nSource: {}\n{}\n{}\n{}{}\n",
                 item.name,
                 format_size(item.size),
                 format_source(item),
+                filesystem,
                 status,
                 deps,
                 item.description
@@ -107,6 +125,11 @@ pub fn render_main_screen(
         format_size(state.total_size)
     );
     render_info_panel(frame, right_chunks[1], "Summary", &summary_text);
+    if state.show_trace {
+        render_trace_panel(frame, right_chunks[2], state);
+    } else {
+        render_disks_panel(frame, right_chunks[2], state);
+    }
 
     let mut keys = vec![
         "[Tab] Next",
@@ -114,8 +137,10 @@ pub fn render_main_screen(
         "[Up/Down] Move",
         "[Space] Select",
         "[A] All",
+        "[W] Why",
         "[Enter] Clean",
         "[S] Settings",
+        "[B] Backups",
         "[R] Refresh",
         "[/] Search",
         "[Q] Quit",
@@ -135,6 +160,78 @@ pub fn render_main_screen(
     render_status_bar(frame, chunks[3], &keys);
 }
 
+/// Панель со всеми результатами проверок безопасности выбранного элемента.
+fn render_trace_panel(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, state: &State) {
+    let lines: Vec<Line> = match state.selected_item() {
+        Some(item) if !item.verdicts.is_empty() => item
+            .verdicts
+            .iter()
+            .map(|verdict| {
+                let color = match verdict.outcome {
+                    VerdictOutcome::Pass => Color::Green,
+                    VerdictOutcome::Block => Color::Red,
+                    VerdictOutcome::Shadowed => Color::DarkGray,
+                };
+                Line::from(Span::styled(
+                    verdict.to_string(),
+                    Style::default().fg(color),
+                ))
+            })
+            .collect(),
+        Some(_) => vec![Line::from("No safety checks recorded.")],
+        None => vec![Line::from("No item selected.")],
+    };
+    let panel = Paragraph::new(lines)
+        .block(Block::default().title("Why").borders(Borders::ALL))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(panel, area);
+}
+
+/// Панель заполненности файловых систем с освобождаемым местом.
+fn render_disks_panel(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, state: &State) {
+    let estimates = state.selected_by_mount();
+    let lines: Vec<Line> = if state.filesystems.is_empty() {
+        vec![Line::from("No filesystem data.")]
+    } else {
+        state
+            .filesystems
+            .iter()
+            .map(|filesystem| {
+                let percent = filesystem.usage.used_percent();
+                let color = if percent >= 90.0 {
+                    Color::Red
+                } else if percent >= 75.0 {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                };
+                let mut spans = vec![
+                    Span::raw(format!("{} ", filesystem.mount_point.display())),
+                    Span::styled(format!("{percent:.0}%"), Style::default().fg(color)),
+                    Span::raw(format!(
+                        ", {} free",
+                        format_size(filesystem.usage.available)
+                    )),
+                ];
+                if let Some(estimate) = estimates
+                    .iter()
+                    .find(|estimate| estimate.filesystem.mount_point == filesystem.mount_point)
+                {
+                    spans.push(Span::styled(
+                        format!(" (+{})", format_size(estimate.bytes)),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                }
+                Line::from(spans)
+            })
+            .collect()
+    };
+    let panel = Paragraph::new(lines)
+        .block(Block::default().title("Disks").borders(Borders::ALL))
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(panel, area);
+}
+
 fn format_source(item: &crate::models::CleanupItem) -> String {
     match &item.source {
         crate::models::CleanupSource::FileSystem => "Files".to_string(),
@@ -1,7 +1,9 @@
+pub mod backups;
 pub mod cleanup;
 pub mod common;
 pub mod confirm;
 pub mod main;
 pub mod progress;
+pub mod recovery;
 pub mod results;
 pub mod settings;
@@ -0,0 +1,94 @@
+use crate::tui::screens::common::render_header;
+use crate::tui::state::State;
+use crate::tui::widgets::status_bar::render_status_bar;
+use crate::utils::size_format::format_size;
+use chrono::Local;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::widgets::{Block, BorderType, Borders, List, ListItem, Paragraph};
+
+/// Отрисовывает экран восстановления после прерванной очистки.
+pub fn render_recovery_screen(
+    frame: &mut ratatui::Frame,
+    area: ratatui::layout::Rect,
+    state: &State,
+    system_label: &str,
+) {
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Interrupted cleanup");
+    let inner = outer.inner(area);
+    frame.render_widget(outer, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(inner);
+
+    render_header(frame, chunks[0], system_label, state.safety_level);
+
+    let body = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(7), Constraint::Min(0)])
+        .split(chunks[1]);
+
+    let Some(session) = &state.interrupted_session else {
+        let empty = Paragraph::new("No interrupted cleanup found.")
+            .block(Block::default().borders(Borders::ALL).title("Summary"));
+        frame.render_widget(empty, chunks[1]);
+        render_status_bar(frame, chunks[2], &["[Esc] Back".to_string()]);
+        return;
+    };
+
+    let remaining = session.remaining_items();
+    let summary = Paragraph::new(format!(
+        "The previous cleanup was interrupted.\nStarted: {}\nPlanned: {} items, done: {}, remaining: {}\nBackups: {}, removed paths: {}",
+        session
+            .started_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M:%S"),
+        session.items.len(),
+        session.items.len() - remaining.len(),
+        remaining.len(),
+        session.backup_ids.len(),
+        session.removed.len()
+    ))
+    .block(Block::default().borders(Borders::ALL).title("Summary"))
+    .style(Style::default().fg(Color::Yellow));
+    frame.render_widget(summary, body[0]);
+
+    let list_items: Vec<ListItem> = if remaining.is_empty() {
+        vec![ListItem::new("All planned items were processed.")]
+    } else {
+        remaining
+            .iter()
+            .take(body[1].height.saturating_sub(2) as usize)
+            .map(|item| {
+                let label = item.path.as_deref().unwrap_or(&item.name);
+                ListItem::new(format!("{label} ({})", format_size(item.size)))
+            })
+            .collect()
+    };
+    let list = List::new(list_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Remaining items"),
+    );
+    frame.render_widget(list, body[1]);
+
+    let mut keys = vec![
+        "[F] Finish cleanup".to_string(),
+        "[R] Roll back".to_string(),
+        "[D] Discard journal".to_string(),
+        "[Esc] Decide later".to_string(),
+    ];
+    if let Some(message) = state.status_message.as_deref() {
+        keys.push(message.to_string());
+    }
+    render_status_bar(frame, chunks[2], &keys);
+}
@@ -30,30 +30,78 @@ pub fn render_results_screen(
 
     render_header(frame, chunks[0], system_label, state.safety_level);
 
+    let freed_by_mount = state
+        .last_result
+        .as_ref()
+        .map(|result| &result.freed_by_mount)
+        .filter(|freed| !freed.is_empty());
+    let mount_rows = freed_by_mount.map_or(0, |freed| freed.len().min(6) as u16 + 2);
     let body = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(5), Constraint::Min(0)])
+        .constraints([
+            Constraint::Length(6),
+            Constraint::Length(mount_rows),
+            Constraint::Min(0),
+        ])
         .split(chunks[1]);
 
     let summary_text = match &state.last_result {
-        Some(result) => format!(
-            "Cleaned: {}\nSkipped: {}\nFreed: {}\nErrors: {}",
-            result.cleaned_items,
-            result.skipped_items,
-            format_size(result.freed_bytes),
-            result.errors.len()
-        ),
+        Some(result) => {
+            let mut freed = format_size(result.freed_bytes);
+            if result.moved_bytes > 0 {
+                freed.push_str(&format!(
+                    ", {} moved to trash/quarantine",
+                    format_size(result.moved_bytes)
+                ));
+            }
+            if result.held_by_snapshots > 0 {
+                freed.push_str(&format!(
+                    ", {} kept in backup snapshots until the backup is deleted",
+                    format_size(result.held_by_snapshots)
+                ));
+            }
+            format!(
+                "Cleaned: {}\nSkipped: {}\nFreed: {}\nErrors: {}",
+                result.cleaned_items,
+                result.skipped_items,
+                freed,
+                result.errors.len()
+            )
+        }
         None => "No cleanup results available.".to_string(),
     };
     let summary =
         Paragraph::new(summary_text).block(Block::default().borders(Borders::ALL).title("Summary"));
     frame.render_widget(summary, body[0]);
 
+    if let Some(freed) = freed_by_mount {
+        let mount_items: Vec<ListItem> = freed
+            .iter()
+            .map(|(mount_point, bytes)| {
+                let usage = state
+                    .filesystems
+                    .iter()
+                    .find(|filesystem| filesystem.mount_point.to_string_lossy() == *mount_point)
+                    .map(|filesystem| {
+                        format!(" (now {:.0}% full)", filesystem.usage.used_percent())
+                    })
+                    .unwrap_or_default();
+                ListItem::new(format!("{mount_point}: {}{usage}", format_size(*bytes)))
+            })
+            .collect();
+        let mounts = List::new(mount_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Freed per filesystem"),
+        );
+        frame.render_widget(mounts, body[1]);
+    }
+
     let error_items = match &state.last_result {
         Some(result) if !result.errors.is_empty() => result
             .errors
             .iter()
-            .take(body[1].height.saturating_sub(2) as usize)
+            .take(body[2].height.saturating_sub(2) as usize)
             .map(|err| ListItem::new(err.clone()))
             .collect(),
         _ => vec![ListItem::new("No errors reported.")],
@@ -62,7 +110,7 @@ pub fn render_results_screen(
     let errors = List::new(error_items)
         .block(Block::default().borders(Borders::ALL).title("Errors"))
         .style(Style::default().fg(Color::White));
-    frame.render_widget(errors, body[1]);
+    frame.render_widget(errors, body[2]);
 
     let keys = vec!["[Enter] Back".to_string(), "[Esc] Back".to_string()];
     render_status_bar(frame, chunks[2], &keys);
@@ -1,3 +1,4 @@
+use crate::config::{DeletionMode, RuleConfig};
 use crate::tui::action::{SafetyLevel, SettingsEdit};
 use crate::tui::screens::common::render_header;
 use crate::tui::state::State;
@@ -14,11 +15,11 @@ pub struct SettingsScreenParams<'a> {
     pub auto_confirm: bool,
     pub dry_run: bool,
     pub temp_max_age_days: u64,
+    pub deletion_mode: DeletionMode,
     pub config_path: &'a str,
     pub safety_enabled: bool,
     pub only_root_can_disable: bool,
-    pub whitelist: &'a [String],
-    pub blacklist: &'a [String],
+    pub rules: &'a [RuleConfig],
 }
 
 /// Отрисовывает экран настроек.
@@ -31,11 +32,11 @@ pub fn render_settings_screen(
     auto_confirm: bool,
     dry_run: bool,
     temp_max_age_days: u64,
+    deletion_mode: DeletionMode,
     config_path: &str,
     safety_enabled: bool,
     only_root_can_disable: bool,
-    whitelist: &[String],
-    blacklist: &[String],
+    rules: &[RuleConfig],
 ) {
     let outer = Block::default()
         .borders(Borders::ALL)
@@ -62,7 +63,7 @@ pub fn render_settings_screen(
 
     let left_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(9), Constraint::Min(0)])
+        .constraints([Constraint::Length(10), Constraint::Min(0)])
         .split(content_chunks[0]);
 
     let safety_status = if safety_enabled {
@@ -77,9 +78,10 @@ pub fn render_settings_screen(
     };
 
     let info = Paragraph::new(format!(
-        "Safety: {safety_status} (E)\nRoot-only disable: {root_status} (O)\nLevel: {level_label}\nAuto confirm: {}\nDry run: {}\nTemp age: {temp_max_age_days}d\nConfig: {}",
+        "Safety: {safety_status} (E)\nRoot-only disable: {root_status} (O)\nLevel: {level_label}\nAuto confirm: {}\nDry run: {}\nTemp age: {temp_max_age_days}d\nDeletion: {} (M)\nConfig: {}",
         if auto_confirm { "on" } else { "off" },
         if dry_run { "on" } else { "off" },
+        deletion_mode.label(),
         config_path
     ))
     .block(Block::default().borders(Borders::ALL).title("Config"));
@@ -113,28 +115,28 @@ pub fn render_settings_screen(
         .constraints([Constraint::Min(0), Constraint::Length(3)])
         .split(content_chunks[1]);
 
-    let whitelist_lines = format_rules_list(whitelist);
-    let blacklist_lines = format_rules_list(blacklist);
-    let rules_text = format!("Whitelist:\n{whitelist_lines}\n\nBlacklist:\n{blacklist_lines}");
-    let rules =
-        Paragraph::new(rules_text).block(Block::default().borders(Borders::ALL).title("Rules"));
+    let rules = Paragraph::new(format_rules_list(rules)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Rules (higher priority wins, deny on ties)"),
+    );
     frame.render_widget(rules, right_chunks[0]);
 
     let edit_label = match state.settings_edit {
-        Some(SettingsEdit::Whitelist) => "Edit whitelist",
-        Some(SettingsEdit::Blacklist) => "Edit blacklist",
+        Some(SettingsEdit::Protect) => "Edit protected paths",
+        Some(SettingsEdit::Allow) => "Edit allowed paths",
         None => "Edit rules",
     };
 
     let edit_text = if let Some(edit) = state.settings_edit {
         let input = state.settings_input.as_str();
         let hint = match edit {
-            SettingsEdit::Whitelist => "Comma-separated paths",
-            SettingsEdit::Blacklist => "Comma-separated patterns",
+            SettingsEdit::Protect => "Comma-separated paths or patterns to deny",
+            SettingsEdit::Allow => "Comma-separated paths or patterns to allow",
         };
         format!("{edit_label}: {input}\n{hint} (Enter: save, Esc: cancel)")
     } else {
-        "W: edit whitelist  B: edit blacklist".to_string()
+        "P: edit protected paths  A: edit allowed paths".to_string()
     };
 
     let edit_block = Block::default().borders(Borders::ALL).title("Edit");
@@ -164,7 +166,8 @@ pub fn render_settings_screen(
             "[E] Toggle safety".to_string(),
             "[O] Root-only".to_string(),
             "[D] Dry run".to_string(),
-            "[W/B] Edit rules".to_string(),
+            "[M] Deletion mode".to_string(),
+            "[P/A] Edit rules".to_string(),
             "[Enter] Back".to_string(),
             "[Esc] Back".to_string(),
         ]
@@ -175,13 +178,15 @@ pub fn render_settings_screen(
     render_status_bar(frame, chunks[2], &keys);
 }
 
-fn format_rules_list(rules: &[String]) -> String {
+fn format_rules_list(rules: &[RuleConfig]) -> String {
     if rules.is_empty() {
         return "(empty)".to_string();
     }
-    rules
+    let mut sorted: Vec<_> = rules.iter().collect();
+    sorted.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+    sorted
         .iter()
-        .map(|rule| format!("- {rule}"))
+        .map(|rule| format!("- {}", rule.summary()))
         .collect::<Vec<_>>()
         .join("\n")
 }
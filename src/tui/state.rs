@@ -1,6 +1,10 @@
 //! Состояние приложения.
 
+use crate::backup::BackupSummary;
+use crate::backup::journal::JournalSession;
 use crate::models::{CleanupCategory, CleanupItem, CleanupResult};
+use crate::system::disk_usage::{self, FilesystemUsage, MountEstimate};
+use crate::system::package_manager::RemovalPlan;
 use crate::tui::action::{SafetyLevel, Screen, SettingsEdit};
 
 /// Состояние TUI приложения.
@@ -18,6 +22,8 @@ pub struct State {
     pub total_size: u64,
     /// Размер выбранных элементов.
     pub selected_size: u64,
+    /// Файловые системы и их заполненность.
+    pub filesystems: Vec<FilesystemUsage>,
     /// Уровень безопасности.
     pub safety_level: SafetyLevel,
     /// Идёт ли очистка.
@@ -34,10 +40,22 @@ pub struct State {
     pub search_query: String,
     /// Активен ли режим поиска.
     pub search_active: bool,
+    /// Показывать ли трассировку проверок безопасности вместо панели дисков.
+    pub show_trace: bool,
+    /// Симуляции транзакций для выбранных пакетов (экран подтверждения).
+    pub removal_plans: Vec<RemovalPlan>,
     /// Текущее редактирование настроек.
     pub settings_edit: Option<SettingsEdit>,
     /// Ввод в настройках.
     pub settings_input: String,
+    /// Бэкапы для экрана управления.
+    pub backups: Vec<BackupSummary>,
+    /// Индекс выбранного бэкапа.
+    pub backup_selected: usize,
+    /// Бэкап, удаление которого ждёт подтверждения.
+    pub backup_delete_pending: Option<String>,
+    /// Прерванная сессия очистки, найденная при запуске.
+    pub interrupted_session: Option<JournalSession>,
     /// Флаг выхода из приложения.
     pub should_exit: bool,
 }
@@ -51,6 +69,7 @@ impl Default for State {
             items: Vec::new(),
             total_size: 0,
             selected_size: 0,
+            filesystems: Vec::new(),
             safety_level: SafetyLevel::Safe,
             cleanup_in_progress: false,
             cleanup_progress: 0.0,
@@ -59,8 +78,14 @@ impl Default for State {
             status_message: None,
             search_query: String::new(),
             search_active: false,
+            show_trace: false,
+            removal_plans: Vec::new(),
             settings_edit: None,
             settings_input: String::new(),
+            backups: Vec::new(),
+            backup_selected: 0,
+            backup_delete_pending: None,
+            interrupted_session: None,
             should_exit: false,
         }
     }
@@ -80,7 +105,8 @@ impl State {
             2 => CleanupCategory::TempFiles,
             3 => CleanupCategory::Logs,
             4 => CleanupCategory::OldPackages,
-            _ => CleanupCategory::OldKernels,
+            5 => CleanupCategory::OldKernels,
+            _ => CleanupCategory::Locales,
         }
     }
 
@@ -126,7 +152,20 @@ impl State {
             .and_then(|index| self.items.get(index))
     }
 
+    /// Возвращает выбранный бэкап.
+    pub fn selected_backup(&self) -> Option<&BackupSummary> {
+        self.backups.get(self.backup_selected)
+    }
+
     /// Обновляет общий размер всех элементов.
+    /// Сколько освободят выбранные элементы на каждой файловой системе.
+    pub fn selected_by_mount(&self) -> Vec<MountEstimate> {
+        disk_usage::estimate_by_mount(
+            self.items.iter().filter(|item| item.selected),
+            &self.filesystems,
+        )
+    }
+
     pub fn update_total_size(&mut self) {
         self.total_size = self.items.iter().map(|item| item.size).sum();
     }
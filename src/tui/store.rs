@@ -5,7 +5,7 @@ pub struct Store {
     state: State,
 }
 
-const TAB_COUNT: usize = 6;
+const TAB_COUNT: usize = 7;
 
 impl Default for Store {
     fn default() -> Self {
@@ -57,6 +57,10 @@ impl Store {
                 self.state.status_message = None;
             }
 
+            Action::SetFilesystems(filesystems) => {
+                self.state.filesystems = filesystems;
+            }
+
             Action::SetStatus(message) => {
                 self.state.status_message = message;
             }
@@ -148,6 +152,10 @@ impl Store {
                 }
             }
 
+            Action::ToggleTrace => {
+                self.state.show_trace = !self.state.show_trace;
+            }
+
             Action::ToggleAllVisible => {
                 let visible_indices = self.state.visible_item_indices();
                 let mut selectable_indices = Vec::new();
@@ -215,6 +223,10 @@ impl Store {
                 self.state.settings_input.pop();
             }
 
+            Action::SetRemovalPlans(plans) => {
+                self.state.removal_plans = plans;
+            }
+
             Action::OpenConfirm => {
                 self.state.active_screen = crate::tui::action::Screen::Confirm;
                 self.state.search_active = false;
@@ -226,6 +238,48 @@ impl Store {
                 self.state.search_active = false;
             }
 
+            Action::OpenBackups => {
+                self.state.active_screen = crate::tui::action::Screen::Backups;
+                self.state.search_active = false;
+                self.state.backup_selected = 0;
+                self.state.backup_delete_pending = None;
+            }
+
+            Action::SetBackups(backups) => {
+                self.state.backups = backups;
+                if self.state.backup_selected >= self.state.backups.len() {
+                    self.state.backup_selected = self.state.backups.len().saturating_sub(1);
+                }
+            }
+
+            Action::SelectNextBackup => {
+                let count = self.state.backups.len();
+                if count > 0 {
+                    self.state.backup_selected = (self.state.backup_selected + 1) % count;
+                }
+            }
+
+            Action::SetBackupDeletePending(backup_id) => {
+                self.state.backup_delete_pending = backup_id;
+            }
+
+            Action::OpenRecovery(session) => {
+                self.state.interrupted_session = Some(session);
+                self.state.active_screen = crate::tui::action::Screen::Recovery;
+            }
+
+            Action::CloseRecovery => {
+                self.state.interrupted_session = None;
+                self.state.active_screen = crate::tui::action::Screen::Main;
+            }
+
+            Action::SelectPrevBackup => {
+                let count = self.state.backups.len();
+                if count > 0 {
+                    self.state.backup_selected = (self.state.backup_selected + count - 1) % count;
+                }
+            }
+
             Action::BackToMain => {
                 self.state.active_screen = crate::tui::action::Screen::Main;
                 self.state.cleanup_in_progress = false;
@@ -233,6 +287,7 @@ impl Store {
                 self.state.cleanup_step = None;
                 self.state.search_active = false;
                 self.state.settings_edit = None;
+                self.state.backup_delete_pending = None;
             }
 
             Action::StartCleanup => {
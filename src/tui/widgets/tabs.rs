@@ -2,7 +2,9 @@ use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::Line;
 use ratatui::widgets::{Block, Borders, Tabs};
 
-pub const TAB_TITLES: [&str; 6] = ["Cache", "Apps", "Temp", "Logs", "Packages", "Kernels"];
+pub const TAB_TITLES: [&str; 7] = [
+    "Cache", "Apps", "Temp", "Logs", "Packages", "Kernels", "Locales",
+];
 
 pub fn render_tabs(frame: &mut ratatui::Frame, area: ratatui::layout::Rect, active: usize) {
     let titles = TAB_TITLES
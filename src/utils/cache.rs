@@ -98,6 +98,7 @@ mod tests {
             can_clean: true,
             blocked_reason: None,
             dependencies: Vec::new(),
+            verdicts: Vec::new(),
         }];
 
         save_cached_items(&items).unwrap();
@@ -0,0 +1,171 @@
+//! Подсчёт места, которое файлы занимают на диске.
+//!
+//! Размер считается по выделенным блокам (`st_blocks * 512`), поэтому
+//! разреженные файлы не завышают результат. Жёсткие ссылки учитываются один
+//! раз по паре (устройство, inode), символические ссылки не разыменовываются,
+//! а вложенные точки монтирования не обходятся.
+
+use std::collections::HashSet;
+use std::fs::Metadata;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Размер в байтах: видимый и фактически занятый на диске.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskSize {
+    /// Сумма длин файлов (`st_size`), как `du --apparent-size`.
+    pub apparent: u64,
+    /// Занятые блоки (`st_blocks * 512`), как `du`.
+    pub allocated: u64,
+}
+
+impl DiskSize {
+    /// Размер одного объекта по его метаданным.
+    pub fn of(metadata: &Metadata) -> Self {
+        let apparent = if metadata.is_dir() { 0 } else { metadata.len() };
+        Self {
+            apparent,
+            allocated: metadata.blocks().saturating_mul(512),
+        }
+    }
+
+    fn add(&mut self, other: Self) {
+        self.apparent = self.apparent.saturating_add(other.apparent);
+        self.allocated = self.allocated.saturating_add(other.allocated);
+    }
+}
+
+/// Счётчик размера, который помнит уже учтённые inode.
+///
+/// Один счётчик можно использовать для нескольких путей: общие жёсткие
+/// ссылки и пересекающиеся каталоги посчитаются один раз.
+#[derive(Debug, Default)]
+pub struct SizeCounter {
+    seen: HashSet<(u64, u64)>,
+    cross_mounts: bool,
+}
+
+impl SizeCounter {
+    /// Счётчик, который не выходит за пределы файловой системы пути.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Разрешает обход вложенных точек монтирования.
+    pub fn cross_mounts(mut self, cross_mounts: bool) -> Self {
+        self.cross_mounts = cross_mounts;
+        self
+    }
+
+    /// Считает размер файла или каталога со всем содержимым.
+    ///
+    /// Недоступные записи пропускаются; отсутствующий путь имеет размер 0.
+    pub fn measure(&mut self, path: &Path) -> DiskSize {
+        let mut total = DiskSize::default();
+        let walker = WalkDir::new(path)
+            .follow_links(false)
+            .same_file_system(!self.cross_mounts);
+        for entry in walker.into_iter().flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if self.first_time(&metadata) {
+                total.add(DiskSize::of(&metadata));
+            }
+        }
+        total
+    }
+
+    /// Считает суммарный размер нескольких путей.
+    pub fn measure_all<P: AsRef<Path>>(&mut self, paths: &[P]) -> DiskSize {
+        let mut total = DiskSize::default();
+        for path in paths {
+            total.add(self.measure(path.as_ref()));
+        }
+        total
+    }
+
+    fn first_time(&mut self, metadata: &Metadata) -> bool {
+        self.seen.insert((metadata.dev(), metadata.ino()))
+    }
+}
+
+/// Размер пути на диске.
+pub fn disk_size(path: &Path) -> DiskSize {
+    SizeCounter::new().measure(path)
+}
+
+/// Занятое на диске место, которое освободит удаление пути.
+pub fn allocated_size(path: &Path) -> u64 {
+    disk_size(path).allocated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::{Seek, SeekFrom, Write};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "rcleaner-size-{name}-{nanos}-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sparse_files_and_hardlinks() {
+        let dir = temp_dir("sparse");
+        let data = dir.join("data.bin");
+        fs::write(&data, vec![7u8; 64 * 1024]).unwrap();
+        fs::hard_link(&data, dir.join("data-link.bin")).unwrap();
+
+        let sparse = dir.join("sparse.img");
+        let mut file = File::create(&sparse).unwrap();
+        file.seek(SeekFrom::Start(64 * 1024 * 1024)).unwrap();
+        file.write_all(b"end").unwrap();
+        drop(file);
+
+        let size = disk_size(&dir);
+        // Жёсткая ссылка не удваивает размер.
+        assert!(size.apparent >= 64 * 1024 + 64 * 1024 * 1024);
+        assert!(size.apparent < 2 * 64 * 1024 + 64 * 1024 * 1024);
+        // Разреженный файл почти не занимает блоков.
+        assert!(size.allocated < 16 * 1024 * 1024);
+        assert!(allocated_size(&data) >= 64 * 1024);
+
+        // Пересекающиеся пути считаются один раз.
+        let mut counter = SizeCounter::new();
+        let once = counter.measure(&dir);
+        let again = counter.measure_all(&[&data, &dir]);
+        assert_eq!(once, size);
+        assert_eq!(again, DiskSize::default());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_symlinks_are_not_followed() {
+        let dir = temp_dir("symlink");
+        let outside = temp_dir("symlink-target");
+        fs::write(outside.join("big.bin"), vec![1u8; 256 * 1024]).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+
+        assert!(disk_size(&dir).apparent < 256 * 1024);
+        assert_eq!(
+            disk_size(Path::new("/nonexistent/rcleaner")),
+            DiskSize::default()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+}
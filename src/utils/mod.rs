@@ -1,5 +1,7 @@
 pub mod cache;
 pub mod command;
+pub mod disk_size;
 pub mod file_utils;
 pub mod logger;
+pub mod safe_fs;
 pub mod size_format;
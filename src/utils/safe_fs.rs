@@ -0,0 +1,623 @@
+//! Рекурсивное удаление и копирование без перехода по ссылкам и границам
+//! файловых систем.
+//!
+//! Удаление работает относительно дескрипторов каталогов (`openat`,
+//! `fstatat`, `unlinkat`): каждый подкаталог открывается с `O_NOFOLLOW`, а его
+//! (устройство, inode) сверяется с тем, что было видно при обходе. Если путь
+//! подменили символической ссылкой во время удаления, операция прерывается,
+//! а не продолжается по ссылке.
+//!
+//! Родительский каталог тоже открывается по одному компоненту от `/`:
+//! ссылка по пути допускается, только если она и её каталог принадлежат
+//! root (например, `/home` → `var/home` на Atomic) или самому процессу.
+//! Подменить компонент такой ссылкой другой пользователь не может.
+
+use crate::error::{RcleanerError, Result};
+use crate::system::mounts::{self, MountEntry};
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::fs;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Component, Path, PathBuf};
+
+/// Сколько ссылок можно пройти при открытии пути (как `MAXSYMLINKS` ядра).
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Безвозвратно удаляет файл, ссылку или каталог со всем содержимым.
+///
+/// Символические ссылки удаляются как ссылки, их цели не затрагиваются.
+/// Точки монтирования (в том числе bind-mount системных каталогов) внутри
+/// пути или сам путь приводят к отказу до удаления чего-либо.
+pub fn remove_tree(path: &Path) -> Result<()> {
+    let (parent, name) = split_path(path)?;
+    let parent_fd = open_dir_nofollow(&parent)?;
+    remove_at(&parent_fd, &name, path)
+}
+
+/// Как [`remove_tree`], но внутри `root` не проходит ни одной ссылки.
+///
+/// Для путей в каталогах, куда пишут другие пользователи (`/tmp`,
+/// `/var/log/<служба>`): подменённый ссылкой компонент ниже `root` даёт
+/// ошибку, даже если ссылка ведёт в разрешённое место.
+pub fn remove_tree_beneath(root: &Path, path: &Path) -> Result<()> {
+    let relative = path.strip_prefix(root).map_err(|_| {
+        RcleanerError::InvalidInput(format!("{} is outside {}", path.display(), root.display()))
+    })?;
+    let (parent, name) = split_path(relative)?;
+    let mut parent_fd = open_dir_nofollow(root)?;
+    for component in parent.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => {
+                let part = c_string(part.as_bytes())?;
+                parent_fd = open_dir_at(parent_fd.as_raw_fd(), &part)
+                    .map_err(|err| refused_link(err, path))?;
+            }
+            _ => {
+                return Err(RcleanerError::InvalidInput(format!(
+                    "non-normalized path {}",
+                    path.display()
+                )));
+            }
+        }
+    }
+    remove_at(&parent_fd, &name, path)
+}
+
+/// Создаёт файл заново, не проходя по ссылке на его месте.
+///
+/// Существующий файл или ссылка удаляется, новый открывается с
+/// `O_CREAT | O_EXCL | O_NOFOLLOW` относительно родителя, открытого через
+/// [`open_dir_nofollow`]. Подложенная ссылка не перенаправит запись в чужой файл.
+pub fn create_file(path: &Path, mode: u32) -> Result<fs::File> {
+    let (parent, name) = split_path(path)?;
+    let parent_fd = open_dir_nofollow(&parent)?;
+    create_file_at(parent_fd.as_raw_fd(), &name, mode)
+}
+
+/// Записывает файл под временным именем и переименовывает его на место.
+///
+/// Пока `write` не завершился успешно, прежний файл по пути не тронут, а
+/// при ошибке временный файл удаляется. Права `mode` выставляются после
+/// записи.
+pub fn replace_file(
+    path: &Path,
+    mode: u32,
+    write: impl FnOnce(&mut fs::File) -> Result<()>,
+) -> Result<()> {
+    let (parent, name) = split_path(path)?;
+    let parent_fd = create_dir_all_nofollow(&parent)?;
+    let mut temp_name = b".".to_vec();
+    temp_name.extend_from_slice(name.to_bytes());
+    temp_name.extend_from_slice(format!(".rcleaner-{}", std::process::id()).as_bytes());
+    let temp_name = c_string(&temp_name)?;
+
+    let mut file = create_file_at(parent_fd.as_raw_fd(), &temp_name, 0o600)?;
+    let written =
+        write(&mut file).and_then(|()| Ok(file.set_permissions(fs::Permissions::from_mode(mode))?));
+    drop(file);
+    if let Err(err) = written {
+        let _ = unlink_at(parent_fd.as_raw_fd(), &temp_name, false);
+        return Err(err);
+    }
+
+    let renamed = unsafe {
+        libc::renameat(
+            parent_fd.as_raw_fd(),
+            temp_name.as_ptr(),
+            parent_fd.as_raw_fd(),
+            name.as_ptr(),
+        )
+    };
+    if renamed != 0 {
+        let err = io::Error::last_os_error();
+        let _ = unlink_at(parent_fd.as_raw_fd(), &temp_name, false);
+        return Err(err.into());
+    }
+    Ok(())
+}
+
+/// Создаёт недостающие каталоги пути и открывает последний.
+///
+/// Существующая часть пути открывается через [`open_dir_nofollow`], а новые
+/// каталоги создаются `mkdirat` и открываются с `O_NOFOLLOW`: ссылка,
+/// подложенная на место создаваемого каталога, даёт ошибку.
+pub fn create_dir_all_nofollow(path: &Path) -> Result<OwnedFd> {
+    let path = std::path::absolute(path)?;
+    let mut missing = Vec::new();
+    let mut existing = path.as_path();
+    while fs::symlink_metadata(existing).is_err() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            break;
+        };
+        missing.push(name);
+        existing = parent;
+    }
+
+    let mut fd = open_dir_nofollow(existing)?;
+    for name in missing.into_iter().rev() {
+        let name = c_string(name.as_bytes())?;
+        fd = make_dir_at(fd.as_raw_fd(), &name, 0o777).map_err(|err| refused_link(err, &path))?;
+    }
+    Ok(fd)
+}
+
+/// Открывает каталог, проходя путь по одному компоненту с `O_NOFOLLOW`.
+///
+/// Ссылка по пути разрешается, только если она и содержащий её каталог
+/// принадлежат root или текущему пользователю; любая другая ссылка даёт
+/// [`RcleanerError::SafetyRule`]. Помощник работает от root и проходит
+/// только ссылки root.
+pub fn open_dir_nofollow(path: &Path) -> Result<OwnedFd> {
+    let path = std::path::absolute(path)?;
+    let mut pending: VecDeque<OsString> = path
+        .components()
+        .map(|component| component.as_os_str().to_os_string())
+        .collect();
+    let mut fd = open_root()?;
+    let mut hops = 0;
+
+    while let Some(part) = pending.pop_front() {
+        if part == "/" {
+            fd = open_root()?;
+            continue;
+        }
+        if part == "." || part.is_empty() {
+            continue;
+        }
+        let name = c_string(part.as_bytes())?;
+        match open_dir_at(fd.as_raw_fd(), &name) {
+            Ok(next) => fd = next,
+            Err(err) if is_link_error(&err) => {
+                let stat = stat_at(fd.as_raw_fd(), &name)?;
+                if stat.st_mode & libc::S_IFMT != libc::S_IFLNK {
+                    return Err(err.into());
+                }
+                hops += 1;
+                if !is_trusted_owner(stat.st_uid)
+                    || !is_trusted_owner(fstat(fd.as_raw_fd())?.st_uid)
+                {
+                    return Err(RcleanerError::SafetyRule(format!(
+                        "{} goes through a symlink owned by uid {}, refusing to follow",
+                        path.display(),
+                        stat.st_uid
+                    )));
+                }
+                if hops > MAX_SYMLINK_HOPS {
+                    return Err(io::Error::from_raw_os_error(libc::ELOOP).into());
+                }
+                let target = read_link_at(fd.as_raw_fd(), &name)?;
+                for component in Path::new(&target).components().rev() {
+                    pending.push_front(component.as_os_str().to_os_string());
+                }
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(fd)
+}
+
+fn remove_at(parent_fd: &OwnedFd, name: &CStr, path: &Path) -> Result<()> {
+    let stat = stat_at(parent_fd.as_raw_fd(), name)?;
+    if !is_dir(&stat) {
+        return Ok(unlink_at(parent_fd.as_raw_fd(), name, false)?);
+    }
+
+    check_mount_boundaries(path)?;
+    let dir = open_verified(parent_fd.as_raw_fd(), name, &stat, path)?;
+    remove_contents(&dir, stat.st_dev, path)?;
+    drop(dir);
+    Ok(unlink_at(parent_fd.as_raw_fd(), name, true)?)
+}
+
+/// Отказывает, если путь является точкой монтирования или содержит её.
+pub fn check_mount_boundaries(path: &Path) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+    if !metadata.is_dir() {
+        return Ok(());
+    }
+    let resolved = fs::canonicalize(path)?;
+    match mount_conflict(&resolved, &mounts::read_mounts()) {
+        Some(mount_point) if mount_point == resolved => Err(RcleanerError::SafetyRule(format!(
+            "{} is a mount point",
+            path.display()
+        ))),
+        Some(mount_point) => Err(RcleanerError::SafetyRule(format!(
+            "{} contains mount point {}",
+            path.display(),
+            mount_point.display()
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Ищет точку монтирования, совпадающую с путём или лежащую внутри него.
+pub fn mount_conflict(resolved: &Path, mounts: &[MountEntry]) -> Option<PathBuf> {
+    mounts
+        .iter()
+        .map(|mount| &mount.mount_point)
+        .filter(|mount_point| mount_point.starts_with(resolved))
+        .min_by_key(|mount_point| mount_point.as_os_str().len())
+        .cloned()
+}
+
+/// Копирует файл или каталог, сохраняя символические ссылки как ссылки.
+///
+/// Назначение строится относительно дескрипторов каталогов: существующие
+/// файлы и ссылки удаляются, новые создаются с `O_EXCL | O_NOFOLLOW`, поэтому
+/// ссылка, подложенная в дерево назначения, не перенаправит запись.
+/// Вложенные файловые системы и специальные файлы (сокеты, устройства,
+/// каналы) пропускаются.
+pub fn copy_tree(source: &Path, dest: &Path) -> Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+    let (parent, name) = split_path(dest)?;
+    let parent_fd = create_dir_all_nofollow(&parent)?;
+    copy_entry(source, &metadata, &parent_fd, &name, dest, metadata.dev())
+}
+
+fn copy_entry(
+    source: &Path,
+    metadata: &fs::Metadata,
+    parent_fd: &OwnedFd,
+    name: &CStr,
+    dest: &Path,
+    root_dev: u64,
+) -> Result<()> {
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        unlink_existing(parent_fd.as_raw_fd(), name)?;
+        let target = c_string(fs::read_link(source)?.as_os_str().as_bytes())?;
+        if unsafe { libc::symlinkat(target.as_ptr(), parent_fd.as_raw_fd(), name.as_ptr()) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+    } else if file_type.is_dir() {
+        if metadata.dev() != root_dev {
+            log::warn!("Skipping mount point {}", source.display());
+            return Ok(());
+        }
+        let dir = make_dir_at(parent_fd.as_raw_fd(), name, 0o700)
+            .map_err(|err| refused_link(err, dest))?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            let child = entry.path();
+            let child_metadata = fs::symlink_metadata(&child)?;
+            let child_name = c_string(entry.file_name().as_bytes())?;
+            copy_entry(
+                &child,
+                &child_metadata,
+                &dir,
+                &child_name,
+                &dest.join(entry.file_name()),
+                root_dev,
+            )?;
+        }
+        fs::File::from(dir).set_permissions(metadata.permissions())?;
+    } else if file_type.is_file() {
+        let mut input = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(source)?;
+        let mut output = create_file_at(parent_fd.as_raw_fd(), name, 0o600)?;
+        io::copy(&mut input, &mut output)?;
+        output.set_permissions(metadata.permissions())?;
+    }
+    Ok(())
+}
+
+/// Создаёт файл в каталоге, удалив стоящий на его месте файл или ссылку.
+fn create_file_at(dirfd: RawFd, name: &CStr, mode: u32) -> Result<fs::File> {
+    unlink_existing(dirfd, name)?;
+    let fd = unsafe {
+        libc::openat(
+            dirfd,
+            name.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+            mode as libc::c_uint,
+        )
+    };
+    Ok(fs::File::from(owned_fd(fd)?))
+}
+
+/// Удаляет файл или ссылку с именем `name`; каталоги не трогает.
+fn unlink_existing(dirfd: RawFd, name: &CStr) -> Result<()> {
+    match stat_at(dirfd, name) {
+        Ok(stat) if !is_dir(&stat) => Ok(unlink_at(dirfd, name, false)?),
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Создаёт каталог, если его нет, и открывает его без перехода по ссылке.
+fn make_dir_at(dirfd: RawFd, name: &CStr, mode: libc::mode_t) -> io::Result<OwnedFd> {
+    if unsafe { libc::mkdirat(dirfd, name.as_ptr(), mode) } != 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::AlreadyExists {
+            return Err(err);
+        }
+    }
+    open_dir_at(dirfd, name)
+}
+
+fn remove_contents(dir: &OwnedFd, root_dev: libc::dev_t, path: &Path) -> Result<()> {
+    for name in list_dir(dir)? {
+        let stat = match stat_at(dir.as_raw_fd(), &name) {
+            Ok(stat) => stat,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let child_path = path.join(OsStr::from_bytes(name.to_bytes()));
+        if is_dir(&stat) {
+            if stat.st_dev != root_dev {
+                return Err(RcleanerError::SafetyRule(format!(
+                    "{} is on another filesystem",
+                    child_path.display()
+                )));
+            }
+            let child = open_verified(dir.as_raw_fd(), &name, &stat, &child_path)?;
+            remove_contents(&child, root_dev, &child_path)?;
+            drop(child);
+            unlink_at(dir.as_raw_fd(), &name, true)?;
+        } else {
+            unlink_at(dir.as_raw_fd(), &name, false)?;
+        }
+    }
+    Ok(())
+}
+
+/// Открывает подкаталог без перехода по ссылке и сверяет его с `expected`.
+fn open_verified(dirfd: RawFd, name: &CStr, expected: &libc::stat, path: &Path) -> Result<OwnedFd> {
+    let fd = open_dir_at(dirfd, name).map_err(|err| refused_link(err, path))?;
+    let opened = fstat(fd.as_raw_fd())?;
+    if opened.st_dev != expected.st_dev || opened.st_ino != expected.st_ino {
+        return Err(swapped(path));
+    }
+    Ok(fd)
+}
+
+/// Ссылки и каталоги root и текущего пользователя подменить извне нельзя.
+fn is_trusted_owner(uid: libc::uid_t) -> bool {
+    uid == 0 || uid == unsafe { libc::geteuid() }
+}
+
+/// Ошибка открытия каталога, на месте которого оказалась ссылка или файл.
+fn is_link_error(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::ELOOP) | Some(libc::ENOTDIR))
+}
+
+fn refused_link(err: io::Error, path: &Path) -> RcleanerError {
+    if is_link_error(&err) {
+        swapped(path)
+    } else {
+        err.into()
+    }
+}
+
+fn swapped(path: &Path) -> RcleanerError {
+    RcleanerError::SafetyRule(format!(
+        "{} changed during deletion, aborting",
+        path.display()
+    ))
+}
+
+fn split_path(path: &Path) -> Result<(PathBuf, CString)> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| RcleanerError::InvalidInput(format!("Cannot remove {}", path.display())))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    Ok((parent, c_string(name.as_bytes())?))
+}
+
+fn c_string(bytes: &[u8]) -> io::Result<CString> {
+    CString::new(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
+
+fn is_dir(stat: &libc::stat) -> bool {
+    stat.st_mode & libc::S_IFMT == libc::S_IFDIR
+}
+
+fn open_root() -> io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::open(
+            c"/".as_ptr(),
+            libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC,
+        )
+    };
+    owned_fd(fd)
+}
+
+fn open_dir_at(dirfd: RawFd, name: &CStr) -> io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::openat(
+            dirfd,
+            name.as_ptr(),
+            libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC,
+        )
+    };
+    owned_fd(fd)
+}
+
+fn owned_fd(fd: RawFd) -> io::Result<OwnedFd> {
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn stat_at(dirfd: RawFd, name: &CStr) -> io::Result<libc::stat> {
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstatat(dirfd, name.as_ptr(), &mut stat, libc::AT_SYMLINK_NOFOLLOW) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat)
+}
+
+fn read_link_at(dirfd: RawFd, name: &CStr) -> io::Result<OsString> {
+    let mut buffer = vec![0u8; libc::PATH_MAX as usize];
+    let length = unsafe {
+        libc::readlinkat(
+            dirfd,
+            name.as_ptr(),
+            buffer.as_mut_ptr().cast(),
+            buffer.len(),
+        )
+    };
+    if length < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buffer.truncate(length as usize);
+    Ok(OsString::from_vec(buffer))
+}
+
+fn fstat(fd: RawFd) -> io::Result<libc::stat> {
+    let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(stat)
+}
+
+fn unlink_at(dirfd: RawFd, name: &CStr, dir: bool) -> io::Result<()> {
+    let flags = if dir { libc::AT_REMOVEDIR } else { 0 };
+    if unsafe { libc::unlinkat(dirfd, name.as_ptr(), flags) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Читает имена записей каталога (без `.` и `..`).
+fn list_dir(dir: &OwnedFd) -> io::Result<Vec<CString>> {
+    // closedir закрывает дескриптор, поэтому читаем через копию.
+    let dup = unsafe { libc::dup(dir.as_raw_fd()) };
+    if dup < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let stream = unsafe { libc::fdopendir(dup) };
+    if stream.is_null() {
+        let err = io::Error::last_os_error();
+        unsafe { libc::close(dup) };
+        return Err(err);
+    }
+    unsafe { libc::rewinddir(stream) };
+
+    let mut names = Vec::new();
+    loop {
+        let entry = unsafe { libc::readdir(stream) };
+        if entry.is_null() {
+            break;
+        }
+        let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+        if name.to_bytes() != b"." && name.to_bytes() != b".." {
+            names.push(name.to_owned());
+        }
+    }
+    unsafe { libc::closedir(stream) };
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::mounts::parse_mountinfo;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "rcleaner-safe-{label}-{nanos}-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_mount_conflict() {
+        let mounts = parse_mountinfo(
+            "22 1 0:21 / / rw - ext4 /dev/sda1 rw\n\
+             40 22 0:21 /usr /home/u/.cache/usr-bind rw - ext4 /dev/sda1 rw\n\
+             41 22 0:30 / /home/u/.cache/fuse/drive rw - fuse.rclone drive: rw",
+        );
+        assert_eq!(
+            mount_conflict(Path::new("/home/u/.cache"), &mounts),
+            Some(PathBuf::from("/home/u/.cache/usr-bind"))
+        );
+        assert_eq!(
+            mount_conflict(Path::new("/home/u/.cache/usr-bind"), &mounts),
+            Some(PathBuf::from("/home/u/.cache/usr-bind"))
+        );
+        assert_eq!(
+            mount_conflict(Path::new("/home/u/.cache/thumbnails"), &mounts),
+            None
+        );
+    }
+
+    #[test]
+    fn test_swapped_directory_is_refused() {
+        let root = temp_dir("swap");
+        let outside = temp_dir("swap-outside");
+        fs::write(outside.join("keep.txt"), b"keep").unwrap();
+        let victim = root.join("victim");
+        fs::create_dir(&victim).unwrap();
+
+        let parent_fd = open_dir_nofollow(&root).unwrap();
+        let name = c_string(b"victim").unwrap();
+        let seen = stat_at(parent_fd.as_raw_fd(), &name).unwrap();
+
+        // Между обходом и открытием каталог подменили ссылкой наружу.
+        fs::remove_dir(&victim).unwrap();
+        std::os::unix::fs::symlink(&outside, &victim).unwrap();
+        let result = open_verified(parent_fd.as_raw_fd(), &name, &seen, &victim);
+        assert!(matches!(result, Err(RcleanerError::SafetyRule(_))));
+
+        assert!(outside.join("keep.txt").exists());
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn test_copy_tree_does_not_write_through_planted_links() {
+        let source = temp_dir("copy-source");
+        fs::create_dir(source.join("nested")).unwrap();
+        fs::write(source.join("file.txt"), b"backup").unwrap();
+        fs::write(source.join("nested").join("inner.txt"), b"backup").unwrap();
+        let outside = temp_dir("copy-outside");
+        fs::write(outside.join("victim.txt"), b"keep").unwrap();
+
+        // Ссылка на месте файла заменяется, а не используется для записи.
+        let dest = temp_dir("copy-dest");
+        std::os::unix::fs::symlink(outside.join("victim.txt"), dest.join("file.txt")).unwrap();
+        // Ссылка на месте каталога останавливает копирование.
+        std::os::unix::fs::symlink(&outside, dest.join("nested")).unwrap();
+
+        assert!(copy_tree(&source, &dest).is_err());
+        assert_eq!(fs::read(outside.join("victim.txt")).unwrap(), b"keep");
+        assert!(!outside.join("inner.txt").exists());
+
+        fs::remove_file(dest.join("nested")).unwrap();
+        copy_tree(&source, &dest).unwrap();
+        assert!(
+            !fs::symlink_metadata(dest.join("file.txt"))
+                .unwrap()
+                .is_symlink()
+        );
+        assert_eq!(fs::read(dest.join("file.txt")).unwrap(), b"backup");
+        assert_eq!(fs::read(dest.join("nested/inner.txt")).unwrap(), b"backup");
+        assert_eq!(fs::read(outside.join("victim.txt")).unwrap(), b"keep");
+
+        for dir in [&source, &dest, &outside] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -12,10 +12,9 @@ pub fn format_size(bytes: u64) -> String {
 }
 
 pub fn format_percentage(part: u64, total: u64) -> String {
-    if total == 0 {
-        "0%".to_string()
-    } else {
-        format!("{}%", (part * 100) / total)
+    match (part * 100).checked_div(total) {
+        Some(percent) => format!("{percent}%"),
+        None => "0%".to_string(),
     }
 }
 
@@ -0,0 +1,109 @@
+//! Проверка снимков Btrfs на loopback-образе.
+//!
+//! Требует root, `mkfs.btrfs`, `btrfs` и loop-устройств; без них тест
+//! сообщает о пропуске и завершается успешно.
+
+use rcleaner::backup::backend::{BackupBackend, BtrfsSnapshotBackend};
+use rcleaner::backup::{BackupFormat, BackupManager};
+use rcleaner::system::detection::{BackupBackendType, detect_backup_backend};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn temp_path(label: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "rcleaner-it-{label}-{nanos}-{}",
+        std::process::id()
+    ));
+    path
+}
+
+fn run(program: &str, args: &[&str]) -> bool {
+    Command::new(program)
+        .args(args)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+struct LoopbackBtrfs {
+    image: PathBuf,
+    mount_point: PathBuf,
+}
+
+impl LoopbackBtrfs {
+    /// Создаёт и монтирует образ или возвращает `None`, если окружение этого
+    /// не позволяет.
+    fn new() -> Option<Self> {
+        if unsafe { libc::geteuid() } != 0 || !run("btrfs", &["--version"]) {
+            return None;
+        }
+        let image = temp_path("btrfs-image");
+        let mount_point = temp_path("btrfs-mnt");
+        std::fs::create_dir_all(&mount_point).unwrap();
+
+        let file = std::fs::File::create(&image).unwrap();
+        file.set_len(256 * 1024 * 1024).unwrap();
+        let fs = Self { image, mount_point };
+        let mounted = run("mkfs.btrfs", &["-q", &fs.image.to_string_lossy()])
+            && run(
+                "mount",
+                &[
+                    "-o",
+                    "loop",
+                    &fs.image.to_string_lossy(),
+                    &fs.mount_point.to_string_lossy(),
+                ],
+            );
+        mounted.then_some(fs)
+    }
+
+    fn root(&self) -> &Path {
+        &self.mount_point
+    }
+}
+
+impl Drop for LoopbackBtrfs {
+    fn drop(&mut self) {
+        let _ = Command::new("umount").arg(&self.mount_point).status();
+        let _ = std::fs::remove_dir(&self.mount_point);
+        let _ = std::fs::remove_file(&self.image);
+    }
+}
+
+#[test]
+fn test_btrfs_snapshot_backup_and_release() {
+    let Some(fs) = LoopbackBtrfs::new() else {
+        eprintln!("skipping: needs root, btrfs-progs and a loop device");
+        return;
+    };
+    let cache = fs.root().join("user").join(".cache");
+    std::fs::create_dir_all(&cache).unwrap();
+    std::fs::write(cache.join("blob"), b"cached data").unwrap();
+    assert_eq!(
+        detect_backup_backend(std::slice::from_ref(&cache)),
+        BackupBackendType::BtrfsSnapshot
+    );
+
+    let manager = BackupManager::new(temp_path("btrfs-backups"), 0).unwrap();
+    let backup = BtrfsSnapshotBackend
+        .create_backup(&manager, std::slice::from_ref(&cache))
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(backup.format, BackupFormat::BtrfsSnapshot);
+    assert_eq!(backup.snapshots.len(), 1);
+    let snapshot_copy = Path::new(&backup.items[0].backup_path).join("blob");
+    assert_eq!(std::fs::read(&snapshot_copy).unwrap(), b"cached data");
+
+    std::fs::remove_dir_all(&cache).unwrap();
+    assert!(snapshot_copy.exists());
+
+    manager.delete_backup(&backup.id).unwrap();
+    assert!(!Path::new(&backup.snapshots[0]).exists());
+    let _ = std::fs::remove_dir_all(&manager.backup_dir);
+}
@@ -0,0 +1,159 @@
+//! Удаление и копирование деревьев с враждебными символическими ссылками.
+
+use rcleaner::RcleanerError;
+use rcleaner::cleaner::deletion::remove_permanently;
+use rcleaner::utils::safe_fs::{copy_tree, remove_tree, remove_tree_beneath};
+use std::fs;
+use std::os::unix::fs::{lchown, symlink};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn temp_path(label: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "rcleaner-it-{label}-{nanos}-{}",
+        std::process::id()
+    ));
+    path
+}
+
+/// Каталог "жертвы" за пределами удаляемого дерева.
+fn outside_tree() -> PathBuf {
+    let outside = temp_path("outside");
+    fs::create_dir_all(outside.join("nested")).unwrap();
+    fs::write(outside.join("secret.txt"), b"secret").unwrap();
+    fs::write(outside.join("nested/deep.txt"), b"deep").unwrap();
+    outside
+}
+
+fn assert_outside_intact(outside: &Path) {
+    assert_eq!(fs::read(outside.join("secret.txt")).unwrap(), b"secret");
+    assert_eq!(fs::read(outside.join("nested/deep.txt")).unwrap(), b"deep");
+}
+
+/// Дерево, где на каждом уровне есть ссылки наружу, на корень, петли и
+/// битые ссылки.
+fn symlink_farm(outside: &Path) -> PathBuf {
+    let root = temp_path("farm");
+    let mut dir = root.clone();
+    for level in 0..4 {
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("file{level}.txt")), b"data").unwrap();
+        symlink(outside, dir.join("to-outside")).unwrap();
+        symlink(outside.join("secret.txt"), dir.join("to-secret")).unwrap();
+        symlink(outside.join("nested"), dir.join("to-nested")).unwrap();
+        symlink("/", dir.join("to-root")).unwrap();
+        symlink("../../../../../../../../../", dir.join("dotdot")).unwrap();
+        symlink(&dir, dir.join("loop")).unwrap();
+        symlink(dir.join("missing"), dir.join("dangling")).unwrap();
+        dir = dir.join(format!("level{level}"));
+    }
+    root
+}
+
+#[test]
+fn test_remove_tree_never_follows_symlink_farm() {
+    let outside = outside_tree();
+    let farm = symlink_farm(&outside);
+
+    remove_tree(&farm).unwrap();
+
+    assert!(fs::symlink_metadata(&farm).is_err());
+    assert_outside_intact(&outside);
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+#[test]
+fn test_removing_symlink_root_keeps_target() {
+    let outside = outside_tree();
+    let link = temp_path("root-link");
+    symlink(&outside, &link).unwrap();
+
+    remove_permanently(&link).unwrap();
+
+    assert!(fs::symlink_metadata(&link).is_err());
+    assert_outside_intact(&outside);
+
+    // Ссылка со слешем в конце тоже не разыменовывается.
+    let link = temp_path("root-link-slash");
+    symlink(&outside, &link).unwrap();
+    let with_slash = PathBuf::from(format!("{}/", link.display()));
+    let _ = remove_tree(&with_slash);
+    assert_outside_intact(&outside);
+
+    let _ = fs::remove_file(&link);
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+#[test]
+fn test_copy_tree_preserves_links() {
+    let outside = outside_tree();
+    let farm = symlink_farm(&outside);
+    let copy = temp_path("farm-copy");
+
+    copy_tree(&farm, &copy).unwrap();
+
+    let link = fs::symlink_metadata(copy.join("to-outside")).unwrap();
+    assert!(link.file_type().is_symlink());
+    assert!(
+        fs::symlink_metadata(copy.join("to-root"))
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert!(copy.join("level0/file1.txt").is_file());
+
+    remove_tree(&farm).unwrap();
+    remove_tree(&copy).unwrap();
+    assert_outside_intact(&outside);
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+/// Ссылка, созданная другим пользователем (`nobody`); передать её можно
+/// только под root, иначе возвращается `false`.
+fn foreign_symlink(target: &Path, link: &Path) -> bool {
+    symlink(target, link).unwrap();
+    (unsafe { libc::geteuid() }) == 0 && lchown(link, Some(65534), Some(65534)).is_ok()
+}
+
+#[test]
+fn test_swapped_parent_component_is_refused() {
+    let outside = outside_tree();
+    let root = temp_path("parent-swap");
+    fs::create_dir_all(root.join("service/nested")).unwrap();
+    fs::write(root.join("service/nested/deep.txt"), b"mine").unwrap();
+
+    // После проверки пути каталог выше цели подменили ссылкой наружу.
+    fs::rename(root.join("service"), root.join("service-old")).unwrap();
+    let foreign = foreign_symlink(&outside, &root.join("service"));
+
+    let target = root.join("service/nested/deep.txt");
+    if foreign {
+        let result = remove_tree(&target);
+        assert!(matches!(result, Err(RcleanerError::SafetyRule(_))));
+    }
+    // Ниже разрешённого корня не проходится никакая ссылка.
+    let result = remove_tree_beneath(&root, &target);
+    assert!(matches!(result, Err(RcleanerError::SafetyRule(_))));
+    assert_outside_intact(&outside);
+
+    fs::remove_file(root.join("service")).unwrap();
+    fs::rename(root.join("service-old"), root.join("service")).unwrap();
+    remove_tree_beneath(&root, &target).unwrap();
+    assert!(!target.exists());
+
+    remove_tree(&root).unwrap();
+    fs::remove_dir_all(&outside).unwrap();
+}
+
+#[test]
+fn test_mount_point_is_refused() {
+    // /proc всегда смонтирован; отказ должен случиться до обхода.
+    let result = remove_tree(Path::new("/proc"));
+    assert!(matches!(result, Err(RcleanerError::SafetyRule(_))));
+    assert!(Path::new("/proc/self").exists());
+}